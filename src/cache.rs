@@ -0,0 +1,163 @@
+use log::{debug, info};
+use once_cell::sync::Lazy;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use umya_spreadsheet::Spreadsheet;
+
+/// Version of a file, any write changing its modification time (or its size on coarse file systems)
+#[derive(Debug, Clone, PartialEq)]
+struct FileStamp {
+    modified: SystemTime,
+    len: u64,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(FileStamp { modified: metadata.modified().ok()?, len: metadata.len() })
+    }
+}
+
+/// A workbook parsed from a file, as it was in the version `stamp` of the file
+struct CachedWorkbook {
+    stamp: FileStamp,
+    spreadsheet: Arc<Spreadsheet>,
+    last_used: u64,
+}
+
+/// The parsed workbooks of the process, by canonical path, the least recently used ones dropped first
+///
+/// Nothing is logged while the cache is locked: records forwarded to Python take the GIL, which a Python
+/// thread waiting for the cache could hold.
+#[derive(Default)]
+struct WorkbookCache {
+    max_workbooks: usize,
+    workbooks: HashMap<PathBuf, CachedWorkbook>,
+    uses: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl WorkbookCache {
+    /// Drops the least recently used workbooks beyond the size of the cache
+    ///
+    /// :return: The number of workbooks dropped.
+    fn evict(&mut self) -> usize {
+        let mut dropped = 0;
+        while self.workbooks.len() > self.max_workbooks {
+            let oldest = self.workbooks.iter().min_by_key(|(_, workbook)| workbook.last_used).map(|(path, _)| path.clone());
+            if let Some(path) = oldest {
+                self.workbooks.remove(&path);
+                dropped += 1;
+            }
+        }
+        dropped
+    }
+}
+
+/// Disabled until set_workbook_cache is called
+static CACHE: Lazy<Mutex<WorkbookCache>> = Lazy::new(|| Mutex::new(WorkbookCache::default()));
+
+/// Whether the workbooks read are cached
+pub fn is_enabled() -> bool {
+    CACHE.lock().unwrap().max_workbooks > 0
+}
+
+/// Loads a workbook through the cache: the cached workbook if its file has not changed since, otherwise
+/// the one `read` parses, cached for the next loads. Without the cache, the workbook is only read.
+///
+/// The cached workbooks are shared, never modified: a template modifying one works on a copy.
+pub fn load<F, E>(file_path: &str, read: F) -> Result<Arc<Spreadsheet>, E>
+where
+    F: FnOnce() -> Result<Spreadsheet, E>,
+{
+    if !is_enabled() {
+        return read().map(Arc::new);
+    }
+    // The version is the one before reading, so that a file written meanwhile is read again next time
+    let (path, stamp) = match std::fs::canonicalize(file_path).ok().and_then(|path| Some((FileStamp::of(&path)?, path))) {
+        Some((stamp, path)) => (path, stamp),
+        None => return read().map(Arc::new),
+    };
+    let cached = {
+        let mut cache = CACHE.lock().unwrap();
+        cache.uses += 1;
+        let uses = cache.uses;
+        let cached = match cache.workbooks.get_mut(&path) {
+            Some(workbook) if workbook.stamp == stamp => {
+                workbook.last_used = uses;
+                Some(workbook.spreadsheet.clone())
+            },
+            _ => None,
+        };
+        if cached.is_some() {
+            cache.hits += 1;
+        }
+        cached
+    };
+    if let Some(spreadsheet) = cached {
+        debug!("Workbook {} taken from the cache", file_path);
+        return Ok(spreadsheet);
+    }
+
+    // The cache is not locked while parsing, for the workbooks read in parallel
+    let spreadsheet = Arc::new(read()?);
+    let dropped = {
+        let mut cache = CACHE.lock().unwrap();
+        cache.misses += 1;
+        let last_used = cache.uses;
+        cache.workbooks.insert(path, CachedWorkbook { stamp, spreadsheet: spreadsheet.clone(), last_used });
+        cache.evict()
+    };
+    debug!("Workbook {} cached, {} least recently used dropped", file_path, dropped);
+    Ok(spreadsheet)
+}
+
+/// Caches the workbooks parsed by the process, up to `max_workbooks` of them (0, the default, disables the cache)
+///
+/// Templates opened and workbooks read from (copies, aggregations, consolidations) reuse the workbook parsed
+/// from the same file, until the file is modified. Lowering the size drops the least recently used workbooks.
+#[pyfunction]
+pub fn set_workbook_cache(max_workbooks: usize) -> PyResult<()> {
+    let dropped = {
+        let mut cache = CACHE.lock().unwrap();
+        cache.max_workbooks = max_workbooks;
+        cache.evict()
+    };
+    info!("Workbook cache set to {} workbooks, {} dropped", max_workbooks, dropped);
+    Ok(())
+}
+
+/// Drops the cached workbooks, e.g. to release their memory, and resets the counts of workbook_cache_info
+#[pyfunction]
+pub fn clear_workbook_cache() -> PyResult<()> {
+    {
+        let mut cache = CACHE.lock().unwrap();
+        cache.workbooks.clear();
+        cache.hits = 0;
+        cache.misses = 0;
+    }
+    debug!("Workbook cache cleared");
+    Ok(())
+}
+
+/// Describes the workbook cache
+///
+/// :return: A dictionary with 'max_workbooks', the paths of the cached 'workbooks', and the 'hits' and
+///     'misses' of the loads since the cache was last cleared.
+#[pyfunction]
+pub fn workbook_cache_info(py: Python) -> PyResult<PyObject> {
+    let cache = CACHE.lock().unwrap();
+    let mut workbooks: Vec<String> = cache.workbooks.keys().map(|path| path.to_string_lossy().to_string()).collect();
+    workbooks.sort();
+    let info = PyDict::new(py);
+    info.set_item("max_workbooks", cache.max_workbooks)?;
+    info.set_item("workbooks", workbooks)?;
+    info.set_item("hits", cache.hits)?;
+    info.set_item("misses", cache.misses)?;
+    Ok(info.into())
+}
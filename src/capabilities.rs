@@ -0,0 +1,143 @@
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::utils::features::CARGO_FEATURES;
+
+/// Actions supported by aggregate_range_from
+const ACTIONS: &[&str] = &["sum", "count", "avg", "min", "max", "median", "variance", "stddev", "product"];
+
+/// Modes supported by set_header_location, fill_with and aggregate_range_from
+const MODES: &[&str] = &["row", "col"];
+
+/// Coercions supported by copy_range_from
+const COERCIONS: &[&str] = &["none", "integer", "float", "string"];
+
+/// Stamp modes supported by stamp
+const STAMP_MODES: &[&str] = &["header", "background_image", "cell_overlay"];
+
+/// Policies for sheet names already taken, supported by include and consolidate
+const SHEET_CONFLICTS: &[&str] = &["suffix", "prefix_source_file", "error", "overwrite"];
+
+/// Data formats accepted by fill_with
+const DATA_FORMATS: &[&str] = &["pandas", "polars", "arrow", "dict", "list"];
+
+/// File formats read and written
+const FILE_FORMATS: &[&str] = &["xlsx", "xlsm"];
+
+/// Features of the API, and whether they are compiled into this binary
+const FEATURES: &[(&str, bool)] = &[
+    ("aggregate_across_files", cfg!(feature = "aggregate")),
+    ("aggregate_formulas", cfg!(feature = "aggregate")),
+    ("atomic_saves", true),
+    ("autofilter", true),
+    ("autosave", true),
+    ("bytes_io", true),
+    ("capture_log", true),
+    ("cell_tags", true),
+    ("charts", cfg!(feature = "charts")),
+    ("coerce_columns", cfg!(feature = "dataframe")),
+    ("collect", cfg!(feature = "dataframe")),
+    ("column_positions", cfg!(feature = "dataframe")),
+    ("comment_coercions", cfg!(feature = "dataframe")),
+    ("comments", true),
+    ("conditional_aggregation", cfg!(feature = "aggregate")),
+    ("consolidate", true),
+    ("consolidate_files", cfg!(feature = "dataframe")),
+    ("controls", true),
+    ("copy_many_ranges", true),
+    ("copy_styles", cfg!(feature = "styles")),
+    ("copy_transform", true),
+    ("crosstab", cfg!(feature = "dataframe")),
+    ("create", true),
+    ("custom_actions", cfg!(feature = "aggregate")),
+    ("data_loss_warnings", true),
+    ("data_validation", true),
+    ("date_values", cfg!(feature = "dataframe")),
+    ("dimensions", true),
+    ("document_properties", true),
+    ("drop_empty_sheets", true),
+    ("encryption", true),
+    ("error_types", true),
+    ("fill_defaults", cfg!(feature = "dataframe")),
+    ("fill_many", cfg!(feature = "dataframe")),
+    ("fill_partitioned", cfg!(feature = "dataframe")),
+    ("fill_validation", cfg!(feature = "dataframe")),
+    ("fill_with_csv", cfg!(feature = "dataframe")),
+    ("fill_with_ipc", cfg!(feature = "dataframe")),
+    ("fill_with_parquet", cfg!(feature = "dataframe")),
+    ("finalize_for_distribution", true),
+    ("find_broken_refs", true),
+    ("find_replace", true),
+    ("formulas", true),
+    ("freeze_panes", true),
+    ("fx", cfg!(feature = "dataframe")),
+    ("gantt", cfg!(all(feature = "dataframe", feature = "styles"))),
+    ("generated_columns", cfg!(feature = "dataframe")),
+    ("group_by", cfg!(feature = "dataframe")),
+    ("header_matching", cfg!(feature = "dataframe")),
+    ("include", true),
+    ("insert_rows", cfg!(feature = "dataframe")),
+    ("integer_format", true),
+    ("log_level", true),
+    ("merged_cells", true),
+    ("multiline_text", true),
+    ("named_ranges", true),
+    ("number_locale", true),
+    ("open_ranges", true),
+    ("optimize", true),
+    ("parallel_reads", true),
+    ("paste_formulas", true),
+    ("paste_operations", true),
+    ("paste_special", true),
+    ("protect", true),
+    ("provenance", cfg!(feature = "dataframe")),
+    ("python_logging", true),
+    ("region_registry", cfg!(feature = "dataframe")),
+    ("render", true),
+    ("reset_data", true),
+    ("row_colors", cfg!(all(feature = "dataframe", feature = "styles"))),
+    ("row_styles", cfg!(feature = "dataframe")),
+    ("sheet_management", true),
+    ("sheet_patterns", true),
+    ("sheet_protection", true),
+    ("sparklines", cfg!(feature = "charts")),
+    ("stamp", cfg!(feature = "styles")),
+    ("stats", true),
+    ("structured_warnings", true),
+    ("subtotals", cfg!(feature = "dataframe")),
+    ("table_growth", cfg!(feature = "dataframe")),
+    ("template_sources", true),
+    ("to_polars", cfg!(feature = "dataframe")),
+    ("totals_row", cfg!(feature = "dataframe")),
+    ("transactions", true),
+    ("truncate", cfg!(feature = "dataframe")),
+    ("upsert", cfg!(feature = "dataframe")),
+    ("verify_fill", cfg!(feature = "dataframe")),
+    ("workbook_cache", true),
+];
+
+/// Names of the compiled entries of a list of features
+fn compiled(features: &[(&'static str, bool)]) -> Vec<&'static str> {
+    features.iter().filter(|(_, enabled)| *enabled).map(|(name, _)| *name).collect()
+}
+
+/// Describes what the compiled module supports, so wrappers can detect features instead of checking versions.
+///
+/// :return: A dictionary with the 'version' and the lists of supported 'actions', 'modes', 'coercions',
+///     'stamp_modes', 'sheet_conflicts', 'data_formats', 'file_formats' and 'features', and the 'cargo_features' the binary
+///     was built with.
+#[pyfunction]
+pub fn capabilities(py: Python) -> PyResult<PyObject> {
+    let capabilities = PyDict::new(py);
+    capabilities.set_item("version", env!("CARGO_PKG_VERSION"))?;
+    capabilities.set_item("actions", ACTIONS)?;
+    capabilities.set_item("modes", MODES)?;
+    capabilities.set_item("coercions", COERCIONS)?;
+    capabilities.set_item("stamp_modes", STAMP_MODES)?;
+    capabilities.set_item("sheet_conflicts", SHEET_CONFLICTS)?;
+    capabilities.set_item("data_formats", DATA_FORMATS)?;
+    capabilities.set_item("file_formats", FILE_FORMATS)?;
+    capabilities.set_item("features", compiled(FEATURES))?;
+    capabilities.set_item("cargo_features", compiled(CARGO_FEATURES))?;
+    Ok(capabilities.into())
+}
@@ -0,0 +1,362 @@
+use env_logger::Logger;
+use log::{info, Level, LevelFilter, Log, Metadata, Record};
+use once_cell::sync::Lazy;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// A log record captured while a capture is active
+#[derive(Debug, Clone)]
+pub struct CapturedRecord {
+    level: Level,
+    target: String,
+    message: String,
+}
+
+impl IntoPy<PyObject> for CapturedRecord {
+    fn into_py(self, py: Python) -> PyObject {
+        let record = PyDict::new(py);
+        // Set items can only fail on unhashable keys, which string keys are not
+        let _ = record.set_item("level", self.level.as_str());
+        let _ = record.set_item("target", self.target);
+        let _ = record.set_item("message", self.message);
+        record.into()
+    }
+}
+
+/// Active captures, by id, with the level they capture and the records captured so far
+type Captures = HashMap<u64, (LevelFilter, Vec<CapturedRecord>)>;
+
+static ACTIVE_CAPTURES: Lazy<Mutex<Captures>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Source of the capture ids
+static NEXT_CAPTURE_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Level of the output, restored when no capture is active anymore
+static OUTPUT_LEVEL: Lazy<Mutex<LevelFilter>> = Lazy::new(|| Mutex::new(LevelFilter::Error));
+
+/// Name of the logger of the Python logging module the records are forwarded to
+pub const PYTHON_LOGGER: &str = "ez_excel_mgt";
+
+/// Where the records are written, besides the active captures
+pub enum Output {
+    /// env_logger, writing to stderr the records of the output level when RUST_LOG is set
+    Stderr(Logger),
+    /// The Python logging module, whose levels and handlers decide what is written
+    Python,
+}
+
+/// Logger writing through env_logger or the Python logging module and copying the records to the active captures
+pub struct CapturingLogger {
+    output: Output,
+}
+
+/// Whether the records are forwarded to the Python logging module rather than written to stderr
+static FORWARDED_TO_PYTHON: AtomicBool = AtomicBool::new(false);
+
+/// Most verbose level handled by the Python logger of each log target, read the first time the target logs
+///
+/// The levels are read again by refresh_python_levels. The GIL is never taken while the levels are locked.
+static PYTHON_LEVELS: Lazy<Mutex<HashMap<String, LevelFilter>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+impl CapturingLogger {
+    /// Installs the logger, writing to stderr the records of `level`, which set_log_level changes later
+    ///
+    /// Records forwarded to Python are filtered at the levels of the Python loggers, none reaching the
+    /// logger until refresh_python_levels reads them.
+    pub fn init(output: Output, level: LevelFilter) -> Result<(), log::SetLoggerError> {
+        let level = match &output {
+            Output::Stderr(_) => level,
+            Output::Python => LevelFilter::Off,
+        };
+        let forwarded = matches!(output, Output::Python);
+        log::set_boxed_logger(Box::new(CapturingLogger { output }))?;
+        FORWARDED_TO_PYTHON.store(forwarded, Ordering::Relaxed);
+        *OUTPUT_LEVEL.lock().unwrap() = level;
+        log::set_max_level(level);
+        Ok(())
+    }
+}
+
+/// Python logger of a log target, a child of "ez_excel_mgt" (e.g. "ez_excel_mgt.template")
+fn python_logger<'py>(py: Python<'py>, target: &str) -> PyResult<&'py PyAny> {
+    let name = target.replace("::", ".");
+    let name = if name.starts_with(PYTHON_LOGGER) { name } else { format!("{}.{}", PYTHON_LOGGER, name) };
+    py.import("logging")?.call_method1("getLogger", (name,))
+}
+
+/// Most verbose level the Python logger of a target handles, those that cannot tell handling none
+fn python_logger_level(py: Python, target: &str) -> LevelFilter {
+    let logger = match python_logger(py, target) {
+        Ok(logger) => logger,
+        Err(_) => return LevelFilter::Off,
+    };
+    [Level::Trace, Level::Debug, Level::Info, Level::Warn, Level::Error].iter()
+        .find(|level| {
+            logger.call_method1("isEnabledFor", (python_level(**level),)).and_then(|enabled| enabled.is_true()).unwrap_or(false)
+        })
+        .map_or(LevelFilter::Off, |level| level.to_level_filter())
+}
+
+/// Level handled for a target, as cached in PYTHON_LEVELS
+fn python_target_level(target: &str) -> LevelFilter {
+    if let Some(level) = PYTHON_LEVELS.lock().unwrap().get(target) {
+        return *level;
+    }
+    let level = Python::with_gil(|py| python_logger_level(py, target));
+    PYTHON_LEVELS.lock().unwrap().insert(target.to_string(), level);
+    level
+}
+
+/// Reads the levels of the Python loggers again, the global log level following the most verbose of them
+///
+/// The loggers below "ez_excel_mgt" that the application created may handle more than it does.
+pub fn refresh_python_levels(py: Python) -> PyResult<()> {
+    let names: Vec<String> = py.import("logging")?.getattr("root")?.getattr("manager")?.getattr("loggerDict")?.extract::<HashMap<String, &PyAny>>()?
+        .into_keys()
+        .filter(|name| name.starts_with(&format!("{}.", PYTHON_LOGGER)))
+        .collect();
+    let level = names.iter().map(|name| python_logger_level(py, name)).fold(python_logger_level(py, PYTHON_LOGGER), Ord::max);
+    PYTHON_LEVELS.lock().unwrap().clear();
+    *OUTPUT_LEVEL.lock().unwrap() = level;
+    update_max_level(&ACTIVE_CAPTURES.lock().unwrap());
+    Ok(())
+}
+
+/// Forwards a record to the Python logger of its target
+fn forward_to_python(record: &Record) {
+    Python::with_gil(|py| {
+        let level = python_level(record.level());
+        let forwarded = python_logger(py, record.target()).and_then(|logger| logger.call_method1("log", (level, record.args().to_string()))).map(|_| ());
+        // A failing handler does not fail the operation logging, its error is printed as logging does
+        if let Err(e) = forwarded {
+            e.print(py);
+        }
+    })
+}
+
+impl Log for CapturingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let output_enabled = match &self.output {
+            Output::Stderr(_) => metadata.level() <= *OUTPUT_LEVEL.lock().unwrap(),
+            Output::Python => metadata.level() <= python_target_level(metadata.target()),
+        };
+        output_enabled || !ACTIVE_CAPTURES.lock().unwrap().is_empty()
+    }
+
+    fn log(&self, record: &Record) {
+        match &self.output {
+            Output::Stderr(inner) if record.level() <= *OUTPUT_LEVEL.lock().unwrap() => inner.log(record),
+            Output::Stderr(_) => {},
+            Output::Python if record.level() <= python_target_level(record.target()) => forward_to_python(record),
+            Output::Python => {},
+        }
+
+        let mut captures = ACTIVE_CAPTURES.lock().unwrap();
+        for (level, records) in captures.values_mut() {
+            if record.level() <= *level {
+                records.push(CapturedRecord {
+                    level: record.level(),
+                    target: record.target().to_string(),
+                    message: record.args().to_string(),
+                });
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Output::Stderr(inner) = &self.output {
+            inner.flush();
+        }
+    }
+}
+
+/// Raises the global log level enough for all the active captures, or restores the output level
+fn update_max_level(captures: &Captures) {
+    let output_level = *OUTPUT_LEVEL.lock().unwrap();
+    let level = captures.values().map(|(level, _)| *level).fold(output_level, Ord::max);
+    log::set_max_level(level);
+}
+
+/// Converts a log level into the matching level of the Python logging module
+fn python_level(level: Level) -> u32 {
+    match level {
+        Level::Error => 40,
+        Level::Warn => 30,
+        Level::Info => 20,
+        Level::Debug => 10,
+        Level::Trace => 5,
+    }
+}
+
+/// Parses a level given from Python, as "error", "warn", "info", "debug", "trace" or "off"
+fn parse_level(level: &str) -> PyResult<LevelFilter> {
+    level.parse::<LevelFilter>().map_err(|_| {
+        PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "Invalid log level: {}. Use 'error', 'warn', 'info', 'debug', 'trace' or 'off'.", level
+        ))
+    })
+}
+
+/// Sets the level of the records written, to stderr or to the "ez_excel_mgt" logger of Python
+///
+/// With the records forwarded to Python, this is the level of the "ez_excel_mgt" logger, whose handlers
+/// still decide what is shown. Otherwise it replaces the level given by RUST_LOG.
+fn apply_level(py: Python, level: LevelFilter) -> PyResult<()> {
+    if FORWARDED_TO_PYTHON.load(Ordering::Relaxed) {
+        let python_level = level.to_level().map_or(100, python_level);
+        python_logger(py, PYTHON_LOGGER)?.call_method1("setLevel", (python_level,))?;
+        refresh_python_levels(py)?;
+    } else {
+        *OUTPUT_LEVEL.lock().unwrap() = level;
+        update_max_level(&ACTIVE_CAPTURES.lock().unwrap());
+    }
+    Ok(())
+}
+
+/// Level of the records written, as restored after a verbose operation
+enum SavedLevel {
+    Stderr(LevelFilter),
+    Python(PyObject),
+}
+
+/// Writes the debug records of an operation while alive, for the `verbose` option, then restores the level
+pub struct Verbose(Option<SavedLevel>);
+
+impl Verbose {
+    /// Raises the level to debug if `verbose` is true, and does nothing otherwise
+    pub fn new(py: Python, verbose: Option<bool>) -> PyResult<Self> {
+        if !verbose.unwrap_or(false) {
+            return Ok(Verbose(None));
+        }
+        let saved = if FORWARDED_TO_PYTHON.load(Ordering::Relaxed) {
+            SavedLevel::Python(python_logger(py, PYTHON_LOGGER)?.getattr("level")?.into())
+        } else {
+            SavedLevel::Stderr(*OUTPUT_LEVEL.lock().unwrap())
+        };
+        // A level already more verbose than debug is kept
+        let level = match &saved {
+            SavedLevel::Stderr(level) => LevelFilter::Debug.max(*level),
+            SavedLevel::Python(_) => LevelFilter::Debug,
+        };
+        apply_level(py, level)?;
+        Ok(Verbose(Some(saved)))
+    }
+}
+
+impl Drop for Verbose {
+    fn drop(&mut self) {
+        match self.0.take() {
+            Some(SavedLevel::Stderr(level)) => {
+                *OUTPUT_LEVEL.lock().unwrap() = level;
+                update_max_level(&ACTIVE_CAPTURES.lock().unwrap());
+            },
+            Some(SavedLevel::Python(level)) => Python::with_gil(|py| {
+                let restored = python_logger(py, PYTHON_LOGGER).and_then(|logger| logger.call_method1("setLevel", (level,)))
+                    .and_then(|_| refresh_python_levels(py));
+                if let Err(e) = restored {
+                    e.print(py);
+                }
+            }),
+            None => {},
+        }
+    }
+}
+
+/// Captures the Rust log records of the operations run in a `with` block
+///
+/// ```python
+/// with capture_log() as log:
+///     template.fill_with(df)
+/// print(log.records)
+/// ```
+#[pyclass]
+pub struct LogCapture {
+    id: u64,
+    level: LevelFilter,
+    records: Vec<CapturedRecord>,
+}
+
+#[pymethods]
+impl LogCapture {
+    #[new]
+    pub fn new(level: Option<&str>) -> PyResult<Self> {
+        let level = match level {
+            Some(level) => parse_level(level)?,
+            None => LevelFilter::Debug,
+        };
+        Ok(LogCapture { id: NEXT_CAPTURE_ID.fetch_add(1, Ordering::Relaxed), level, records: Vec::new() })
+    }
+
+    pub fn __enter__(slf: PyRefMut<Self>) -> PyRefMut<Self> {
+        let mut captures = ACTIVE_CAPTURES.lock().unwrap();
+        captures.insert(slf.id, (slf.level, Vec::new()));
+        update_max_level(&captures);
+        slf
+    }
+
+    pub fn __exit__(&mut self, _exc_type: &PyAny, _exc_value: &PyAny, _traceback: &PyAny) -> bool {
+        let mut captures = ACTIVE_CAPTURES.lock().unwrap();
+        if let Some((_, records)) = captures.remove(&self.id) {
+            self.records.extend(records);
+        }
+        update_max_level(&captures);
+        false
+    }
+
+    /// The captured records, as dictionaries with 'level', 'target' and 'message'
+    #[getter]
+    pub fn records(&self) -> Vec<CapturedRecord> {
+        let captures = ACTIVE_CAPTURES.lock().unwrap();
+        match captures.get(&self.id) {
+            Some((_, records)) => self.records.iter().chain(records.iter()).cloned().collect(),
+            None => self.records.clone(),
+        }
+    }
+
+    /// The captured records as text, one line per record
+    pub fn text(&self) -> String {
+        self.records().iter()
+            .map(|r| format!("[RUST:{}] - {}", r.level, r.message))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Replays the captured records through a logger of the Python logging module (default "ez_excel_mgt")
+    pub fn replay(&self, py: Python, logger: Option<&str>) -> PyResult<()> {
+        let logger = py.import("logging")?.call_method1("getLogger", (logger.unwrap_or("ez_excel_mgt"),))?;
+        for record in self.records() {
+            logger.call_method1("log", (python_level(record.level), record.message))?;
+        }
+        Ok(())
+    }
+
+    pub fn __len__(&self) -> usize {
+        self.records().len()
+    }
+}
+
+/// Starts capturing the Rust log records, at the given level (default "debug"), for use in a `with` block
+#[pyfunction]
+pub fn capture_log(level: Option<&str>) -> PyResult<LogCapture> {
+    LogCapture::new(level)
+}
+
+/// Reads the levels of the Python loggers of "ez_excel_mgt" again, after they were changed from Python
+///
+/// The levels are read once and cached, as set_log_level sets them: records of a level the loggers
+/// were not handling then are not forwarded until the levels are read again.
+#[pyfunction]
+pub fn refresh_log_levels(py: Python) -> PyResult<()> {
+    refresh_python_levels(py)
+}
+
+/// Sets the level of the log records written at runtime, e.g. set_log_level("debug")
+#[pyfunction]
+pub fn set_log_level(py: Python, level: &str) -> PyResult<()> {
+    apply_level(py, parse_level(level)?)?;
+    info!("Log level set to {}", level);
+    Ok(())
+}
@@ -0,0 +1,227 @@
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use umya_spreadsheet::*;
+use polars::prelude::*;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use log::*;
+
+use crate::cache;
+use crate::structs::ExcelCell;
+use crate::utils::extract::{extract_table, raw_values_to_series};
+use crate::utils::guard::catch_panic;
+use crate::utils::parallel::read_files;
+use crate::utils::rs2py::rust_polars_df_to_py_polars_df;
+use crate::utils::sheets::parse_sheets;
+
+/// Expected layout of the workbooks returned from a distributed template
+#[derive(Debug, Clone)]
+pub struct CollectSpec {
+    sheet: String,
+    header: ExcelCell,
+    columns: Option<Vec<String>>,
+    named_cells: Vec<(String, ExcelCell)>,
+    strict: bool,
+    source_column: Option<String>,
+}
+
+// Implement FromPyObject for CollectSpec from a dictionary
+impl<'source> pyo3::FromPyObject<'source> for CollectSpec {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        let dict: &PyDict = obj.downcast().map_err(|_| {
+            PyErr::new::<pyo3::exceptions::PyTypeError, _>("Template spec must be a dictionary.")
+        })?;
+
+        let sheet: String = dict.get_item("sheet")
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("Template spec must contain a 'sheet'."))?
+            .extract()?;
+        let header: ExcelCell = match dict.get_item("header") {
+            Some(header) => header.extract()?,
+            None => ExcelCell::default(),
+        };
+        let columns: Option<Vec<String>> = match dict.get_item("columns") {
+            Some(columns) => columns.extract()?,
+            None => None,
+        };
+        let named_cells: Vec<(String, ExcelCell)> = match dict.get_item("named_cells") {
+            Some(named_cells) => {
+                let named_cells: &PyDict = named_cells.downcast().map_err(|_| {
+                    PyErr::new::<pyo3::exceptions::PyTypeError, _>("Named cells must be a dictionary of names and cells.")
+                })?;
+                named_cells.iter()
+                    .map(|(name, cell)| Ok((name.extract()?, cell.extract()?)))
+                    .collect::<PyResult<_>>()?
+            },
+            None => Vec::new(),
+        };
+        let strict: bool = match dict.get_item("strict") {
+            Some(strict) => strict.extract()?,
+            None => false,
+        };
+        let source_column: Option<String> = match dict.get_item("source_column") {
+            Some(source_column) => source_column.extract()?,
+            None => Some("source_file".to_string()),
+        };
+
+        Ok(CollectSpec { sheet, header, columns, named_cells, strict, source_column })
+    }
+}
+
+/// Reads a returned workbook, only the collected sheet being parsed as the workbook is only read from
+///
+/// With the cache, the workbook is parsed whole and shared with it.
+fn read_returned(spec: &CollectSpec, file_path: &str) -> Result<Arc<Spreadsheet>, Vec<String>> {
+    if !Path::new(file_path).exists() {
+        return Err(vec![format!("File not found: {:?}", file_path)]);
+    }
+    if cache::is_enabled() {
+        return cache::load(file_path, || {
+            reader::xlsx::read(Path::new(file_path)).map_err(|e| vec![format!("Failed to read file: {:?}", e)])
+        });
+    }
+    let mut spreadsheet = reader::xlsx::lazy_read(Path::new(file_path))
+        .map_err(|e| vec![format!("Failed to read file: {:?}", e)])?;
+    parse_sheets(&mut spreadsheet, std::slice::from_ref(&spec.sheet));
+    Ok(Arc::new(spreadsheet))
+}
+
+/// Validates a returned workbook and extracts its data region and named cells
+fn collect_file(spec: &CollectSpec, file_path: &str, spreadsheet: &Spreadsheet, expected_columns: Option<&Vec<String>>) -> Result<DataFrame, Vec<String>> {
+    let worksheet = spreadsheet.get_sheet_by_name(&spec.sheet)
+        .ok_or_else(|| vec![format!("Sheet '{}' not found.", spec.sheet)])?;
+
+    let (header_col, header_row) = spec.header.idx();
+    let table = extract_table(worksheet, header_col, header_row, None, None).map_err(|e| vec![e])?;
+    let found: Vec<String> = table.get_column_names().iter().map(|s| s.to_string()).collect();
+
+    let mut errors = Vec::new();
+    let columns = match expected_columns {
+        Some(columns) => columns.clone(),
+        None => found.clone(),
+    };
+    for col_name in &columns {
+        if !found.contains(col_name) {
+            errors.push(format!("Header '{}' is missing in {}.", col_name, spec.sheet));
+        }
+    }
+    if spec.strict {
+        for col_name in &found {
+            if !columns.contains(col_name) {
+                errors.push(format!("Header '{}' in {} is not expected.", col_name, spec.sheet));
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut df = table.select(&columns).map_err(|e| vec![e.to_string()])?;
+    let height = df.height();
+    for (name, cell) in &spec.named_cells {
+        let value = worksheet.get_cell(cell.idx()).map_or(CellRawValue::Empty, |c| c.get_raw_value().clone());
+        debug!("Named cell {} at {} = {}", name, cell.range(), value);
+        let series = raw_values_to_series(name, &vec![value; height]);
+        df.with_column(series).map_err(|e| vec![e.to_string()])?;
+    }
+    if let Some(source_column) = &spec.source_column {
+        df.with_column(Series::new(source_column.into(), vec![file_path; height])).map_err(|e| vec![e.to_string()])?;
+    }
+    Ok(df)
+}
+
+/// Stacks DataFrames, falling back to strings when types differ between files
+///
+/// Columns come in the order they first appear, those missing from a DataFrame being null in its rows.
+pub fn stack(mut frames: Vec<DataFrame>) -> PolarsResult<DataFrame> {
+    if frames.is_empty() {
+        return Ok(DataFrame::default());
+    }
+
+    let mut names: Vec<String> = Vec::new();
+    let mut dtypes: HashMap<String, DataType> = HashMap::new();
+    for df in frames.iter() {
+        for series in df.get_columns() {
+            let name = series.name().to_string();
+            if !names.contains(&name) {
+                names.push(name.clone());
+            }
+            match dtypes.get(&name) {
+                Some(dtype) if dtype != series.dtype() && *series.dtype() != DataType::Null => {
+                    dtypes.insert(name, DataType::String);
+                },
+                None => {
+                    dtypes.insert(name, series.dtype().clone());
+                },
+                _ => {},
+            }
+        }
+    }
+
+    for df in frames.iter_mut() {
+        for name in &names {
+            let dtype = &dtypes[name];
+            if df.column(name).is_err() {
+                df.with_column(Series::full_null(name.into(), df.height(), dtype))?;
+            } else if df.column(name)?.dtype() != dtype {
+                let series = df.column(name)?.cast(dtype)?;
+                df.with_column(series)?;
+            }
+        }
+    }
+
+    let mut stacked = frames.remove(0);
+    for df in frames.iter() {
+        stacked.vstack_mut(&df.select(&names)?)?;
+    }
+    Ok(stacked)
+}
+
+/// Collects the data of workbooks returned from a distributed template.
+///
+/// Each file is validated against the expected layout (sheet, header location, columns) and its data
+/// region and named cells are extracted. Valid files are combined into one Polars DataFrame, with the
+/// source file in a `source_file` column. Files failing validation are reported, not combined.
+///
+/// :param template_spec: Dictionary with 'sheet', and optionally 'header' (default A1), 'columns'
+///     (default: the columns of the first valid file), 'named_cells' ({name: cell}), 'strict' and
+///     'source_column' (None to leave it out).
+/// :param files: The paths of the returned workbooks.
+/// :param max_workers: The number of workbooks read at once, in worker threads (default: one per core).
+/// :return: The combined DataFrame and a dictionary of validation errors per file.
+#[pyfunction]
+pub fn collect(py: Python, template_spec: CollectSpec, files: Vec<String>, max_workers: Option<usize>) -> PyResult<(PyObject, PyObject)> {
+    catch_panic("collect", || {
+        let errors = PyDict::new(py);
+        let mut frames: Vec<DataFrame> = Vec::new();
+        let mut expected_columns = template_spec.columns.clone();
+
+        // The workbooks are read in parallel, then validated in order as the first valid one gives the columns
+        let spreadsheets = read_files(py, &files, max_workers, |file_path| read_returned(&template_spec, file_path))?;
+        for (file_path, spreadsheet) in files.iter().zip(spreadsheets) {
+            let collected = spreadsheet.and_then(|spreadsheet| collect_file(&template_spec, file_path, &spreadsheet, expected_columns.as_ref()));
+            match collected {
+                Ok(df) => {
+                    debug!("{} rows collected from {}", df.height(), file_path);
+                    if expected_columns.is_none() {
+                        let named: Vec<&String> = template_spec.named_cells.iter().map(|(n, _)| n).collect();
+                        expected_columns = Some(df.get_column_names().iter()
+                            .map(|s| s.to_string())
+                            .filter(|s| !named.contains(&s) && Some(s) != template_spec.source_column.as_ref())
+                            .collect());
+                    }
+                    frames.push(df);
+                },
+                Err(file_errors) => {
+                    warn!("{} rejected: {:?}", file_path, file_errors);
+                    errors.set_item(file_path, file_errors)?;
+                },
+            }
+        }
+
+        let mut df = stack(frames).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to combine collected data: {}.", e))
+        })?;
+        Ok((rust_polars_df_to_py_polars_df(py, &mut df)?, errors.into()))
+    })
+}
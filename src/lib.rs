@@ -1,3 +1,4 @@
+
 use env_logger::Builder;
 #[allow(unused_imports)]
 use log::{debug, info, warn, LevelFilter};
@@ -8,37 +9,76 @@ use std::io::Write;
 mod utils;
 mod structs;
 mod template;
+#[cfg(feature = "dataframe")]
+mod collect;
+mod cache;
+mod capabilities;
+mod capture;
 
 
 /// Python module initialization function.
 #[pymodule]
-fn ez_excel_mgt(_py: Python, m: &PyModule) -> PyResult<()> {
+fn ez_excel_mgt(py: Python, m: &PyModule) -> PyResult<()> {
     // Initialize logger only once
-    init_logging();
+    init_logging(py)?;
 
     m.add_class::<template::ExcelTemplate>()?;
+    m.add_class::<template::Transaction>()?;
+    #[cfg(feature = "dataframe")]
+    m.add_function(wrap_pyfunction!(collect::collect, m)?)?;
+    #[cfg(not(feature = "dataframe"))]
+    m.add_function(wrap_pyfunction!(utils::features::collect, m)?)?;
+    m.add_function(wrap_pyfunction!(capabilities::capabilities, m)?)?;
+    m.add_class::<capture::LogCapture>()?;
+    m.add_function(wrap_pyfunction!(capture::capture_log, m)?)?;
+    m.add_function(wrap_pyfunction!(capture::set_log_level, m)?)?;
+    m.add_function(wrap_pyfunction!(capture::refresh_log_levels, m)?)?;
+    m.add_function(wrap_pyfunction!(cache::set_workbook_cache, m)?)?;
+    m.add_function(wrap_pyfunction!(cache::clear_workbook_cache, m)?)?;
+    m.add_function(wrap_pyfunction!(cache::workbook_cache_info, m)?)?;
+    m.add("DataLossWarning", py.get_type::<utils::warnings::DataLossWarning>())?;
+    m.add("TemplateError", py.get_type::<utils::errors::TemplateError>())?;
+    m.add("SheetNotFoundError", py.get_type::<utils::errors::SheetNotFoundError>())?;
+    m.add("ColumnMismatchError", py.get_type::<utils::errors::ColumnMismatchError>())?;
+    m.add("RangeError", py.get_type::<utils::errors::RangeError>())?;
 
     Ok(())
 }
 
 /// Initialize logging for the module.
 ///
-/// This function initializes logging by checking the environment variables `LOGLEVEL` and `RUST_LOG`.
-/// If neither is set, it defaults to the "error" log level.
-fn init_logging() {
-    // First check `LOGLEVEL`, then fallback to `RUST_LOG`, or default to "error"
-    let log_env = env::var("RUST_LOG").unwrap_or_else(|_| "error".to_string());
-
-    let log_level = log_env.parse::<LevelFilter>().unwrap_or_else(|_| {
-        warn!("Invalid log level: {}. Defaulting to 'error'.", log_env);
-        LevelFilter::Error
-    });
-
-    // Directly initialize env_logger with the log level we determined
-    Builder::new()
-        .filter_level(log_level)
-        .format(|buf, record| writeln!(buf, "[RUST:{}] - {}", record.level(), record.args()))
-        .init();
-
-    info!("Logging initialized with level: {}", log_level);
-}
\ No newline at end of file
+/// With the `RUST_LOG` environment variable set, records of its level are written to stderr by env_logger.
+/// Otherwise they are forwarded to the "ez_excel_mgt" logger of the Python logging module, whose levels
+/// and handlers decide what is written; a NullHandler keeps them quiet until logging is configured.
+/// `set_log_level` changes the level later on. A module initialized again (e.g. reloaded) keeps its logger.
+fn init_logging(py: Python) -> PyResult<()> {
+    let (output, log_level) = match env::var("RUST_LOG") {
+        Ok(log_env) => {
+            let log_level = log_env.parse::<LevelFilter>().unwrap_or_else(|_| {
+                warn!("Invalid log level: {}. Defaulting to 'error'.", log_env);
+                LevelFilter::Error
+            });
+            // Initialize env_logger writing all records, wrapped to filter them at the level we determined and capture them
+            let logger = Builder::new()
+                .filter_level(LevelFilter::Trace)
+                .format(|buf, record| writeln!(buf, "[RUST:{}] - {}", record.level(), record.args()))
+                .build();
+            (capture::Output::Stderr(logger), log_level)
+        },
+        Err(_) => (capture::Output::Python, LevelFilter::Trace),
+    };
+    let forwarded = matches!(output, capture::Output::Python);
+    if capture::CapturingLogger::init(output, log_level).is_err() {
+        debug!("Logging already initialized");
+        return Ok(());
+    }
+    if forwarded {
+        let logging = py.import("logging")?;
+        let null_handler = logging.getattr("NullHandler")?.call0()?;
+        logging.call_method1("getLogger", (capture::PYTHON_LOGGER,))?.call_method1("addHandler", (null_handler,))?;
+        capture::refresh_python_levels(py)?;
+    }
+
+    info!("Logging initialized, level: {}", log::max_level());
+    Ok(())
+}
@@ -2,9 +2,14 @@
 use log::{debug, info, warn};
 use pyo3::prelude::*;
 
-use crate::utils::excel::{excel_to_index, index_to_excel, index_to_excel_col};
+use crate::utils::errors::{range_error, sheet_not_found, RangeError};
+use crate::utils::excel::{excel_col_to_index, excel_to_index, index_to_excel, index_to_excel_col, is_defined_name, parse_excel_cell, EXCEL_MAX_COLUMNS, EXCEL_MAX_ROWS};
 use crate::structs::Mode;
-use umya_spreadsheet::structs::Worksheet;
+use crate::utils::select::select_names;
+#[cfg(any(feature = "dataframe", feature = "aggregate"))]
+use crate::utils::select::{glob_files, is_glob};
+use umya_spreadsheet::structs::{DefinedName, Worksheet};
+use umya_spreadsheet::Spreadsheet;
 
 #[derive(Debug, Clone)]
 pub enum ExcelCell {
@@ -16,9 +21,14 @@ pub enum ExcelCell {
 impl<'source> pyo3::FromPyObject<'source> for ExcelCell {
     fn extract(obj: &'source PyAny) -> PyResult<Self> {
         if let Ok(tuple) = obj.extract::<(u32, u32)>() {
+            if tuple.0 == 0 || tuple.1 == 0 {
+                return Err(range_error(format!("Invalid cell {:?}. Row and column numbering starts at 1.", tuple), None, None));
+            }
             Ok(ExcelCell::Tuple(tuple))
         } else if let Ok(string) = obj.extract::<String>() {
-            Ok(ExcelCell::String(string))
+            // Validated and normalized (e.g. "$b$5" to "B5") so that idx cannot fail
+            let (col, row) = parse_excel_cell(&string).map_err(|e| range_error(e, None, Some(&string)))?;
+            Ok(ExcelCell::String(index_to_excel(col, row)))
         } else {
             Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
                 "Invalid input type. Expected a tuple of 2 tuples or a string.",
@@ -52,19 +62,148 @@ impl Default for ExcelCell {
 }
 
 
+#[derive(Debug, Clone)]
+pub enum ExcelPosition {
+    Index(u32), // 1-based column (or row) number
+    Letter(String),
+}
+
+// Implement FromPyObject for ExcelPosition from a number or column letters
+impl<'source> pyo3::FromPyObject<'source> for ExcelPosition {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        if let Ok(index) = obj.extract::<u32>() {
+            if index > 0 {
+                return Ok(ExcelPosition::Index(index));
+            }
+        } else if let Ok(string) = obj.extract::<String>() {
+            let string = string.trim().to_uppercase();
+            if !string.is_empty() && string.chars().all(|c| c.is_ascii_digit()) && string != "0" {
+                return Ok(ExcelPosition::Index(string.parse().unwrap_or(1)));
+            } else if !string.is_empty() && string.chars().all(|c| c.is_ascii_uppercase()) {
+                return Ok(ExcelPosition::Letter(string));
+            }
+        }
+        Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "Invalid position. Expected column letters (e.g. 'F') or a 1-based number.",
+        ))
+    }
+}
+
+impl ExcelPosition {
+    pub fn idx(&self) -> u32 {
+        match self {
+            ExcelPosition::Index(i) => *i,
+            ExcelPosition::Letter(s) => excel_col_to_index(s),
+        }
+    }
+}
+
+
 #[derive(Debug, Clone)]
 pub enum ExcelRange {
     Range(((u32, u32), (u32, u32))), // (row, col)
     String(String),
+    // Sheet-qualified, whole column or row, or open-ended range: first and last (col, row), 0 for an open end
+    Reference { sheet: Option<String>, first: (u32, u32), last: (u32, u32) },
+    // Name defined in the workbook ("SalesData"), to resolve against the workbook before use
+    Name(String),
+}
+
+/// Parses a side of a range: a cell ("A2"), a column ("A") or a row ("2"), as (col, row) with 0 for the open part
+fn parse_range_side(side: &str) -> Result<(u32, u32), String> {
+    let side = side.trim().replace('$', "").to_uppercase();
+    if !side.is_empty() && side.len() <= 3 && side.chars().all(|c| c.is_ascii_alphabetic()) {
+        return match excel_col_to_index(&side) {
+            col if col <= EXCEL_MAX_COLUMNS => Ok((col, 0)),
+            _ => Err(format!("Invalid column {:?}. The last column of Excel is {}.", side, index_to_excel_col(EXCEL_MAX_COLUMNS))),
+        };
+    }
+    if !side.is_empty() && side.len() <= 7 && side.chars().all(|c| c.is_ascii_digit()) {
+        return match side.parse::<u32>() {
+            Ok(row) if row > 0 => Ok((0, row)),
+            _ => Err(format!("Invalid row {:?}. Row numbering starts at 1.", side)),
+        };
+    }
+    parse_excel_cell(&side)
+}
+
+/// Splits the sheet qualifier off a reference ("Data!A1:B2", "'My data'!A:A")
+fn split_sheet(reference: &str) -> (Option<String>, &str) {
+    match reference.rsplit_once('!') {
+        Some((sheet, range)) => {
+            let sheet = sheet.trim();
+            let sheet = match sheet.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')) {
+                Some(quoted) => quoted.replace("''", "'"),
+                None => sheet.to_string(),
+            };
+            (Some(sheet), range)
+        },
+        None => (None, reference),
+    }
+}
+
+/// Parses a range of the A1 notation, optionally sheet-qualified, with whole columns ("A:C"), whole rows ("2:5")
+/// and open-ended ranges ("A2:A", to the last used row)
+fn parse_range(string: &str) -> Result<ExcelRange, String> {
+    let (sheet, reference) = split_sheet(string);
+    let invalid = || format!("Invalid range {:?}. Expected e.g. A1:B2, Data!A1:B2, A:C, 2:5 or A2:A.", string);
+    let mut sides = reference.split(':');
+    let (first, last) = match (sides.next(), sides.next(), sides.next()) {
+        (Some(first), None, None) => {
+            let first = parse_excel_cell(first)?;
+            (first, first)
+        },
+        (Some(first), Some(last), None) => (parse_range_side(first)?, parse_range_side(last)?),
+        _ => return Err(invalid()),
+    };
+    let closed = first.0 > 0 && first.1 > 0 && last.0 > 0 && last.1 > 0;
+    // Columns pair with columns, rows with rows, and a cell with a cell or the column or row it starts
+    let valid = closed
+        || (first.1 == 0 && last.1 == 0)
+        || (first.0 == 0 && last.0 == 0)
+        || (first.0 > 0 && first.1 > 0 && ((last.1 == 0 && last.0 >= first.0) || (last.0 == 0 && last.1 >= first.1)));
+    if !valid {
+        return Err(invalid());
+    }
+    if closed && sheet.is_none() {
+        return Ok(ExcelRange::String(format!("{}:{}", index_to_excel(first.0, first.1), index_to_excel(last.0, last.1))));
+    }
+    Ok(ExcelRange::Reference { sheet, first, last })
+}
+
+/// Finds the address of a defined name, the names scoped to the given sheet taking precedence over the
+/// names of the workbook. Names are case-insensitive, as in Excel.
+fn find_defined_name(spreadsheet: &Spreadsheet, name: &str, sheet_name: Option<&str>) -> Option<String> {
+    let matches = |defined_name: &&DefinedName| defined_name.get_name().eq_ignore_ascii_case(name);
+    let sheets = spreadsheet.get_sheet_collection_no_check();
+    let local = sheet_name
+        .and_then(|sheet_name| sheets.iter().position(|ws| ws.get_name() == sheet_name))
+        .and_then(|index| sheets[index].get_defined_names().iter()
+            .filter(|defined_name| defined_name.has_local_sheet_id() && *defined_name.get_local_sheet_id() == index as u32)
+            .find(matches));
+    // The names of the workbook referring to a sheet are read into that sheet, without a local sheet id
+    local
+        .or_else(|| spreadsheet.get_defined_names().iter()
+            .chain(sheets.iter().flat_map(|ws| ws.get_defined_names().iter()))
+            .filter(|defined_name| !defined_name.has_local_sheet_id())
+            .find(matches))
+        .map(|defined_name| defined_name.get_address())
 }
 
 // Implement FromPyObject for SourceRange
 impl<'source> pyo3::FromPyObject<'source> for ExcelRange {
     fn extract(obj: &'source PyAny) -> PyResult<Self> {
         if let Ok(tuple) = obj.extract::<((u32, u32), (u32, u32))>() {
+            if tuple.0.0 == 0 || tuple.0.1 == 0 || tuple.1.0 == 0 || tuple.1.1 == 0 {
+                return Err(range_error(format!("Invalid range {:?}. Row and column numbering starts at 1.", tuple), None, None));
+            }
             Ok(ExcelRange::Range(tuple))
         } else if let Ok(string) = obj.extract::<String>() {
-            Ok(ExcelRange::String(string))
+            // Validated and normalized (e.g. "b1" to "B1:B1") so that idx cannot fail
+            parse_range(&string).or_else(|e| match is_defined_name(&string) {
+                true => Ok(ExcelRange::Name(string.trim().to_string())),
+                false => Err(range_error(e, None, Some(&string))),
+            })
         } else {
             Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
                 "Invalid input type. Expected a tuple of 2 tuples or a string.",
@@ -76,20 +215,116 @@ impl<'source> pyo3::FromPyObject<'source> for ExcelRange {
 // Implement conversion from string to tuple of tuple
 // The should define a range as in Excel A1:B2
 impl ExcelRange {
+    /// Returns the top left and bottom right cells as (col, row), whatever the order of the corners
+    ///
+    /// Open ends stretch to the limits of Excel, use idx_in to stop them at the used range of a sheet.
     pub fn idx(&self) -> ((u32, u32), (u32, u32)) {
-        match self {
+        self.bounds((EXCEL_MAX_COLUMNS, EXCEL_MAX_ROWS))
+    }
+
+    /// Returns the top left and bottom right cells as (col, row), open ends stopping at the used range of the sheet
+    pub fn idx_in(&self, worksheet: &Worksheet) -> ((u32, u32), (u32, u32)) {
+        self.bounds(worksheet.get_highest_column_and_row())
+    }
+
+    /// Returns the corners with the open ends stopping at the given last (col, row), never before the first cell
+    pub fn bounds(&self, highest: (u32, u32)) -> ((u32, u32), (u32, u32)) {
+        let ((col1, row1), (col2, row2)) = match self {
             ExcelRange::Range(r) => ((r.0.1, r.0.0), (r.1.1, r.1.0)),
             ExcelRange::String(s) => {
-                let (col1, row1) = excel_to_index(s.split(':').next().unwrap());
-                let (col2, row2) = excel_to_index(s.split(':').nth(1).unwrap());
-                ((col1, row1), (col2, row2))
-            }
+                let mut cells = s.split(':').map(excel_to_index);
+                let first = cells.next().unwrap_or((0, 0));
+                (first, cells.next().unwrap_or(first))
+            },
+            ExcelRange::Reference { first, last, .. } => {
+                let (first_col, first_row) = (first.0.max(1), first.1.max(1));
+                let last_col = if last.0 == 0 { highest.0.max(first_col) } else { last.0 };
+                let last_row = if last.1 == 0 { highest.1.max(first_row) } else { last.1 };
+                ((first_col, first_row), (last_col, last_row))
+            },
+            // Names are resolved before use, an unresolved one covering A1 only
+            ExcelRange::Name(_) => ((1, 1), (1, 1)),
+        };
+        ((col1.min(col2), row1.min(row2)), (col1.max(col2), row1.max(row2)))
+    }
+
+    /// Returns the sheet the range is qualified with, if any
+    pub fn sheet(&self) -> Option<&str> {
+        match self {
+            ExcelRange::Reference { sheet, .. } => sheet.as_deref(),
+            _ => None,
         }
     }
+
+    /// Resolves a defined name to the range it refers to, qualified with its sheet, other ranges being kept
+    ///
+    /// Names scoped to `sheet_name` take precedence over the names of the workbook.
+    pub fn resolve(&self, spreadsheet: &Spreadsheet, sheet_name: Option<&str>) -> PyResult<ExcelRange> {
+        let name = match self {
+            ExcelRange::Name(name) => name,
+            range => return Ok(range.clone()),
+        };
+        let address = find_defined_name(spreadsheet, name, sheet_name).ok_or_else(|| {
+            range_error(format!("Name '{}' is not defined in the workbook.", name), sheet_name, Some(name))
+        })?;
+        let range = parse_range(&address).ok().filter(|range| range.sheet().is_some()).ok_or_else(|| {
+            range_error(format!("Name '{}' refers to {:?}, not to a range of a sheet.", name, address), sheet_name, Some(name))
+        })?;
+        debug!("Name {} resolved to {}", name, range.range());
+        Ok(range)
+    }
+
+    /// Returns the address of the range on the given sheet, with absolute references (e.g. Data!$A$1:$B$2)
+    ///
+    /// The sheet qualifier of the range, if any, takes precedence over `sheet_name`.
+    pub fn address(&self, sheet_name: &str) -> String {
+        let ends = match self {
+            ExcelRange::Reference { first, last, .. } => (*first, *last),
+            range => range.idx(),
+        };
+        let side = |(col, row): (u32, u32)| match (col, row) {
+            (0, row) => format!("${}", row),
+            (col, 0) => format!("${}", index_to_excel_col(col)),
+            (col, row) => format!("${}${}", index_to_excel_col(col), row),
+        };
+        let sheet = self.sheet().unwrap_or(sheet_name);
+        let sheet = match sheet.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            true => sheet.to_string(),
+            false => format!("'{}'", sheet.replace('\'', "''")),
+        };
+        format!("{}!{}:{}", sheet, side(ends.0), side(ends.1))
+    }
+
+    /// Checks that a sheet qualifier of the range, if any, names the given sheet
+    pub fn check_sheet(&self, sheet_name: &str) -> PyResult<()> {
+        match self.sheet() {
+            Some(sheet) if sheet != sheet_name => Err(range_error(
+                format!("Range {} refers to sheet '{}' where sheet '{}' is used.", self.range(), sheet, sheet_name),
+                Some(sheet_name),
+                Some(&self.range()),
+            )),
+            _ => Ok(()),
+        }
+    }
+
     pub fn range(&self) -> String {
         match self {
             ExcelRange::Range(r) => format!("{}:{}", index_to_excel(r.0.1, r.0.0), index_to_excel(r.1.1, r.1.0)),
             ExcelRange::String(s) => s.clone(),
+            ExcelRange::Reference { sheet, first, last } => {
+                let side = |(col, row): (u32, u32)| match (col, row) {
+                    (0, row) => row.to_string(),
+                    (col, 0) => index_to_excel_col(col),
+                    (col, row) => index_to_excel(col, row),
+                };
+                let range = format!("{}:{}", side(*first), side(*last));
+                match sheet {
+                    Some(sheet) if sheet.chars().all(|c| c.is_alphanumeric() || c == '_') => format!("{}!{}", sheet, range),
+                    Some(sheet) => format!("'{}'!{}", sheet.replace('\'', "''"), range),
+                    None => range,
+                }
+            },
+            ExcelRange::Name(name) => name.clone(),
         }
     }
 }
@@ -106,7 +341,8 @@ pub enum ExcelHeader {
 // Implement FromPyObject for SourceRange
 impl<'source> pyo3::FromPyObject<'source> for ExcelHeader {
     fn extract(obj: &'source PyAny) -> PyResult<Self> {
-        if let Ok(range) = obj.extract::<ExcelRange>() {
+        // Names, "first" and "last" included, are not header locations
+        if let Some(range) = obj.extract::<ExcelRange>().ok().filter(|range| !matches!(range, ExcelRange::Name(_))) {
             if range.idx().0.1 == range.idx().1.1 || range.idx().0.0 == range.idx().1.0 {
                 Ok(ExcelHeader::ExcelRange(range))
             } else {
@@ -160,4 +396,242 @@ impl ExcelHeader {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// One or more sheet names or patterns ("Region_*", "re:Q[1-4]")
+#[derive(Debug, Clone)]
+pub struct SheetSelector(pub Vec<String>);
+
+// Implement FromPyObject for SheetSelector from a string or a list of strings
+impl<'source> pyo3::FromPyObject<'source> for SheetSelector {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        if let Ok(pattern) = obj.extract::<String>() {
+            Ok(SheetSelector(vec![pattern]))
+        } else if let Ok(patterns) = obj.extract::<Vec<String>>() {
+            Ok(SheetSelector(patterns))
+        } else {
+            Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Invalid sheet selection. Expected a sheet name, a pattern or a list of them.",
+            ))
+        }
+    }
+}
+
+impl SheetSelector {
+    /// Resolves the patterns against the sheet names, in the order of the patterns, each pattern having to match
+    pub fn resolve(&self, names: &[String]) -> PyResult<Vec<String>> {
+        let mut selected: Vec<String> = Vec::new();
+        for pattern in &self.0 {
+            let matched = select_names(names, pattern).map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+            if matched.is_empty() {
+                return Err(sheet_not_found(pattern));
+            }
+            for name in matched {
+                if !selected.contains(&name) {
+                    selected.push(name);
+                }
+            }
+        }
+        Ok(selected)
+    }
+
+    /// Resolves the patterns to exactly one sheet name
+    pub fn resolve_one(&self, names: &[String]) -> PyResult<String> {
+        let mut selected = self.resolve(names)?;
+        if selected.len() != 1 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Selection {:?} matches {} sheets ({:?}) where one is expected.", self.0, selected.len(), selected,
+            )));
+        }
+        Ok(selected.remove(0))
+    }
+}
+
+/// One or more workbook paths or glob patterns of file names ("returns/*.xlsx")
+#[cfg(any(feature = "dataframe", feature = "aggregate"))]
+#[derive(Debug, Clone)]
+pub struct FileSelector(pub Vec<String>);
+
+// Implement FromPyObject for FileSelector from a string or a list of strings
+#[cfg(any(feature = "dataframe", feature = "aggregate"))]
+impl<'source> pyo3::FromPyObject<'source> for FileSelector {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        if let Ok(pattern) = obj.extract::<String>() {
+            Ok(FileSelector(vec![pattern]))
+        } else if let Ok(patterns) = obj.extract::<Vec<String>>() {
+            Ok(FileSelector(patterns))
+        } else {
+            Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Invalid file selection. Expected a path, a glob pattern or a list of them.",
+            ))
+        }
+    }
+}
+
+#[cfg(any(feature = "dataframe", feature = "aggregate"))]
+impl FileSelector {
+    /// Resolves the patterns to the files they match, in the order of the patterns, each pattern having to match
+    ///
+    /// Paths without wildcards are kept as they are, missing files being reported when they are read.
+    pub fn resolve(&self) -> PyResult<Vec<String>> {
+        let mut selected: Vec<String> = Vec::new();
+        for pattern in &self.0 {
+            let matched = match is_glob(pattern) {
+                true => glob_files(pattern).map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?,
+                false => vec![pattern.clone()],
+            };
+            if matched.is_empty() {
+                return Err(PyErr::new::<pyo3::exceptions::PyFileNotFoundError, _>(format!("No file matches {:?}.", pattern)));
+            }
+            for file in matched {
+                if !selected.contains(&file) {
+                    selected.push(file);
+                }
+            }
+        }
+        Ok(selected)
+    }
+}
+
+/// Ranges of a copy: a range pasted at the current cell, or a list of (range, destination cell) pairs
+#[derive(Debug, Clone)]
+pub enum CopyRanges {
+    One(ExcelRange),
+    Many(Vec<(ExcelRange, ExcelCell)>),
+}
+
+// Implement FromPyObject for CopyRanges from a range, or a list of pairs of a range and the cell to paste it at
+impl<'source> pyo3::FromPyObject<'source> for CopyRanges {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        if obj.is_instance_of::<pyo3::types::PyList>()? {
+            let blocks: Vec<(ExcelRange, ExcelCell)> = obj.extract().map_err(|e: PyErr| match e.is_instance_of::<RangeError>(obj.py()) {
+                true => e,
+                false => PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                    "Invalid ranges. Expected a list of (range, destination cell) pairs.",
+                ),
+            })?;
+            if blocks.is_empty() {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("At least one range is required."));
+            }
+            return Ok(CopyRanges::Many(blocks));
+        }
+        Ok(CopyRanges::One(obj.extract()?))
+    }
+}
+
+/// What find and replace look for: a text, or a regular expression compiled in Python with re.compile
+#[derive(Debug, Clone)]
+pub enum SearchPattern {
+    Text(String),
+    Regex { pattern: String, ignore_case: bool },
+}
+
+impl SearchPattern {
+    /// Returns the text or the pattern of the regular expression, as given
+    pub fn as_str(&self) -> &str {
+        match self {
+            SearchPattern::Text(text) => text,
+            SearchPattern::Regex { pattern, .. } => pattern,
+        }
+    }
+}
+
+// Implement FromPyObject for SearchPattern from a text or a compiled pattern, of which the
+// IGNORECASE, MULTILINE and DOTALL flags are kept
+impl<'source> pyo3::FromPyObject<'source> for SearchPattern {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        if let Ok(text) = obj.extract::<String>() {
+            return Ok(SearchPattern::Text(text));
+        }
+        let compiled = obj.getattr("pattern").and_then(|pattern| Ok((pattern.extract::<String>()?, obj.getattr("flags")?.extract::<u32>()?)));
+        match compiled {
+            Ok((pattern, flags)) => {
+                let multiline = if flags & 8 != 0 { "(?m)" } else { "" };
+                let dotall = if flags & 16 != 0 { "(?s)" } else { "" };
+                Ok(SearchPattern::Regex { pattern: format!("{}{}{}", multiline, dotall, pattern), ignore_case: flags & 2 != 0 })
+            },
+            Err(_) => Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>("Expected a text or a compiled regular expression (re.compile).")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::{any_cell, check};
+    use rand::Rng;
+
+    /// The top left and bottom right cells of the rectangle with the given corners
+    fn corners(a: (u32, u32), b: (u32, u32)) -> ((u32, u32), (u32, u32)) {
+        ((a.0.min(b.0), a.1.min(b.1)), (a.0.max(b.0), a.1.max(b.1)))
+    }
+
+    #[test]
+    fn ranges_are_normalized_whatever_the_order_of_their_corners() {
+        check(|rng| {
+            let (a, b) = (any_cell(rng), any_cell(rng));
+            let expected = corners(a, b);
+            let string = format!("{}:{}", index_to_excel(a.0, a.1), index_to_excel(b.0, b.1));
+            assert_eq!(ExcelRange::String(string.clone()).idx(), expected, "{}", string);
+            assert_eq!(ExcelRange::Range(((a.1, a.0), (b.1, b.0))).idx(), expected, "{}", string);
+            assert_eq!(parse_range(&string).map(|range| range.idx()), Ok(expected), "{}", string);
+        });
+    }
+
+    #[test]
+    fn single_cells_cover_themselves() {
+        check(|rng| {
+            let (col, row) = any_cell(rng);
+            let cell = index_to_excel(col, row);
+            assert_eq!(parse_range(&cell).map(|range| range.idx()), Ok(((col, row), (col, row))), "{}", cell);
+        });
+    }
+
+    #[test]
+    fn open_ends_stop_at_the_limits_never_before_the_first_cell() {
+        check(|rng| {
+            let (col, row) = any_cell(rng);
+            let last_col = rng.gen_range(col..=EXCEL_MAX_COLUMNS);
+            let last_row = rng.gen_range(row..=EXCEL_MAX_ROWS);
+            let highest = any_cell(rng);
+            let (first, letters) = (index_to_excel(col, row), index_to_excel_col(last_col));
+
+            let columns = format!("{}:{}", index_to_excel_col(col), letters);
+            assert_eq!(parse_range(&columns).map(|range| range.idx()), Ok(((col, 1), (last_col, EXCEL_MAX_ROWS))), "{}", columns);
+            assert_eq!(parse_range(&columns).map(|range| range.bounds(highest)), Ok(((col, 1), (last_col, highest.1))), "{}", columns);
+
+            let rows = format!("{}:{}", row, last_row);
+            assert_eq!(parse_range(&rows).map(|range| range.idx()), Ok(((1, row), (EXCEL_MAX_COLUMNS, last_row))), "{}", rows);
+
+            let down = format!("{}:{}", first, letters);
+            assert_eq!(parse_range(&down).map(|range| range.bounds(highest)), Ok(((col, row), (last_col, highest.1.max(row)))), "{}", down);
+
+            let across = format!("{}:{}", first, last_row);
+            assert_eq!(parse_range(&across).map(|range| range.bounds(highest)), Ok(((col, row), (highest.0.max(col), last_row))), "{}", across);
+        });
+    }
+
+    #[test]
+    fn columns_beyond_the_last_column_of_excel_are_rejected() {
+        assert_eq!(parse_range("XFD:XFD").map(|range| range.idx()), Ok(((EXCEL_MAX_COLUMNS, 1), (EXCEL_MAX_COLUMNS, EXCEL_MAX_ROWS))));
+        assert!(parse_range("ZZZ:ZZZ").is_err());
+        check(|rng| {
+            let letters = index_to_excel_col(rng.gen_range(EXCEL_MAX_COLUMNS + 1..=excel_col_to_index("ZZZ")));
+            let (col, row) = any_cell(rng);
+            for string in [format!("{}:{}", letters, letters), format!("A:{}", letters), format!("{}:XFD", letters), format!("{}:{}", index_to_excel(col, row), letters)].iter() {
+                assert!(parse_range(string).is_err(), "{}", string);
+            }
+        });
+    }
+
+    #[test]
+    fn sheet_qualified_ranges_keep_their_sheet_and_corners() {
+        check(|rng| {
+            let (a, b) = (any_cell(rng), any_cell(rng));
+            let string = format!("'My data'!{}:{}", index_to_excel(a.0, a.1), index_to_excel(b.0, b.1));
+            let range = parse_range(&string).unwrap();
+            assert_eq!(range.sheet(), Some("My data"));
+            assert_eq!(range.idx(), corners(a, b), "{}", string);
+        });
+    }
+}
@@ -1,5 +1,7 @@
 pub mod excel;
 pub mod options;
+pub mod validation;
 
 pub use excel::*;
 pub use options::*;
+pub use validation::*;
@@ -1,16 +1,23 @@
 #[allow(unused_imports)]
 use log::{debug, info, warn};
 use pyo3::prelude::*;
+use std::collections::HashMap;
 use std::fmt;
 
+use crate::structs::ExcelCell;
+#[cfg(feature = "aggregate")]
+use crate::utils::select::glob_to_regex;
+
 // Define an enum to represent different value types
 #[derive(Debug, Clone)]
 pub enum Value {
     None,
-    Int(i32),
+    Int(i64),
     Boolean(bool),
     Float(f64),
     String(String),
+    // Formula without its leading '=', written as a formula rather than as text
+    Formula(String),
 }
 
 // Implement FromPyObject for SourceRange
@@ -19,7 +26,7 @@ impl<'source> pyo3::FromPyObject<'source> for Value {
         if obj.is_none() {
             return Ok(Value::None); // Handle None case
         }
-        if let Ok(int) = obj.extract::<i32>() {
+        if let Ok(int) = obj.extract::<i64>() {
             Ok(Value::Int(int))
         } 
         else if let Ok(boolean) = obj.extract::<bool>() {
@@ -47,11 +54,26 @@ impl IntoPy<PyObject> for Value {
             Value::Boolean(v) => v.into_py(py),
             Value::Float(v) => v.into_py(py),
             Value::String(v) => v.into_py(py),
+            Value::Formula(v) => format!("={}", v).into_py(py),
         }
     }
 }
 
 impl Value {
+    /// Reads a cell value as umya types it when set: blank, boolean, integer, number or text
+    pub fn parse(text: &str) -> Self {
+        match text {
+            "" => Value::None,
+            "TRUE" => Value::Boolean(true),
+            "FALSE" => Value::Boolean(false),
+            _ => match (text.parse::<i64>(), text.parse::<f64>()) {
+                (Ok(int), _) => Value::Int(int),
+                (_, Ok(float)) => Value::Float(float),
+                _ => Value::String(text.to_string()),
+            },
+        }
+    }
+
     pub fn value(&self) -> String {
         match self {
             Value::None => "".to_string(),
@@ -65,17 +87,133 @@ impl Value {
             },
             Value::Float(v) => v.to_string(),
             Value::String(v) => v.clone(),
+            Value::Formula(v) => format!("={}", v),
         }
     }
 }
 
-// Define the Mode enum
+/// Value of the context of a render: a cell value, a record of names (a dict) or a list repeating rows
 #[derive(Debug, Clone)]
+pub enum ContextValue {
+    Value(Value),
+    Record(HashMap<String, ContextValue>),
+    List(Vec<ContextValue>),
+}
+
+impl<'source> pyo3::FromPyObject<'source> for ContextValue {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        if let Ok(dict) = obj.downcast::<pyo3::types::PyDict>() {
+            let mut fields = HashMap::new();
+            for (key, value) in dict.iter() {
+                let key: String = key.extract().map_err(|_| {
+                    PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!("The names of the context must be texts, not {}.", key))
+                })?;
+                fields.insert(key, value.extract()?);
+            }
+            return Ok(ContextValue::Record(fields));
+        }
+        if obj.downcast::<pyo3::types::PyList>().is_ok() || obj.downcast::<pyo3::types::PyTuple>().is_ok() {
+            return Ok(ContextValue::List(obj.iter()?.map(|item| item?.extract()).collect::<PyResult<_>>()?));
+        }
+        // Booleans are integers in Python, they are checked first so they stay booleans
+        if let Ok(boolean) = obj.downcast::<pyo3::types::PyBool>() {
+            return Ok(ContextValue::Value(Value::Boolean(boolean.is_true())));
+        }
+        match obj.extract::<Value>() {
+            Ok(value) => Ok(ContextValue::Value(value)),
+            // Other values (dates, decimals...) are written as Python prints them
+            Err(_) => Ok(ContextValue::Value(Value::String(obj.str()?.to_string()))),
+        }
+    }
+}
+
+/// Date of a document property, in the W3C format of the files (2024-03-01T09:30:00Z)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyDate(pub String);
+
+impl<'source> pyo3::FromPyObject<'source> for PropertyDate {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        if let Ok(text) = obj.extract::<String>() {
+            let text = text.trim().trim_end_matches('Z');
+            let datetime = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S"].iter()
+                .find_map(|format| chrono::NaiveDateTime::parse_from_str(text, format).ok())
+                .or_else(|| chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d").ok().and_then(|date| date.and_hms_opt(0, 0, 0)));
+            return datetime.map(|datetime| PropertyDate(datetime.format("%Y-%m-%dT%H:%M:%SZ").to_string())).ok_or_else(|| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Invalid date {:?}. Use YYYY-MM-DD or YYYY-MM-DDTHH:MM:SS.", text))
+            });
+        }
+        if obj.hasattr("year")? && obj.hasattr("isoformat")? {
+            // Aware datetimes are stored in UTC, naive ones and dates as they are
+            let utc = obj.py().import("datetime")?.getattr("timezone")?.getattr("utc")?;
+            let obj = if obj.hasattr("utcoffset")? && !obj.call_method0("utcoffset")?.is_none() {
+                obj.call_method1("astimezone", (utc,))?
+            } else {
+                obj
+            };
+            let part = |attribute: &str| -> PyResult<u32> { if obj.hasattr(attribute)? { obj.getattr(attribute)?.extract() } else { Ok(0) } };
+            let year: i32 = obj.getattr("year")?.extract()?;
+            return Ok(PropertyDate(format!(
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, part("month")?, part("day")?, part("hour")?, part("minute")?, part("second")?
+            )));
+        }
+        Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>("A date must be a date, a datetime or a text."))
+    }
+}
+
+/// Value of a custom document property: a text, an integer, a boolean or a date
+#[derive(Debug, Clone, PartialEq)]
+pub enum CustomProperty {
+    Text(String),
+    Integer(i32),
+    Boolean(bool),
+    Date(PropertyDate),
+}
+
+impl<'source> pyo3::FromPyObject<'source> for CustomProperty {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        // Booleans are integers in Python, they are checked first so they stay booleans
+        if let Ok(boolean) = obj.downcast::<pyo3::types::PyBool>() {
+            return Ok(CustomProperty::Boolean(boolean.is_true()));
+        }
+        if let Ok(text) = obj.extract::<String>() {
+            return Ok(CustomProperty::Text(text));
+        }
+        if obj.downcast::<pyo3::types::PyLong>().is_ok() {
+            return obj.extract::<i32>().map(CustomProperty::Integer).map_err(|_| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Integer {} is too large for a custom property, give a text instead.", obj))
+            });
+        }
+        if obj.downcast::<pyo3::types::PyFloat>().is_ok() {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Decimal number {} cannot be a custom property, give a text instead.", obj
+            )));
+        }
+        obj.extract::<PropertyDate>().map(CustomProperty::Date).map_err(|_| {
+            PyErr::new::<pyo3::exceptions::PyTypeError, _>("A custom property must be a text, an integer, a boolean or a date.")
+        })
+    }
+}
+
+impl IntoPy<PyObject> for CustomProperty {
+    fn into_py(self, py: Python) -> PyObject {
+        match self {
+            CustomProperty::Text(text) => text.into_py(py),
+            CustomProperty::Integer(number) => number.into_py(py),
+            CustomProperty::Boolean(boolean) => boolean.into_py(py),
+            CustomProperty::Date(date) => date.0.into_py(py),
+        }
+    }
+}
+
+// Define the Mode enum
+#[derive(Debug, Clone, Default)]
 pub enum Coerce {
+    #[default]
     None,
     Integer,
     Float,
     String,
+    Date,
 }
 
 // Implement conversion from Python to Rust enum
@@ -86,9 +224,10 @@ impl FromPyObject<'_> for Coerce {
             "int" | "integer" => Ok(Coerce::Integer),
             "float" | "double" => Ok(Coerce::Float),
             "str" | "string" => Ok(Coerce::String),
+            "date" => Ok(Coerce::Date),
             "none" => Ok(Coerce::None),
             _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
-                "Invalid mode. Use 'int/integer' or 'float/double' or 'str/string'.",
+                "Invalid mode. Use 'int/integer' or 'float/double' or 'str/string' or 'date'.",
             )),
         }
     }
@@ -101,62 +240,407 @@ impl IntoPy<PyObject> for Coerce {
             Coerce::Integer => "integer".into_py(py),
             Coerce::Float => "float".into_py(py),
             Coerce::String => "string".into_py(py),
+            Coerce::Date => "date".into_py(py),
             Coerce::None => "none".into_py(py),
         }
     }
 }
 
-impl Default for Coerce {
-    fn default() -> Self {
-        Coerce::None
+// Define the Paste enum
+/// What a copy pastes of the cells, as Excel's Paste Special
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Paste {
+    #[default]
+    Values,
+    Formulas,
+    Formats,
+    All,
+}
+
+impl Paste {
+    /// Whether the values (or formulas) of the cells are pasted
+    pub fn values(&self) -> bool {
+        !matches!(self, Paste::Formats)
+    }
+
+    /// Whether the formulas are pasted as formulas rather than as their cached results
+    pub fn formulas(&self) -> bool {
+        matches!(self, Paste::Formulas | Paste::All)
+    }
+
+    /// Whether the styles of the cells are pasted
+    pub fn formats(&self) -> bool {
+        matches!(self, Paste::Formats | Paste::All)
+    }
+}
+
+// Implement conversion from Python to Rust enum
+impl FromPyObject<'_> for Paste {
+    fn extract(obj: &PyAny) -> PyResult<Self> {
+        let paste_str: String = obj.extract()?;
+        match paste_str.as_str() {
+            "values" => Ok(Paste::Values),
+            "formulas" => Ok(Paste::Formulas),
+            "formats" => Ok(Paste::Formats),
+            "all" => Ok(Paste::All),
+            _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Invalid paste. Use 'values', 'formulas', 'formats' or 'all'.",
+            )),
+        }
+    }
+}
+
+// Implement conversion from Rust enum to Python
+impl IntoPy<PyObject> for Paste {
+    fn into_py(self, py: Python) -> PyObject {
+        match self {
+            Paste::Values => "values".into_py(py),
+            Paste::Formulas => "formulas".into_py(py),
+            Paste::Formats => "formats".into_py(py),
+            Paste::All => "all".into_py(py),
+        }
+    }
+}
+
+// Define the Operation enum
+/// How a pasted number is combined with the value of the destination, as Excel's Paste Special operations
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl Operation {
+    /// Returns the name of the operation, as given from Python
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Operation::Add => "add",
+            Operation::Subtract => "subtract",
+            Operation::Multiply => "multiply",
+            Operation::Divide => "divide",
+        }
+    }
+
+    /// Operator of the operation in a formula
+    pub fn operator(&self) -> char {
+        match self {
+            Operation::Add => '+',
+            Operation::Subtract => '-',
+            Operation::Multiply => '*',
+            Operation::Divide => '/',
+        }
+    }
+
+    /// Combines the destination value with the pasted one, None for a division by zero
+    pub fn apply(&self, existing: f64, pasted: f64) -> Option<f64> {
+        match self {
+            Operation::Add => Some(existing + pasted),
+            Operation::Subtract => Some(existing - pasted),
+            Operation::Multiply => Some(existing * pasted),
+            Operation::Divide if pasted == 0.0 => None,
+            Operation::Divide => Some(existing / pasted),
+        }
+    }
+}
+
+// Implement conversion from Python to Rust enum
+impl FromPyObject<'_> for Operation {
+    fn extract(obj: &PyAny) -> PyResult<Self> {
+        let operation_str: String = obj.extract()?;
+        match operation_str.as_str() {
+            "add" => Ok(Operation::Add),
+            "subtract" => Ok(Operation::Subtract),
+            "multiply" => Ok(Operation::Multiply),
+            "divide" => Ok(Operation::Divide),
+            _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Invalid operation. Use 'add', 'subtract', 'multiply' or 'divide'.",
+            )),
+        }
+    }
+}
+
+// Implement conversion from Rust enum to Python
+impl IntoPy<PyObject> for Operation {
+    fn into_py(self, py: Python) -> PyObject {
+        self.as_str().into_py(py)
+    }
+}
+
+// Define the HeaderMatching enum
+#[cfg(feature = "dataframe")]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum HeaderMatching {
+    #[default]
+    Exact,
+    CaseInsensitive,
+    Normalized,
+}
+
+#[cfg(feature = "dataframe")]
+impl HeaderMatching {
+    /// Form of a header or column name compared under this policy
+    ///
+    /// Normalized names are lowercase and keep only letters and digits, so "Unit Price", "unit_price"
+    /// and "UnitPrice" match.
+    pub fn key(&self, name: &str) -> String {
+        match self {
+            HeaderMatching::Exact => name.to_string(),
+            HeaderMatching::CaseInsensitive => name.trim().to_lowercase(),
+            HeaderMatching::Normalized => name.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect(),
+        }
+    }
+}
+
+// Implement conversion from Python to Rust enum
+#[cfg(feature = "dataframe")]
+impl FromPyObject<'_> for HeaderMatching {
+    fn extract(obj: &PyAny) -> PyResult<Self> {
+        let matching_str: String = obj.extract()?;
+        match matching_str.as_str() {
+            "exact" => Ok(HeaderMatching::Exact),
+            "case_insensitive" => Ok(HeaderMatching::CaseInsensitive),
+            "normalized" => Ok(HeaderMatching::Normalized),
+            _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Invalid header matching. Use 'exact', 'case_insensitive' or 'normalized'.",
+            )),
+        }
     }
 }
 
 
 // Define the Action enum
-#[derive(Debug, Clone)]
+#[cfg(any(feature = "dataframe", feature = "aggregate"))]
+#[derive(Debug, Clone, Default)]
 pub enum Action {
+    #[default]
     Sum,
     Count,
     Average,
+    Min,
+    Max,
+    Median,
+    Variance,
+    Stddev,
+    Product,
+    /// Python function called with the list of numbers, returning a number or None
+    Custom(PyObject),
+}
+
+#[cfg(any(feature = "dataframe", feature = "aggregate"))]
+impl Action {
+    /// Whether the action is a Python function rather than one of the built-in actions
+    pub fn is_custom(&self) -> bool {
+        matches!(self, Action::Custom(_))
+    }
+
+    /// Computes the action over numbers, None when there are too few of them
+    ///
+    /// Variance and standard deviation are those of a sample, as VAR and STDEV compute them in Excel.
+    /// Python functions are not computed here, see `apply`.
+    pub fn compute(&self, values: &[f64]) -> Option<f64> {
+        let count = values.len() as f64;
+        // Folded from 0.0, as the sum of no numbers would be -0.0
+        let sum: f64 = values.iter().fold(0.0, |sum, value| sum + value);
+        match self {
+            Action::Sum => Some(sum),
+            Action::Count => Some(count),
+            _ if values.is_empty() => None,
+            Action::Average => Some(sum / count),
+            Action::Min => values.iter().copied().reduce(f64::min),
+            Action::Max => values.iter().copied().reduce(f64::max),
+            Action::Median => {
+                let mut sorted = values.to_vec();
+                sorted.sort_by(|a, b| a.total_cmp(b));
+                let middle = sorted.len() / 2;
+                match sorted.len() % 2 {
+                    0 => Some((sorted[middle - 1] + sorted[middle]) / 2.0),
+                    _ => Some(sorted[middle]),
+                }
+            },
+            Action::Variance | Action::Stddev if values.len() < 2 => None,
+            Action::Variance | Action::Stddev => {
+                let mean = sum / count;
+                let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (count - 1.0);
+                match self {
+                    Action::Stddev => Some(variance.sqrt()),
+                    _ => Some(variance),
+                }
+            },
+            Action::Product => Some(values.iter().product()),
+            Action::Custom(_) => None,
+        }
+    }
+
+    /// Computes the action over numbers, calling the Python function of a custom action
+    ///
+    /// :return: An error when the function raises or returns something other than a number or None.
+    pub fn apply(&self, values: &[f64]) -> Result<Option<f64>, String> {
+        match self {
+            Action::Custom(function) => Python::with_gil(|py| {
+                let result = function.call1(py, (values.to_vec(),))
+                    .map_err(|e| format!("the action function raised {}", e))?;
+                result.extract::<Option<f64>>(py).map_err(|_| {
+                    let type_name = result.as_ref(py).get_type().name().unwrap_or("?").to_string();
+                    format!("the action function must return a number or None, not {}", type_name)
+                })
+            }),
+            _ => Ok(self.compute(values)),
+        }
+    }
 }
 
 // Implement conversion from Python to Rust enum
+#[cfg(any(feature = "dataframe", feature = "aggregate"))]
 impl FromPyObject<'_> for Action {
     fn extract(obj: &PyAny) -> PyResult<Self> {
+        if obj.is_callable() {
+            return Ok(Action::Custom(obj.into()));
+        }
         let mode_str: String = obj.extract()?;
         match mode_str.as_str() {
             "sum" => Ok(Action::Sum),
             "count" => Ok(Action::Count),
             "avg" => Ok(Action::Average),
+            "min" => Ok(Action::Min),
+            "max" => Ok(Action::Max),
+            "median" => Ok(Action::Median),
+            "variance" => Ok(Action::Variance),
+            "stddev" => Ok(Action::Stddev),
+            "product" => Ok(Action::Product),
             _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
-                "Invalid mode. Use 'row' or 'col'.",
+                "Invalid action. Use 'sum', 'count', 'avg', 'min', 'max', 'median', 'variance', 'stddev', 'product' or a function.",
             )),
         }
     }
 }
 
 // Implement conversion from Rust enum to Python
+#[cfg(any(feature = "dataframe", feature = "aggregate"))]
 impl IntoPy<PyObject> for Action {
     fn into_py(self, py: Python) -> PyObject {
         match self {
             Action::Sum => "sum".into_py(py),
             Action::Count => "count".into_py(py),
             Action::Average => "avg".into_py(py),
+            Action::Min => "min".into_py(py),
+            Action::Max => "max".into_py(py),
+            Action::Median => "median".into_py(py),
+            Action::Variance => "variance".into_py(py),
+            Action::Stddev => "stddev".into_py(py),
+            Action::Product => "product".into_py(py),
+            Action::Custom(function) => function,
         }
     }
 }
 
+// Define the NonNumeric enum
+/// How aggregations take the cells holding something other than a number
+#[cfg(feature = "aggregate")]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum NonNumeric {
+    #[default]
+    Skip,
+    Zero,
+    Error,
+}
 
-impl Default for Action {
-    fn default() -> Self {
-        Action::Sum
+// Implement conversion from Python to Rust enum
+#[cfg(feature = "aggregate")]
+impl FromPyObject<'_> for NonNumeric {
+    fn extract(obj: &PyAny) -> PyResult<Self> {
+        let policy_str: String = obj.extract()?;
+        match policy_str.as_str() {
+            "skip" => Ok(NonNumeric::Skip),
+            "zero" => Ok(NonNumeric::Zero),
+            "error" => Ok(NonNumeric::Error),
+            _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Invalid non-numeric policy. Use 'skip', 'zero' or 'error'.",
+            )),
+        }
     }
 }
 
-// Define the Mode enum
+// Define the Criteria struct
+/// Condition on the cells of a conditional aggregation, as the criteria of SUMIF and COUNTIF
+///
+/// Numbers are compared as numbers, cells that are not numbers only satisfying '<>'. Texts are compared
+/// without case, with the wildcards * and ? for '=' and '<>'. An empty text matches the blank cells.
+#[cfg(feature = "aggregate")]
 #[derive(Debug, Clone)]
+pub struct Criteria {
+    pub operator: String,
+    pub number: Option<f64>,
+    pub text: String,
+    pattern: Option<regex::Regex>,
+}
+
+#[cfg(feature = "aggregate")]
+impl Criteria {
+    /// Whether the value of a cell, as read from the sheet, satisfies the criteria
+    pub fn matches(&self, value: &str) -> bool {
+        let ordering = match self.number {
+            Some(number) => match value.trim().parse::<f64>() {
+                Ok(value) if !value.is_nan() => value.partial_cmp(&number),
+                _ => return self.operator == "<>",
+            },
+            None if matches!(self.operator.as_str(), "=" | "<>") => {
+                let equal = self.pattern.as_ref().map_or(value.eq_ignore_ascii_case(&self.text), |pattern| pattern.is_match(value));
+                return equal == (self.operator == "=");
+            },
+            None => Some(value.to_lowercase().cmp(&self.text.to_lowercase())),
+        };
+        match (self.operator.as_str(), ordering) {
+            (_, None) => false,
+            ("=", Some(ordering)) => ordering.is_eq(),
+            ("<>", Some(ordering)) => ordering.is_ne(),
+            (">", Some(ordering)) => ordering.is_gt(),
+            (">=", Some(ordering)) => ordering.is_ge(),
+            ("<", Some(ordering)) => ordering.is_lt(),
+            (_, Some(ordering)) => ordering.is_le(),
+        }
+    }
+}
+
+// Implement FromPyObject for Criteria from an (operator, value) tuple, or a value the cells must be equal to
+#[cfg(feature = "aggregate")]
+impl<'source> FromPyObject<'source> for Criteria {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        let (operator, value): (String, &PyAny) = match obj.extract::<(String, &PyAny)>() {
+            Ok((operator, value)) => (operator, value),
+            Err(_) => ("=".to_string(), obj),
+        };
+        let operator = match operator.as_str() {
+            "=" | "==" => "=",
+            "<>" | "!=" => "<>",
+            ">" => ">",
+            ">=" => ">=",
+            "<" => "<",
+            "<=" => "<=",
+            _ => return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid criteria operator: {}. Use '=', '<>', '>', '>=', '<' or '<='.", operator
+            ))),
+        }.to_string();
+        if value.is_instance_of::<pyo3::types::PyBool>()? {
+            let text = if value.extract::<bool>()? { "TRUE" } else { "FALSE" };
+            return Ok(Criteria { operator, number: None, text: text.to_string(), pattern: None });
+        }
+        if let Ok(number) = value.extract::<f64>() {
+            return Ok(Criteria { operator, number: Some(number), text: number.to_string(), pattern: None });
+        }
+        let text: String = value.extract().map_err(|_| {
+            PyErr::new::<pyo3::exceptions::PyTypeError, _>("Criteria must compare to a number or a text.")
+        })?;
+        let pattern = regex::Regex::new(&format!("(?i){}", glob_to_regex(&text))).ok();
+        Ok(Criteria { operator, number: None, text, pattern })
+    }
+}
+
+// Define the Mode enum
+#[derive(Debug, Clone, Default)]
 pub enum Mode {
+    #[default]
     Row,
     Column,
 }
@@ -185,9 +669,25 @@ impl IntoPy<PyObject> for Mode {
     }
 }
 
-impl Default for Mode {
-    fn default() -> Self {
-        Mode::Row
+impl Mode {
+    /// Returns the name of the mode, as given from Python
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Mode::Row => "row",
+            Mode::Column => "col",
+        }
+    }
+}
+
+impl std::str::FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "row" => Ok(Mode::Row),
+            "col" => Ok(Mode::Column),
+            _ => Err(format!("Invalid mode: {}.", s)),
+        }
     }
 }
 
@@ -199,4 +699,739 @@ impl fmt::Display for Mode {
             Mode::Column => write!(f, "column per column"),
         }
     }
-}
\ No newline at end of file
+}
+
+// Define the StampMode enum
+#[derive(Debug, Clone, Default)]
+pub enum StampMode {
+    #[default]
+    Header,
+    BackgroundImage,
+    CellOverlay,
+}
+
+// Implement conversion from Python to Rust enum
+impl FromPyObject<'_> for StampMode {
+    fn extract(obj: &PyAny) -> PyResult<Self> {
+        let mode_str: String = obj.extract()?;
+        match mode_str.as_str() {
+            "header" => Ok(StampMode::Header),
+            "background_image" => Ok(StampMode::BackgroundImage),
+            "cell_overlay" => Ok(StampMode::CellOverlay),
+            _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Invalid stamp mode. Use 'header', 'background_image' or 'cell_overlay'.",
+            )),
+        }
+    }
+}
+
+// Implement conversion from Rust enum to Python
+impl IntoPy<PyObject> for StampMode {
+    fn into_py(self, py: Python) -> PyObject {
+        match self {
+            StampMode::Header => "header".into_py(py),
+            StampMode::BackgroundImage => "background_image".into_py(py),
+            StampMode::CellOverlay => "cell_overlay".into_py(py),
+        }
+    }
+}
+// Define the SparklineType enum
+#[cfg(feature = "charts")]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum SparklineType {
+    #[default]
+    Line,
+    Column,
+    WinLoss,
+}
+
+#[cfg(feature = "charts")]
+impl SparklineType {
+    /// Returns the name of the type, as given from Python
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SparklineType::Line => "line",
+            SparklineType::Column => "column",
+            SparklineType::WinLoss => "win_loss",
+        }
+    }
+}
+
+#[cfg(feature = "charts")]
+impl std::str::FromStr for SparklineType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "line" => Ok(SparklineType::Line),
+            "column" => Ok(SparklineType::Column),
+            "win_loss" | "stacked" => Ok(SparklineType::WinLoss),
+            _ => Err(format!("Invalid sparkline type: {}. Use 'line', 'column' or 'win_loss'.", s)),
+        }
+    }
+}
+
+// Implement conversion from Python to Rust enum
+#[cfg(feature = "charts")]
+impl FromPyObject<'_> for SparklineType {
+    fn extract(obj: &PyAny) -> PyResult<Self> {
+        let type_str: String = obj.extract()?;
+        type_str.parse().map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)
+    }
+}
+
+// Implement conversion from Rust enum to Python
+#[cfg(feature = "charts")]
+impl IntoPy<PyObject> for SparklineType {
+    fn into_py(self, py: Python) -> PyObject {
+        self.as_str().into_py(py)
+    }
+}
+
+// Define the ChartKind enum
+/// Type of the charts drawn over a range of data
+#[cfg(feature = "charts")]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum ChartKind {
+    #[default]
+    Bar,
+    Line,
+    Pie,
+}
+
+#[cfg(feature = "charts")]
+impl ChartKind {
+    /// Returns the name of the type, as given from Python
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChartKind::Bar => "bar",
+            ChartKind::Line => "line",
+            ChartKind::Pie => "pie",
+        }
+    }
+}
+
+#[cfg(feature = "charts")]
+impl std::str::FromStr for ChartKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bar" | "column" => Ok(ChartKind::Bar),
+            "line" => Ok(ChartKind::Line),
+            "pie" => Ok(ChartKind::Pie),
+            _ => Err(format!("Invalid chart type: {}. Use 'bar', 'line' or 'pie'.", s)),
+        }
+    }
+}
+
+// Implement conversion from Python to Rust enum
+#[cfg(feature = "charts")]
+impl FromPyObject<'_> for ChartKind {
+    fn extract(obj: &PyAny) -> PyResult<Self> {
+        let type_str: String = obj.extract()?;
+        type_str.parse().map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)
+    }
+}
+
+// Implement conversion from Rust enum to Python
+#[cfg(feature = "charts")]
+impl IntoPy<PyObject> for ChartKind {
+    fn into_py(self, py: Python) -> PyObject {
+        self.as_str().into_py(py)
+    }
+}
+
+// Define the SheetAction enum
+/// Action left to the users of a protected sheet, the others being refused
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SheetAction {
+    FormatCells,
+    FormatColumns,
+    FormatRows,
+    InsertColumns,
+    InsertRows,
+    InsertHyperlinks,
+    DeleteColumns,
+    DeleteRows,
+    Sort,
+    AutoFilter,
+    PivotTables,
+    Objects,
+    Scenarios,
+}
+
+impl SheetAction {
+    pub const ALL: [SheetAction; 13] = [
+        SheetAction::FormatCells,
+        SheetAction::FormatColumns,
+        SheetAction::FormatRows,
+        SheetAction::InsertColumns,
+        SheetAction::InsertRows,
+        SheetAction::InsertHyperlinks,
+        SheetAction::DeleteColumns,
+        SheetAction::DeleteRows,
+        SheetAction::Sort,
+        SheetAction::AutoFilter,
+        SheetAction::PivotTables,
+        SheetAction::Objects,
+        SheetAction::Scenarios,
+    ];
+
+    /// Returns the name of the action, as given from Python
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SheetAction::FormatCells => "format_cells",
+            SheetAction::FormatColumns => "format_columns",
+            SheetAction::FormatRows => "format_rows",
+            SheetAction::InsertColumns => "insert_columns",
+            SheetAction::InsertRows => "insert_rows",
+            SheetAction::InsertHyperlinks => "insert_hyperlinks",
+            SheetAction::DeleteColumns => "delete_columns",
+            SheetAction::DeleteRows => "delete_rows",
+            SheetAction::Sort => "sort",
+            SheetAction::AutoFilter => "auto_filter",
+            SheetAction::PivotTables => "pivot_tables",
+            SheetAction::Objects => "objects",
+            SheetAction::Scenarios => "scenarios",
+        }
+    }
+}
+
+impl std::str::FromStr for SheetAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SheetAction::ALL.iter().find(|action| action.as_str() == s).copied().ok_or_else(|| {
+            let names: Vec<&str> = SheetAction::ALL.iter().map(SheetAction::as_str).collect();
+            format!("Invalid sheet action: {}. Use one of {}.", s, names.join(", "))
+        })
+    }
+}
+
+// Implement conversion from Python to Rust enum
+impl FromPyObject<'_> for SheetAction {
+    fn extract(obj: &PyAny) -> PyResult<Self> {
+        let action: String = obj.extract()?;
+        action.parse().map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)
+    }
+}
+
+// Define the SheetConflict enum
+/// What to do with an imported sheet whose name is already taken
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum SheetConflict {
+    Suffix,
+    PrefixSourceFile,
+    #[default]
+    Error,
+    Overwrite,
+}
+
+impl SheetConflict {
+    /// Returns the name of the policy, as given from Python
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SheetConflict::Suffix => "suffix",
+            SheetConflict::PrefixSourceFile => "prefix_source_file",
+            SheetConflict::Error => "error",
+            SheetConflict::Overwrite => "overwrite",
+        }
+    }
+}
+
+impl std::str::FromStr for SheetConflict {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "suffix" => Ok(SheetConflict::Suffix),
+            "prefix_source_file" => Ok(SheetConflict::PrefixSourceFile),
+            "error" => Ok(SheetConflict::Error),
+            "overwrite" => Ok(SheetConflict::Overwrite),
+            _ => Err(format!("Invalid sheet conflict policy: {}. Use 'suffix', 'prefix_source_file', 'error' or 'overwrite'.", s)),
+        }
+    }
+}
+
+// Implement conversion from Python to Rust enum
+impl FromPyObject<'_> for SheetConflict {
+    fn extract(obj: &PyAny) -> PyResult<Self> {
+        let policy_str: String = obj.extract()?;
+        policy_str.parse().map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)
+    }
+}
+
+// Implement conversion from Rust enum to Python
+impl IntoPy<PyObject> for SheetConflict {
+    fn into_py(self, py: Python) -> PyObject {
+        self.as_str().into_py(py)
+    }
+}
+
+// Define the Period enum
+/// Length of the periods of the date columns of a Gantt chart
+#[cfg(all(feature = "dataframe", feature = "styles"))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Period {
+    #[default]
+    Day,
+    Week,
+    Month,
+}
+
+#[cfg(all(feature = "dataframe", feature = "styles"))]
+impl Period {
+    /// Returns the name of the period, as given from Python
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Period::Day => "day",
+            Period::Week => "week",
+            Period::Month => "month",
+        }
+    }
+}
+
+#[cfg(all(feature = "dataframe", feature = "styles"))]
+impl std::str::FromStr for Period {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "day" => Ok(Period::Day),
+            "week" => Ok(Period::Week),
+            "month" => Ok(Period::Month),
+            _ => Err(format!("Invalid period: {}. Use 'day', 'week' or 'month'.", s)),
+        }
+    }
+}
+
+// Implement conversion from Python to Rust enum
+#[cfg(all(feature = "dataframe", feature = "styles"))]
+impl FromPyObject<'_> for Period {
+    fn extract(obj: &PyAny) -> PyResult<Self> {
+        let period_str: String = obj.extract()?;
+        period_str.parse().map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)
+    }
+}
+
+// Implement conversion from Rust enum to Python
+#[cfg(all(feature = "dataframe", feature = "styles"))]
+impl IntoPy<PyObject> for Period {
+    fn into_py(self, py: Python) -> PyObject {
+        self.as_str().into_py(py)
+    }
+}
+
+/// Default options of fill_with, used when an option is not given in the call
+#[derive(Debug, Clone, Default)]
+pub struct FillDefaults {
+    pub mode: Option<Mode>,
+    pub strict: Option<bool>,
+    pub skip_null: Option<bool>,
+    pub overwrite: Option<bool>,
+    pub protect: Option<bool>,
+    pub null_display: Option<String>,
+    pub styles: HashMap<String, String>,
+    pub allow_gaps: Option<bool>,
+    pub date_format: Option<String>,
+    pub truncate: Option<bool>,
+    pub formula_columns: Option<Vec<String>>,
+}
+
+impl FillDefaults {
+    /// Completes these defaults with the ones of a wider profile (sheet defaults over template defaults)
+    pub fn or(&self, other: &FillDefaults) -> FillDefaults {
+        let mut styles = other.styles.clone();
+        styles.extend(self.styles.clone());
+        FillDefaults {
+            mode: self.mode.clone().or_else(|| other.mode.clone()),
+            strict: self.strict.or(other.strict),
+            skip_null: self.skip_null.or(other.skip_null),
+            overwrite: self.overwrite.or(other.overwrite),
+            protect: self.protect.or(other.protect),
+            null_display: self.null_display.clone().or_else(|| other.null_display.clone()),
+            styles,
+            allow_gaps: self.allow_gaps.or(other.allow_gaps),
+            date_format: self.date_format.clone().or_else(|| other.date_format.clone()),
+            truncate: self.truncate.or(other.truncate),
+            formula_columns: self.formula_columns.clone().or_else(|| other.formula_columns.clone()),
+        }
+    }
+}
+
+impl IntoPy<PyObject> for FillDefaults {
+    fn into_py(self, py: Python) -> PyObject {
+        let mut defaults: HashMap<&str, PyObject> = HashMap::new();
+        defaults.insert("mode", self.mode.into_py(py));
+        defaults.insert("strict", self.strict.into_py(py));
+        defaults.insert("skip_null", self.skip_null.into_py(py));
+        defaults.insert("overwrite", self.overwrite.into_py(py));
+        defaults.insert("protect", self.protect.into_py(py));
+        defaults.insert("null_display", self.null_display.into_py(py));
+        defaults.insert("styles", self.styles.into_py(py));
+        defaults.insert("allow_gaps", self.allow_gaps.into_py(py));
+        defaults.insert("date_format", self.date_format.into_py(py));
+        defaults.insert("truncate", self.truncate.into_py(py));
+        defaults.insert("formula_columns", self.formula_columns.into_py(py));
+        defaults.into_py(py)
+    }
+}
+
+/// Options of fill_with, shared by the other fills (fill_many, fill_with_csv...) and given as keyword arguments
+///
+/// Options not given are None, falling back to the defaults of the sheet and of the template.
+#[cfg(feature = "dataframe")]
+#[derive(Debug, Clone, Default)]
+pub struct FillOptions {
+    pub mode: Option<Mode>,
+    pub strict: Option<bool>,
+    pub skip_null: Option<bool>,
+    pub overwrite: Option<bool>,
+    pub protect: Option<bool>,
+    pub password: Option<String>,
+    pub provenance: Option<HashMap<String, String>>,
+    pub allow_gaps: Option<bool>,
+    pub warn_blank_headers: Option<bool>,
+    pub style_from: Option<String>,
+    pub subtotals: Option<Subtotals>,
+    pub fx: Option<Fx>,
+    pub generated_columns: Option<HashMap<String, GeneratedColumn>>,
+    pub verify_fill: Option<bool>,
+    pub date_format: Option<String>,
+    pub copy_style_from_row: Option<u32>,
+    pub upsert_on: Option<String>,
+    pub truncate: Option<bool>,
+    pub header_matching: Option<HeaderMatching>,
+    pub aliases: Option<HashMap<String, String>>,
+    pub coerce: Option<HashMap<String, Coerce>>,
+    pub coerce_strict: Option<bool>,
+    pub insert: Option<bool>,
+    pub autofilter: Option<bool>,
+    pub freeze_header: Option<bool>,
+    pub formula_columns: Option<Vec<String>>,
+    pub totals: Option<HashMap<String, Action>>,
+    pub group_by: Option<String>,
+    pub comment_coercions: Option<bool>,
+}
+
+#[cfg(feature = "dataframe")]
+impl FillOptions {
+    /// Reads the options from the keyword arguments of a fill, all of them being optional
+    pub fn from_kwargs(kwargs: Option<&pyo3::types::PyDict>) -> PyResult<Self> {
+        match kwargs {
+            Some(kwargs) => kwargs.extract(),
+            None => Ok(FillOptions::default()),
+        }
+    }
+}
+
+// Implement FromPyObject for FillOptions from a dictionary of options, None leaving an option unset
+#[cfg(feature = "dataframe")]
+impl<'source> FromPyObject<'source> for FillOptions {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        let py = obj.py();
+        let dict: &pyo3::types::PyDict = obj.downcast().map_err(|_| {
+            PyErr::new::<pyo3::exceptions::PyTypeError, _>("Fill options must be a dictionary.")
+        })?;
+        let mut options = FillOptions::default();
+        for (key, value) in dict.iter() {
+            let key: &str = key.extract()?;
+            let invalid = |e: PyErr| PyErr::from_type(e.get_type(py), format!("argument '{}': {}", key, e.value(py)));
+            match key {
+                "mode" => options.mode = value.extract().map_err(invalid)?,
+                "strict" => options.strict = value.extract().map_err(invalid)?,
+                "skip_null" => options.skip_null = value.extract().map_err(invalid)?,
+                "overwrite" => options.overwrite = value.extract().map_err(invalid)?,
+                "protect" => options.protect = value.extract().map_err(invalid)?,
+                "password" => options.password = value.extract().map_err(invalid)?,
+                "provenance" => options.provenance = value.extract().map_err(invalid)?,
+                "allow_gaps" => options.allow_gaps = value.extract().map_err(invalid)?,
+                "warn_blank_headers" => options.warn_blank_headers = value.extract().map_err(invalid)?,
+                "style_from" => options.style_from = value.extract().map_err(invalid)?,
+                "subtotals" => options.subtotals = value.extract().map_err(invalid)?,
+                "fx" => options.fx = value.extract().map_err(invalid)?,
+                "generated_columns" => options.generated_columns = value.extract().map_err(invalid)?,
+                "verify_fill" => options.verify_fill = value.extract().map_err(invalid)?,
+                "date_format" => options.date_format = value.extract().map_err(invalid)?,
+                "copy_style_from_row" => options.copy_style_from_row = value.extract().map_err(invalid)?,
+                "upsert_on" => options.upsert_on = value.extract().map_err(invalid)?,
+                "truncate" => options.truncate = value.extract().map_err(invalid)?,
+                "header_matching" => options.header_matching = value.extract().map_err(invalid)?,
+                "aliases" => options.aliases = value.extract().map_err(invalid)?,
+                "coerce" => options.coerce = value.extract().map_err(invalid)?,
+                "coerce_strict" => options.coerce_strict = value.extract().map_err(invalid)?,
+                "insert" => options.insert = value.extract().map_err(invalid)?,
+                "autofilter" => options.autofilter = value.extract().map_err(invalid)?,
+                "freeze_header" => options.freeze_header = value.extract().map_err(invalid)?,
+                "formula_columns" => options.formula_columns = value.extract().map_err(invalid)?,
+                "totals" => options.totals = value.extract().map_err(invalid)?,
+                "group_by" => options.group_by = value.extract().map_err(invalid)?,
+                "comment_coercions" => options.comment_coercions = value.extract().map_err(invalid)?,
+                _ => return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!("Unexpected fill option: '{}'.", key))),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Options of finalize_for_distribution, each step enabled by default
+#[derive(Debug, Clone)]
+pub struct DistributionPreset {
+    pub hide_gridlines: bool,
+    pub freeze_header: bool,
+    pub autofit: bool,
+    pub print_area: bool,
+    pub protect_formulas: bool,
+    pub password: Option<String>,
+    pub active_sheet: Option<String>,
+    pub active_cell: String,
+    pub strip_metadata: bool,
+}
+
+impl Default for DistributionPreset {
+    fn default() -> Self {
+        DistributionPreset {
+            hide_gridlines: true,
+            freeze_header: true,
+            autofit: true,
+            print_area: true,
+            protect_formulas: true,
+            password: None,
+            active_sheet: None,
+            active_cell: "A1".to_string(),
+            strip_metadata: true,
+        }
+    }
+}
+
+/// Keys accepted in a distribution preset
+const DISTRIBUTION_PRESET_KEYS: &[&str] = &[
+    "hide_gridlines", "freeze_header", "autofit", "print_area", "protect_formulas",
+    "password", "active_sheet", "active_cell", "strip_metadata",
+];
+
+// Implement FromPyObject for DistributionPreset from a dictionary, missing keys keeping their default
+impl<'source> FromPyObject<'source> for DistributionPreset {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        let dict: &pyo3::types::PyDict = obj.downcast().map_err(|_| {
+            PyErr::new::<pyo3::exceptions::PyTypeError, _>("Preset must be a dictionary.")
+        })?;
+        for key in dict.keys() {
+            let key: String = key.extract()?;
+            if !DISTRIBUTION_PRESET_KEYS.contains(&key.as_str()) {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Invalid preset option: {}. Use {}.", key, DISTRIBUTION_PRESET_KEYS.join(", ")
+                )));
+            }
+        }
+
+        let mut preset = DistributionPreset::default();
+        let flag = |key: &str, default: bool| -> PyResult<bool> {
+            match dict.get_item(key) {
+                Some(value) => value.extract(),
+                None => Ok(default),
+            }
+        };
+        preset.hide_gridlines = flag("hide_gridlines", preset.hide_gridlines)?;
+        preset.freeze_header = flag("freeze_header", preset.freeze_header)?;
+        preset.autofit = flag("autofit", preset.autofit)?;
+        preset.print_area = flag("print_area", preset.print_area)?;
+        preset.protect_formulas = flag("protect_formulas", preset.protect_formulas)?;
+        preset.strip_metadata = flag("strip_metadata", preset.strip_metadata)?;
+        if let Some(password) = dict.get_item("password") {
+            preset.password = password.extract()?;
+        }
+        if let Some(active_sheet) = dict.get_item("active_sheet") {
+            preset.active_sheet = active_sheet.extract()?;
+        }
+        if let Some(active_cell) = dict.get_item("active_cell") {
+            preset.active_cell = active_cell.extract::<ExcelCell>()?.range();
+        }
+        Ok(preset)
+    }
+}
+
+/// Grouped layout of fill_with: the rows of each run of the same value of a column followed by a subtotal row
+#[cfg(feature = "dataframe")]
+#[derive(Debug, Clone)]
+pub struct Subtotals {
+    pub by: String,
+    pub agg: Vec<(String, Action)>,
+    pub label: String,
+    pub group_headers: bool,
+    pub grand_total: Option<String>,
+    pub formulas: bool,
+    pub outline: bool,
+}
+
+#[cfg(feature = "dataframe")]
+impl Subtotals {
+    /// Subtotals of the runs of `by` summing the given columns, with the default labels, grand total and outline
+    pub fn summing(by: String, columns: Vec<String>) -> Self {
+        Subtotals {
+            by,
+            agg: columns.into_iter().map(|column| (column, Action::Sum)).collect(),
+            label: "{} Total".to_string(),
+            group_headers: false,
+            grand_total: Some("Grand Total".to_string()),
+            formulas: true,
+            outline: true,
+        }
+    }
+}
+
+/// Keys accepted in the subtotals option
+#[cfg(feature = "dataframe")]
+const SUBTOTALS_KEYS: &[&str] = &["by", "agg", "label", "group_headers", "grand_total", "formulas", "outline"];
+
+// Implement FromPyObject for Subtotals from a dictionary, 'by' and 'agg' being required
+#[cfg(feature = "dataframe")]
+impl<'source> FromPyObject<'source> for Subtotals {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        let dict: &pyo3::types::PyDict = obj.downcast().map_err(|_| {
+            PyErr::new::<pyo3::exceptions::PyTypeError, _>("Subtotals must be a dictionary.")
+        })?;
+        for key in dict.keys() {
+            let key: String = key.extract()?;
+            if !SUBTOTALS_KEYS.contains(&key.as_str()) {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Invalid subtotals option: {}. Use {}.", key, SUBTOTALS_KEYS.join(", ")
+                )));
+            }
+        }
+
+        let by: String = dict.get_item("by")
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("Subtotals must contain the column to group 'by'."))?
+            .extract()?;
+        let agg: &pyo3::types::PyDict = dict.get_item("agg")
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("Subtotals must contain the 'agg' action of each column."))?
+            .downcast()
+            .map_err(|_| PyErr::new::<pyo3::exceptions::PyTypeError, _>("Subtotals 'agg' must be a dictionary of columns and actions."))?;
+        let agg = agg.iter()
+            .map(|(column, action)| Ok((column.extract()?, action.extract()?)))
+            .collect::<PyResult<Vec<(String, Action)>>>()?;
+        if let Some((column, _)) = agg.iter().find(|(_, action)| action.is_custom()) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Subtotals of column '{}' need a built-in action, not a function.", column
+            )));
+        }
+        let flag = |key: &str, default: bool| -> PyResult<bool> {
+            match dict.get_item(key) {
+                Some(value) => value.extract(),
+                None => Ok(default),
+            }
+        };
+        let label: String = match dict.get_item("label") {
+            Some(label) => label.extract()?,
+            None => "{} Total".to_string(),
+        };
+        // The grand total is on by default, and can be given its own label
+        let grand_total = match dict.get_item("grand_total") {
+            Some(value) if value.extract::<bool>().is_ok() => value.extract::<bool>()?.then(|| "Grand Total".to_string()),
+            Some(value) => Some(value.extract()?),
+            None => Some("Grand Total".to_string()),
+        };
+
+        Ok(Subtotals {
+            by,
+            agg,
+            label,
+            group_headers: flag("group_headers", false)?,
+            grand_total,
+            formulas: flag("formulas", true)?,
+            outline: flag("outline", true)?,
+        })
+    }
+}
+
+/// Currency conversion of fill_with: columns converted with the rate of the currency of their row
+#[cfg(feature = "dataframe")]
+#[derive(Debug, Clone)]
+pub struct Fx {
+    pub rates: HashMap<String, f64>,
+    pub currency: String,
+    pub columns: Vec<(String, String)>,
+    pub format: String,
+}
+
+/// Keys accepted in the fx option
+#[cfg(feature = "dataframe")]
+const FX_KEYS: &[&str] = &["rates", "currency", "columns", "format"];
+
+// Implement FromPyObject for Fx from a dictionary, 'rates' and 'columns' being required
+#[cfg(feature = "dataframe")]
+impl<'source> FromPyObject<'source> for Fx {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        let dict: &pyo3::types::PyDict = obj.downcast().map_err(|_| {
+            PyErr::new::<pyo3::exceptions::PyTypeError, _>("Fx must be a dictionary.")
+        })?;
+        for key in dict.keys() {
+            let key: String = key.extract()?;
+            if !FX_KEYS.contains(&key.as_str()) {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Invalid fx option: {}. Use {}.", key, FX_KEYS.join(", ")
+                )));
+            }
+        }
+
+        let rates: HashMap<String, f64> = dict.get_item("rates")
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("Fx must contain the 'rates' of the currencies."))?
+            .extract()?;
+        let columns = dict.get_item("columns")
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("Fx must contain the 'columns' to convert."))?;
+        // A list of columns is converted into columns of the same name suffixed with '_converted'
+        let columns: Vec<(String, String)> = match columns.downcast::<pyo3::types::PyDict>() {
+            Ok(columns) => columns.iter()
+                .map(|(column, converted)| Ok((column.extract()?, converted.extract()?)))
+                .collect::<PyResult<_>>()?,
+            Err(_) => columns.extract::<Vec<String>>()?.into_iter()
+                .map(|column| (column.clone(), format!("{}_converted", column)))
+                .collect(),
+        };
+        let currency: String = match dict.get_item("currency") {
+            Some(currency) => currency.extract()?,
+            None => "currency".to_string(),
+        };
+        let format: String = match dict.get_item("format") {
+            Some(format) => format.extract()?,
+            None => "#,##0.00".to_string(),
+        };
+        Ok(Fx { rates, currency, columns, format })
+    }
+}
+
+/// Column of fill_with generated for each row rather than taken from the data
+#[cfg(feature = "dataframe")]
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeneratedColumn {
+    /// Sequence number of the row, from the given start
+    RowNumber(i64),
+    /// Same text on every row, e.g. a batch identifier
+    Constant(String),
+}
+
+#[cfg(feature = "dataframe")]
+impl std::str::FromStr for GeneratedColumn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "row_number" => Ok(GeneratedColumn::RowNumber(1)),
+            Some(("row_number", start)) => start.trim().parse()
+                .map(GeneratedColumn::RowNumber)
+                .map_err(|_| format!("Invalid row_number start: {}. Use an integer, e.g. 'row_number:0'.", start)),
+            Some(("constant", value)) => Ok(GeneratedColumn::Constant(value.to_string())),
+            _ => Err(format!("Invalid generated column: {}. Use 'row_number', 'row_number:<start>' or 'constant:<value>'.", s)),
+        }
+    }
+}
+
+// Implement conversion from Python to Rust enum
+#[cfg(feature = "dataframe")]
+impl FromPyObject<'_> for GeneratedColumn {
+    fn extract(obj: &PyAny) -> PyResult<Self> {
+        let generator: String = obj.extract()?;
+        generator.parse().map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)
+    }
+}
@@ -0,0 +1,296 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use chrono::NaiveDate;
+use pyo3::prelude::*;
+use umya_spreadsheet::{DataValidation, DataValidationOperatorValues, DataValidationValues};
+
+/// Longest list of options Excel accepts in a dropdown, separators included
+const MAX_LIST_LEN: usize = 255;
+
+// Define the ValidationKind enum
+/// What the cells of a data validation accept
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum ValidationKind {
+    #[default]
+    List,
+    Whole,
+    Decimal,
+    Date,
+    TextLength,
+    Custom,
+}
+
+impl ValidationKind {
+    /// Returns the name of the kind, as given from Python
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ValidationKind::List => "list",
+            ValidationKind::Whole => "whole",
+            ValidationKind::Decimal => "decimal",
+            ValidationKind::Date => "date",
+            ValidationKind::TextLength => "text_length",
+            ValidationKind::Custom => "custom",
+        }
+    }
+
+    fn umya(&self) -> DataValidationValues {
+        match self {
+            ValidationKind::List => DataValidationValues::List,
+            ValidationKind::Whole => DataValidationValues::Whole,
+            ValidationKind::Decimal => DataValidationValues::Decimal,
+            ValidationKind::Date => DataValidationValues::Date,
+            ValidationKind::TextLength => DataValidationValues::TextLength,
+            ValidationKind::Custom => DataValidationValues::Custom,
+        }
+    }
+}
+
+impl std::str::FromStr for ValidationKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "list" => Ok(ValidationKind::List),
+            "whole" | "integer" => Ok(ValidationKind::Whole),
+            "decimal" => Ok(ValidationKind::Decimal),
+            "date" => Ok(ValidationKind::Date),
+            "text_length" => Ok(ValidationKind::TextLength),
+            "custom" => Ok(ValidationKind::Custom),
+            _ => Err(format!("Invalid validation kind: {}. Use 'list', 'whole', 'decimal', 'date', 'text_length' or 'custom'.", s)),
+        }
+    }
+}
+
+// Implement conversion from Python to Rust enum
+impl FromPyObject<'_> for ValidationKind {
+    fn extract(obj: &PyAny) -> PyResult<Self> {
+        let kind_str: String = obj.extract()?;
+        kind_str.parse().map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)
+    }
+}
+
+// Implement conversion from Rust enum to Python
+impl IntoPy<PyObject> for ValidationKind {
+    fn into_py(self, py: Python) -> PyObject {
+        self.as_str().into_py(py)
+    }
+}
+
+// Define the ValidationOperator enum
+/// How the value of a cell compares to the bounds of a data validation
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationOperator {
+    Between,
+    NotBetween,
+    Equal,
+    NotEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+    LessThan,
+    LessThanOrEqual,
+}
+
+impl ValidationOperator {
+    /// Returns the name of the operator, as given from Python
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ValidationOperator::Between => "between",
+            ValidationOperator::NotBetween => "not_between",
+            ValidationOperator::Equal => "equal",
+            ValidationOperator::NotEqual => "not_equal",
+            ValidationOperator::GreaterThan => "greater_than",
+            ValidationOperator::GreaterThanOrEqual => "greater_than_or_equal",
+            ValidationOperator::LessThan => "less_than",
+            ValidationOperator::LessThanOrEqual => "less_than_or_equal",
+        }
+    }
+
+    /// Whether the operator compares to a minimum and a maximum rather than to a single value
+    pub fn is_range(&self) -> bool {
+        matches!(self, ValidationOperator::Between | ValidationOperator::NotBetween)
+    }
+
+    fn umya(&self) -> DataValidationOperatorValues {
+        match self {
+            ValidationOperator::Between => DataValidationOperatorValues::Between,
+            ValidationOperator::NotBetween => DataValidationOperatorValues::NotBetween,
+            ValidationOperator::Equal => DataValidationOperatorValues::Equal,
+            ValidationOperator::NotEqual => DataValidationOperatorValues::NotEqual,
+            ValidationOperator::GreaterThan => DataValidationOperatorValues::GreaterThan,
+            ValidationOperator::GreaterThanOrEqual => DataValidationOperatorValues::GreaterThanOrEqual,
+            ValidationOperator::LessThan => DataValidationOperatorValues::LessThan,
+            ValidationOperator::LessThanOrEqual => DataValidationOperatorValues::LessThanOrEqual,
+        }
+    }
+}
+
+impl std::str::FromStr for ValidationOperator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "between" => Ok(ValidationOperator::Between),
+            "not_between" => Ok(ValidationOperator::NotBetween),
+            "equal" | "==" => Ok(ValidationOperator::Equal),
+            "not_equal" | "!=" => Ok(ValidationOperator::NotEqual),
+            "greater_than" | ">" => Ok(ValidationOperator::GreaterThan),
+            "greater_than_or_equal" | ">=" => Ok(ValidationOperator::GreaterThanOrEqual),
+            "less_than" | "<" => Ok(ValidationOperator::LessThan),
+            "less_than_or_equal" | "<=" => Ok(ValidationOperator::LessThanOrEqual),
+            _ => Err(format!(
+                "Invalid validation operator: {}. Use 'between', 'not_between', 'equal', 'not_equal', 'greater_than', \
+                 'greater_than_or_equal', 'less_than' or 'less_than_or_equal'.", s
+            )),
+        }
+    }
+}
+
+// Implement conversion from Python to Rust enum
+impl FromPyObject<'_> for ValidationOperator {
+    fn extract(obj: &PyAny) -> PyResult<Self> {
+        let operator_str: String = obj.extract()?;
+        operator_str.parse().map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)
+    }
+}
+
+// Implement conversion from Rust enum to Python
+impl IntoPy<PyObject> for ValidationOperator {
+    fn into_py(self, py: Python) -> PyObject {
+        self.as_str().into_py(py)
+    }
+}
+
+// Define the ValidationBound enum
+/// A minimum or maximum of a data validation: a number, a date or a formula
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationBound {
+    Number(f64),
+    Date(NaiveDate),
+    Formula(String),
+}
+
+impl ValidationBound {
+    /// Returns the bound as written in the validation, dates as their Excel serial number
+    pub fn formula(&self) -> String {
+        match self {
+            ValidationBound::Number(number) => number.to_string(),
+            ValidationBound::Date(date) => {
+                let epoch = NaiveDate::from_ymd_opt(1899, 12, 30).unwrap_or_default();
+                (*date - epoch).num_days().to_string()
+            },
+            ValidationBound::Formula(formula) => formula.trim_start_matches('=').to_string(),
+        }
+    }
+}
+
+// Implement FromPyObject for ValidationBound from a number, a date (or datetime) or a text,
+// texts in the YYYY-MM-DD format being dates and other texts formulas
+impl<'source> FromPyObject<'source> for ValidationBound {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        if obj.is_instance_of::<pyo3::types::PyBool>()? {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>("A bound cannot be a boolean."));
+        }
+        if let Ok(number) = obj.extract::<f64>() {
+            return Ok(ValidationBound::Number(number));
+        }
+        if let Ok(text) = obj.extract::<String>() {
+            return Ok(match NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d") {
+                Ok(date) => ValidationBound::Date(date),
+                Err(_) => ValidationBound::Formula(text),
+            });
+        }
+        let part = |attribute: &str| obj.getattr(attribute).ok()?.extract::<u32>().ok();
+        let date = obj.getattr("year").ok().and_then(|year| year.extract::<i32>().ok())
+            .and_then(|year| NaiveDate::from_ymd_opt(year, part("month")?, part("day")?));
+        date.map(ValidationBound::Date).ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyTypeError, _>("A bound must be a number, a date or a text.")
+        })
+    }
+}
+
+/// A data validation of a range, checked when built
+#[derive(Debug, Clone)]
+pub struct Validation {
+    pub kind: ValidationKind,
+    pub operator: Option<ValidationOperator>,
+    pub formula1: String,
+    pub formula2: Option<String>,
+}
+
+impl Validation {
+    /// Dropdown of the given options
+    pub fn list(values: &[String]) -> Result<Self, String> {
+        if values.is_empty() {
+            return Err("At least one option is required.".to_string());
+        }
+        if let Some(option) = values.iter().find(|o| o.contains(',') || o.contains('"')) {
+            return Err(format!("Option {:?} cannot contain commas or quotes.", option));
+        }
+        let list = values.join(",");
+        if list.chars().count() > MAX_LIST_LEN {
+            return Err(format!("The options cannot exceed {} characters, use a source range instead.", MAX_LIST_LEN));
+        }
+        Ok(Validation { kind: ValidationKind::List, operator: None, formula1: format!("\"{}\"", list), formula2: None })
+    }
+
+    /// Dropdown of the values of a range, given as its address
+    pub fn list_of(address: String) -> Self {
+        Validation { kind: ValidationKind::List, operator: None, formula1: address, formula2: None }
+    }
+
+    /// Cells whose formula evaluates to TRUE
+    pub fn custom(formula: &str) -> Self {
+        let formula = formula.trim_start_matches('=').to_string();
+        Validation { kind: ValidationKind::Custom, operator: None, formula1: formula, formula2: None }
+    }
+
+    /// Numbers, dates or text lengths compared to bounds
+    ///
+    /// The operator defaults to 'between' with both bounds, 'greater_than_or_equal' with only a
+    /// minimum and 'less_than_or_equal' with only a maximum. Single value operators take the one bound given.
+    pub fn bounded(
+        kind: ValidationKind,
+        operator: Option<ValidationOperator>,
+        minimum: Option<ValidationBound>,
+        maximum: Option<ValidationBound>,
+    ) -> Result<Self, String> {
+        let operator = match (operator, &minimum, &maximum) {
+            (Some(operator), _, _) => operator,
+            (None, Some(_), Some(_)) => ValidationOperator::Between,
+            (None, Some(_), None) => ValidationOperator::GreaterThanOrEqual,
+            (None, None, Some(_)) => ValidationOperator::LessThanOrEqual,
+            (None, None, None) => return Err(format!("A minimum or a maximum is required for a '{}' validation.", kind.as_str())),
+        };
+        let (formula1, formula2) = match (operator.is_range(), minimum, maximum) {
+            (true, Some(minimum), Some(maximum)) => (minimum.formula(), Some(maximum.formula())),
+            (true, _, _) => return Err(format!("Operator '{}' requires a minimum and a maximum.", operator.as_str())),
+            (false, Some(bound), None) | (false, None, Some(bound)) => (bound.formula(), None),
+            (false, _, _) => return Err(format!("Operator '{}' requires exactly one of minimum and maximum.", operator.as_str())),
+        };
+        Ok(Validation { kind, operator: Some(operator), formula1, formula2 })
+    }
+
+    /// Builds the validation of umya for the cells of `sqref`
+    pub fn to_data_validation(&self, sqref: &str, allow_blank: bool, prompt: Option<String>, error_message: Option<String>) -> DataValidation {
+        let mut validation = DataValidation::default();
+        validation.set_type(self.kind.umya());
+        if let Some(operator) = &self.operator {
+            validation.set_operator(operator.umya());
+        }
+        validation.set_allow_blank(allow_blank);
+        validation.set_show_error_message(true);
+        validation.set_formula1(self.formula1.clone());
+        if let Some(formula2) = &self.formula2 {
+            validation.set_formula2(formula2.clone());
+        }
+        validation.get_sequence_of_references_mut().set_sqref(sqref);
+        if let Some(prompt) = prompt {
+            validation.set_show_input_message(true);
+            validation.set_prompt(prompt);
+        }
+        if let Some(error_message) = error_message {
+            validation.set_error_message(error_message);
+        }
+        validation
+    }
+}
@@ -1,481 +1,4261 @@
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
+// Only the stand-ins of the APIs of disabled features take their arguments as a tuple
+#[allow(unused_imports)]
+use pyo3::types::PyTuple;
 use once_cell::sync::Lazy;
 use umya_spreadsheet::*;
+#[cfg(feature = "dataframe")]
 use polars::prelude::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::sync::Arc;
 use log::*;
 
+use crate::cache;
+use crate::capture::Verbose;
 use crate::structs::*;
-use crate::utils::aggregate::aggregate_range;
-use crate::utils::excel::{index_to_excel, index_to_excel_col};
-use crate::utils::py2rs::{get_datatype, convert, convert_anyvalue_to_string};
+#[cfg(feature = "aggregate")]
+use crate::utils::aggregate::{aggregate_across_sheets, aggregate_formula, aggregate_range, Condition, NumberPolicy};
+use crate::utils::controls::{add_dropdown, add_validation, read_control_value};
+#[cfg(feature = "dataframe")]
+use crate::utils::coerce::coerce_columns;
+use crate::utils::comments::{read_comments, set_comment, CellComment, COMMENT_AUTHOR};
+#[cfg(feature = "dataframe")]
+use crate::utils::crosstab::pivot;
+use crate::utils::dimensions::{autofit_columns, MAX_COLUMN_WIDTH, MAX_ROW_HEIGHT};
+use crate::utils::distribution::{freeze_panes, hide_gridlines, protect_formulas, protect_sheet, set_active, set_autofilter, set_locked, set_print_area, sheet_view_mut, strip_personal_metadata, unprotect_sheet};
+use crate::utils::errors::{range_error, sheet_not_found, source_sheet_not_found, template_error};
+#[cfg(feature = "dataframe")]
+use crate::utils::errors::column_mismatch;
+use crate::utils::excel::{check_area_limits, check_cell_limits, excel_to_index, index_to_excel, index_to_excel_col, is_defined_name};
+use crate::utils::merges::{area_range, merge_cells, unmerge_cells, MergedCells};
+use crate::utils::meta::{append_record, clear_values, is_meta_sheet, read_records, read_regions, remove_records_of, rename_in_records, Region};
+#[cfg(feature = "dataframe")]
+use crate::utils::meta::register_region;
+#[cfg(feature = "dataframe")]
+use crate::utils::sources::{read_csv, read_ipc, read_parquet};
+#[cfg(feature = "dataframe")]
+use crate::utils::rs2py::rust_polars_df_to_py_polars_df;
+#[cfg(feature = "dataframe")]
+use crate::utils::py2rs::{get_datatype, convert, convert_anyvalue_to_string, OriginalDataType};
+use crate::utils::locale::NumberLocale;
+use crate::utils::encryption;
+use crate::utils::macros::{enable_macros, is_macro_path, restore_code_name};
+use crate::utils::package::write_atomically;
+use crate::utils::parallel::read_files;
+use crate::utils::outline::{read_row_groups, write_row_outlines};
+#[cfg(feature = "dataframe")]
+use crate::utils::outline::add_row_group;
+use crate::utils::optimize::{rebuild_tables, remove_broken_names, OptimizeReport};
+use crate::utils::properties::{read_properties, set_custom_property, DocumentProperties};
+#[cfg(feature = "charts")]
+use crate::utils::charts::{build_chart, DEFAULT_CHART_SIZE};
+#[cfg(feature = "charts")]
+use crate::utils::sparkline::{build_sparklines, rename_sparkline_refs, write_sparklines, Sparkline, SPARKLINES_SHEET};
+use crate::utils::stamp::Stamp;
+use crate::utils::stats::{workbook_stats, WorkbookStats};
+use crate::utils::styles::copy_layout;
+#[cfg(feature = "dataframe")]
+use crate::utils::styles::set_fill_color;
+#[cfg(all(feature = "dataframe", feature = "styles"))]
+use crate::utils::styles::parse_color;
+#[cfg(feature = "dataframe")]
+use crate::utils::subtotals::{group_runs, interleave, layout, write_subtotal_rows, write_totals_row, Group, LayoutRow};
+use crate::utils::sampling::CellLog;
+use crate::utils::search::{find_cells, replace_in_cells, CellMatch, Matcher};
+use crate::utils::render::render_sheet;
+use crate::utils::refs::{break_removed_refs, find_broken_refs, rename_sheet_refs, translate_formula, BrokenRef};
+#[cfg(feature = "aggregate")]
+use crate::utils::refs::{external_sheet_name, quote_sheet_name};
+#[cfg(feature = "dataframe")]
+use crate::utils::refs::shift_inserted_refs;
+#[cfg(feature = "dataframe")]
+use crate::utils::tables::{fit_table_to_data, overlapping_table, shift_tables_for_inserted_rows};
+use crate::utils::guard::catch_panic;
+#[cfg(feature = "dataframe")]
+use crate::utils::headers::match_headers;
+use crate::utils::select::{check_sheet_name, sanitize_sheet_name, unique_sheet_name};
+use crate::utils::sheets::{activate_sheet, active_sheet_name, is_visible, parse_sheets, reindex_local_names, rename_copied_tables};
+use crate::utils::features::missing_feature;
+#[cfg(feature = "dataframe")]
+use crate::collect::stack;
+#[cfg(feature = "dataframe")]
+use crate::utils::extract::extract_table;
+#[cfg(feature = "dataframe")]
+use crate::utils::fx::{add_converted_columns, set_currency_formats};
+#[cfg(all(feature = "dataframe", feature = "styles"))]
+use crate::utils::gantt::plan_gantt;
+#[cfg(feature = "dataframe")]
+use crate::utils::generated::add_generated_columns;
+#[cfg(feature = "dataframe")]
+use crate::utils::verify::{expected_checks, verify_checks, ColumnCheck, FillReport};
+use crate::utils::warnings::{emit_structured_warnings, emit_warnings, Warning};
+#[cfg(feature = "dataframe")]
+use crate::utils::write::write_anyvalue;
+use crate::utils::write::{combine_value, write_value, IntegerFormat, WriteOptions};
 use crate::structs::{ExcelCell, ExcelRange, ExcelHeader};
 
+/// Hidden sheet holding the provenance of the filled columns
+const PROVENANCE_SHEET: &str = "_ezxl_provenance";
+
+/// Hidden sheet holding the tags of the cells
+const TAGS_SHEET: &str = "_ezxl_tags";
+
+/// Color of the bars of Gantt charts, the blue of Excel's default theme
+#[cfg(all(feature = "dataframe", feature = "styles"))]
+const DEFAULT_BAR_COLOR: &str = "4472C4";
+
+/// Rows of an upsert left to append, with their colors, and the row of the sheet each row of the data updated
+#[cfg(feature = "dataframe")]
+type UpsertRows = (DataFrame, Vec<Option<String>>, Vec<Option<u32>>);
+
 #[pyclass]
 pub struct ExcelTemplate {
     spreadsheet: Arc<Spreadsheet>,
     current_sheet_name: Option<String>,
     current_cell_in_current_sheet: Option<ExcelCell>,
+    header_locations: HashMap<String, (ExcelCell, Mode)>,
+    drop_empty_sheets: bool,
+    sheet_inclusion_rules: HashMap<String, PyObject>,
+    stamp: Option<Stamp>,
+    write_options: WriteOptions,
+    fill_defaults: FillDefaults,
+    sheet_fill_defaults: HashMap<String, FillDefaults>,
+    file_path: Option<String>,
+    autosave_path: Option<String>,
+    password: Option<String>,
+    snapshot: Option<Box<Snapshot>>,
+}
+
+/// State of a template saved when a transaction begins, restored by a rollback
+struct Snapshot {
+    spreadsheet: Spreadsheet,
+    current_sheet_name: Option<String>,
+    current_cell_in_current_sheet: Option<ExcelCell>,
+    header_locations: HashMap<String, (ExcelCell, Mode)>,
+    drop_empty_sheets: bool,
+    sheet_inclusion_rules: HashMap<String, PyObject>,
+    stamp: Option<Stamp>,
+    write_options: WriteOptions,
+    fill_defaults: FillDefaults,
+    sheet_fill_defaults: HashMap<String, FillDefaults>,
+    autosave_path: Option<String>,
+}
+
+/// Context manager of a transaction: committed when the `with` block exits cleanly, rolled back after an exception
+#[pyclass]
+pub struct Transaction {
+    template: Py<ExcelTemplate>,
+}
+
+#[pymethods]
+impl Transaction {
+    pub fn __enter__(&self, py: Python) -> PyResult<Py<ExcelTemplate>> {
+        self.template.borrow_mut(py).begin()?;
+        Ok(self.template.clone_ref(py))
+    }
+
+    pub fn __exit__(&self, py: Python, exc_type: &PyAny, _exc_value: &PyAny, _traceback: &PyAny) -> PyResult<bool> {
+        let mut template = match self.template.try_borrow_mut(py) {
+            Ok(template) => template,
+            Err(e) if exc_type.is_none() => return Err(e.into()),
+            // The exception of the block is the one raised
+            Err(e) => {
+                warn!("Transaction not rolled back: {}", e);
+                return Ok(false);
+            },
+        };
+        // Committed or rolled back within the block
+        if template.snapshot.is_none() {
+            return Ok(false);
+        }
+        if exc_type.is_none() {
+            template.commit()?;
+        } else if let Err(e) = template.rollback() {
+            warn!("Transaction not rolled back: {}", e);
+        }
+        Ok(false)
+    }
 }
 
 impl ExcelTemplate {
-    /// Internal function to load an Excel spreadsheet
-    fn load_spreadsheet(file_path: &str) -> PyResult<Spreadsheet> {
+    /// Internal function to wrap a loaded spreadsheet
+    fn from_spreadsheet(spreadsheet: Spreadsheet) -> Self {
+        // Header locations of the regions filled by earlier runs, the latest one of each sheet
+        let mut header_locations = HashMap::new();
+        for region in read_regions(&spreadsheet) {
+            debug!("Region {} of {} with header {} found in the registry", region.range, region.sheet, region.header);
+            header_locations.insert(region.sheet, (ExcelCell::String(region.header), region.mode));
+        }
+
+        ExcelTemplate {
+            spreadsheet: Arc::new(spreadsheet),
+            current_sheet_name: None,
+            current_cell_in_current_sheet: None,
+            header_locations,
+            drop_empty_sheets: false,
+            sheet_inclusion_rules: HashMap::new(),
+            stamp: None,
+            write_options: WriteOptions::default(),
+            fill_defaults: FillDefaults::default(),
+            sheet_fill_defaults: HashMap::new(),
+            file_path: None,
+            autosave_path: None,
+            password: None,
+            snapshot: None,
+        }
+    }
+
+    /// Internal function to load an Excel spreadsheet, modified by the template
+    ///
+    /// A workbook of the cache is copied, the template modifying its copy. The copy is read again from the
+    /// file but for its sheets, the parsed ones being cloned: copies cannot share the strings of the cached
+    /// workbook, to which the writer adds the strings of each file it saves. A hit of the cache thus only
+    /// saves the parse of the cells, the rest of the workbook (strings, styles, relationships) being read.
+    fn load_spreadsheet(file_path: &str, password: Option<&str>) -> PyResult<Spreadsheet> {
+        if password.is_some() {
+            return Self::read_spreadsheet(file_path, password);
+        }
+        match Arc::try_unwrap(Self::load_workbook(file_path)?) {
+            Ok(spreadsheet) => Ok(spreadsheet),
+            Err(cached) => {
+                let mut spreadsheet = reader::xlsx::lazy_read(Path::new(file_path)).map_err(|e| Self::read_error(file_path, e))?;
+                if Self::names_of_sheets(&spreadsheet) != Self::names_of_sheets(&cached) {
+                    // Modified since it was cached
+                    return Self::read_spreadsheet(file_path, None);
+                }
+                let copy_error = |e: &str| PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("Failed to read file {:?}: {}", file_path, e));
+                while spreadsheet.get_sheet_count() > 0 {
+                    spreadsheet.remove_sheet(0).map_err(copy_error)?;
+                }
+                for worksheet in cached.get_sheet_collection() {
+                    spreadsheet.add_sheet(worksheet.clone()).map_err(copy_error)?;
+                }
+                if let Some(code_name) = cached.get_code_name() {
+                    spreadsheet.set_code_name(code_name);
+                }
+                Ok(spreadsheet)
+            },
+        }
+    }
+
+    /// Internal function to load an Excel spreadsheet only read from, shared with the cache if enabled
+    fn load_workbook(file_path: &str) -> PyResult<Arc<Spreadsheet>> {
+        cache::load(file_path, || Self::read_spreadsheet(file_path, None))
+    }
+
+    /// Internal function to read an Excel spreadsheet from its file
+    fn read_spreadsheet(file_path: &str, password: Option<&str>) -> PyResult<Spreadsheet> {
         // Check if the file exists
         if !Path::new(file_path).exists() {
             return Err(PyErr::new::<pyo3::exceptions::PyFileNotFoundError, _>(format!("File not found: {:?}", file_path)));
         }
 
-        reader::xlsx::read(Path::new(file_path)).map_err(|e| {
+        let data = std::fs::read(file_path).map_err(|e| {
             PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("Failed to read file: {:?}", e))
+        })?;
+        if !encryption::is_encrypted(&data) {
+            let mut spreadsheet = reader::xlsx::read(Path::new(file_path)).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("Failed to read file: {:?}", e))
+            })?;
+            restore_code_name(&mut spreadsheet, std::io::Cursor::new(data));
+            return Ok(spreadsheet);
+        }
+        let xlsx = Self::decrypt(&data, password).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to read file {:?}: {}", file_path, e))
+        })?;
+        Self::read_xlsx(&xlsx).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("Failed to read file: {}", e))
         })
     }
+
+    /// Internal function to load an Excel spreadsheet only read from, e.g. the source files of an aggregation
+    ///
+    /// The workbook is read lazily: only the sheets `select` returns among its sheet names are parsed.
+    /// With the cache, it is parsed whole instead, for the sheets read from it next.
+    #[cfg(any(feature = "dataframe", feature = "aggregate"))]
+    fn load_sheets<F>(file_path: &str, select: F) -> PyResult<Arc<Spreadsheet>>
+    where
+        F: FnOnce(&[String]) -> PyResult<Vec<String>>,
+    {
+        if !Path::new(file_path).exists() {
+            return Err(PyErr::new::<pyo3::exceptions::PyFileNotFoundError, _>(format!("File not found: {:?}", file_path)));
+        }
+        if cache::is_enabled() {
+            let spreadsheet = Self::load_workbook(file_path)?;
+            select(&Self::names_of_sheets(&spreadsheet))?;
+            return Ok(spreadsheet);
+        }
+        let mut spreadsheet = reader::xlsx::lazy_read(Path::new(file_path)).map_err(|e| Self::read_error(file_path, e))?;
+        let sheet_names = select(&Self::names_of_sheets(&spreadsheet))?;
+        parse_sheets(&mut spreadsheet, &sheet_names);
+        Ok(Arc::new(spreadsheet))
+    }
+
+    /// The error of a workbook that cannot be read, an encrypted workbook needing its password
+    fn read_error(file_path: &str, e: XlsxError) -> PyErr {
+        if std::fs::read(file_path).is_ok_and(|data| encryption::is_encrypted(&data)) {
+            return PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Failed to read file {:?}: The workbook is encrypted, open it with its password.", file_path
+            ));
+        }
+        PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("Failed to read file: {:?}", e))
+    }
+
+    /// Decrypts the bytes of a password-protected workbook, which cannot be opened without its password
+    fn decrypt(data: &[u8], password: Option<&str>) -> Result<Vec<u8>, String> {
+        match password {
+            Some(password) => encryption::decrypt(data, password),
+            None => Err("The workbook is encrypted, open it with its password.".to_string()),
+        }
+    }
+
+    /// Reads a spreadsheet from the bytes of an xlsx file
+    fn read_xlsx(xlsx: &[u8]) -> Result<Spreadsheet, String> {
+        let mut spreadsheet = reader::xlsx::read_reader(std::io::Cursor::new(xlsx), true).map_err(|e| format!("{:?}", e))?;
+        restore_code_name(&mut spreadsheet, std::io::Cursor::new(xlsx));
+        Ok(spreadsheet)
+    }
 }
 
 #[allow(dead_code)] // Suppress the warning for unused static
 static LAZY_TEMPLATE: Lazy<Box<ExcelTemplate>> = Lazy::new(|| {
-    Box::new(ExcelTemplate {
-        spreadsheet: Arc::new(new_file()),
-        current_sheet_name: None,
-        current_cell_in_current_sheet: None,
-    })
+    Box::new(ExcelTemplate::from_spreadsheet(new_file()))
 });
 
 #[pymethods]
 impl ExcelTemplate {
     /// Initializes a new ExcelTemplate by opening an existing file
+    ///
+    /// Used in a `with` block, the template is saved when the block exits without an exception, to
+    /// `autosave_path` if given, otherwise over the opened file.
+    /// A password-protected workbook is opened with its `password`, and saved encrypted with it on exit.
     #[new]
-    pub fn new(_py: Python, file_path: &str) -> PyResult<Self> {
-        let spreadsheet = Arc::new(Self::load_spreadsheet(file_path)?);
-        debug!("Spreadsheet loadedfrom {}", file_path);
-        Ok(ExcelTemplate { spreadsheet, current_sheet_name: None, current_cell_in_current_sheet: None })
+    pub fn new(_py: Python, file_path: &str, autosave_path: Option<String>, password: Option<String>) -> PyResult<Self> {
+        catch_panic("new", || {
+            let spreadsheet = Self::load_spreadsheet(file_path, password.as_deref())?;
+            debug!("Spreadsheet loadedfrom {}", file_path);
+            let mut template = Self::from_spreadsheet(spreadsheet);
+            template.file_path = Some(file_path.to_string());
+            template.autosave_path = autosave_path;
+            template.password = password;
+            Ok(template)
+        })
+    }
+
+    /// Initializes a new ExcelTemplate with a blank workbook of one sheet, "Sheet1"
+    #[staticmethod]
+    pub fn create() -> PyResult<Self> {
+        catch_panic("create", || {
+            debug!("Blank spreadsheet created");
+            Ok(Self::from_spreadsheet(new_file()))
+        })
+    }
+
+    /// Initializes a new ExcelTemplate with a blank workbook holding the given sheets, in this order
+    #[staticmethod]
+    pub fn create_from_sheets(sheet_names: Vec<String>) -> PyResult<Self> {
+        catch_panic("create_from_sheets", || {
+            if sheet_names.is_empty() {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("A workbook needs at least one sheet."));
+            }
+            let mut spreadsheet = new_file_empty_worksheet();
+            for sheet_name in &sheet_names {
+                spreadsheet.new_sheet(sheet_name).map_err(|e| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to add sheet '{}': {:?}.", sheet_name, e))
+                })?;
+            }
+            debug!("Blank spreadsheet created with sheets {:?}", sheet_names);
+            Ok(Self::from_spreadsheet(spreadsheet))
+        })
+    }
+
+    /// Initializes a new ExcelTemplate from the bytes of an xlsx file, e.g. a template stored in a database
+    ///
+    /// The bytes of a password-protected workbook are decrypted with its `password`.
+    #[staticmethod]
+    pub fn from_bytes(data: &[u8], password: Option<&str>) -> PyResult<Self> {
+        catch_panic("from_bytes", || {
+            let xlsx = if encryption::is_encrypted(data) { Some(Self::decrypt(data, password)) } else { None }.transpose().map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to read the workbook from bytes: {}", e))
+            })?;
+            let spreadsheet = Self::read_xlsx(xlsx.as_deref().unwrap_or(data)).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to read the workbook from bytes: {}", e))
+            })?;
+            debug!("Spreadsheet loaded from {} bytes", data.len());
+            Ok(Self::from_spreadsheet(spreadsheet))
+        })
+    }
+
+    pub fn __enter__(slf: PyRefMut<Self>) -> PyRefMut<Self> {
+        slf
+    }
+
+    /// Saves the template when the `with` block exits cleanly, and leaves the files alone after an exception
+    pub fn __exit__(&self, py: Python, exc_type: &PyAny, _exc_value: &PyAny, _traceback: &PyAny) -> PyResult<bool> {
+        catch_panic("__exit__", || {
+            if !exc_type.is_none() {
+                debug!("Exception raised in the with block, template not saved");
+                return Ok(false);
+            }
+            if let Some(file_path) = self.autosave_path.as_ref().or(self.file_path.as_ref()) {
+                debug!("Saving the template to {} on exit", file_path);
+                self.save(py, file_path, None, self.password.as_deref())?;
+            }
+            Ok(false)
+        })
+    }
+
+    /// Begins a transaction, saving the state of the template for a rollback
+    ///
+    /// The whole workbook is copied, with the current sheet and cell and the options of the template.
+    pub fn begin(&mut self) -> PyResult<()> {
+        catch_panic("begin", || {
+            if self.snapshot.is_some() {
+                return Err(template_error("A transaction is already open. Commit or roll it back first."));
+            }
+            self.snapshot = Some(Box::new(Snapshot {
+                spreadsheet: Spreadsheet::clone(&self.spreadsheet),
+                current_sheet_name: self.current_sheet_name.clone(),
+                current_cell_in_current_sheet: self.current_cell_in_current_sheet.clone(),
+                header_locations: self.header_locations.clone(),
+                drop_empty_sheets: self.drop_empty_sheets,
+                sheet_inclusion_rules: self.sheet_inclusion_rules.clone(),
+                stamp: self.stamp.clone(),
+                write_options: self.write_options.clone(),
+                fill_defaults: self.fill_defaults.clone(),
+                sheet_fill_defaults: self.sheet_fill_defaults.clone(),
+                autosave_path: self.autosave_path.clone(),
+            }));
+            debug!("Transaction begun");
+            Ok(())
+        })
+    }
+
+    /// Commits the open transaction, keeping the changes made since it began
+    pub fn commit(&mut self) -> PyResult<()> {
+        catch_panic("commit", || {
+            self.snapshot.take().ok_or_else(|| template_error("No transaction is open. Use begin first."))?;
+            debug!("Transaction committed");
+            Ok(())
+        })
+    }
+
+    /// Rolls the open transaction back, restoring the template as it was when the transaction began
+    pub fn rollback(&mut self) -> PyResult<()> {
+        catch_panic("rollback", || {
+            let snapshot = self.snapshot.take().ok_or_else(|| template_error("No transaction is open. Use begin first."))?;
+            self.spreadsheet = Arc::new(snapshot.spreadsheet);
+            self.current_sheet_name = snapshot.current_sheet_name;
+            self.current_cell_in_current_sheet = snapshot.current_cell_in_current_sheet;
+            self.header_locations = snapshot.header_locations;
+            self.drop_empty_sheets = snapshot.drop_empty_sheets;
+            self.sheet_inclusion_rules = snapshot.sheet_inclusion_rules;
+            self.stamp = snapshot.stamp;
+            self.write_options = snapshot.write_options;
+            self.fill_defaults = snapshot.fill_defaults;
+            self.sheet_fill_defaults = snapshot.sheet_fill_defaults;
+            self.autosave_path = snapshot.autosave_path;
+            debug!("Transaction rolled back");
+            Ok(())
+        })
+    }
+
+    /// Returns a context manager running its `with` block in a transaction, rolled back when the block raises
+    pub fn transaction(slf: PyRef<Self>) -> Transaction {
+        Transaction { template: slf.into() }
     }
 
     /// Adds a new sheet to the spreadsheet with a specified name
     pub fn add_sheet(&mut self, sheet_name: &str) -> PyResult<()> {
-        let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
-            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
+        catch_panic("add_sheet", || {
+            let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
+                .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
         
-        // Capture potential errors when adding a new sheet
-        spreadsheet.new_sheet(sheet_name).map_err(|e| {
-            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to add sheet '{}': {:?}.", sheet_name, e))
-        })?;
-        Ok(())
+            // Capture potential errors when adding a new sheet
+            spreadsheet.new_sheet(sheet_name).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to add sheet '{}': {:?}.", sheet_name, e))
+            })?;
+            Ok(())
+        })
+    }
+
+    /// Renames a sheet, along with the references of the formulas to it and what the template knows of it
+    /// (header location, defaults, registered regions, tags, provenance and sparklines)
+    pub fn rename_sheet(&mut self, sheet_name: &str, new_name: &str) -> PyResult<()> {
+        catch_panic("rename_sheet", || {
+            let names = Self::names_of_sheets(&self.spreadsheet);
+            if !names.iter().any(|name| name == sheet_name) {
+                return Err(sheet_not_found(sheet_name));
+            }
+            let taken: Vec<String> = names.into_iter().filter(|name| name != sheet_name).collect();
+            check_sheet_name(new_name, &taken).map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+
+            let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
+                .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
+            // The defined names of the sheet follow it
+            spreadsheet.get_sheet_by_name_mut(sheet_name).ok_or_else(|| sheet_not_found(sheet_name))?.set_name(new_name);
+            let formulas = rename_sheet_refs(spreadsheet, sheet_name, new_name);
+            let records = rename_in_records(spreadsheet, sheet_name, new_name);
+            #[cfg(feature = "charts")]
+            rename_sparkline_refs(spreadsheet, sheet_name, new_name);
+
+            if let Some(header_location) = self.header_locations.remove(sheet_name) {
+                self.header_locations.insert(new_name.to_string(), header_location);
+            }
+            if let Some(rule) = self.sheet_inclusion_rules.remove(sheet_name) {
+                self.sheet_inclusion_rules.insert(new_name.to_string(), rule);
+            }
+            if let Some(defaults) = self.sheet_fill_defaults.remove(sheet_name) {
+                self.sheet_fill_defaults.insert(new_name.to_string(), defaults);
+            }
+            if self.current_sheet_name.as_deref() == Some(sheet_name) {
+                self.current_sheet_name = Some(new_name.to_string());
+            }
+            debug!("Sheet {} renamed to {}, {} formulas and {} records updated", sheet_name, new_name, formulas, records);
+            Ok(())
+        })
+    }
+
+    /// Deletes a sheet, with what the template knows of it, keeping at least one visible sheet
+    ///
+    /// Formulas of the other sheets referring to it are left as they are, find_broken_refs reports them.
+    pub fn delete_sheet(&mut self, sheet_name: &str) -> PyResult<()> {
+        catch_panic("delete_sheet", || {
+            if !Self::names_of_sheets(&self.spreadsheet).iter().any(|name| name == sheet_name) {
+                return Err(sheet_not_found(sheet_name));
+            }
+            let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
+                .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
+            let sheets = spreadsheet.get_sheet_collection();
+            let index = sheets.iter().position(|ws| ws.get_name() == sheet_name).ok_or_else(|| sheet_not_found(sheet_name))?;
+            if !sheets.iter().enumerate().any(|(i, ws)| i != index && is_visible(ws)) {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Sheet '{}' cannot be deleted. A workbook must keep at least one visible sheet.", sheet_name
+                )));
+            }
+
+            let active = active_sheet_name(spreadsheet);
+            spreadsheet.remove_sheet(index).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to delete sheet '{}': {:?}.", sheet_name, e))
+            })?;
+            reindex_local_names(spreadsheet);
+            activate_sheet(spreadsheet, active.as_deref());
+            let records = remove_records_of(spreadsheet, sheet_name);
+
+            self.header_locations.remove(sheet_name);
+            self.sheet_inclusion_rules.remove(sheet_name);
+            self.sheet_fill_defaults.remove(sheet_name);
+            if self.current_sheet_name.as_deref() == Some(sheet_name) {
+                self.current_sheet_name = None;
+                self.current_cell_in_current_sheet = None;
+            }
+            debug!("Sheet {} deleted with {} records", sheet_name, records);
+            Ok(())
+        })
+    }
+
+    /// Moves a sheet to a position among the sheets of the workbook, 0 being the first
+    pub fn move_sheet(&mut self, sheet_name: &str, index: usize) -> PyResult<()> {
+        catch_panic("move_sheet", || {
+            let names = Self::names_of_sheets(&self.spreadsheet);
+            if !names.iter().any(|name| name == sheet_name) {
+                return Err(sheet_not_found(sheet_name));
+            }
+            if index >= names.len() {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Invalid index {}. The workbook has {} sheets.", index, names.len()
+                )));
+            }
+
+            let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
+                .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
+            let active = active_sheet_name(spreadsheet);
+            let sheets = spreadsheet.get_sheet_collection_mut();
+            let from = sheets.iter().position(|ws| ws.get_name() == sheet_name).ok_or_else(|| sheet_not_found(sheet_name))?;
+            let worksheet = sheets.remove(from);
+            // Positions count the sheets of the template only, the metadata sheets staying where they are
+            let others: Vec<&String> = names.iter().filter(|name| *name != sheet_name).collect();
+            let position_of = |name: &str| sheets.iter().position(|ws| ws.get_name() == name);
+            let to = match others.get(index) {
+                Some(name) => position_of(name),
+                None => others.last().and_then(|name| position_of(name)).map(|position| position + 1),
+            }.unwrap_or(0);
+            sheets.insert(to, worksheet);
+            reindex_local_names(spreadsheet);
+            activate_sheet(spreadsheet, active.as_deref());
+            debug!("Sheet {} moved to position {}", sheet_name, index);
+            Ok(())
+        })
+    }
+
+    /// Copies a sheet, with its values, formulas, styles and layout, into a new sheet placed right after it
+    ///
+    /// The names scoped to the sheet are copied, and its tables renamed ("Sales" to "Sales_2"). The header
+    /// location and defaults of the sheet are kept for the copy.
+    pub fn clone_sheet(&mut self, sheet_name: &str, new_name: &str) -> PyResult<()> {
+        catch_panic("clone_sheet", || {
+            let names = Self::names_of_sheets(&self.spreadsheet);
+            if !names.iter().any(|name| name == sheet_name) {
+                return Err(sheet_not_found(sheet_name));
+            }
+            check_sheet_name(new_name, &names).map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+
+            let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
+                .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
+            let index = spreadsheet.get_sheet_collection().iter().position(|ws| ws.get_name() == sheet_name)
+                .ok_or_else(|| sheet_not_found(sheet_name))?;
+            let mut worksheet = spreadsheet.get_sheet_collection()[index].clone();
+            worksheet.set_name(new_name);
+            // The names of the workbook stay on the original sheet
+            worksheet.get_defined_names_mut().retain(|defined_name| defined_name.has_local_sheet_id());
+            rename_copied_tables(spreadsheet, &mut worksheet);
+            sheet_view_mut(&mut worksheet).set_tab_selected(false);
+
+            let active = active_sheet_name(spreadsheet);
+            spreadsheet.get_sheet_collection_mut().insert(index + 1, worksheet);
+            reindex_local_names(spreadsheet);
+            activate_sheet(spreadsheet, active.as_deref());
+
+            if let Some(header_location) = self.header_locations.get(sheet_name).cloned() {
+                self.header_locations.insert(new_name.to_string(), header_location);
+            }
+            if let Some(defaults) = self.sheet_fill_defaults.get(sheet_name).cloned() {
+                self.sheet_fill_defaults.insert(new_name.to_string(), defaults);
+            }
+            debug!("Sheet {} cloned to {}", sheet_name, new_name);
+            Ok(())
+        })
+    }
+
+    /// Hides sheets, given by names or patterns, keeping at least one visible sheet
+    ///
+    /// :return: The hidden sheets.
+    pub fn hide_sheet(&mut self, sheet_name: SheetSelector) -> PyResult<Vec<String>> {
+        catch_panic("hide_sheet", || {
+            self.set_sheets_visibility(sheet_name, false)
+        })
+    }
+
+    /// Shows sheets hidden before, given by names or patterns
+    ///
+    /// :return: The shown sheets.
+    pub fn show_sheet(&mut self, sheet_name: SheetSelector) -> PyResult<Vec<String>> {
+        catch_panic("show_sheet", || {
+            self.set_sheets_visibility(sheet_name, true)
+        })
+    }
+
+    /// Imports sheets of another template, e.g. a library workbook of standard sheets, with their styles,
+    /// defined names and data validations
+    ///
+    /// `other` is an ExcelTemplate or the path of a workbook. All its sheets are imported unless `sheets`
+    /// selects some. A sheet with the same name as one of this template is handled after `on_conflict`:
+    /// 'error' (the default) raises a ValueError, 'overwrite' (or `replace=True`) replaces the sheet in place,
+    /// 'suffix' names it "Name (2)" and 'prefix_source_file' prefixes it with the name of the workbook.
+    ///
+    /// :return: The final names of the imported sheets.
+    pub fn include(&mut self, other: &PyAny, sheets: Option<SheetSelector>, replace: Option<bool>, on_conflict: Option<SheetConflict>) -> PyResult<Vec<String>> {
+        catch_panic("include", || {
+            let on_conflict = match replace {
+                Some(true) => SheetConflict::Overwrite,
+                _ => on_conflict.unwrap_or_default(),
+            };
+            let (source, source_file): (Arc<Spreadsheet>, Option<&str>) = if let Ok(template) = other.extract::<PyRef<ExcelTemplate>>() {
+                (template.spreadsheet.clone(), None)
+            } else if let Ok(file_path) = other.extract::<&str>() {
+                (Self::load_workbook(file_path)?, Some(file_path))
+            } else {
+                return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>("Expected an ExcelTemplate (other than this one) or the path of a workbook."));
+            };
+
+            let available = Self::names_of_sheets(&source);
+            let selected = match sheets {
+                Some(sheets) => sheets.resolve(&available)?,
+                None => available,
+            };
+
+            let mut taken = Self::names_of_sheets(&self.spreadsheet);
+            let names = Self::plan_sheet_names(&selected, &mut taken, &on_conflict, source_file)?;
+            self.import_sheets(&source, &names)?;
+            Ok(names.into_iter().map(|(_, name)| name).collect())
+        })
+    }
+
+    /// Consolidates the sheets of several workbooks into this template
+    ///
+    /// Sheets whose name is already taken, by this template or a workbook consolidated before, are handled
+    /// after `on_conflict` ('suffix' by default, 'prefix_source_file', 'error' or 'overwrite'). All the names
+    /// are checked before any sheet is imported, so a conflict raising an error leaves the template unchanged.
+    ///
+    /// :param files: The paths of the workbooks, consolidated in order.
+    /// :param sheets: Names or patterns of the sheets to take from each workbook (default: all).
+    /// :param max_workers: The number of workbooks read at once, in worker threads (default: one per core).
+    /// :return: The final name of each original sheet name, by file.
+    pub fn consolidate(
+        &mut self,
+        py: Python,
+        files: Vec<String>,
+        sheets: Option<SheetSelector>,
+        on_conflict: Option<SheetConflict>,
+        max_workers: Option<usize>,
+    ) -> PyResult<HashMap<String, HashMap<String, String>>> {
+        catch_panic("consolidate", || {
+            let on_conflict = on_conflict.unwrap_or(SheetConflict::Suffix);
+            let mut taken = Self::names_of_sheets(&self.spreadsheet);
+            let sources = read_files(py, &files, max_workers, Self::load_workbook)?;
+            let mut plans = Vec::new();
+            for (file_path, source) in files.iter().zip(sources) {
+                let source = source?;
+                let available = Self::names_of_sheets(&source);
+                let selected = match &sheets {
+                    Some(sheets) => sheets.resolve(&available)?,
+                    None => available,
+                };
+                let names = Self::plan_sheet_names(&selected, &mut taken, &on_conflict, Some(file_path))?;
+                plans.push((file_path, source, names));
+            }
+
+            let mut mapping = HashMap::new();
+            for (file_path, source, names) in plans {
+                self.import_sheets(&source, &names)?;
+                info!("{} sheets consolidated from {}", names.len(), file_path);
+                mapping.insert(file_path.clone(), names.into_iter().collect());
+            }
+            Ok(mapping)
+        })
+    }
+
+    /// Goes to a sheet, given by its name or a pattern matching exactly one sheet
+    pub fn goto_sheet(&mut self, sheet_name: SheetSelector, cell: Option<ExcelCell>) -> PyResult<()> {
+        catch_panic("goto_sheet", || {
+            let sheet_name = sheet_name.resolve_one(&self.sheet_names()?)?;
+            self.current_sheet_name = Some(sheet_name.clone());
+            // Without a cell, the known header location of the sheet (set earlier or registered by a previous run) is used
+            self.current_cell_in_current_sheet = cell.clone().or_else(|| self.header_locations.get(&sheet_name).map(|(header, _)| header.clone()));
+            debug!("Going to sheet {} in cell {}", sheet_name, cell.map_or("None".to_string(), |c| c.range()));
+            Ok(())
+        })
+    }
+
+    pub fn goto_cell(&mut self, cell: ExcelCell) -> PyResult<()> {
+        catch_panic("goto_cell", || {
+            self.current_cell_in_current_sheet = Some(cell.clone());
+            debug!("Going to cell {}", cell.range());
+            Ok(())
+        })
+    }
+
+    pub fn set_header_location(&mut self, header: ExcelHeader, mode: Mode) -> PyResult<()> {
+        catch_panic("set_header_location", || {
+            let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
+                .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
+        
+            let sheet_name = self.current_sheet_name
+                .as_ref()
+                .ok_or_else(|| template_error("No sheet specified."))?
+                .to_string(); // Clone the string to avoid borrowing self
+
+            let worksheet = spreadsheet.get_sheet_by_name_mut(&sheet_name).ok_or_else(|| {
+                sheet_not_found(&sheet_name)
+            })?;
+
+            let header_location = match header {
+                ExcelHeader::ExcelRange(r) => {
+                    let ((start_col, start_row), (_, _)) = r.idx();
+                    ExcelCell::Tuple((start_row, start_col))
+                },
+                ExcelHeader::ExcelCell(c) => c,
+                ExcelHeader::First => ExcelCell::default(),
+                ExcelHeader::Last => {
+                    let (col, row) = worksheet.get_highest_column_and_row();
+                    match mode {
+                        Mode::Row => ExcelCell::Tuple((row, col)),
+                        Mode::Column => ExcelCell::Tuple((col, row)),
+                    }
+                }
+            };
+            self.current_cell_in_current_sheet = Some(header_location.clone());
+            self.header_locations.insert(sheet_name.clone(), (header_location.clone(), mode));
+            debug!("Headers expected in cell {} of {}", index_to_excel(header_location.idx().0, header_location.idx().1), sheet_name);
+
+            Ok(())
+        })
+    }
+
+    pub fn set_value(&mut self, value: Value) -> PyResult<()> {
+        catch_panic("set_value", || {
+            // Store the current sheet name and cell in variables to avoid mutable borrow conflict
+            let sheet_name = self.current_sheet_name
+                .as_ref()
+                .ok_or_else(|| template_error("No sheet specified."))?
+                .to_string(); // Clone the string to avoid borrowing self
+
+            let current_cell = self.current_cell_in_current_sheet
+                .clone()
+                .ok_or_else(|| template_error(format!("No cell specified in {}.", sheet_name)))?;
+                
+            // Now write the value in the current cell
+            self.write_value(&sheet_name, &current_cell, value)
+        })
+    }
+
+    /// Writes data to a specified cell in the given sheets (names or patterns) and returns the sheets written
+    pub fn write_cell(&mut self, sheet_name: SheetSelector, cell: ExcelCell, value: Value) -> PyResult<Vec<String>> {
+        catch_panic("write_cell", || {
+            let sheet_names = sheet_name.resolve(&self.sheet_names()?)?;
+            for sheet_name in &sheet_names {
+                self.write_value(sheet_name, &cell, value.clone())?;
+            }
+            Ok(sheet_names)
+        })
+    }
+
+    /// Writes a formula (with or without its leading '=') in a cell of a sheet (default: the current sheet)
+    pub fn set_formula(&mut self, cell: ExcelCell, formula: &str, sheet_name: Option<&str>) -> PyResult<()> {
+        catch_panic("set_formula", || {
+            let sheet_name = self.sheet_or_current(sheet_name)?;
+            let formula = formula.trim().trim_start_matches('=').trim();
+            if formula.is_empty() {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("The formula is empty."));
+            }
+            self.write_value(&sheet_name, &cell, Value::Formula(formula.to_string()))
+        })
+    }
+
+    /// Sets the comment (note) of a cell of a sheet (default: the current sheet), replacing the one it has
+    ///
+    /// The author defaults to 'ez_excel_mgt'.
+    pub fn add_comment(&mut self, cell: ExcelCell, text: &str, author: Option<&str>, sheet_name: Option<&str>) -> PyResult<()> {
+        catch_panic("add_comment", || {
+            let sheet_name = self.sheet_or_current(sheet_name)?;
+            let (col, row) = cell.idx();
+            check_cell_limits("add_comment", &sheet_name, col as u64, row as u64)?;
+            set_comment(self.worksheet_mut(&sheet_name)?, (col, row), text, author.unwrap_or(COMMENT_AUTHOR));
+            Ok(())
+        })
+    }
+
+    /// Returns the comments (notes) of a sheet (default: the current sheet), by row then column
+    ///
+    /// :return: A list of dictionaries with the 'cell', the 'text' and the 'author' of each comment.
+    pub fn read_comments(&self, sheet_name: Option<&str>) -> PyResult<Vec<CellComment>> {
+        catch_panic("read_comments", || {
+            let sheet_name = self.sheet_or_current(sheet_name)?;
+            let worksheet = self.spreadsheet.get_sheet_by_name(&sheet_name).ok_or_else(|| sheet_not_found(&sheet_name))?;
+            Ok(read_comments(worksheet))
+        })
+    }
+
+    /// Removes a row from the given sheets (names or patterns) and returns the sheets changed
+    pub fn remove_row_from(&mut self, sheet_name: SheetSelector, row: u32) -> PyResult<Vec<String>> {
+        catch_panic("remove_row_from", || {
+            self.remove_rows_from(sheet_name, row, 1)
+        })
+    }
+
+    /// Removes rows from the given sheets (names or patterns) and returns the sheets changed
+    pub fn remove_rows_from(&mut self, sheet_name: SheetSelector, row: u32, num: u32) -> PyResult<Vec<String>> {
+        catch_panic("remove_rows_from", || {
+            let sheet_names = sheet_name.resolve(&self.sheet_names()?)?;
+            for sheet_name in &sheet_names {
+                let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
+                    .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
+                let broken = break_removed_refs(spreadsheet, sheet_name, row, num);
+                if broken > 0 {
+                    warn!("{} formulas referenced rows removed from {} and now hold #REF!", broken, sheet_name);
+                }
+                self.worksheet_mut(sheet_name)?.remove_row(&row, &num);
+                debug!("{} rows removed from row {} of {}", num, row, sheet_name);
+            }
+            Ok(sheet_names)
+        })
+    }
+
+    /// Finds the formulas whose references are broken, e.g. after rows were removed
+    ///
+    /// Formulas of the cells and of the defined names are scanned for #REF! and for references to
+    /// sheets that do not exist (anymore).
+    /// :return: A list of dictionaries with the 'sheet', the 'cell' (or the defined 'name'), the 'formula'
+    ///     and the 'reason' it is broken.
+    pub fn find_broken_refs(&self) -> PyResult<Vec<BrokenRef>> {
+        catch_panic("find_broken_refs", || {
+            Ok(find_broken_refs(&self.spreadsheet))
+        })
+    }
+
+    /// Finds the cells whose value holds a text, or matches a regular expression (re.compile), e.g. placeholders
+    ///
+    /// All sheets are searched unless `sheet_name` (names or patterns) is given; formulas are left out.
+    /// :param whole_cell: Whether the whole value must match, rather than a part of it (default False).
+    /// :param case_sensitive: Whether the case must match (default True).
+    /// :return: A list of dictionaries with the 'sheet', the 'cell' and its 'value', sheet by sheet, by row then column.
+    pub fn find(&self, pattern: SearchPattern, whole_cell: Option<bool>, case_sensitive: Option<bool>, sheet_name: Option<SheetSelector>) -> PyResult<Vec<CellMatch>> {
+        catch_panic("find", || {
+            let matcher = Matcher::new(&pattern, whole_cell.unwrap_or(false), case_sensitive.unwrap_or(true))
+                .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+            let mut found = Vec::new();
+            for sheet_name in self.searched_sheets(sheet_name)? {
+                let worksheet = self.spreadsheet.get_sheet_by_name(&sheet_name).ok_or_else(|| sheet_not_found(&sheet_name))?;
+                found.extend(find_cells(worksheet, &matcher).into_iter().map(|((col, row), value)| {
+                    CellMatch { sheet: sheet_name.clone(), cell: index_to_excel(col, row), value }
+                }));
+            }
+            debug!("{} cells found for {:?}", found.len(), pattern.as_str());
+            Ok(found)
+        })
+    }
+
+    /// Replaces a text, or the matches of a regular expression (re.compile), in the values of the cells
+    ///
+    /// The cells are those `find` finds with the same options. Groups of a regular expression are referred
+    /// to in the replacement as in Python (\1, \g<name>). Numbers and booleans replaced keep their type.
+    /// :return: The cells changed, as dictionaries with the 'sheet', the 'cell' and its new 'value'.
+    pub fn replace(
+        &mut self,
+        pattern: SearchPattern,
+        replacement: &str,
+        whole_cell: Option<bool>,
+        case_sensitive: Option<bool>,
+        sheet_name: Option<SheetSelector>,
+    ) -> PyResult<Vec<CellMatch>> {
+        catch_panic("replace", || {
+            let matcher = Matcher::new(&pattern, whole_cell.unwrap_or(false), case_sensitive.unwrap_or(true))
+                .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+            let mut replaced = Vec::new();
+            for sheet_name in self.searched_sheets(sheet_name)? {
+                let worksheet = self.worksheet_mut(&sheet_name)?;
+                replaced.extend(replace_in_cells(worksheet, &matcher, replacement).into_iter().map(|((col, row), value)| {
+                    CellMatch { sheet: sheet_name.clone(), cell: index_to_excel(col, row), value }
+                }));
+            }
+            info!("{} cells replaced for {:?}", replaced.len(), pattern.as_str());
+            Ok(replaced)
+        })
+    }
+
+    /// Renders the placeholders of the template ({{name}}, {{client.name}}) from a context dict
+    ///
+    /// Placeholders are replaced in the values of the cells and in the headers and footers of all the sheets,
+    /// unless `sheet_name` (names or patterns) is given; a cell holding a single placeholder takes the value as is
+    /// (numbers stay numbers). The rows referring to a list of the context ({{items.name}}, or {{items}} for a list
+    /// of values) are repeated for each of its items, consecutive rows being repeated together, and removed for an
+    /// empty list. Placeholders missing in the context are left as is.
+    /// :param strict: Whether placeholders missing in the context raise a TemplateError (default False).
+    /// :return: The warnings about the placeholders that could not be rendered, with their code, name and cell.
+    pub fn render(&mut self, py: Python, context: ContextValue, strict: Option<bool>, sheet_name: Option<SheetSelector>) -> PyResult<Vec<Warning>> {
+        catch_panic("render", || {
+            if !matches!(context, ContextValue::Record(_)) {
+                return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>("The context must be a dict of names and values."));
+            }
+            let write_options = self.write_options.clone();
+            let sheet_names = self.searched_sheets(sheet_name)?;
+            let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
+                .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
+            let mut warnings = Vec::new();
+            for sheet_name in &sheet_names {
+                warnings.extend(render_sheet(spreadsheet, sheet_name, &context, &write_options).map_err(template_error)?);
+            }
+            if strict.unwrap_or(false) {
+                let mut missing: Vec<&str> = Vec::new();
+                for name in warnings.iter().filter_map(|warning| warning.column.as_deref()) {
+                    if !missing.contains(&name) {
+                        missing.push(name);
+                    }
+                }
+                if !missing.is_empty() {
+                    return Err(template_error(format!("Placeholders could not be rendered: {}.", missing.join(", "))));
+                }
+            }
+            info!("{} rendered, {} placeholders left", sheet_names.join(", "), warnings.len());
+            emit_structured_warnings(py, warnings)
+        })
+    }
+
+    /// Returns the document properties of the workbook, as File > Info shows them in Excel
+    ///
+    /// :return: A dictionary with the 'title', 'subject', 'author', 'last_modified_by', 'company', 'manager',
+    ///     'category', 'keywords', 'description', the 'created' and 'modified' dates (2024-03-01T09:30:00Z)
+    ///     and the 'custom' properties by name, their dates as texts in the same format.
+    pub fn get_properties(&self) -> PyResult<DocumentProperties> {
+        catch_panic("get_properties", || {
+            Ok(read_properties(&self.spreadsheet))
+        })
+    }
+
+    /// Sets document properties of the workbook, the ones not given being kept
+    ///
+    /// The dates are dates, datetimes (aware ones stored in UTC) or texts in the YYYY-MM-DD or
+    /// YYYY-MM-DDTHH:MM:SS formats. `custom` maps names to texts, integers, booleans or dates, None
+    /// removing the property of that name.
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_properties(
+        &mut self,
+        title: Option<&str>,
+        subject: Option<&str>,
+        author: Option<&str>,
+        last_modified_by: Option<&str>,
+        company: Option<&str>,
+        manager: Option<&str>,
+        category: Option<&str>,
+        keywords: Option<&str>,
+        description: Option<&str>,
+        created: Option<PropertyDate>,
+        modified: Option<PropertyDate>,
+        custom: Option<&PyDict>,
+    ) -> PyResult<()> {
+        catch_panic("set_properties", || {
+            // The custom properties are all read before any change, in the order of the dictionary
+            let mut custom_properties: Vec<(String, Option<CustomProperty>)> = Vec::new();
+            for (name, value) in custom.into_iter().flat_map(|custom| custom.iter()) {
+                let name: String = name.extract().map_err(|_| {
+                    PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!("The names of the custom properties must be texts, not {}.", name))
+                })?;
+                if name.trim().is_empty() {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("The name of a custom property cannot be empty."));
+                }
+                let value = value.extract().map_err(|e: PyErr| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Custom property '{}': {}", name, e.value(value.py())))
+                })?;
+                custom_properties.push((name, value));
+            }
+
+            let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
+                .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
+            let properties = spreadsheet.get_properties_mut();
+            if let Some(title) = title {
+                properties.set_title(title);
+            }
+            if let Some(subject) = subject {
+                properties.set_subject(subject);
+            }
+            if let Some(author) = author {
+                properties.set_creator(author);
+            }
+            if let Some(last_modified_by) = last_modified_by {
+                properties.set_last_modified_by(last_modified_by);
+            }
+            if let Some(company) = company {
+                properties.set_company(company);
+            }
+            if let Some(manager) = manager {
+                properties.set_manager(manager);
+            }
+            if let Some(category) = category {
+                properties.set_category(category);
+            }
+            if let Some(keywords) = keywords {
+                properties.set_keywords(keywords);
+            }
+            if let Some(description) = description {
+                properties.set_description(description);
+            }
+            if let Some(created) = created {
+                properties.set_created(created.0);
+            }
+            if let Some(modified) = modified {
+                properties.set_modified(modified.0);
+            }
+            for (name, value) in &custom_properties {
+                set_custom_property(spreadsheet, name, value.as_ref());
+            }
+            debug!("Document properties set");
+            Ok(())
+        })
+    }
+
+    /// Returns statistics of the workbook as it would be saved, to find why a file is large
+    ///
+    /// :return: A dictionary with the 'file_size' in bytes, the 'sheets' (name, cells, formulas, strings,
+    ///     and the compressed 'size' and uncompressed 'xml_size' of their part), the 'shared_strings'
+    ///     (count, unique and sizes), the 'styles' (count and sizes) and the 'other_size' of the package.
+    pub fn stats(&self, py: Python) -> PyResult<WorkbookStats> {
+        catch_panic("stats", || {
+            let output = self.output_spreadsheet(py)?;
+            let spreadsheet = output.as_ref().unwrap_or(&self.spreadsheet);
+            let xlsx = Self::to_xlsx(spreadsheet)?;
+            workbook_stats(spreadsheet, &xlsx).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Failed to compute the statistics: {}.", e))
+            })
+        })
+    }
+
+    /// Prunes what accumulates in a long-lived template over many fill and copy cycles
+    ///
+    /// The styles and shared strings no cell uses anymore are dropped, by rebuilding both tables
+    /// from the cells, as are the defined names pointing to #REF! or to sheets that do not exist.
+    /// Named cell styles are reset to the default ones.
+    /// :return: A dictionary with the number of 'styles_removed' and 'strings_removed', the
+    ///     'defined_names_removed' and the 'size_before' and 'size_after' of the saved file in bytes.
+    pub fn optimize(&mut self) -> PyResult<OptimizeReport> {
+        catch_panic("optimize", || {
+            let optimize_error = |e: String| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Failed to optimize the workbook: {}.", e));
+            let before = workbook_stats(&self.spreadsheet, &Self::to_xlsx(&self.spreadsheet)?).map_err(optimize_error)?;
+
+            let mut optimized = rebuild_tables(&self.spreadsheet).map_err(optimize_error)?;
+            let defined_names_removed = remove_broken_names(&mut optimized);
+            let after = workbook_stats(&optimized, &Self::to_xlsx(&optimized)?).map_err(optimize_error)?;
+            self.spreadsheet = Arc::new(optimized);
+
+            let report = OptimizeReport {
+                styles_removed: before.style_count.saturating_sub(after.style_count),
+                strings_removed: before.unique_strings.saturating_sub(after.unique_strings),
+                defined_names_removed,
+                size_before: before.file_size,
+                size_after: after.file_size,
+            };
+            info!(
+                "Workbook optimized: {} styles, {} strings and {} defined names removed, {} bytes instead of {}",
+                report.styles_removed, report.strings_removed, report.defined_names_removed.len(), report.size_after, report.size_before
+            );
+            Ok(report)
+        })
+    }
+
+    /// Returns the sheet names matching a name, a glob pattern ("Region_*") or a regular expression ("re:...")
+    pub fn select_sheets(&self, pattern: SheetSelector) -> PyResult<Vec<String>> {
+        catch_panic("select_sheets", || {
+            pattern.resolve(&self.sheet_names()?)
+        })
+    }
+
+    /// Returns the list of sheet names
+    pub fn sheet_names(&self) -> PyResult<Vec<String>> {
+        catch_panic("sheet_names", || {
+            Ok(Self::names_of_sheets(&self.spreadsheet))
+        })
+    }
+
+    /// Saves the spreadsheet to a specified file path
+    ///
+    /// Sheets excluded by `drop_empty_sheets` or `include_sheet_if` are left out of the saved file
+    /// and the stamp, if any, is applied. The template itself is left unchanged.
+    /// The file is written to a temporary file renamed over it, so an interrupted save leaves the
+    /// previous file whole; with `backup=True` the previous file is kept as `<file_path>.bak`.
+    /// The macros of a workbook are kept in .xlsm files; saved as .xlsx, which cannot hold them, they are
+    /// left out with a `DataLossWarning`.
+    /// With a `password`, the file is encrypted as Excel does, and opened again with this password.
+    pub fn save(&self, py: Python, file_path: &str, backup: Option<bool>, password: Option<&str>) -> PyResult<()> {
+        let backup = backup.unwrap_or(false);
+        catch_panic("save", || {
+            let mut output = self.output_spreadsheet(py)?;
+            if !is_macro_path(file_path) && output.as_ref().unwrap_or(&self.spreadsheet).get_has_macros() {
+                let spreadsheet = output.get_or_insert_with(|| (*self.spreadsheet).clone());
+                spreadsheet.remove_macros_code();
+                emit_warnings(py, &[format!("The macros of the workbook are left out of {}, save it as .xlsm to keep them.", file_path)])?;
+            }
+            match output {
+                Some(spreadsheet) => Self::write_file(&spreadsheet, file_path, backup, password),
+                None => Self::write_file(&self.spreadsheet, file_path, backup, password),
+            }
+        })
+    }
+
+    /// Saves the spreadsheet to a new file, refusing to overwrite the file the template was opened from
+    pub fn save_as(&self, py: Python, file_path: &str, backup: Option<bool>, password: Option<&str>) -> PyResult<()> {
+        catch_panic("save_as", || {
+            if let Some(template_path) = &self.file_path {
+                if Self::same_file(template_path, file_path) {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "save_as cannot overwrite the template {:?}, use save to do so.", template_path
+                    )));
+                }
+            }
+            self.save(py, file_path, backup, password)
+        })
+    }
+
+    /// Returns the bytes of the xlsx file the spreadsheet would be saved as, without writing any file
+    ///
+    /// The same sheets and stamp as with `save` are applied.
+    pub fn to_bytes(&self, py: Python) -> PyResult<PyObject> {
+        catch_panic("to_bytes", || {
+            let output = self.output_spreadsheet(py)?;
+            let xlsx = Self::to_xlsx(output.as_ref().unwrap_or(&self.spreadsheet))?;
+            Ok(pyo3::types::PyBytes::new(py, &xlsx).into())
+        })
+    }
+
+    /// Stamps all sheets of the saved files (e.g. DRAFT in the page header)
+    #[cfg(feature = "styles")]
+    pub fn stamp(&mut self, text: Option<String>, mode: Option<StampMode>, image_path: Option<String>, cell: Option<ExcelCell>) -> PyResult<()> {
+        catch_panic("stamp", || {
+            let stamp = Stamp {
+                text: text.unwrap_or_else(|| "DRAFT".to_string()),
+                mode: mode.unwrap_or_default(),
+                image_path,
+                cell,
+            };
+            stamp.validate()?;
+            debug!("Stamp {:?} set", stamp);
+            self.stamp = Some(stamp);
+            Ok(())
+        })
+    }
+
+    #[cfg(not(feature = "styles"))]
+    #[pyo3(signature = (*_args, **_kwargs))]
+    pub fn stamp(&mut self, _args: &PyTuple, _kwargs: Option<&PyDict>) -> PyResult<()> {
+        Err(missing_feature("stamp", "styles"))
+    }
+
+    /// Adds sparklines (in-cell trend charts) in a row or column of cells, one per row or column of data
+    ///
+    /// A vertical `cell_range` (e.g. "F2:F10") takes one row of `data_range` (e.g. "B2:E10") per cell, a
+    /// horizontal one a column per cell. The type is 'line' (default), 'column' or 'win_loss'. The sheet of
+    /// the sparklines defaults to the current sheet, and the sheet of the data to the sheet of the sparklines.
+    #[cfg(feature = "charts")]
+    pub fn add_sparkline(
+        &mut self,
+        cell_range: ExcelRange,
+        data_range: ExcelRange,
+        r#type: Option<SparklineType>,
+        data_sheet_name: Option<&str>,
+        sheet_name: Option<&str>,
+    ) -> PyResult<()> {
+        catch_panic("add_sparkline", || {
+            // Sheet-qualified ranges, and defined names, give their sheet when it is not given
+            let cell_range = cell_range.resolve(&self.spreadsheet, sheet_name)?;
+            let data_range = data_range.resolve(&self.spreadsheet, data_sheet_name.or(sheet_name))?;
+            let sheet_name = self.sheet_or_current(sheet_name.or(cell_range.sheet()))?;
+            let data_sheet_name = data_sheet_name.or(data_range.sheet()).map_or_else(|| sheet_name.clone(), |s| s.to_string());
+            cell_range.check_sheet(&sheet_name)?;
+            data_range.check_sheet(&data_sheet_name)?;
+            for name in [&sheet_name, &data_sheet_name] {
+                if self.spreadsheet.get_sheet_by_name(name).is_none() {
+                    return Err(sheet_not_found(name));
+                }
+            }
+
+            let location = self.spreadsheet.get_sheet_by_name(&sheet_name).map_or_else(|| cell_range.idx(), |ws| cell_range.idx_in(ws));
+            let data = self.spreadsheet.get_sheet_by_name(&data_sheet_name).map_or_else(|| data_range.idx(), |ws| data_range.idx_in(ws));
+            check_area_limits("add_sparkline", &sheet_name, location.0, (location.1.0 - location.0.0 + 1, location.1.1 - location.0.1 + 1))?;
+            check_area_limits("add_sparkline", &data_sheet_name, data.0, (data.1.0 - data.0.0 + 1, data.1.1 - data.0.1 + 1))?;
+            let sparklines = build_sparklines(&sheet_name, location, &data_sheet_name, data, r#type.unwrap_or_default())
+                .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+
+            let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
+                .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
+            for sparkline in &sparklines {
+                append_record(spreadsheet, SPARKLINES_SHEET, &sparkline.to_record()).map_err(|e| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to add sparkline: {}.", e))
+                })?;
+            }
+            debug!("{} sparklines added in {} of {}", sparklines.len(), cell_range.range(), sheet_name);
+            Ok(())
+        })
+    }
+
+    #[cfg(not(feature = "charts"))]
+    #[pyo3(signature = (*_args, **_kwargs))]
+    pub fn add_sparkline(&mut self, _args: &PyTuple, _kwargs: Option<&PyDict>) -> PyResult<()> {
+        Err(missing_feature("add_sparkline", "charts"))
+    }
+
+    /// Adds a native Excel chart ('bar', 'line' or 'pie') over a range of data, one series per column
+    ///
+    /// The chart is drawn from `anchor_cell` of the sheet (default: the current sheet) over `size` columns and
+    /// rows (default: 8 by 15). A sheet-qualified `data_range` may read another sheet. With `header`, the first
+    /// row of the data names the series; `categories_range` gives the labels of the values, one cell per row.
+    #[cfg(feature = "charts")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_chart(
+        &mut self,
+        anchor_cell: ExcelCell,
+        chart_type: ChartKind,
+        data_range: ExcelRange,
+        categories_range: Option<ExcelRange>,
+        title: Option<&str>,
+        header: Option<bool>,
+        size: Option<(u32, u32)>,
+        sheet_name: Option<&str>,
+    ) -> PyResult<()> {
+        catch_panic("add_chart", || {
+            let sheet_name = self.sheet_or_current(sheet_name)?;
+            let data_range = data_range.resolve(&self.spreadsheet, Some(&sheet_name))?;
+            let data_sheet_name = data_range.sheet().map_or_else(|| sheet_name.clone(), |s| s.to_string());
+            let categories_range = categories_range.map(|range| range.resolve(&self.spreadsheet, Some(&data_sheet_name))).transpose()?;
+            if let Some(range) = &categories_range {
+                range.check_sheet(&data_sheet_name)?;
+            }
+            if self.spreadsheet.get_sheet_by_name(&sheet_name).is_none() {
+                return Err(sheet_not_found(&sheet_name));
+            }
+            let data_sheet = self.spreadsheet.get_sheet_by_name(&data_sheet_name).ok_or_else(|| sheet_not_found(&data_sheet_name))?;
+
+            let data = data_range.idx_in(data_sheet);
+            let categories = categories_range.as_ref().map(|range| range.idx_in(data_sheet));
+            let size = size.unwrap_or(DEFAULT_CHART_SIZE);
+            if size.0 == 0 || size.1 == 0 {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Invalid chart size {:?}: it spans at least a cell.", size)));
+            }
+            check_area_limits("add_chart", &sheet_name, anchor_cell.idx(), (size.0 + 1, size.1 + 1))?;
+            check_area_limits("add_chart", &data_sheet_name, data.0, (data.1.0 - data.0.0 + 1, data.1.1 - data.0.1 + 1))?;
+            let mut chart = build_chart(&chart_type, anchor_cell.idx(), size, &data_sheet_name, data, categories, header.unwrap_or(false))
+                .map_err(|e| range_error(e, Some(&data_sheet_name), Some(&data_range.range())))?;
+            if let Some(title) = title {
+                chart.set_title(title);
+            }
+            self.worksheet_mut(&sheet_name)?.add_chart(chart);
+            debug!("{} chart added at {} of {}", chart_type.as_str(), anchor_cell.range(), sheet_name);
+            Ok(())
+        })
+    }
+
+    #[cfg(not(feature = "charts"))]
+    #[pyo3(signature = (*_args, **_kwargs))]
+    pub fn add_chart(&mut self, _args: &PyTuple, _kwargs: Option<&PyDict>) -> PyResult<()> {
+        Err(missing_feature("add_chart", "charts"))
+    }
+
+    /// Removes the stamp so the next saved files are final ones
+    pub fn remove_stamp(&mut self) -> PyResult<()> {
+        catch_panic("remove_stamp", || {
+            self.stamp = None;
+            debug!("Stamp removed");
+            Ok(())
+        })
+    }
+
+    /// Prepares the workbook for distribution in one call, with steps that can be turned off in `preset`
+    ///
+    /// On every sheet (or the ones selected by `sheets`): hides the gridlines, freezes the panes above
+    /// (or left of) the header, sizes the columns to their content, sets the print area to the used range
+    /// and, on sheets with formulas, locks the formulas and protects the sheet (with the preset 'password').
+    /// Then activates 'active_sheet' (default: the first sheet) at 'active_cell' (default A1) and strips
+    /// the personal metadata (author, last editor, manager, company) of the document properties.
+    pub fn finalize_for_distribution(&mut self, preset: Option<DistributionPreset>, sheets: Option<SheetSelector>) -> PyResult<()> {
+        catch_panic("finalize_for_distribution", || {
+            let preset = preset.unwrap_or_default();
+            let names = Self::names_of_sheets(&self.spreadsheet);
+            let selected = match sheets {
+                Some(sheets) => sheets.resolve(&names)?,
+                None => names.clone(),
+            };
+            let active_sheet = match &preset.active_sheet {
+                Some(sheet_name) => SheetSelector(vec![sheet_name.clone()]).resolve_one(&names)?,
+                None => names.first().cloned().ok_or_else(|| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>("The workbook has no sheet to activate.")
+                })?,
+            };
+            let header_locations = self.header_locations.clone();
+
+            let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
+                .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
+
+            let mut active_index = 0;
+            for (index, worksheet) in spreadsheet.get_sheet_collection_mut().iter_mut().enumerate() {
+                let sheet_name = worksheet.get_name().to_string();
+                if sheet_name == active_sheet {
+                    active_index = index;
+                }
+                if !selected.contains(&sheet_name) {
+                    continue;
+                }
+
+                if preset.hide_gridlines {
+                    hide_gridlines(worksheet);
+                }
+                if preset.freeze_header {
+                    match header_locations.get(&sheet_name) {
+                        Some((header, Mode::Row)) => freeze_panes(worksheet, 1, header.idx().1 + 1),
+                        Some((header, Mode::Column)) => freeze_panes(worksheet, header.idx().0 + 1, 1),
+                        None => debug!("No header location in {}, panes left as is", sheet_name),
+                    }
+                }
+                if preset.autofit {
+                    autofit_columns(worksheet, None, MAX_COLUMN_WIDTH);
+                }
+                if preset.print_area {
+                    set_print_area(worksheet, index);
+                }
+                if preset.protect_formulas {
+                    protect_formulas(worksheet, preset.password.as_deref());
+                }
+                debug!("Sheet {} finalized for distribution", sheet_name);
+            }
+
+            set_active(spreadsheet, active_index, &preset.active_cell);
+            if preset.strip_metadata {
+                strip_personal_metadata(spreadsheet);
+            }
+            Ok(())
+        })
+    }
+
+    /// Sets the width of a column of a sheet (default: the current sheet), in characters of the default font
+    pub fn set_column_width(&mut self, column: ExcelPosition, width: f64, sheet_name: Option<&str>) -> PyResult<()> {
+        catch_panic("set_column_width", || {
+            let sheet_name = self.sheet_or_current(sheet_name)?;
+            if !(0.0..=MAX_COLUMN_WIDTH).contains(&width) {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Width {} is not between 0 and {}.", width, MAX_COLUMN_WIDTH)));
+            }
+            check_cell_limits("set_column_width", &sheet_name, column.idx() as u64, 1)?;
+            let worksheet = self.worksheet_mut(&sheet_name)?;
+            worksheet.get_column_dimension_by_number_mut(&column.idx()).set_width(width);
+            debug!("Width of column {} of {} set to {}", index_to_excel_col(column.idx()), sheet_name, width);
+            Ok(())
+        })
+    }
+
+    /// Sets the height of a row of a sheet (default: the current sheet), in points
+    pub fn set_row_height(&mut self, row: u32, height: f64, sheet_name: Option<&str>) -> PyResult<()> {
+        catch_panic("set_row_height", || {
+            let sheet_name = self.sheet_or_current(sheet_name)?;
+            if !(0.0..=MAX_ROW_HEIGHT).contains(&height) {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Height {} is not between 0 and {}.", height, MAX_ROW_HEIGHT)));
+            }
+            check_cell_limits("set_row_height", &sheet_name, 1, row as u64)?;
+            let worksheet = self.worksheet_mut(&sheet_name)?;
+            worksheet.get_row_dimension_mut(&row).set_height(height);
+            debug!("Height of row {} of {} set to {}", row, sheet_name, height);
+            Ok(())
+        })
+    }
+
+    /// Sizes the columns of a sheet (default: the current sheet, all its used columns) to the values written in them
+    ///
+    /// Widths are estimated from the lengths of the values as formatted, so that they show without
+    /// "#####" or truncation, up to `max_width` (default 255). Returns the widths set by column letters.
+    pub fn autofit_columns(&mut self, columns: Option<Vec<ExcelPosition>>, max_width: Option<f64>, sheet_name: Option<&str>) -> PyResult<HashMap<String, f64>> {
+        catch_panic("autofit_columns", || {
+            let sheet_name = self.sheet_or_current(sheet_name)?;
+            let max_width = max_width.unwrap_or(MAX_COLUMN_WIDTH).clamp(0.0, MAX_COLUMN_WIDTH);
+            let columns: Option<Vec<u32>> = columns.map(|columns| columns.iter().map(ExcelPosition::idx).collect());
+            for col in columns.iter().flatten() {
+                check_cell_limits("autofit_columns", &sheet_name, *col as u64, 1)?;
+            }
+            let worksheet = self.worksheet_mut(&sheet_name)?;
+            let sized = autofit_columns(worksheet, columns.as_deref(), max_width);
+            Ok(sized.into_iter().map(|(col, width)| (index_to_excel_col(col), width)).collect())
+        })
+    }
+
+    /// Freezes the rows above and the columns left of a cell of a sheet (default: the current sheet)
+    pub fn freeze_panes(&mut self, cell: ExcelCell, sheet_name: Option<&str>) -> PyResult<()> {
+        catch_panic("freeze_panes", || {
+            let sheet_name = self.sheet_or_current(sheet_name)?;
+            let (col, row) = cell.idx();
+            check_cell_limits("freeze_panes", &sheet_name, col as u64, row as u64)?;
+            if (col, row) == (1, 1) {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("Cell A1 has no rows or columns to freeze above or left of it."));
+            }
+            freeze_panes(self.worksheet_mut(&sheet_name)?, col, row);
+            Ok(())
+        })
+    }
+
+    /// Adds an autofilter to a range of a sheet (default: the current sheet), replacing the one it had
+    ///
+    /// The first row of the range holds the buttons of the filter. With "auto" (default), the range goes
+    /// from the header location of the sheet to its last used column and row, in row mode.
+    pub fn add_autofilter(&mut self, range: Option<ExcelRange>, sheet_name: Option<&str>) -> PyResult<String> {
+        catch_panic("add_autofilter", || {
+            let range = match range {
+                Some(ExcelRange::Name(name)) if name.eq_ignore_ascii_case("auto") => None,
+                Some(range) => Some(range.resolve(&self.spreadsheet, sheet_name)?),
+                None => None,
+            };
+            let sheet_name = self.sheet_or_current(sheet_name.or(range.as_ref().and_then(|r| r.sheet())))?;
+            if let Some(range) = &range {
+                range.check_sheet(&sheet_name)?;
+            }
+            let header = match self.header_locations.get(&sheet_name) {
+                Some((header, Mode::Row)) => Some(header.idx()),
+                _ => None,
+            };
+
+            let worksheet = self.worksheet_mut(&sheet_name)?;
+            let (first, last) = match (range, header) {
+                (Some(range), _) => range.idx_in(worksheet),
+                (None, Some((header_col, header_row))) => {
+                    let (_, last_row) = worksheet.get_highest_column_and_row();
+                    let last_col = worksheet.get_collection_by_row(&header_row).iter()
+                        .filter(|cell| !cell.get_value().trim().is_empty())
+                        .map(|cell| *cell.get_coordinate().get_col_num())
+                        .fold(header_col, u32::max);
+                    ((header_col, header_row), (last_col, last_row.max(header_row + 1)))
+                },
+                (None, None) => return Err(template_error(format!(
+                    "Sheet '{}' has no header location in row mode to filter. Use set_header_location or give the range.", sheet_name
+                ))),
+            };
+            check_area_limits("add_autofilter", &sheet_name, first, (last.0 - first.0 + 1, last.1 - first.1 + 1))?;
+            set_autofilter(worksheet, first, last).map_err(|e| range_error(e, Some(&sheet_name), None))
+        })
+    }
+
+    /// Protects a sheet (default: the current sheet), leaving its unlocked cells editable
+    ///
+    /// The cells are locked unless unlocked with `unlock_cells`. `allow` lists the actions left to the users
+    /// ('format_cells', 'insert_rows', 'sort', 'auto_filter'...), the others being refused.
+    pub fn protect_sheet(&mut self, password: Option<&str>, allow: Option<Vec<SheetAction>>, sheet_name: Option<&str>) -> PyResult<()> {
+        catch_panic("protect_sheet", || {
+            let sheet_name = self.sheet_or_current(sheet_name)?;
+            protect_sheet(self.worksheet_mut(&sheet_name)?, password, &allow.unwrap_or_default());
+            Ok(())
+        })
+    }
+
+    /// Removes the protection of a sheet (default: the current sheet) and returns whether it was protected
+    pub fn unprotect_sheet(&mut self, sheet_name: Option<&str>) -> PyResult<bool> {
+        catch_panic("unprotect_sheet", || {
+            let sheet_name = self.sheet_or_current(sheet_name)?;
+            Ok(unprotect_sheet(self.worksheet_mut(&sheet_name)?))
+        })
+    }
+
+    /// Locks the cells of a range of a sheet (default: the current sheet), read-only once the sheet is protected
+    pub fn lock_cells(&mut self, range: ExcelRange, sheet_name: Option<&str>) -> PyResult<()> {
+        catch_panic("lock_cells", || {
+            self.set_cells_locked("lock_cells", range, sheet_name, true)
+        })
+    }
+
+    /// Unlocks the cells of a range of a sheet (default: the current sheet), editable once the sheet is protected
+    pub fn unlock_cells(&mut self, range: ExcelRange, sheet_name: Option<&str>) -> PyResult<()> {
+        catch_panic("unlock_cells", || {
+            self.set_cells_locked("unlock_cells", range, sheet_name, false)
+        })
+    }
+
+    /// Merges the cells of a range of a sheet (default: the current sheet) into one, showing the top left value
+    ///
+    /// The values of the other cells are cleared, as Excel does, with a warning. A range overlapping
+    /// merged cells raises a `RangeError`.
+    pub fn merge_cells(&mut self, py: Python, range: ExcelRange, sheet_name: Option<&str>) -> PyResult<Vec<Warning>> {
+        catch_panic("merge_cells", || {
+            let range = range.resolve(&self.spreadsheet, sheet_name)?;
+            let sheet_name = self.sheet_or_current(sheet_name.or(range.sheet()))?;
+            range.check_sheet(&sheet_name)?;
+            let worksheet = self.worksheet_mut(&sheet_name)?;
+            let area = range.idx_in(worksheet);
+            check_area_limits("merge_cells", &sheet_name, area.0, (area.1.0 - area.0.0 + 1, area.1.1 - area.0.1 + 1))?;
+            if area.0 == area.1 {
+                return Err(range_error(format!("Range {} has a single cell to merge.", range.range()), Some(&sheet_name), Some(&range.range())));
+            }
+            let cleared = merge_cells(worksheet, area).map_err(|e| range_error(e, Some(&sheet_name), Some(&range.range())))?;
+            let warnings: Vec<Warning> = cleared.into_iter()
+                .map(|cell| Warning::new("cleared_by_merge", format!("Value of {} in {} cleared by merging {}.", cell, sheet_name, area_range(area))).with_cell(cell))
+                .collect();
+            emit_structured_warnings(py, warnings)
+        })
+    }
+
+    /// Splits the merged cells having a cell in a range of a sheet (default: the current sheet) back into cells
+    ///
+    /// The value stays in the top left cell. Returns the ranges of the merges split.
+    pub fn unmerge_cells(&mut self, range: ExcelRange, sheet_name: Option<&str>) -> PyResult<Vec<String>> {
+        catch_panic("unmerge_cells", || {
+            let range = range.resolve(&self.spreadsheet, sheet_name)?;
+            let sheet_name = self.sheet_or_current(sheet_name.or(range.sheet()))?;
+            range.check_sheet(&sheet_name)?;
+            let worksheet = self.worksheet_mut(&sheet_name)?;
+            let area = range.idx_in(worksheet);
+            Ok(unmerge_cells(worksheet, area))
+        })
+    }
+
+    /// Drops sheets without data below (or right of) their header when saving
+    pub fn drop_empty_sheets(&mut self, enabled: Option<bool>) -> PyResult<()> {
+        catch_panic("drop_empty_sheets", || {
+            self.drop_empty_sheets = enabled.unwrap_or(true);
+            debug!("Dropping empty sheets on save: {}", self.drop_empty_sheets);
+            Ok(())
+        })
+    }
+
+    /// Keeps a sheet in the saved file only if the predicate holds
+    ///
+    /// The predicate is either a boolean or a callable receiving the sheet name and returning a boolean.
+    /// The sheets are given by names or patterns, and the matching sheets are returned.
+    pub fn include_sheet_if(&mut self, py: Python, sheet_name: SheetSelector, predicate: PyObject) -> PyResult<Vec<String>> {
+        catch_panic("include_sheet_if", || {
+            let sheet_names = sheet_name.resolve(&self.sheet_names()?)?;
+            for sheet_name in &sheet_names {
+                self.sheet_inclusion_rules.insert(sheet_name.to_string(), predicate.clone_ref(py));
+                debug!("Inclusion rule registered for sheet {}", sheet_name);
+            }
+            Ok(sheet_names)
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn copy_range_from(
+        &mut self,
+        py: Python,
+        source_file_path: &PyAny,
+        source_sheet_name: SheetSelector,
+        source_ranges: CopyRanges,
+        transpose: Option<bool>,
+        coerce: Option<Coerce>,
+        with_styles: Option<bool>,
+        paste: Option<Paste>,
+        transform: Option<PyObject>,
+        skip_blanks: Option<bool>,
+        operation: Option<Operation>,
+        verbose: Option<bool>,
+    ) -> PyResult<Vec<Warning>> {
+        let _verbose = Verbose::new(py, verbose)?;
+        let paste = paste.unwrap_or_default();
+        let with_styles = with_styles.unwrap_or(false) || paste.formats();
+        let skip_blanks = skip_blanks.unwrap_or(false);
+        if with_styles && !cfg!(feature = "styles") {
+            return Err(missing_feature("copy_range_from with styles", "styles"));
+        }
+        catch_panic("copy_range_from", || {
+            let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
+        
+            let current_sheet_name = self.current_sheet_name
+                .as_ref()
+                .ok_or_else(|| template_error("No sheet specified. Use goto_sheet to set the sheet."))?
+                .to_string(); // Clone the string to avoid borrowing self
+
+            let worksheet = spreadsheet.get_sheet_by_name_mut(&current_sheet_name).ok_or_else(|| {
+                sheet_not_found(&current_sheet_name)
+            })?;
+
+            // Read the source workbook or return an error if it doesn't exist  
+            let (source_workbook, source_path) = Self::read_source(source_file_path, |names| source_sheet_name.resolve(names))?;
+            let source_sheet_name = source_sheet_name.resolve_one(&Self::names_of_sheets(&source_workbook))?;
+            let source_sheet = source_workbook.get_sheet_by_name(&source_sheet_name).ok_or_else(|| {
+                source_sheet_not_found(&source_sheet_name)
+            })?;
+            debug!("Source sheet {} found in {}", source_sheet_name, source_path.as_deref().unwrap_or("a template"));
+            let transpose = transpose.unwrap_or(false);
+            let coerce = coerce.unwrap_or(Coerce::None);
+            if let Coerce::Date = coerce {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("Coercion to dates is only supported by fill_with."));
+            }
+
+            let mut warnings: Vec<Warning> = Vec::new();
+            let mut cell_log = CellLog::new("copy_range_from");
+            // Each range is pasted at its own cell, a single range at the current cell
+            let blocks = match source_ranges {
+                CopyRanges::One(source_range) => {
+                    let current_cell = self.current_cell_in_current_sheet
+                        .clone()
+                        .ok_or_else(|| template_error("No cell specified. Use goto_cell to set the cell."))?;
+                    vec![(source_range, current_cell)]
+                },
+                CopyRanges::Many(blocks) => blocks,
+            };
+            // All the ranges are checked before any is pasted
+            let mut checked = Vec::with_capacity(blocks.len());
+            for (source_range, current_cell) in blocks {
+                // Match on the SourceRange enum to handle both cases, open ends stopping at the used range of the source
+                let source_range = source_range.resolve(&source_workbook, Some(&source_sheet_name))?;
+                source_range.check_sheet(&source_sheet_name)?;
+                let ((start_col, start_row), (end_col, end_row)) = source_range.idx_in(source_sheet);
+                let size = if transpose {
+                    (end_row - start_row + 1, end_col - start_col + 1)
+                } else {
+                    (end_col - start_col + 1, end_row - start_row + 1)
+                };
+                check_area_limits("copy_range_from", &current_sheet_name, current_cell.idx(), size)?;
+                checked.push((source_range, ((start_col, start_row), (end_col, end_row)), current_cell));
+            }
+            for (source_range, ((start_col, start_row), (end_col, end_row)), current_cell) in checked {
+                let (current_cell_col, current_cell_row) = current_cell.idx();
+                // Copy the range from the source sheet to the destination sheet
+                debug!("Copying range {} of {} to {} of {}", 
+                    source_range.range(), source_sheet_name, current_cell.range(), current_sheet_name);
+        
+                // Values falling on cells hidden by a merge go to its top left cell, unless the range fills that one too
+                let merges = MergedCells::of(worksheet);
+                let source_of = |(d_col, d_row): (u32, u32)| -> Option<(u32, u32)> {
+                    let (d_col, d_row) = (d_col.checked_sub(current_cell_col)?, d_row.checked_sub(current_cell_row)?);
+                    let (col, row) = if transpose { (start_col + d_row, start_row + d_col) } else { (start_col + d_col, start_row + d_row) };
+                    (col <= end_col && row <= end_row).then_some((col, row))
+                };
+                let copied = |cell: (u32, u32)| source_of(cell)
+                    .and_then(|source| source_sheet.get_cell(source))
+                    .is_some_and(|source_cell| !source_cell.get_value().is_empty() || source_cell.is_formula());
+                let mut redirected: HashSet<(u32, u32)> = HashSet::new();
+
+                for col in start_col..=end_col {
+                    for row in start_row..=end_row {
+                        if let Some(source_cell) = source_sheet.get_cell((col, row)) {
+                            let original_value = source_cell.get_value().to_string();
+                            // Blank cells are left out, so the destination keeps what it holds there
+                            if skip_blanks && original_value.is_empty() && !source_cell.is_formula() {
+                                cell_log.debug(|| format!("Cell {} is blank, skipped", index_to_excel(col, row)));
+                                continue;
+                            }
+                            // Calculate destination cell coordinates, with optional transposing
+                            let (d_col, d_row) = if transpose {
+                                (current_cell_col + row - start_row, current_cell_row + col - start_col)
+                            } else {
+                                (current_cell_col + col - start_col, current_cell_row + row - start_row)
+                            };
+                            if with_styles {
+                                worksheet.get_cell_mut((d_col, d_row)).set_style(source_cell.get_style().clone());
+                            }
+                            if !paste.values() {
+                                continue;
+                            }
+                            let value = match (&coerce, &self.write_options.number_locale) {
+                                (Coerce::String, Some(locale)) => locale.format_str(&original_value).unwrap_or_else(|| original_value.clone()),
+                                (Coerce::None, _) | (Coerce::String, _) => original_value.clone(),
+                                _ => match original_value.parse::<f64>() {
+                                    Ok(value) => {
+                                        match coerce {
+                                            Coerce::Integer => {
+                                                if value.fract() != 0.0 || value < i32::MIN as f64 || value > i32::MAX as f64 {
+                                                    let message = format!("Value {:?} at {} cannot be coerced to an integer without loss. Written as {}.", 
+                                                        original_value, index_to_excel(col, row), value as i32);
+                                                    warn!("{}", message);
+                                                    warnings.push(Warning::new("lossy_coercion", message).with_cell(index_to_excel(col, row)));
+                                                }
+                                                (value as i32).to_string()
+                                            },
+                                            _ => value.to_string(),
+                                        }
+                                    },
+                                    Err(_) => {
+                                        let message = format!("Value {:?} at {} is not a number. Ignored.", original_value, index_to_excel(col, row));
+                                        warn!("{}", message);
+                                        warnings.push(Warning::new("not_a_number", message).with_cell(index_to_excel(col, row)));
+                                        String::new()
+                                    },
+                                },
+                            };
+                            let (d_col, d_row) = match merges.hiding((d_col, d_row)) {
+                                None => (d_col, d_row),
+                                Some(_) if original_value.is_empty() && !source_cell.is_formula() => continue,
+                                Some(merge) if copied(merge.0) || !redirected.insert(merge.0) => {
+                                    let message = format!(
+                                        "Value {:?} of {} is hidden by the merged cells {} of {}. Not copied.",
+                                        original_value, index_to_excel(col, row), area_range(merge), current_sheet_name
+                                    );
+                                    warn!("{}", message);
+                                    warnings.push(Warning::new("hidden_by_merge", message).with_cell(index_to_excel(d_col, d_row)));
+                                    continue;
+                                },
+                                Some(merge) => merge.0,
+                            };
+                            // Formulas are pasted with their relative references moved along, as Excel does
+                            if paste.formulas() && !source_cell.get_formula().is_empty() {
+                                let formula = translate_formula(source_cell.get_formula(), d_col as i64 - col as i64, d_row as i64 - row as i64);
+                                let cell = worksheet.get_cell_mut((d_col, d_row));
+                                cell.set_blank();
+                                cell.set_formula(formula.clone());
+                                cell_log.debug(|| format!("Formula {:?} taken from {} and set to {:?} at {}",
+                                    source_cell.get_formula(), index_to_excel(col, row), formula, index_to_excel(d_col, d_row)));
+                                continue;
+                            }
+                            // The transform gets the value with the source and destination cells, and returns the value written
+                            let transformed: Option<Value> = match &transform {
+                                Some(transform) => {
+                                    let value = match coerce {
+                                        Coerce::String => Value::String(value.clone()),
+                                        _ => Value::parse(&value),
+                                    };
+                                    let transformed: Value = transform
+                                        .call1(py, (value.clone(), index_to_excel(col, row), index_to_excel(d_col, d_row)))?
+                                        .extract(py)?;
+                                    cell_log.debug(|| format!("Value {:?} of {} transformed to {:?}", value, index_to_excel(col, row), transformed));
+                                    Some(transformed)
+                                },
+                                None => None,
+                            };
+                            // Numbers are combined with the value of the destination, the other values pasted over it
+                            let number = match (&transformed, &coerce) {
+                                (Some(Value::Int(v)), _) => Some(*v as f64),
+                                (Some(Value::Float(v)), _) => Some(*v),
+                                (Some(_), _) | (None, Coerce::String) => None,
+                                // Blank cells count as 0, as in Excel
+                                (None, _) if value.is_empty() => Some(0.0),
+                                (None, _) => value.parse::<f64>().ok(),
+                            };
+                            if let (Some(operation), Some(number)) = (&operation, number) {
+                                match combine_value(worksheet.get_cell_mut((d_col, d_row)), operation, number, &self.write_options) {
+                                    Ok(result) => cell_log.debug(|| format!("Value {} of {} combined ({}) into {:?} at {}",
+                                        number, index_to_excel(col, row), operation.as_str(), result, index_to_excel(d_col, d_row))),
+                                    Err(reason) => {
+                                        let message = format!(
+                                            "Value {} of {} cannot be combined ({}) with the cell {} of {}: {}. Cell kept.",
+                                            number, index_to_excel(col, row), operation.as_str(), index_to_excel(d_col, d_row), current_sheet_name, reason
+                                        );
+                                        warn!("{}", message);
+                                        warnings.push(Warning::new("not_combined", message).with_cell(index_to_excel(d_col, d_row)));
+                                    },
+                                }
+                                continue;
+                            }
+                            if let Some(transformed) = transformed {
+                                write_value(worksheet.get_cell_mut((d_col, d_row)), &transformed, &self.write_options);
+                                cell_log.debug(|| format!("Value {:?} set at {}", transformed, index_to_excel(d_col, d_row)));
+                                continue;
+                            }
+                            // Attempt to set the value, numbers rendered with the locale separators stay text
+                            match (&coerce, &self.write_options.number_locale) {
+                                (Coerce::String, Some(_)) => worksheet.get_cell_mut((d_col, d_row)).set_value_string(&value),
+                                _ => worksheet.get_cell_mut((d_col, d_row)).set_value(&value),
+                            };
+                            cell_log.debug(|| format!("Value {:?} taken from {} and set to {:?} at {}", 
+                                original_value, index_to_excel(col, row), value, index_to_excel(d_col, d_row)));
+                        }
+                        else {
+                            cell_log.debug(|| format!("Cell {} is empty", index_to_excel(col, row)));
+                        }
+                    }
+                }
+                if with_styles {
+                    let merges = copy_layout(source_sheet, worksheet, ((start_col, start_row), (end_col, end_row)), (current_cell_col, current_cell_row), transpose);
+                    debug!("{} merged cells copied with the styles", merges);
+                }
+            }
+            emit_structured_warnings(py, warnings)
+        })
+    }
+
+    #[cfg(feature = "aggregate")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn aggregate_range_from(
+        &mut self,
+        py: Python,
+        source_file_path: &PyAny,
+        source_sheet_name: SheetSelector,
+        source_range: ExcelRange,
+        action: Action,
+        mode: Mode,
+        on_non_numeric: Option<NonNumeric>,
+        skip_blanks: Option<bool>,
+        criteria: Option<Criteria>,
+        criteria_range: Option<ExcelRange>,
+        as_formula: Option<bool>,
+        verbose: Option<bool>,
+    ) -> PyResult<Vec<Warning>> {
+        let _verbose = Verbose::new(py, verbose)?;
+        catch_panic("aggregate_range_from", || {
+            // Formulas follow Excel, which skips the texts and blanks of the range and has no function of Python
+            let as_formula = as_formula.unwrap_or(false);
+            if as_formula {
+                if action.is_custom() {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("as_formula needs a built-in action, not a function."));
+                }
+                if criteria.is_some() {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("as_formula cannot be combined with criteria."));
+                }
+                if matches!(on_non_numeric, Some(NonNumeric::Zero)) || skip_blanks == Some(false) {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("as_formula cannot take non-numeric or blank cells as 0."));
+                }
+            }
+            let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet"))?;
+        
+            let current_sheet_name = self.current_sheet_name
+                .as_ref()
+                .ok_or_else(|| template_error("No sheet specified. Use goto_sheet to set the sheet."))?
+                .to_string(); // Clone the string to avoid borrowing self
+
+            let worksheet = spreadsheet.get_sheet_by_name_mut(&current_sheet_name).ok_or_else(|| {
+                sheet_not_found(&current_sheet_name)
+            })?;
+
+            let current_cell = self.current_cell_in_current_sheet
+                .as_ref()
+                .ok_or_else(|| template_error("No cell specified. Use goto_cell to set the cell."))?;
+
+            // Read the source workbook or return an error if it doesn't exist  
+            let (source_workbook, source_path) = Self::read_source(source_file_path, |names| source_sheet_name.resolve(names))?;
+            let source_sheet_name = source_sheet_name.resolve_one(&Self::names_of_sheets(&source_workbook))?;
+            let source_sheet = source_workbook.get_sheet_by_name(&source_sheet_name)
+                .ok_or_else(|| source_sheet_not_found(&source_sheet_name))?;
+            debug!("Source sheet {} found in {}", source_sheet_name, source_path.as_deref().unwrap_or("a template"));
+    
+            // Match on the SourceRange enum to handle both cases, open ends stopping at the used range of the source
+            let source_range = source_range.resolve(&source_workbook, Some(&source_sheet_name))?;
+            source_range.check_sheet(&source_sheet_name)?;
+            let ((start_col, start_row), (end_col, end_row)) = source_range.idx_in(source_sheet);
+
+            // Copy the range from the source sheet to the destination sheet
+            let mut warnings: Vec<Warning> = Vec::new();
+            let mut policy = NumberPolicy::new(on_non_numeric, skip_blanks);
+            policy.condition = Self::aggregate_condition(
+                criteria, criteria_range, &source_workbook, Some(&source_sheet_name), ((start_col, start_row), (end_col, end_row)), source_sheet.get_highest_column_and_row(),
+            )?;
+            // The sheet is referenced in the workbook of the template, or as a sheet of another workbook
+            let qualifier = match (&self.file_path, &source_path) {
+                (Some(template_path), Some(source_path)) if Self::same_file(template_path, source_path) => quote_sheet_name(&source_sheet_name),
+                (_, Some(source_path)) => external_sheet_name(source_path, &source_sheet_name),
+                (_, None) if as_formula => {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("as_formula needs a source template loaded from a file."));
+                },
+                (_, None) => String::new(),
+            };
+            let formulas: Vec<String> = match mode {
+                Mode::Row => (start_row..=end_row)
+                    .filter_map(|row| aggregate_formula(&action, &format!("{}!{}:{}", qualifier, index_to_excel(start_col, row), index_to_excel(end_col, row))))
+                    .collect(),
+                Mode::Column => (start_col..=end_col)
+                    .filter_map(|col| aggregate_formula(&action, &format!("{}!{}:{}", qualifier, index_to_excel(col, start_row), index_to_excel(col, end_row))))
+                    .collect(),
+            };
+            let results = aggregate_range(&[source_sheet], start_row, start_col, end_row, end_col, action, mode.clone(), &policy, &mut warnings).map_err(|e| {
+                let err_msg = format!("Failed to aggregate range: {}", e);
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(err_msg)
+            })?;
+    
+            debug!("Results: {:?}", results);
+
+            let current_cell_idx = current_cell.idx();
+            let size = match mode {
+                Mode::Row => (1, results.len() as u32),
+                Mode::Column => (results.len() as u32, 1),
+            };
+            check_area_limits("aggregate_range_from", &current_sheet_name, current_cell_idx, size)?;
+            let mut cell_log = CellLog::new("aggregate_range_from");
+            for (i, value) in results.iter().enumerate() {
+                let (col, row) = match mode {
+                    Mode::Row => (current_cell_idx.0, current_cell_idx.1 + i as u32),
+                    Mode::Column => (current_cell_idx.0 + i as u32, current_cell_idx.1),
+                };
+                // Formulas are written with the result computed as cached value, if any
+                if as_formula {
+                    let cell = worksheet.get_cell_mut((col, row));
+                    if let Some(value) = value {
+                        cell.set_value_number(*value);
+                    }
+                    cell.set_formula(formulas[i].clone());
+                    cell_log.debug(|| format!("Pasted formula {} to cell {}", formulas[i], index_to_excel(col, row)));
+                    continue;
+                }
+                // Rows (or columns) with too few numbers for the action are left empty
+                let value = match value {
+                    Some(value) => value,
+                    None => continue,
+                };
+                match &self.write_options.number_locale {
+                    Some(locale) => worksheet.get_cell_mut((col, row)).set_value_string(locale.format(*value)),
+                    None => worksheet.get_cell_mut((col, row)).set_value(value.to_string()),
+                };
+                cell_log.debug(|| format!("Pasted value {} to cell {}", value, index_to_excel(col, row)));
+            }
+            emit_structured_warnings(py, warnings)
+        })
+    }
+
+    #[cfg(not(feature = "aggregate"))]
+    #[pyo3(signature = (*_args, **_kwargs))]
+    pub fn aggregate_range_from(&mut self, _args: &PyTuple, _kwargs: Option<&PyDict>) -> PyResult<()> {
+        Err(missing_feature("aggregate_range_from", "aggregate"))
+    }
+
+    /// Aggregates the same range across several sheets of a file, cell per cell (3-D aggregation)
+    ///
+    /// The grid of results, of the size of the range, is pasted at the current cell. Cells with too few
+    /// numbers for the action (none for an average, one for a variance) are left empty.
+    #[cfg(feature = "aggregate")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn aggregate_across_sheets(
+        &mut self,
+        py: Python,
+        source_file_path: &PyAny,
+        sheets: SheetSelector,
+        source_range: ExcelRange,
+        action: Action,
+        on_non_numeric: Option<NonNumeric>,
+        skip_blanks: Option<bool>,
+        criteria: Option<Criteria>,
+        criteria_range: Option<ExcelRange>,
+    ) -> PyResult<Vec<Warning>> {
+        catch_panic("aggregate_across_sheets", || {
+            let number_locale = self.write_options.number_locale.clone();
+            let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
+                .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
+
+            let current_sheet_name = self.current_sheet_name
+                .as_ref()
+                .ok_or_else(|| template_error("No sheet specified. Use goto_sheet to set the sheet."))?
+                .to_string(); // Clone the string to avoid borrowing self
+
+            let worksheet = spreadsheet.get_sheet_by_name_mut(&current_sheet_name).ok_or_else(|| {
+                sheet_not_found(&current_sheet_name)
+            })?;
+
+            let current_cell = self.current_cell_in_current_sheet
+                .as_ref()
+                .ok_or_else(|| template_error("No cell specified. Use goto_cell to set the cell."))?;
+
+            if sheets.0.is_empty() {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("At least one source sheet must be given."));
+            }
+
+            // Read the source workbook or return an error if it doesn't exist  
+            let (source_workbook, source_path) = Self::read_source(source_file_path, |names| sheets.resolve(names))?;
+            let sheets = sheets.resolve(&Self::names_of_sheets(&source_workbook))?;
+            let source_sheets = sheets.iter()
+                .map(|sheet_name| source_workbook.get_sheet_by_name(sheet_name).ok_or_else(|| {
+                    source_sheet_not_found(sheet_name)
+                }))
+                .collect::<PyResult<Vec<&Worksheet>>>()?;
+            debug!("Aggregating {} of {} sheets of {}", source_range.range(), source_sheets.len(), source_path.as_deref().unwrap_or("a template"));
+
+            // A defined name gives the cells aggregated on each sheet, whatever the sheet it refers to
+            let source_range = match source_range.resolve(&source_workbook, None)? {
+                ExcelRange::Reference { first, last, .. } if matches!(source_range, ExcelRange::Name(_)) => ExcelRange::Reference { sheet: None, first, last },
+                range => range,
+            };
+            if source_range.sheet().is_some() {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("The range of aggregate_across_sheets cannot name a sheet."));
+            }
+            // Open ends stop at the used range of the largest sheet
+            let highest = source_sheets.iter().map(|ws| ws.get_highest_column_and_row()).fold((0, 0), |a, b| (a.0.max(b.0), a.1.max(b.1)));
+            let ((start_col, start_row), (end_col, end_row)) = source_range.bounds(highest);
+            let mut warnings: Vec<Warning> = Vec::new();
+            let mut policy = NumberPolicy::new(on_non_numeric, skip_blanks);
+            policy.condition = Self::aggregate_condition(criteria, criteria_range, &source_workbook, None, ((start_col, start_row), (end_col, end_row)), highest)?;
+            let grid = aggregate_across_sheets(&source_sheets, start_row, start_col, end_row, end_col, action, &policy, &mut warnings)
+                .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to aggregate range: {}", e)))?;
+
+            let (current_col, current_row) = current_cell.idx();
+            check_area_limits("aggregate_across_sheets", &current_sheet_name, (current_col, current_row), (end_col - start_col + 1, end_row - start_row + 1))?;
+            let mut cell_log = CellLog::new("aggregate_across_sheets");
+            for (i, results) in grid.iter().enumerate() {
+                for (j, value) in results.iter().enumerate() {
+                    let (col, row) = (current_col + j as u32, current_row + i as u32);
+                    let value = match value {
+                        Some(value) => *value,
+                        None => continue,
+                    };
+                    match &number_locale {
+                        Some(locale) => worksheet.get_cell_mut((col, row)).set_value_string(locale.format(value)),
+                        None => worksheet.get_cell_mut((col, row)).set_value_number(value),
+                    };
+                    cell_log.debug(|| format!("Pasted value {} to cell {}", value, index_to_excel(col, row)));
+                }
+            }
+            emit_structured_warnings(py, warnings)
+        })
+    }
+
+    #[cfg(not(feature = "aggregate"))]
+    #[pyo3(signature = (*_args, **_kwargs))]
+    pub fn aggregate_across_sheets(&mut self, _args: &PyTuple, _kwargs: Option<&PyDict>) -> PyResult<()> {
+        Err(missing_feature("aggregate_across_sheets", "aggregate"))
+    }
+
+    /// Aggregates the same range across several workbooks, e.g. monthly files into a yearly summary
+    ///
+    /// `files` are paths or glob patterns of file names ("2024-*.xlsx"), and the range is read on the sheet
+    /// `source_sheet_name` selects in each of them. Without `mode`, the range is aggregated cell per cell and
+    /// the grid of results, of the size of the range, is pasted at the current cell. With 'row' (or 'col'),
+    /// the numbers of each row (or column) of all the files are aggregated together, the results being pasted
+    /// down (or across) from the current cell. Results with too few numbers for the action are left empty.
+    /// The files are read in `max_workers` worker threads at most (default: one per core).
+    #[cfg(feature = "aggregate")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn aggregate_across_files(
+        &mut self,
+        py: Python,
+        files: FileSelector,
+        source_sheet_name: SheetSelector,
+        source_range: ExcelRange,
+        action: Action,
+        mode: Option<Mode>,
+        on_non_numeric: Option<NonNumeric>,
+        skip_blanks: Option<bool>,
+        criteria: Option<Criteria>,
+        criteria_range: Option<ExcelRange>,
+        max_workers: Option<usize>,
+    ) -> PyResult<Vec<Warning>> {
+        catch_panic("aggregate_across_files", || {
+            let current_sheet_name = self.sheet_or_current(None)?;
+            let (current_col, current_row) = self.current_cell_in_current_sheet
+                .as_ref()
+                .ok_or_else(|| template_error("No cell specified. Use goto_cell to set the cell."))?
+                .idx();
+
+            let files = files.resolve()?;
+            let sources = read_files(py, &files, max_workers, |file_path| -> PyResult<(Arc<Spreadsheet>, String)> {
+                let workbook = Self::load_sheets(file_path, |names| source_sheet_name.resolve(names))?;
+                let sheet_name = source_sheet_name.resolve_one(&Self::names_of_sheets(&workbook))?;
+                Ok((workbook, sheet_name))
+            })?;
+            let mut workbooks: Vec<(String, Arc<Spreadsheet>, String)> = Vec::new();
+            for (file_path, source) in files.into_iter().zip(sources) {
+                let (workbook, sheet_name) = source.map_err(|e| {
+                    PyErr::from_type(e.get_type(py), format!("Failed to read '{}': {}", file_path, e.value(py)))
+                })?;
+                workbooks.push((file_path, workbook, sheet_name));
+            }
+
+            // The range (or the name, defined in each file) has to give the same cells in all the files
+            let mut ranges: Vec<(&str, ExcelRange)> = Vec::new();
+            for (file_path, workbook, sheet_name) in &workbooks {
+                let range = source_range.resolve(workbook, Some(sheet_name))?;
+                range.check_sheet(sheet_name)?;
+                if let Some((first_file, first_range)) = ranges.first() {
+                    if first_range.idx() != range.idx() {
+                        return Err(range_error(
+                            format!("Range {} refers to {} in '{}' and to {} in '{}'.", source_range.range(), first_range.range(), first_file, range.range(), file_path),
+                            Some(sheet_name),
+                            Some(&source_range.range()),
+                        ));
+                    }
+                }
+                ranges.push((file_path, range));
+            }
+            let source_sheets = workbooks.iter()
+                .map(|(_, workbook, sheet_name)| workbook.get_sheet_by_name(sheet_name).ok_or_else(|| source_sheet_not_found(sheet_name)))
+                .collect::<PyResult<Vec<&Worksheet>>>()?;
+            let range = match ranges.first() {
+                Some((_, range)) => range,
+                None => return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("At least one source file must be given.")),
+            };
+            // Open ends stop at the used range of the largest sheet
+            let highest = source_sheets.iter().map(|ws| ws.get_highest_column_and_row()).fold((0, 0), |a, b| (a.0.max(b.0), a.1.max(b.1)));
+            let ((start_col, start_row), (end_col, end_row)) = range.bounds(highest);
+            debug!("Aggregating {} of {} files", range.range(), source_sheets.len());
+
+            let mut warnings: Vec<Warning> = Vec::new();
+            let mut policy = NumberPolicy::new(on_non_numeric, skip_blanks);
+            let (_, first_workbook, first_sheet_name) = &workbooks[0];
+            policy.condition = Self::aggregate_condition(
+                criteria, criteria_range, first_workbook, Some(first_sheet_name), ((start_col, start_row), (end_col, end_row)), highest,
+            )?;
+            let grid: Vec<Vec<Option<f64>>> = match &mode {
+                None => aggregate_across_sheets(&source_sheets, start_row, start_col, end_row, end_col, action, &policy, &mut warnings)
+                    .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to aggregate range: {}", e)))?,
+                Some(mode) => {
+                    let results = aggregate_range(&source_sheets, start_row, start_col, end_row, end_col, action, mode.clone(), &policy, &mut warnings)
+                        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to aggregate range: {}", e)))?;
+                    match mode {
+                        Mode::Row => results.into_iter().map(|result| vec![result]).collect(),
+                        Mode::Column => vec![results],
+                    }
+                },
+            };
+
+            let size = (grid.first().map_or(0, |results| results.len()) as u32, grid.len() as u32);
+            check_area_limits("aggregate_across_files", &current_sheet_name, (current_col, current_row), size)?;
+            let number_locale = self.write_options.number_locale.clone();
+            let worksheet = self.worksheet_mut(&current_sheet_name)?;
+            let mut cell_log = CellLog::new("aggregate_across_files");
+            for (i, results) in grid.iter().enumerate() {
+                for (j, value) in results.iter().enumerate() {
+                    let (col, row) = (current_col + j as u32, current_row + i as u32);
+                    let value = match value {
+                        Some(value) => *value,
+                        None => continue,
+                    };
+                    match &number_locale {
+                        Some(locale) => worksheet.get_cell_mut((col, row)).set_value_string(locale.format(value)),
+                        None => worksheet.get_cell_mut((col, row)).set_value_number(value),
+                    };
+                    cell_log.debug(|| format!("Pasted value {} to cell {}", value, index_to_excel(col, row)));
+                }
+            }
+            emit_structured_warnings(py, warnings)
+        })
+    }
+
+    #[cfg(not(feature = "aggregate"))]
+    #[pyo3(signature = (*_args, **_kwargs))]
+    pub fn aggregate_across_files(&mut self, _args: &PyTuple, _kwargs: Option<&PyDict>) -> PyResult<()> {
+        Err(missing_feature("aggregate_across_files", "aggregate"))
+    }
+
+    /// Defines a name of the workbook (e.g. "SalesData") referring to a range, replacing a previous definition
+    ///
+    /// The range is on the sheet it is qualified with, else `sheet_name`, else the current sheet. The name
+    /// can then be given in place of a range, e.g. to copy_range_from or aggregate_range_from.
+    pub fn define_name(&mut self, name: &str, range: ExcelRange, sheet_name: Option<&str>) -> PyResult<()> {
+        catch_panic("define_name", || {
+            let name = name.trim();
+            if !is_defined_name(name) {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Invalid name {:?}. Expected a letter or underscore followed by letters, digits, underscores or periods, other than a cell reference.", name
+                )));
+            }
+            let range = range.resolve(&self.spreadsheet, sheet_name)?;
+            let sheet_name = self.sheet_or_current(sheet_name.or(range.sheet()))?;
+            range.check_sheet(&sheet_name)?;
+            let address = range.address(&sheet_name);
+
+            let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
+                .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
+            if spreadsheet.get_sheet_by_name(&sheet_name).is_none() {
+                return Err(sheet_not_found(&sheet_name));
+            }
+            // Previous definitions of the workbook are replaced, the names scoped to a sheet are kept
+            spreadsheet.get_defined_names_mut().retain(|defined_name| !defined_name.get_name().eq_ignore_ascii_case(name));
+            for worksheet in spreadsheet.get_sheet_collection_mut().iter_mut() {
+                worksheet.get_defined_names_mut()
+                    .retain(|defined_name| defined_name.has_local_sheet_id() || !defined_name.get_name().eq_ignore_ascii_case(name));
+            }
+            // Names of the workbook live in the sheet they refer to, as when read
+            let worksheet = spreadsheet.get_sheet_by_name_mut(&sheet_name).ok_or_else(|| sheet_not_found(&sheet_name))?;
+            worksheet.add_defined_name(name.to_string(), address.clone()).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to define name {}: {}.", name, e))
+            })?;
+            debug!("Name {} defined as {}", name, address);
+            Ok(())
+        })
+    }
+
+    /// Renders numbers written from now on as text with the given separators (e.g. "," and " ")
+    ///
+    /// Applies to fill_with, set_value/write_cell, aggregation pastes and copies coerced to string.
+    pub fn set_number_locale(&mut self, decimal_separator: &str, thousands_separator: Option<&str>) -> PyResult<()> {
+        catch_panic("set_number_locale", || {
+            if decimal_separator.is_empty() || thousands_separator == Some(decimal_separator) {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("Decimal separator must be non-empty and differ from the thousands separator."));
+            }
+            self.write_options.number_locale = Some(NumberLocale {
+                decimal_separator: decimal_separator.to_string(),
+                thousands_separator: thousands_separator.map(|s| s.to_string()),
+            });
+            debug!("Number locale set to {:?}", self.write_options.number_locale);
+            Ok(())
+        })
+    }
+
+    /// Sets the number format applied to integers of at least `min_digits` digits (default "0" from 12 digits)
+    ///
+    /// This keeps IDs and amounts from being displayed in scientific notation. Use "General" to disable it.
+    pub fn set_integer_format(&mut self, format_code: Option<String>, min_digits: Option<u32>) -> PyResult<()> {
+        catch_panic("set_integer_format", || {
+            let default = IntegerFormat::default();
+            self.write_options.integer_format = IntegerFormat {
+                format_code: format_code.unwrap_or(default.format_code),
+                min_digits: min_digits.unwrap_or(default.min_digits),
+            };
+            debug!("Integer format set to {:?}", self.write_options.integer_format);
+            Ok(())
+        })
+    }
+
+    /// Writes numbers as numbers again
+    pub fn reset_number_locale(&mut self) -> PyResult<()> {
+        catch_panic("reset_number_locale", || {
+            self.write_options.number_locale = None;
+            Ok(())
+        })
+    }
+
+    /// Sets the default options of fill_with, for all sheets or for the given sheet only
+    ///
+    /// Options given in a fill_with call take precedence over the sheet defaults, which take precedence
+    /// over the template defaults. `null_display` is written in place of null values and `styles` maps
+    /// column names to the number format of their cells. `date_format` is the number format of the date
+    /// columns and `truncate` whether the rows below the data are removed. Calling it again replaces the
+    /// previous profile.
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_defaults(
+        &mut self,
+        mode: Option<Mode>,
+        strict: Option<bool>,
+        skip_null: Option<bool>,
+        overwrite: Option<bool>,
+        protect: Option<bool>,
+        null_display: Option<String>,
+        styles: Option<HashMap<String, String>>,
+        allow_gaps: Option<bool>,
+        date_format: Option<String>,
+        truncate: Option<bool>,
+        formula_columns: Option<Vec<String>>,
+        sheet_name: Option<&str>,
+    ) -> PyResult<()> {
+        if styles.is_some() && !cfg!(feature = "styles") {
+            return Err(missing_feature("set_defaults with styles", "styles"));
+        }
+        let defaults = FillDefaults {
+            mode,
+            strict,
+            skip_null,
+            overwrite,
+            protect,
+            null_display,
+            styles: styles.unwrap_or_default(),
+            allow_gaps,
+            date_format,
+            truncate,
+            formula_columns,
+        };
+        match sheet_name {
+            Some(sheet_name) => {
+                if self.spreadsheet.get_sheet_by_name(sheet_name).is_none() {
+                    return Err(sheet_not_found(sheet_name));
+                }
+                debug!("Defaults of sheet {} set to {:?}", sheet_name, defaults);
+                self.sheet_fill_defaults.insert(sheet_name.to_string(), defaults);
+            },
+            None => {
+                debug!("Defaults set to {:?}", defaults);
+                self.fill_defaults = defaults;
+            },
+        }
+        Ok(())
+    }
+
+    /// Returns the default options applying to a sheet (or to all sheets) as a dictionary
+    pub fn get_defaults(&self, sheet_name: Option<&str>) -> PyResult<FillDefaults> {
+        catch_panic("get_defaults", || {
+            Ok(self.defaults_for(sheet_name))
+        })
+    }
+
+    /// Removes the default options of the given sheet, or all default options
+    pub fn reset_defaults(&mut self, sheet_name: Option<&str>) -> PyResult<()> {
+        catch_panic("reset_defaults", || {
+            match sheet_name {
+                Some(sheet_name) => {
+                    self.sheet_fill_defaults.remove(sheet_name);
+                },
+                None => {
+                    self.fill_defaults = FillDefaults::default();
+                    self.sheet_fill_defaults.clear();
+                },
+            }
+            Ok(())
+        })
+    }
+
+    /// Adds a checkbox linked to a cell of a sheet (default: the current sheet), as a TRUE/FALSE dropdown in the cell itself
+    pub fn add_checkbox(&mut self, cell: ExcelCell, checked: Option<bool>, label: Option<String>, sheet_name: Option<&str>) -> PyResult<()> {
+        catch_panic("add_checkbox", || {
+            let sheet_name = self.sheet_or_current(sheet_name)?;
+            let (col, row) = cell.idx();
+            check_cell_limits("add_checkbox", &sheet_name, col as u64, row as u64)?;
+            let worksheet = self.worksheet_mut(&sheet_name)?;
+            add_dropdown(worksheet, &cell, &["TRUE".to_string(), "FALSE".to_string()], label)?;
+            worksheet.get_cell_mut(cell.idx()).set_value_bool(checked.unwrap_or(false));
+            Ok(())
+        })
+    }
+
+    /// Adds option buttons linked to a cell of a sheet (default: the current sheet), as a dropdown of the options in the cell itself
+    pub fn add_option_buttons(&mut self, cell: ExcelCell, options: Vec<String>, selected: Option<String>, label: Option<String>, sheet_name: Option<&str>) -> PyResult<()> {
+        catch_panic("add_option_buttons", || {
+            let sheet_name = self.sheet_or_current(sheet_name)?;
+            if let Some(selected) = selected.as_ref() {
+                if !options.contains(selected) {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Selected option {:?} is not one of {:?}.", selected, options)));
+                }
+            }
+            let (col, row) = cell.idx();
+            check_cell_limits("add_option_buttons", &sheet_name, col as u64, row as u64)?;
+            let worksheet = self.worksheet_mut(&sheet_name)?;
+            add_dropdown(worksheet, &cell, &options, label)?;
+            match selected {
+                Some(selected) => worksheet.get_cell_mut(cell.idx()).set_value_string(selected),
+                None => worksheet.get_cell_mut(cell.idx()).set_blank(),
+            };
+            Ok(())
+        })
+    }
+
+    /// Restricts the values of a range of a sheet (default: the current sheet) with a data validation
+    ///
+    /// 'list' (default) gives a dropdown of `values` or of the cells of `source_range`, 'whole', 'decimal',
+    /// 'date' and 'text_length' compare to `minimum` and/or `maximum` with `operator`, and 'custom' accepts
+    /// the values for which `formula` is TRUE. Bounds are numbers, dates or formulas.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_data_validation(
+        &mut self,
+        range: ExcelRange,
+        kind: Option<ValidationKind>,
+        values: Option<Vec<String>>,
+        source_range: Option<ExcelRange>,
+        operator: Option<ValidationOperator>,
+        minimum: Option<ValidationBound>,
+        maximum: Option<ValidationBound>,
+        formula: Option<String>,
+        allow_blank: Option<bool>,
+        prompt: Option<String>,
+        error_message: Option<String>,
+        sheet_name: Option<&str>,
+    ) -> PyResult<()> {
+        catch_panic("add_data_validation", || {
+            let sheet_name = self.sheet_or_current(sheet_name)?;
+            let kind = kind.unwrap_or_default();
+            let range = range.resolve(&self.spreadsheet, Some(&sheet_name))?;
+            range.check_sheet(&sheet_name)?;
+            if kind != ValidationKind::List && (values.is_some() || source_range.is_some()) {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("values and source_range only apply to 'list' validations."));
+            }
+            if kind != ValidationKind::Custom && formula.is_some() {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("formula only applies to 'custom' validations."));
+            }
+            if matches!(kind, ValidationKind::List | ValidationKind::Custom) && (operator.is_some() || minimum.is_some() || maximum.is_some()) {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "operator, minimum and maximum do not apply to '{}' validations.", kind.as_str()
+                )));
+            }
+            let validation = match kind {
+                ValidationKind::List => match (values, source_range) {
+                    (Some(values), None) => Validation::list(&values).map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?,
+                    (None, Some(source_range)) => {
+                        let source_range = source_range.resolve(&self.spreadsheet, Some(&sheet_name))?;
+                        let source_sheet = source_range.sheet().unwrap_or(&sheet_name);
+                        if self.spreadsheet.get_sheet_by_name(source_sheet).is_none() {
+                            return Err(sheet_not_found(source_sheet));
+                        }
+                        Validation::list_of(source_range.address(&sheet_name))
+                    },
+                    _ => return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("A 'list' validation requires either values or source_range.")),
+                },
+                ValidationKind::Custom => match formula {
+                    Some(formula) => Validation::custom(&formula),
+                    None => return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("A 'custom' validation requires a formula.")),
+                },
+                kind => Validation::bounded(kind, operator, minimum, maximum).map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?,
+            };
+
+            // Whole columns and rows are validated to the limits of Excel, for the rows still to be entered
+            let ((col1, row1), (col2, row2)) = range.idx();
+            check_area_limits("add_data_validation", &sheet_name, (col1, row1), (col2 - col1 + 1, row2 - row1 + 1))?;
+            let sqref = match (col1, row1) == (col2, row2) {
+                true => index_to_excel(col1, row1),
+                false => format!("{}:{}", index_to_excel(col1, row1), index_to_excel(col2, row2)),
+            };
+            let worksheet = self.worksheet_mut(&sheet_name)?;
+            add_validation(worksheet, validation.to_data_validation(&sqref, allow_blank.unwrap_or(true), prompt, error_message));
+            debug!("Validation {} added at {} in {}", validation.kind.as_str(), sqref, sheet_name);
+            Ok(())
+        })
+    }
+
+    /// Reads back the value of a cell linked to a checkbox or option buttons, in a sheet (default: the current sheet)
+    pub fn get_control_value(&self, cell: ExcelCell, sheet_name: Option<&str>) -> PyResult<Value> {
+        catch_panic("get_control_value", || {
+            let sheet_name = self.sheet_or_current(sheet_name)?;
+            let worksheet = self.spreadsheet.get_sheet_by_name(&sheet_name).ok_or_else(|| {
+                sheet_not_found(&sheet_name)
+            })?;
+            Ok(read_control_value(worksheet, &cell))
+        })
+    }
+
+    /// Returns the provenance recorded for the filled columns, optionally for one sheet only
+    pub fn get_provenance(&self, sheet_name: Option<&str>) -> PyResult<Vec<HashMap<String, String>>> {
+        catch_panic("get_provenance", || {
+            let mut provenance: Vec<HashMap<String, String>> = Vec::new();
+            for record in read_records(&self.spreadsheet, PROVENANCE_SHEET) {
+                if let [sheet, range, column, key, value, ..] = record.as_slice() {
+                    if sheet_name.is_some_and(|s| s != sheet) {
+                        continue;
+                    }
+                    let same_column = provenance.last().is_some_and(|p| p["sheet"] == *sheet && p["range"] == *range && p["column"] == *column);
+                    if !same_column {
+                        provenance.push(HashMap::from([
+                            ("sheet".to_string(), sheet.clone()),
+                            ("range".to_string(), range.clone()),
+                            ("column".to_string(), column.clone()),
+                        ]));
+                    }
+                    if let Some(p) = provenance.last_mut() {
+                        p.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            Ok(provenance)
+        })
+    }
+
+    /// Returns the regions filled in the workbook by this and earlier runs, oldest first, optionally for one sheet only
+    ///
+    /// :return: A list of dictionaries with the 'sheet', the 'header' cell, the 'mode', the data 'range'
+    ///     and the time it was 'filled_at' (UTC, ISO 8601).
+    pub fn get_regions(&self, sheet_name: Option<&str>) -> PyResult<Vec<Region>> {
+        catch_panic("get_regions", || {
+            Ok(read_regions(&self.spreadsheet).into_iter()
+                .filter(|region| sheet_name.is_none_or(|s| s == region.sheet))
+                .collect())
+        })
+    }
+
+    /// Clears the data of the regions filled in the workbook, so the same template can be filled again
+    ///
+    /// The values and formulas of the cells of the regions of the registry are removed, their styles
+    /// and the rest of the sheets (headers, validations, tables) are kept. The registry itself is kept,
+    /// so later fills, in this or a later run, find the headers again, and the current cell goes back to
+    /// the header of the current sheet as after goto_sheet.
+    /// :param regions: The sheets whose regions are cleared, by name or pattern, all by default.
+    /// :return: The regions cleared, the latest record of each.
+    pub fn reset_data(&mut self, regions: Option<SheetSelector>) -> PyResult<Vec<Region>> {
+        catch_panic("reset_data", || {
+            let sheet_names = match regions {
+                Some(selector) => selector.resolve(&self.sheet_names()?)?,
+                None => self.sheet_names()?,
+            };
+            // A region filled several times is cleared once
+            let mut seen = HashSet::new();
+            let mut cleared: Vec<Region> = read_regions(&self.spreadsheet).into_iter().rev()
+                .filter(|region| sheet_names.contains(&region.sheet))
+                .filter(|region| seen.insert((region.sheet.clone(), region.range.clone())))
+                .collect();
+            cleared.reverse();
+
+            for region in &cleared {
+                let worksheet = self.worksheet_mut(&region.sheet)?;
+                let cells = clear_values(worksheet, ExcelRange::String(region.range.clone()).idx());
+                debug!("Region {} of {} cleared: {} cells", region.range, region.sheet, cells);
+            }
+            if let Some(sheet_name) = &self.current_sheet_name {
+                if let Some((header, _)) = self.header_locations.get(sheet_name) {
+                    self.current_cell_in_current_sheet = Some(header.clone());
+                }
+            }
+            info!("{} regions cleared", cleared.len());
+            Ok(cleared)
+        })
+    }
+
+    /// Tags a cell with a machine-readable key and value (e.g. a lineage id or a validation status)
+    ///
+    /// Tags are kept in a hidden sheet of the workbook, so post-processors can find regions by tag
+    /// instead of coordinates. Tagging a cell again with the same key replaces its value.
+    pub fn tag_cell(&mut self, cell: ExcelCell, key: &str, value: &str, sheet_name: Option<&str>) -> PyResult<()> {
+        catch_panic("tag_cell", || {
+            let sheet_name = self.sheet_or_current(sheet_name)?;
+            let (col, row) = cell.idx();
+            check_cell_limits("tag_cell", &sheet_name, col as u64, row as u64)?;
+            self.worksheet_mut(&sheet_name)?;
+
+            let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
+                .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
+            let record = [sheet_name.clone(), index_to_excel(col, row), key.to_string(), value.to_string()];
+            append_record(spreadsheet, TAGS_SHEET, &record).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to tag cell: {}.", e))
+            })?;
+            debug!("Cell {} in {} tagged {} = {}", index_to_excel(col, row), sheet_name, key, value);
+            Ok(())
+        })
+    }
+
+    /// Returns the tags of the cells of a sheet (default: the current sheet), optionally within a range
+    ///
+    /// :return: A dictionary of the tags ({key: value}) of each tagged cell, by cell (e.g. "B5").
+    pub fn get_tags(&self, range: Option<ExcelRange>, sheet_name: Option<&str>) -> PyResult<HashMap<String, HashMap<String, String>>> {
+        catch_panic("get_tags", || {
+            let range = range.map(|range| range.resolve(&self.spreadsheet, sheet_name)).transpose()?;
+            let sheet_name = self.sheet_or_current(sheet_name.or(range.as_ref().and_then(|range| range.sheet())))?;
+            if let Some(range) = &range {
+                range.check_sheet(&sheet_name)?;
+            }
+            let bounds = range.map(|range| range.idx());
+
+            let mut tags: HashMap<String, HashMap<String, String>> = HashMap::new();
+            for record in read_records(&self.spreadsheet, TAGS_SHEET) {
+                if let [sheet, cell, key, value, ..] = record.as_slice() {
+                    if *sheet != sheet_name {
+                        continue;
+                    }
+                    let (col, row) = excel_to_index(cell);
+                    if let Some(((first_col, first_row), (last_col, last_row))) = bounds {
+                        if col < first_col || col > last_col || row < first_row || row > last_row {
+                            continue;
+                        }
+                    }
+                    // Later records replace the earlier values of the same key
+                    tags.entry(cell.clone()).or_default().insert(key.clone(), value.clone());
+                }
+            }
+            Ok(tags)
+        })
+    }
+
+    /// Reads a table of a sheet (default: the current sheet) back into a Polars DataFrame
+    ///
+    /// With a `range`, its first row (or `header_row`) holds the column names and the rows below, up to
+    /// the end of the range, the data. Without, the columns are read on `header_row` from column A, by
+    /// default the registered header of the sheet (or row 1), down to the first empty row. Columns
+    /// holding only numbers become Float64, only booleans Boolean, anything else String.
+    #[cfg(feature = "dataframe")]
+    pub fn to_polars(&self, py: Python, header_row: Option<u32>, range: Option<ExcelRange>, sheet_name: Option<&str>) -> PyResult<PyObject> {
+        catch_panic("to_polars", || {
+            let sheet_name = self.sheet_or_current(sheet_name)?;
+            let worksheet = self.spreadsheet.get_sheet_by_name(&sheet_name).ok_or_else(|| {
+                sheet_not_found(&sheet_name)
+            })?;
+            if header_row == Some(0) {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("Invalid header row 0. Row numbering starts at 1."));
+            }
+
+            let range = range.map(|range| range.resolve(&self.spreadsheet, Some(&sheet_name))).transpose()?;
+            if let Some(range) = &range {
+                range.check_sheet(&sheet_name)?;
+            }
+            let ((header_col, header_row), last_col, last_row) = match range.map(|range| range.idx_in(worksheet)) {
+                Some(((first_col, first_row), (last_col, last_row))) => {
+                    let header_row = header_row.unwrap_or(first_row);
+                    if header_row < first_row || header_row > last_row {
+                        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                            "Header row {} is outside of the range {}:{}.", header_row, index_to_excel(first_col, first_row), index_to_excel(last_col, last_row)
+                        )));
+                    }
+                    ((first_col, header_row), Some(last_col), Some(last_row))
+                },
+                None => {
+                    let header = match (header_row, self.header_locations.get(&sheet_name)) {
+                        (Some(row), _) => (1, row),
+                        (None, Some((header, Mode::Row))) => header.idx(),
+                        (None, _) => (1, 1),
+                    };
+                    (header, None, None)
+                },
+            };
+
+            let mut df = extract_table(worksheet, header_col, header_row, last_col, last_row).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to read {}: {}", sheet_name, e))
+            })?;
+            debug!("{} rows of {} columns read from {}", df.height(), df.width(), sheet_name);
+            rust_polars_df_to_py_polars_df(py, &mut df)
+        })
+    }
+
+    #[cfg(not(feature = "dataframe"))]
+    #[pyo3(signature = (*_args, **_kwargs))]
+    pub fn to_polars(&self, _args: &PyTuple, _kwargs: Option<&PyDict>) -> PyResult<()> {
+        Err(missing_feature("to_polars", "dataframe"))
+    }
+
+    /// The options of the fill (mode, strict, skip_null...) are keyword arguments, read into FillOptions.
+    #[cfg(feature = "dataframe")]
+    #[pyo3(signature = (df, columns=None, *, validate_only=None, verbose=None, **options))]
+    pub fn fill_with(
+        &mut self,
+        py: Python,
+        df: PyObject,
+        columns: Option<PyObject>,
+        validate_only: Option<bool>,
+        verbose: Option<bool>,
+        options: Option<&PyDict>,
+    ) -> PyResult<PyObject> {
+        let _verbose = Verbose::new(py, verbose)?;
+        let options = FillOptions::from_kwargs(options)?;
+        catch_panic("fill_with", || self.fill_object(py, df.as_ref(py), columns, options, validate_only.unwrap_or(false)))
+    }
+
+
+    #[cfg(not(feature = "dataframe"))]
+    #[pyo3(signature = (*_args, **_kwargs))]
+    pub fn fill_with(&mut self, _args: &PyTuple, _kwargs: Option<&PyDict>) -> PyResult<()> {
+        Err(missing_feature("fill_with", "dataframe"))
+    }
+
+    /// Fills several sheets in one pass, with the same options as fill_with
+    ///
+    /// `data` maps sheet names to their data, filled in order at the header location of each sheet
+    /// (set with set_header_location or registered by a previous run). The workbook is loaded and saved
+    /// once for all sheets. Sheets that do not exist or have no header location raise a `ValueError`
+    /// before any sheet is filled. The current sheet and cell are left as they were.
+    #[cfg(feature = "dataframe")]
+    #[pyo3(signature = (data, columns=None, **options))]
+    pub fn fill_many(&mut self, py: Python, data: &PyDict, columns: Option<PyObject>, options: Option<&PyDict>) -> PyResult<()> {
+        catch_panic("fill_many", || {
+            let options = FillOptions::from_kwargs(options)?;
+            let sheet_names = self.sheet_names()?;
+            let mut sheets: Vec<(String, PyObject)> = Vec::new();
+            for (sheet_name, df) in data.iter() {
+                let sheet_name: String = sheet_name.extract()?;
+                if !sheet_names.contains(&sheet_name) {
+                    return Err(sheet_not_found(&sheet_name));
+                }
+                if !self.header_locations.contains_key(&sheet_name) {
+                    return Err(template_error(format!(
+                        "Sheet '{}' has no header location. Use set_header_location to set it.", sheet_name
+                    )));
+                }
+                sheets.push((sheet_name, df.into()));
+            }
+
+            let current_sheet_name = self.current_sheet_name.clone();
+            let current_cell = self.current_cell_in_current_sheet.clone();
+            let mut result = Ok(());
+            for (sheet_name, df) in sheets {
+                debug!("Filling sheet {}", sheet_name);
+                self.current_sheet_name = Some(sheet_name.clone());
+                self.current_cell_in_current_sheet = self.header_locations.get(&sheet_name).map(|(header, _)| header.clone());
+                result = self.fill_object(py, df.as_ref(py), columns.clone(), options.clone(), false).map(|_| ()).map_err(|e| PyErr::from_type(e.get_type(py), format!("Failed to fill sheet '{}': {}", sheet_name, e.value(py))));
+                if result.is_err() {
+                    break;
+                }
+            }
+            self.current_sheet_name = current_sheet_name;
+            self.current_cell_in_current_sheet = current_cell;
+            result
+        })
+    }
+
+    #[cfg(not(feature = "dataframe"))]
+    #[pyo3(signature = (*_args, **_kwargs))]
+    pub fn fill_many(&mut self, _args: &PyTuple, _kwargs: Option<&PyDict>) -> PyResult<()> {
+        Err(missing_feature("fill_many", "dataframe"))
+    }
+
+    /// Fills the current sheet with the rows of a CSV file, with the same options as fill_with
+    ///
+    /// The file is read by polars in Rust, without a DataFrame built in Python, the column types being
+    /// inferred from the values. `delimiter` separates the fields (default ","); for a file without a
+    /// header row (`has_header=False`), `columns` names its columns, otherwise named column_1, column_2...
+    #[cfg(feature = "dataframe")]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (csv_path, delimiter=None, has_header=None, columns=None, *, validate_only=None, verbose=None, **options))]
+    pub fn fill_with_csv(
+        &mut self,
+        py: Python,
+        csv_path: &str,
+        delimiter: Option<String>,
+        has_header: Option<bool>,
+        columns: Option<Vec<String>>,
+        validate_only: Option<bool>,
+        verbose: Option<bool>,
+        options: Option<&PyDict>,
+    ) -> PyResult<PyObject> {
+        let _verbose = Verbose::new(py, verbose)?;
+        let options = FillOptions::from_kwargs(options)?;
+        catch_panic("fill_with_csv", || {
+            let delimiter = match delimiter.as_deref().unwrap_or(",").as_bytes() {
+                [delimiter] => *delimiter,
+                _ => return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "The delimiter must be a single ASCII character, not {:?}.", delimiter.unwrap_or_default()
+                ))),
+            };
+            if !Path::new(csv_path).exists() {
+                return Err(PyErr::new::<pyo3::exceptions::PyFileNotFoundError, _>(format!("File not found: {:?}", csv_path)));
+            }
+            let df = read_csv(csv_path, delimiter, has_header.unwrap_or(true), columns.as_deref()).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to read {:?}: {}", csv_path, e))
+            })?;
+            self.fill_frame(py, df, Vec::new(), options, validate_only.unwrap_or(false))
+        })
+    }
+
+    #[cfg(not(feature = "dataframe"))]
+    #[pyo3(signature = (*_args, **_kwargs))]
+    pub fn fill_with_csv(&mut self, _args: &PyTuple, _kwargs: Option<&PyDict>) -> PyResult<()> {
+        Err(missing_feature("fill_with_csv", "dataframe"))
+    }
+
+    /// Fills the current sheet with the rows of an Arrow IPC (Feather v2) file, with the same options as fill_with
+    ///
+    /// The file is read by polars in Rust with its column types, skipping the conversion of a Python
+    /// DataFrame through Arrow. With `columns`, only these columns are read.
+    #[cfg(feature = "dataframe")]
+    #[pyo3(signature = (ipc_path, columns=None, *, validate_only=None, verbose=None, **options))]
+    pub fn fill_with_ipc(
+        &mut self,
+        py: Python,
+        ipc_path: &str,
+        columns: Option<Vec<String>>,
+        validate_only: Option<bool>,
+        verbose: Option<bool>,
+        options: Option<&PyDict>,
+    ) -> PyResult<PyObject> {
+        let _verbose = Verbose::new(py, verbose)?;
+        let options = FillOptions::from_kwargs(options)?;
+        catch_panic("fill_with_ipc", || {
+            if !Path::new(ipc_path).exists() {
+                return Err(PyErr::new::<pyo3::exceptions::PyFileNotFoundError, _>(format!("File not found: {:?}", ipc_path)));
+            }
+            let df = read_ipc(ipc_path, columns).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to read {:?}: {}", ipc_path, e))
+            })?;
+            self.fill_frame(py, df, Vec::new(), options, validate_only.unwrap_or(false))
+        })
+    }
+
+    #[cfg(not(feature = "dataframe"))]
+    #[pyo3(signature = (*_args, **_kwargs))]
+    pub fn fill_with_ipc(&mut self, _args: &PyTuple, _kwargs: Option<&PyDict>) -> PyResult<()> {
+        Err(missing_feature("fill_with_ipc", "dataframe"))
+    }
+
+    /// Fills the current sheet with the rows of a Parquet file, with the same options as fill_with
+    ///
+    /// The file is read by polars in Rust with its column types, skipping the conversion of a Python
+    /// DataFrame through Arrow. With `columns`, only these columns are read. Files compressed with
+    /// snappy or brotli cannot be read (zstd, gzip and lz4 can).
+    #[cfg(feature = "dataframe")]
+    #[pyo3(signature = (parquet_path, columns=None, *, validate_only=None, verbose=None, **options))]
+    pub fn fill_with_parquet(
+        &mut self,
+        py: Python,
+        parquet_path: &str,
+        columns: Option<Vec<String>>,
+        validate_only: Option<bool>,
+        verbose: Option<bool>,
+        options: Option<&PyDict>,
+    ) -> PyResult<PyObject> {
+        let _verbose = Verbose::new(py, verbose)?;
+        let options = FillOptions::from_kwargs(options)?;
+        catch_panic("fill_with_parquet", || {
+            if !Path::new(parquet_path).exists() {
+                return Err(PyErr::new::<pyo3::exceptions::PyFileNotFoundError, _>(format!("File not found: {:?}", parquet_path)));
+            }
+            let df = read_parquet(parquet_path, columns).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to read {:?}: {}", parquet_path, e))
+            })?;
+            self.fill_frame(py, df, Vec::new(), options, validate_only.unwrap_or(false))
+        })
+    }
+
+    #[cfg(not(feature = "dataframe"))]
+    #[pyo3(signature = (*_args, **_kwargs))]
+    pub fn fill_with_parquet(&mut self, _args: &PyTuple, _kwargs: Option<&PyDict>) -> PyResult<()> {
+        Err(missing_feature("fill_with_parquet", "dataframe"))
+    }
+
+    /// Fills a copy of a template sheet with the rows of each value of a column, e.g. a sheet per country
+    ///
+    /// The copies are named after the values, made valid and unique as sheet names (empty values giving
+    /// "(blank)"), and placed after the template sheet in the order the values first appear. Each copy is
+    /// filled at the header location of the template sheet, with its defaults. `columns` names the lists
+    /// of a list of lists. The template sheet is deleted unless `keep_template` is True (default).
+    /// If the fill of a copy fails, the copies are deleted before the error is raised.
+    /// :return: The names of the created sheets.
+    #[cfg(feature = "dataframe")]
+    pub fn fill_partitioned(
+        &mut self,
+        py: Python,
+        df: PyObject,
+        by: &str,
+        template_sheet: &str,
+        columns: Option<Vec<String>>,
+        keep_template: Option<bool>,
+    ) -> PyResult<Vec<String>> {
+        catch_panic("fill_partitioned", || {
+            let names = self.sheet_names()?;
+            if !names.iter().any(|name| name == template_sheet) {
+                return Err(sheet_not_found(template_sheet));
+            }
+            if !self.header_locations.contains_key(template_sheet) {
+                return Err(template_error(format!(
+                    "Sheet '{}' has no header location. Use set_header_location to set it.", template_sheet
+                )));
+            }
+            let columns: Option<PyObject> = columns.map(|columns| columns.into_py(py));
+            let frame = convert(py, get_datatype(py, df.as_ref(py))?, df.as_ref(py), columns)?;
+            let keys = frame.column(by)
+                .map_err(|_| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Partition column '{}' is missing in the data.", by)))?;
+
+            // The rows of each value, in the order the values first appear
+            let mut partitions: Vec<(String, Vec<IdxSize>)> = Vec::new();
+            let mut positions: HashMap<String, usize> = HashMap::new();
+            for i in 0..frame.height() {
+                let key = convert_anyvalue_to_string(keys.get(i).map_err(|e| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to read the partition column: {}.", e))
+                })?);
+                match positions.get(&key) {
+                    Some(&position) => partitions[position].1.push(i as IdxSize),
+                    None => {
+                        positions.insert(key.clone(), partitions.len());
+                        partitions.push((key, vec![i as IdxSize]));
+                    },
+                }
+            }
+
+            let mut taken = names.clone();
+            let mut created = Vec::new();
+            for (key, _) in &partitions {
+                let name = match sanitize_sheet_name(key.trim()) {
+                    name if name.trim().is_empty() => "(blank)".to_string(),
+                    name => name,
+                };
+                let name = unique_sheet_name(&name, &taken, None);
+                taken.push(name.clone());
+                created.push(name);
+            }
+            let current_sheet_name = self.current_sheet_name.clone();
+            let current_cell = self.current_cell_in_current_sheet.clone();
+            let mut result = Ok(());
+            // Copies go right after the template sheet, so they are made from the last one to keep their order
+            let mut cloned = Vec::new();
+            for name in created.iter().rev() {
+                result = self.clone_sheet(template_sheet, name);
+                if result.is_err() {
+                    break;
+                }
+                cloned.push(name.clone());
+            }
+            if result.is_ok() {
+                for (name, (_, rows)) in created.iter().zip(partitions) {
+                    debug!("Filling sheet {} with {} rows", name, rows.len());
+                    self.current_sheet_name = Some(name.clone());
+                    self.current_cell_in_current_sheet = self.header_locations.get(name).map(|(header, _)| header.clone());
+                    result = match frame.take(&IdxCa::from_vec("rows".into(), rows)) {
+                        Ok(part) => self.fill_frame(py, part, Vec::new(), FillOptions::default(), false).map(|_| ()),
+                        Err(e) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to take the rows: {}.", e))),
+                    }
+                    .map_err(|e| PyErr::from_type(e.get_type(py), format!("Failed to fill sheet '{}': {}", name, e.value(py))));
+                    if result.is_err() {
+                        break;
+                    }
+                }
+            }
+            self.current_sheet_name = current_sheet_name;
+            self.current_cell_in_current_sheet = current_cell;
+            // A failed partition leaves no copy behind, the workbook being as it was before the call
+            if result.is_err() {
+                for name in &cloned {
+                    self.delete_sheet(name)?;
+                }
+            }
+            result?;
+
+            if !keep_template.unwrap_or(true) {
+                self.delete_sheet(template_sheet)?;
+            }
+            Ok(created)
+        })
+    }
+
+    #[cfg(not(feature = "dataframe"))]
+    #[pyo3(signature = (*_args, **_kwargs))]
+    pub fn fill_partitioned(&mut self, _args: &PyTuple, _kwargs: Option<&PyDict>) -> PyResult<Vec<String>> {
+        Err(missing_feature("fill_partitioned", "dataframe"))
+    }
+
+    /// Appends the tables of several workbooks to a sheet, e.g. to gather the filled copies of a template
+    ///
+    /// `files` are paths or glob patterns of file names ("returns/*.xlsx"), read in order. In each file, the
+    /// table of `source_sheet` is read below `header` (default A1) down to its first empty row, or within
+    /// `range`, its first row being the headers. The tables are appended below the last row of `sheet_name`
+    /// (default: the current sheet) as fill_with does, matched on the headers at its header location, with
+    /// the path of each file in `source_column` if given. Columns missing from a file are left empty in its rows.
+    /// The files are read in `max_workers` worker threads at most (default: one per core).
+    /// :return: The number of rows appended from each file.
+    #[cfg(feature = "dataframe")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn consolidate_files(
+        &mut self,
+        py: Python,
+        files: FileSelector,
+        source_sheet: &str,
+        header: Option<ExcelCell>,
+        range: Option<ExcelRange>,
+        source_column: Option<String>,
+        max_workers: Option<usize>,
+        sheet_name: Option<&str>,
+    ) -> PyResult<HashMap<String, usize>> {
+        catch_panic("consolidate_files", || {
+            let sheet_name = self.sheet_or_current(sheet_name)?;
+            if !self.sheet_names()?.contains(&sheet_name) {
+                return Err(sheet_not_found(&sheet_name));
+            }
+            if !self.header_locations.contains_key(&sheet_name) {
+                return Err(template_error(format!(
+                    "Sheet '{}' has no header location. Use set_header_location to set it.", sheet_name
+                )));
+            }
+            if header.is_some() && range.is_some() {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("Give either the header or the range of the tables, not both."));
+            }
+
+            // The tables are extracted in the worker threads too, each workbook being dropped once read
+            let files = files.resolve()?;
+            let tables = read_files(py, &files, max_workers, |file_path| -> PyResult<DataFrame> {
+                let spreadsheet = Self::load_sheets(file_path, |_| Ok(vec![source_sheet.to_string()]))?;
+                let worksheet = spreadsheet.get_sheet_by_name(source_sheet).ok_or_else(|| sheet_not_found(source_sheet))?;
+                let table = match &range {
+                    Some(range) => {
+                        let ((first_col, first_row), (last_col, last_row)) = range.idx_in(worksheet);
+                        extract_table(worksheet, first_col, first_row, Some(last_col), Some(last_row))
+                    },
+                    None => {
+                        let (header_col, header_row) = header.clone().unwrap_or_default().idx();
+                        extract_table(worksheet, header_col, header_row, None, None)
+                    },
+                };
+                table.map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)
+            })?;
+            let mut frames: Vec<DataFrame> = Vec::new();
+            let mut rows = HashMap::new();
+            for (file_path, table) in files.into_iter().zip(tables) {
+                let mut table = table.map_err(|e| {
+                    PyErr::from_type(e.get_type(py), format!("Failed to read '{}': {}", file_path, e.value(py)))
+                })?;
+                if let Some(source_column) = &source_column {
+                    let height = table.height();
+                    table.with_column(Series::new(source_column.into(), vec![file_path.clone(); height])).map_err(|e| {
+                        PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to add the source column: {}.", e))
+                    })?;
+                }
+                debug!("{} rows read from {}", table.height(), file_path);
+                rows.insert(file_path, table.height());
+                frames.push(table);
+            }
+
+            let df = stack(frames).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to combine the tables: {}.", e))
+            })?;
+            if df.height() == 0 {
+                return Ok(rows);
+            }
+
+            let current_sheet_name = self.current_sheet_name.clone();
+            let current_cell = self.current_cell_in_current_sheet.clone();
+            self.current_sheet_name = Some(sheet_name.clone());
+            self.current_cell_in_current_sheet = self.header_locations.get(&sheet_name).map(|(header, _)| header.clone());
+            let result = self.fill_frame(py, df, Vec::new(), FillOptions::default(), false);
+            self.current_sheet_name = current_sheet_name;
+            self.current_cell_in_current_sheet = current_cell;
+            result?;
+            info!("{} rows of {} files appended to {}", rows.values().sum::<usize>(), rows.len(), sheet_name);
+            Ok(rows)
+        })
+    }
+
+    #[cfg(not(feature = "dataframe"))]
+    #[pyo3(signature = (*_args, **_kwargs))]
+    pub fn consolidate_files(&mut self, _args: &PyTuple, _kwargs: Option<&PyDict>) -> PyResult<HashMap<String, usize>> {
+        Err(missing_feature("consolidate_files", "dataframe"))
+    }
+
+    /// Pivots the data and writes the matrix at the current cell: a row per value of `index`, a column
+    /// per value of `columns` and the `agg` ("sum", "count" or "avg") of `values` in each cell
+    ///
+    /// With `totals`, a "Total" row and column are added after the last ones.
+    /// :return: The range of the written matrix, headers and totals included.
+    #[cfg(feature = "dataframe")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn fill_crosstab(
+        &mut self,
+        py: Python,
+        df: PyObject,
+        index: &str,
+        columns: &str,
+        values: &str,
+        agg: Option<Action>,
+        totals: Option<bool>,
+    ) -> PyResult<String> {
+        catch_panic("fill_crosstab", || {
+            let data_type = get_datatype(py, df.as_ref(py))?;
+            let df: DataFrame = convert(py, data_type, df.as_ref(py), None)?;
+            let agg = agg.unwrap_or_default();
+            if agg.is_custom() {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("A crosstab needs a built-in action, not a function."));
+            }
+
+            let column_error = |e: PolarsError| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Invalid crosstab: {}.", e));
+            let dtype = df.column(values).map_err(column_error)?.dtype();
+            if !matches!(agg, Action::Count) && !dtype.is_numeric() {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Crosstab values of column '{}' need numbers, not {}.", values, dtype
+                )));
+            }
+            let crosstab = pivot(&df, index, columns, values, agg).map_err(column_error)?;
+
+            let current_sheet_name = self.current_sheet_name
+                .as_ref()
+                .ok_or_else(|| template_error("No sheet specified. Use goto_sheet to set the sheet."))?
+                .to_string();
+            let first_cell = self.current_cell_in_current_sheet
+                .as_ref()
+                .ok_or_else(|| template_error("No cell specified. Use goto_cell to set the cell."))?
+                .idx();
+            let totals = totals.unwrap_or(false);
+            let (width, height) = crosstab.size(totals);
+            check_area_limits("fill_crosstab", &current_sheet_name, first_cell, (width, height))?;
+
+            let write_options = self.write_options.clone();
+            let worksheet = self.worksheet_mut(&current_sheet_name)?;
+            crosstab.write(worksheet, first_cell, totals.then_some("Total"), &write_options);
+
+            let (first_col, first_row) = first_cell;
+            let range = format!("{}:{}", index_to_excel(first_col, first_row), index_to_excel(first_col + width - 1, first_row + height - 1));
+            debug!("Crosstab of {} by {} and {} written in {}", values, index, columns, range);
+            Ok(range)
+        })
+    }
+
+    #[cfg(not(feature = "dataframe"))]
+    #[pyo3(signature = (*_args, **_kwargs))]
+    pub fn fill_crosstab(&mut self, _args: &PyTuple, _kwargs: Option<&PyDict>) -> PyResult<String> {
+        Err(missing_feature("fill_crosstab", "dataframe"))
+    }
+
+    /// Writes a Gantt chart at the current cell: a row per task and a column per `period` ("day",
+    /// "week" or "month") from the first start to the last end, with the periods of each task filled
+    ///
+    /// Dates are dates, datetimes or texts starting with YYYY-MM-DD; the end date is included.
+    /// Bars are `color`, or the color of the row in the `color_from` column.
+    /// :return: The range of the written chart, headers included.
+    #[cfg(all(feature = "dataframe", feature = "styles"))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn fill_gantt(
+        &mut self,
+        py: Python,
+        df: PyObject,
+        task: &str,
+        start: &str,
+        end: &str,
+        period: Option<Period>,
+        color: Option<String>,
+        color_from: Option<String>,
+    ) -> PyResult<String> {
+        catch_panic("fill_gantt", || {
+            let data_type = get_datatype(py, df.as_ref(py))?;
+            let df: DataFrame = convert(py, data_type, df.as_ref(py), None)?;
+            let color = parse_color(color.as_deref().unwrap_or(DEFAULT_BAR_COLOR)).map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+
+            let mut warnings: Vec<Warning> = Vec::new();
+            let gantt = plan_gantt(&df, task, start, end, period.unwrap_or_default(), color_from.as_deref(), &mut warnings).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Invalid Gantt chart: {}.", e))
+            })?;
+
+            let current_sheet_name = self.current_sheet_name
+                .as_ref()
+                .ok_or_else(|| template_error("No sheet specified. Use goto_sheet to set the sheet."))?
+                .to_string();
+            let first_cell = self.current_cell_in_current_sheet
+                .as_ref()
+                .ok_or_else(|| template_error("No cell specified. Use goto_cell to set the cell."))?
+                .idx();
+            let (width, height) = gantt.size();
+            check_area_limits("fill_gantt", &current_sheet_name, first_cell, (width, height))?;
+
+            let write_options = self.write_options.clone();
+            let worksheet = self.worksheet_mut(&current_sheet_name)?;
+            gantt.write(worksheet, first_cell, &color, &write_options);
+
+            let (first_col, first_row) = first_cell;
+            let range = format!("{}:{}", index_to_excel(first_col, first_row), index_to_excel(first_col + width - 1, first_row + height - 1));
+            debug!("Gantt chart of {} tasks written in {}", gantt.bars.len(), range);
+            // The warnings of a task are about the cell of its label
+            let warnings = warnings.into_iter()
+                .map(|warning| match warning.row {
+                    Some(row) => warning.with_cell(index_to_excel(first_col, first_row + 1 + row as u32)),
+                    None => warning,
+                })
+                .collect();
+            emit_structured_warnings(py, warnings)?;
+            Ok(range)
+        })
+    }
+
+    #[cfg(not(all(feature = "dataframe", feature = "styles")))]
+    #[pyo3(signature = (*_args, **_kwargs))]
+    pub fn fill_gantt(&mut self, _args: &PyTuple, _kwargs: Option<&PyDict>) -> PyResult<String> {
+        let feature = if cfg!(feature = "dataframe") { "styles" } else { "dataframe" };
+        Err(missing_feature("fill_gantt", feature))
+    }
+
+}
+
+// Methods that are not available in Python
+impl ExcelTemplate {
+    /// Maps the header names to their column (or row in column mode), skipping blank header cells
+    ///
+    /// Without `allow_gaps`, the header ends at the first blank cell. The first of duplicate headers is kept.
+    /// Given `warnings`, a `blank_header` warning is pushed for each blank cell skipped.
+    #[cfg(feature = "dataframe")]
+    fn get_header_map(&self, mode: Mode, allow_gaps: bool, mut warnings: Option<&mut Vec<Warning>>) -> PyResult<HashMap<String, u32>> {
+        let spreadsheet = Arc::as_ref(&self.spreadsheet);        
+        let current_sheet_name = match self.current_sheet_name.as_ref() {
+            Some(sheet_name) => sheet_name.clone(),
+            None => return Err(template_error("No sheet specified. Use goto_sheet to set the sheet.")),
+        };
+
+        let worksheet = spreadsheet.get_sheet_by_name(&current_sheet_name).ok_or_else(|| {
+            sheet_not_found(&current_sheet_name)
+        })?;
+
+        let header_location = match self.current_cell_in_current_sheet.as_ref() {
+            Some(cell) => cell,
+            None => return Err(template_error("No cell specified. Use set_header_location to set the startingcell.")),
+        };
+
+        let (header_col, header_row) = header_location.idx();
+        debug!("Getting headers starting from {} in mode {}", index_to_excel(header_col, header_row), mode);
+
+        let mut header_map = HashMap::new();
+        let first = match mode {
+            Mode::Row => header_col,
+            Mode::Column => header_row,
+        };
+        let last = match mode {
+            Mode::Row => worksheet.get_highest_column(),
+            Mode::Column => worksheet.get_highest_row(),
+        };
+        debug!("From {} to {}", first, last);
+    
+        for i in first..=last {
+            let (col, row) = match mode {
+                Mode::Row => (i, header_row),
+                Mode::Column => (header_col, i),
+            };  
+            let col_name = worksheet.get_value((col, row)).trim().to_string();
+            if col_name.is_empty() {
+                if !allow_gaps {
+                    debug!("Blank header in {}, end of the header", index_to_excel(col, row));
+                    break;
+                }
+                debug!("Blank header in {} skipped", index_to_excel(col, row));
+                if let Some(warnings) = warnings.as_mut() {
+                    let cell = index_to_excel(col, row);
+                    warnings.push(Warning::new("blank_header", format!("Blank header in {} skipped.", cell)).with_cell(cell));
+                }
+                continue;
+            }
+            if header_map.contains_key(&col_name) {
+                warn!("Header {} in {} appears more than once, only the first one is filled", col_name, index_to_excel(col, row));
+                continue;
+            }
+            debug!("Header {} in {}", col_name, index_to_excel(col, row));
+            header_map.insert(col_name, i);
+        }
+    
+        Ok(header_map)
+    }
+
+    /// Reads the workbook cells are copied or aggregated from: another ExcelTemplate, or the path of a workbook
+    ///
+    /// A template is shared as it is in memory, rather than read again from its file. A workbook file is
+    /// read lazily, only the sheets `select` returns among its sheet names being parsed, or shared with the cache.
+    /// :return: The workbook and the path of its file, None for a template not loaded from a file.
+    fn read_source<F>(source: &PyAny, select: F) -> PyResult<(Arc<Spreadsheet>, Option<String>)>
+    where
+        F: FnOnce(&[String]) -> PyResult<Vec<String>>,
+    {
+        if let Ok(template) = source.extract::<PyRef<ExcelTemplate>>() {
+            return Ok((template.spreadsheet.clone(), template.file_path.clone()));
+        }
+        let file_path: &str = source.extract().map_err(|_| {
+            PyErr::new::<pyo3::exceptions::PyTypeError, _>("Expected an ExcelTemplate (other than this one) or the path of a workbook.")
+        })?;
+        if cache::is_enabled() && Path::new(file_path).exists() {
+            let workbook = Self::load_workbook(file_path)?;
+            select(&Self::names_of_sheets(&workbook))?;
+            return Ok((workbook, Some(file_path.to_string())));
+        }
+        let mut workbook = reader::xlsx::lazy_read(Path::new(file_path)).map_err(|e| match Path::new(file_path).exists() {
+            true => PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("Failed to read Excel file: {}. Error: {:?}", file_path, e)),
+            false => PyErr::new::<pyo3::exceptions::PyFileNotFoundError, _>(format!(
+                "Failed to read Excel file: {}. Check if the file exists and is readable.", file_path
+            )),
+        })?;
+        let sheet_names = select(&Self::names_of_sheets(&workbook))?;
+        parse_sheets(&mut workbook, &sheet_names);
+        Ok((Arc::new(workbook), Some(file_path.to_string())))
+    }
+
+    /// Whether two paths are of the same file, compared as given when either does not exist
+    fn same_file(a: &str, b: &str) -> bool {
+        match (Path::new(a).canonicalize(), Path::new(b).canonicalize()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => Path::new(a) == Path::new(b),
+        }
+    }
+
+    /// Checks the columns of the subtotals and lays out the groups of the data
+    #[cfg(feature = "dataframe")]
+    fn plan_subtotals(df: &DataFrame, subtotals: &Subtotals) -> PyResult<(Vec<Group>, Vec<LayoutRow>)> {
+        let column_error = |e: PolarsError| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Invalid subtotals: {}.", e));
+        for (column, action) in &subtotals.agg {
+            let dtype = df.column(column).map_err(column_error)?.dtype();
+            if !matches!(action, Action::Count) && !dtype.is_numeric() {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Subtotals of column '{}' need numbers, not {}.", column, dtype
+                )));
+            }
+        }
+        let groups = group_runs(df, &subtotals.by).map_err(column_error)?;
+        let layout = layout(&groups, subtotals);
+        debug!("{} groups of {} laid out in {} rows", groups.len(), subtotals.by, layout.len());
+        Ok((groups, layout))
+    }
+
+    /// Returns the default options of a sheet completed with the template defaults
+    fn defaults_for(&self, sheet_name: Option<&str>) -> FillDefaults {
+        match sheet_name.and_then(|sheet_name| self.sheet_fill_defaults.get(sheet_name)) {
+            Some(defaults) => defaults.or(&self.fill_defaults),
+            None => self.fill_defaults.clone(),
+        }
+    }
+
+    /// Takes the column giving the color of each row out of the data, as ARGB colors
+    ///
+    /// Null values leave their row uncolored, as do invalid colors, which are reported as warnings.
+    #[cfg(all(feature = "dataframe", feature = "styles"))]
+    fn take_row_colors(df: &mut DataFrame, style_column: &str, warnings: &mut Vec<Warning>) -> PyResult<Vec<Option<String>>> {
+        let series = df.drop_in_place(style_column).map_err(|_| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Style column '{}' is missing in the DataFrame.", style_column))
+        })?;
+
+        let mut colors = Vec::with_capacity(series.len());
+        for i in 0..series.len() {
+            let color = match series.get(i) {
+                Ok(AnyValue::Null) | Err(_) => None,
+                Ok(value) => match parse_color(&convert_anyvalue_to_string(value)) {
+                    Ok(argb) => Some(argb),
+                    Err(e) => {
+                        warn!("{}", e);
+                        let message = format!("{} Row {} of '{}' left uncolored.", e, i + 1, style_column);
+                        warnings.push(Warning::new("invalid_color", message).with_column(style_column));
+                        None
+                    },
+                },
+            };
+            colors.push(color);
+        }
+        debug!("{} row colors taken from {}", colors.iter().flatten().count(), style_column);
+        Ok(colors)
+    }
+
+    #[cfg(all(feature = "dataframe", not(feature = "styles")))]
+    fn take_row_colors(_df: &mut DataFrame, _style_column: &str, _warnings: &mut Vec<Warning>) -> PyResult<Vec<Option<String>>> {
+        Err(missing_feature("fill_with with style_from", "styles"))
+    }
+
+    /// Persists the header location and the data range of a fill in the registry of the workbook
+    #[cfg(feature = "dataframe")]
+    fn register_filled_region(
+        &mut self,
+        sheet_name: &str,
+        header: (u32, u32),
+        mode: Mode,
+        header_map: &HashMap<String, u32>,
+        first_cell: (u32, u32),
+        height: usize,
+    ) -> PyResult<()> {
+        let (first_col, first_row) = first_cell;
+        let last = height.max(1) as u32 - 1;
+        let first_idx = header_map.values().copied().min().unwrap_or(header.0);
+        let last_idx = header_map.values().copied().max().unwrap_or(header.0);
+        let range = match mode {
+            Mode::Row => format!("{}:{}", index_to_excel(first_idx, first_row), index_to_excel(last_idx, first_row + last)),
+            Mode::Column => format!("{}:{}", index_to_excel(first_col, first_idx), index_to_excel(first_col + last, last_idx)),
+        };
+        let region = Region::filled_now(sheet_name, index_to_excel(header.0, header.1), mode.clone(), range);
+
+        let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
+        register_region(spreadsheet, &region).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to register region: {}.", e))
+        })?;
+        self.header_locations.insert(sheet_name.to_string(), (ExcelCell::String(region.header.clone()), mode));
+        debug!("Region {:?} registered", region);
+        Ok(())
+    }
+
+    /// Records where the data of each filled column comes from in the hidden provenance sheet
+    #[cfg(feature = "dataframe")]
+    fn record_provenance(
+        &mut self,
+        sheet_name: &str,
+        df: &DataFrame,
+        header_map: &HashMap<String, u32>,
+        first_cell: (u32, u32),
+        mode: Mode,
+        provenance: &HashMap<String, String>,
+    ) -> PyResult<()> {
+        let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
+
+        let (first_col, first_row) = first_cell;
+        let last = df.height().max(1) as u32 - 1;
+        let mut attributes: Vec<(&String, &String)> = provenance.iter().collect();
+        attributes.sort();
+
+        for col_name in df.get_column_names() {
+            let col_name = col_name.to_string();
+            let idx = match header_map.get(&col_name) {
+                Some(idx) => *idx,
+                None => continue,
+            };
+            let range = match mode {
+                Mode::Row => format!("{}:{}", index_to_excel(idx, first_row), index_to_excel(idx, first_row + last)),
+                Mode::Column => format!("{}:{}", index_to_excel(first_col, idx), index_to_excel(first_col + last, idx)),
+            };
+            let source_column = "source_column".to_string();
+            for (key, value) in std::iter::once((&source_column, &col_name)).chain(attributes.iter().copied()) {
+                let record = [sheet_name.to_string(), range.clone(), col_name.clone(), key.clone(), value.clone()];
+                append_record(spreadsheet, PROVENANCE_SHEET, &record).map_err(|e| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to record provenance: {}.", e))
+                })?;
+            }
+        }
+        debug!("Provenance recorded for {} columns of {}", df.width(), sheet_name);
+        Ok(())
+    }
+
+    /// Builds the condition of a conditional aggregation of `range`, from criteria and the range they are tested on
+    ///
+    /// The criteria range is resolved in the source workbook, on the source sheet if given, else it cannot name a sheet.
+    #[cfg(feature = "aggregate")]
+    fn aggregate_condition(
+        criteria: Option<Criteria>,
+        criteria_range: Option<ExcelRange>,
+        workbook: &Spreadsheet,
+        sheet_name: Option<&str>,
+        range: ((u32, u32), (u32, u32)),
+        highest: (u32, u32),
+    ) -> PyResult<Option<Condition>> {
+        let criteria = match (criteria, &criteria_range) {
+            (Some(criteria), _) => criteria,
+            (None, None) => return Ok(None),
+            (None, Some(_)) => return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("A criteria_range needs criteria.")),
+        };
+        let tested = match criteria_range {
+            Some(criteria_range) => {
+                let tested = match (criteria_range.resolve(workbook, sheet_name)?, sheet_name) {
+                    (ExcelRange::Reference { first, last, .. }, None) if matches!(criteria_range, ExcelRange::Name(_)) => ExcelRange::Reference { sheet: None, first, last },
+                    (tested, _) => tested,
+                };
+                match sheet_name {
+                    Some(sheet_name) => tested.check_sheet(sheet_name)?,
+                    None if tested.sheet().is_some() => {
+                        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("The criteria range cannot name a sheet."));
+                    },
+                    None => {},
+                }
+                Some(tested.bounds(highest))
+            },
+            None => None,
+        };
+        Condition::new(criteria, range, tested).map(Some).map_err(|e| range_error(e, sheet_name, None))
+    }
+
+    /// Returns the sheets find, replace and render go through: the sheets selected, or all of them
+    fn searched_sheets(&self, sheet_name: Option<SheetSelector>) -> PyResult<Vec<String>> {
+        let sheet_names = Self::names_of_sheets(&self.spreadsheet);
+        match sheet_name {
+            Some(selector) => selector.resolve(&sheet_names),
+            None => Ok(sheet_names),
+        }
+    }
+
+    /// Returns the given sheet name, or the current sheet if None
+    fn sheet_or_current(&self, sheet_name: Option<&str>) -> PyResult<String> {
+        match sheet_name {
+            Some(sheet_name) => Ok(sheet_name.to_string()),
+            None => self.current_sheet_name.clone().ok_or_else(|| {
+                template_error("No sheet specified. Use goto_sheet to set the sheet.")
+            }),
+        }
+    }
+
+    /// Fills the current sheet with a DataFrame of Python (Pandas, Polars, Arrow, dict or list of lists)
+    #[cfg(feature = "dataframe")]
+    fn fill_object(&mut self, py: Python, df: &PyAny, columns: Option<PyObject>, options: FillOptions, validate_only: bool) -> PyResult<PyObject> {
+        let data_type = get_datatype(py, df)?;
+
+        // A dictionary of columns maps DataFrame columns to explicit sheet positions (names of a list of lists)
+        let (columns, positions): (Option<PyObject>, Vec<(String, ExcelPosition)>) = match columns {
+            Some(columns) => match columns.as_ref(py).downcast::<PyDict>() {
+                Ok(dict) => {
+                    let positions = dict.iter()
+                        .map(|(name, position)| Ok((name.extract()?, position.extract()?)))
+                        .collect::<PyResult<Vec<(String, ExcelPosition)>>>()?;
+                    let names = match data_type {
+                        OriginalDataType::ListOfLists => Some(dict.keys().into()),
+                        _ => None,
+                    };
+                    (names, positions)
+                },
+                Err(_) => (Some(columns), Vec::new()),
+            },
+            None => (None, Vec::new()),
+        };
+
+        let df: DataFrame = convert(py, data_type, df, columns)?;
+        debug!("df: {:?}", df);
+        self.fill_frame(py, df, positions, options, validate_only)
+    }
+
+    /// Fills the current sheet with a DataFrame, the part of fill_with after the conversion of the data
+    #[cfg(feature = "dataframe")]
+    fn fill_frame(&mut self, py: Python, mut df: DataFrame, positions: Vec<(String, ExcelPosition)>, options: FillOptions, validate_only: bool) -> PyResult<PyObject> {
+        let FillOptions {
+            mode, strict, skip_null, overwrite, protect, password, provenance, allow_gaps, warn_blank_headers, style_from, subtotals, fx,
+            generated_columns, verify_fill, date_format, copy_style_from_row, upsert_on, truncate, header_matching, aliases, coerce, coerce_strict, insert,
+            autofilter, freeze_header, formula_columns, totals, group_by, comment_coercions,
+        } = options;
+
+        // The colors of the rows come from a column of the data, which is not written itself
+        let mut warnings: Vec<Warning> = Vec::new();
+        let mut row_colors = match &style_from {
+            Some(style_column) => Self::take_row_colors(&mut df, style_column, &mut warnings)?,
+            None => Vec::new(),
+        };
+
+        // Options not given fall back to the defaults of the sheet, then of the template
+        let mut defaults = self.defaults_for(self.current_sheet_name.as_deref());
+        let mode = mode.or(defaults.mode.clone()).unwrap_or(Mode::Row);
+        let skip_null = skip_null.or(defaults.skip_null).unwrap_or(false);
+        let strict = strict.or(defaults.strict).unwrap_or(false);
+        let overwrite = overwrite.or(defaults.overwrite).unwrap_or(false);
+        let protect = protect.or(defaults.protect).unwrap_or(false);
+        let allow_gaps = allow_gaps.or(defaults.allow_gaps).unwrap_or(true);
+        defaults.date_format = date_format.or(defaults.date_format);
+        defaults.truncate = truncate.or(defaults.truncate);
+        if let Some(formula_columns) = &formula_columns {
+            if let Some(column) = formula_columns.iter().find(|column| df.column(column).is_err()) {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Formula column '{}' is missing in the data.", column)));
+            }
+        }
+        defaults.formula_columns = formula_columns.or(defaults.formula_columns);
+        let write_options = WriteOptions { date_format: defaults.date_format.clone(), ..self.write_options.clone() };
+
+        // The row of the data each row written comes from, to find the cells of the values coerced
+        let mut data_rows: Vec<Option<usize>> = (0..df.height()).map(Some).collect();
+
+        // Columns are converted to the type they are written with, their unparseable values reported
+        let mut errors: Vec<String> = Vec::new();
+        if let Some(coerce) = coerce {
+            let mut coercions: Vec<(String, Coerce)> = coerce.into_iter().collect();
+            coercions.sort_by(|a, b| a.0.cmp(&b.0));
+            match coerce_columns(&mut df, &coercions, coerce_strict.unwrap_or(false)) {
+                Ok(coerced) => warnings.extend(coerced),
+                Err(e) if validate_only => errors.push(e),
+                Err(e) => return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(e)),
+            }
+        }
+
+        // Generated and converted columns are added to the data written, not to the DataFrame of the caller
+        if let Some(generated_columns) = generated_columns {
+            let mut generated_columns: Vec<(String, GeneratedColumn)> = generated_columns.into_iter().collect();
+            generated_columns.sort_by(|a, b| a.0.cmp(&b.0));
+            add_generated_columns(&mut df, &generated_columns).map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+        }
+        if let Some(fx) = &fx {
+            let converted = add_converted_columns(&mut df, fx).map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+            warnings.extend(converted.into_iter().map(|message| Warning::new("missing_rate", message).with_column(&fx.currency)));
+            for (_, converted) in &fx.columns {
+                defaults.styles.entry(converted.clone()).or_insert_with(|| fx.format.clone());
+            }
+        }
+
+        // group_by is a shorthand of subtotals summing the numeric columns, the rows being gathered by group first
+        let subtotals = match (subtotals, group_by) {
+            (Some(_), Some(_)) => {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("group_by cannot be combined with subtotals, give the column as their 'by'."));
+            },
+            (None, Some(by)) => {
+                let order = df.column(&by)
+                    .map_err(|_| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Group column '{}' is missing in the data.", by)))?
+                    .arg_sort(SortOptions { maintain_order: true, ..Default::default() });
+                df = df.take(&order).map_err(|e| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to group the data by {}: {}.", by, e))
+                })?;
+                data_rows = order.into_no_null_iter().map(|i| data_rows.get(i as usize).copied().flatten()).collect();
+                if !row_colors.is_empty() {
+                    row_colors = order.into_no_null_iter().map(|i| row_colors.get(i as usize).cloned().flatten()).collect();
+                }
+                let columns = df.get_columns().iter()
+                    .filter(|series| series.name() != by.as_str() && series.dtype().is_numeric())
+                    .map(|series| series.name().to_string())
+                    .collect();
+                Some(Subtotals::summing(by, columns))
+            },
+            (subtotals, None) => subtotals,
+        };
+
+        // Subtotal rows are left empty in the written data, and written once the data is
+        let grouped = match &subtotals {
+            Some(subtotals) => {
+                if let Mode::Column = mode {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("Subtotals are only supported in row mode."));
+                }
+                let source = df.clone();
+                let (groups, layout) = Self::plan_subtotals(&source, subtotals)?;
+                df = interleave(&source, &layout).map_err(|e| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to lay out the subtotals: {}.", e))
+                })?;
+                data_rows = layout.iter()
+                    .map(|row| match row {
+                        LayoutRow::Data(i) => data_rows.get(*i).copied().flatten(),
+                        _ => None,
+                    })
+                    .collect();
+                if !row_colors.is_empty() {
+                    row_colors = layout.iter()
+                        .map(|row| match row {
+                            LayoutRow::Data(i) => row_colors.get(*i).cloned().flatten(),
+                            _ => None,
+                        })
+                        .collect();
+                }
+                Some((source, groups, layout))
+            },
+            None => None,
+        };
+
+        let blank_header_warnings = if warn_blank_headers.unwrap_or(false) { Some(&mut warnings) } else { None };
+        let mut header_map = self.get_header_map(mode.clone(), allow_gaps, blank_header_warnings)?;
+        for (col_name, position) in positions {
+            // The header found at an overridden position (blank or merged) is replaced
+            let idx = position.idx();
+            header_map.retain(|_, i| *i != idx);
+            debug!("Column {} mapped to position {}", col_name, idx);
+            header_map.insert(col_name, idx);
+        }
+        // Columns named differently from their header are mapped to it, by alias or matching policy
+        let columns: Vec<String> = df.get_column_names().iter().map(|name| name.to_string()).collect();
+        let header_map = match_headers(header_map, &columns, &header_matching.unwrap_or_default(), &aliases.unwrap_or_default());
+
+        // Inserted rows go right below the header, pushing the rows of the sheet down instead of replacing them
+        let insert = insert.unwrap_or(false);
+        if insert {
+            if let Mode::Column = mode {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("insert is only supported in row mode."));
+            }
+            if upsert_on.is_some() {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("insert cannot be combined with upsert_on."));
+            }
+            defaults.truncate = Some(false);
+        }
+        // The totals row goes right below the data, over the rows below the header
+        let totals: Option<Vec<(String, Action)>> = match totals {
+            Some(totals) => {
+                if let Mode::Column = mode {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("totals are only supported in row mode."));
+                }
+                if subtotals.is_some() || upsert_on.is_some() {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("totals cannot be combined with subtotals or upsert_on."));
+                }
+                if let Some(column) = totals.keys().find(|column| df.column(column).is_err()) {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Totals column '{}' is missing in the data.", column)));
+                }
+                if let Some(column) = totals.iter().find(|(_, action)| action.is_custom()).map(|(column, _)| column) {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Totals of column '{}' need a built-in action, not a function.", column)));
+                }
+                let mut totals: Vec<(String, Action)> = totals.into_iter().collect();
+                totals.sort_by(|a, b| a.0.cmp(&b.0));
+                Some(totals)
+            },
+            None => None,
+        };
+        let autofilter = autofilter.unwrap_or(false);
+        if let (true, Mode::Column) = (autofilter, &mode) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("autofilter is only supported in row mode."));
+        }
+
+        // Everything is checked by now: a validation reports what the fill would write and stops there
+        if validate_only {
+            let overwrite = upsert_on.is_none() && (overwrite || insert);
+            return self.fill_report(py, &df, &header_map, mode, strict, overwrite, totals.is_some(), warnings, errors).map(|report| report.into_py(py));
+        }
+
+        // Rows whose key is already in the sheet are updated in place, the others are appended
+        let mut updated_rows: HashMap<usize, u32> = HashMap::new();
+        let overwrite = match &upsert_on {
+            Some(key) => {
+                if let Mode::Column = mode {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("upsert_on is only supported in row mode."));
+                }
+                if subtotals.is_some() {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("upsert_on cannot be combined with subtotals."));
+                }
+                let updated;
+                (df, row_colors, updated) = self.update_matching_rows(&df, &header_map, key, skip_null, &defaults, &write_options, &row_colors)?;
+                for (i, row) in updated.iter().enumerate() {
+                    if let (Some(row), Some(Some(data_row))) = (row, data_rows.get(i)) {
+                        updated_rows.insert(*data_row, *row);
+                    }
+                }
+                data_rows = data_rows.into_iter().zip(&updated).filter(|(_, row)| row.is_none()).map(|(data_row, _)| data_row).collect();
+                false
+            },
+            None => overwrite || insert,
+        };
+
+        let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet"))?;
+    
+        let current_sheet_name = self.current_sheet_name
+            .as_ref()
+            .ok_or_else(|| template_error("No sheet specified. Use goto_sheet to set the sheet."))?
+            .to_string(); // Clone the string to avoid borrowing self
+
+        let worksheet = spreadsheet.get_sheet_by_name_mut(&current_sheet_name).ok_or_else(|| {
+            sheet_not_found(&current_sheet_name)
+        })?;
+
+        let header_location = self.current_cell_in_current_sheet
+            .as_ref()
+            .ok_or_else(|| template_error("No cell specified. Use set_header_location to set the starting cell."))?;
+
+        let (last_col, last_row) = worksheet.get_highest_column_and_row();
+        let (header_col, header_row) = header_location.idx();
+        let (first_col, first_row) = match mode {
+            Mode::Row => (header_col, if overwrite { header_row + 1 } else { last_row + 1 }),
+            Mode::Column => (if overwrite { header_col + 1 } else { last_col + 1 }, header_row),
+        };
+
+        debug!("Reading {}", mode);
+        match mode {
+            Mode::Row => {
+                debug!("Header location: {}", index_to_excel(header_col, header_row));
+                debug!("Last existing row: {}", last_row);
+                debug!("First row to fill: {}", first_row);
+            },
+            Mode::Column => {
+                debug!("Header location: {}", index_to_excel(header_col, header_row));
+                debug!("Last existing column: {}", last_col);
+                debug!("First column to fill: {}", first_col);
+            },
+        }
+        self.goto_cell(ExcelCell::Tuple((first_row, first_col)))?;
+
+        // Formulas, merges and tables below are moved along with the rows, in this sheet and the others
+        let inserted = if insert { df.height() as u32 + totals.is_some() as u32 } else { 0 };
+        if inserted > 0 {
+            check_area_limits("fill_with", &current_sheet_name, (header_col, last_row.max(header_row) + 1), (1, inserted))?;
+            let worksheet = self.worksheet_mut(&current_sheet_name)?;
+            worksheet.insert_new_row(&first_row, &inserted);
+            shift_tables_for_inserted_rows(worksheet, first_row, inserted);
+            let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
+                .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
+            let moved = shift_inserted_refs(spreadsheet, &current_sheet_name, first_row, inserted);
+            debug!("{} rows inserted at row {} of {}, {} formulas of other sheets moved", inserted, first_row, current_sheet_name, moved);
+        }
+
+        // The rows written take the styles of the first data row of the template, unless 0 is given
+        let style_source = match copy_style_from_row.unwrap_or(match mode {
+            Mode::Row => header_row + 1,
+            Mode::Column => header_col + 1,
+        }) {
+            0 => None,
+            // The template rows pushed down by the inserted rows are taken where they went
+            source if inserted > 0 && source >= first_row => Some(source + inserted),
+            source => Some(source),
+        };
+        let header_map = self.add_df_by_column_name(
+            &df, header_map, mode.clone(), (header_col, header_row), strict, skip_null, protect, &defaults, &row_colors, style_source, &mut warnings,
+        )?;
+
+        // The values coerced are located in the sheet, and flagged with a comment for the reviewers
+        let placed = |data_row: usize| -> Option<u32> {
+            updated_rows.get(&data_row).copied().or_else(|| {
+                let offset = data_rows.iter().position(|row| *row == Some(data_row))? as u32;
+                Some(match mode {
+                    Mode::Row => first_row + offset,
+                    Mode::Column => first_col + offset,
+                })
+            })
+        };
+        let mut coerced_cells: Vec<((u32, u32), String)> = Vec::new();
+        for warning in warnings.iter_mut().filter(|warning| warning.code == "coercion") {
+            let idx = warning.column.as_ref().and_then(|column| header_map.get(column));
+            if let (Some(idx), Some(position)) = (idx, warning.row.and_then(placed)) {
+                let (col, row) = match mode {
+                    Mode::Row => (*idx, position),
+                    Mode::Column => (position, *idx),
+                };
+                warning.cell = Some(index_to_excel(col, row));
+                coerced_cells.push(((col, row), warning.message.clone()));
+            }
+        }
+        if comment_coercions.unwrap_or(false) {
+            let worksheet = self.worksheet_mut(&current_sheet_name)?;
+            for (cell, message) in &coerced_cells {
+                set_comment(worksheet, *cell, message, COMMENT_AUTHOR);
+            }
+            debug!("{} coerced values commented in {}", coerced_cells.len(), current_sheet_name);
+        }
+
+        if let (Some(subtotals), Some((source, groups, layout))) = (&subtotals, &grouped) {
+            let worksheet = self.worksheet_mut(&current_sheet_name)?;
+            let row_groups = write_subtotal_rows(worksheet, source, groups, layout, &header_map, first_row, subtotals).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to write the subtotals: {}.", e))
+            })?;
+            let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
+                .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
+            for row_group in &row_groups {
+                add_row_group(spreadsheet, row_group).map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+            }
+        }
+
+        if let (Some(totals), true) = (&totals, df.height() > 0) {
+            let last_row = first_row + df.height() as u32 - 1;
+            check_cell_limits("fill_with", &current_sheet_name, header_col as u64, last_row as u64 + 1)?;
+            let worksheet = self.worksheet_mut(&current_sheet_name)?;
+            write_totals_row(worksheet, &header_map, totals, (header_row + 1, last_row), last_row + 1);
+        }
+
+        if let Some(fx) = &fx {
+            let worksheet = self.worksheet_mut(&current_sheet_name)?;
+            set_currency_formats(worksheet, &df, fx, &header_map, (first_col, first_row), &mode, &defaults.styles)
+                .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+        }
+
+        // An Excel Table holding the header grows (or shrinks) to the filled rows, inserted rows having grown it already
+        if let (Mode::Row, false) = (&mode, insert) {
+            let last_row = (first_row + df.height() as u32).saturating_sub(1);
+            let worksheet = self.worksheet_mut(&current_sheet_name)?;
+            let table_warnings = fit_table_to_data(worksheet, (header_col, header_row), last_row);
+            warnings.extend(table_warnings.into_iter().map(|message| Warning::new("table_not_resized", message).with_cell(index_to_excel(header_col, header_row))));
+        }
+
+        // The filled region gets its finishing touches: panes frozen below (or right of) the header, and a filter
+        if freeze_header.unwrap_or(false) {
+            let worksheet = self.worksheet_mut(&current_sheet_name)?;
+            match mode {
+                Mode::Row => freeze_panes(worksheet, 1, header_row + 1),
+                Mode::Column => freeze_panes(worksheet, header_col + 1, 1),
+            }
+        }
+        if autofilter {
+            let first = (header_map.values().copied().min().unwrap_or(header_col), header_row);
+            let last = (header_map.values().copied().max().unwrap_or(header_col), (first_row + df.height() as u32).saturating_sub(1).max(header_row + 1));
+            let worksheet = self.worksheet_mut(&current_sheet_name)?;
+            match overlapping_table(worksheet, first, last) {
+                Some(table) => debug!("Filled region of {} left to the filter of table {}", current_sheet_name, table),
+                None => {
+                    set_autofilter(worksheet, first, last).map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+                },
+            }
+        }
+
+        self.register_filled_region(&current_sheet_name, (header_col, header_row), mode.clone(), &header_map, (first_col, first_row), df.height())?;
+
+        if let Some(provenance) = provenance {
+            self.record_provenance(&current_sheet_name, &df, &header_map, (first_col, first_row), mode.clone(), &provenance)?;
+        }
+
+        if protect {
+            self.protect_current_sheet(password)?;
+        }
+
+        // The filled region is read back from the bytes of the saved file and compared with the data
+        if verify_fill.unwrap_or(false) {
+            let checked_rows: Vec<bool> = match &grouped {
+                Some((_, _, layout)) => layout.iter().map(|row| matches!(row, LayoutRow::Data(_))).collect(),
+                None => Vec::new(),
+            };
+            // Formulas have no value until Excel computes them, so their columns are not checked
+            let checked_columns: HashMap<String, u32> = header_map.iter()
+                .filter(|(column, _)| !defaults.formula_columns.as_ref().is_some_and(|columns| columns.contains(column)))
+                .map(|(column, idx)| (column.clone(), *idx))
+                .collect();
+            let checks = expected_checks(
+                &df, &checked_columns, (first_col, first_row), &mode, skip_null, defaults.null_display.as_deref(), &write_options, &checked_rows,
+            ).map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Verification of fill_with failed: {}.", e)))?;
+            self.verify_saved_fill(&current_sheet_name, &checks)?;
+        }
+
+        emit_structured_warnings(py, warnings).map(|warnings| warnings.into_py(py))
+    }
+
+    /// Writes a spreadsheet to a file atomically, with the sparklines the writer does not support
+    fn write_file(spreadsheet: &Spreadsheet, file_path: &str, backup: bool, password: Option<&str>) -> PyResult<()> {
+        let mut xlsx = Self::to_xlsx(spreadsheet)?;
+        if is_macro_path(file_path) && !spreadsheet.get_has_macros() {
+            xlsx = enable_macros(xlsx).map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("Failed to save file: {}.", e)))?;
+        }
+        if let Some(password) = password {
+            let directory = Path::new(file_path).parent().filter(|directory| !directory.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+            xlsx = encryption::encrypt(&xlsx, password, directory).map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("Failed to save file: {}.", e)))?;
+        }
+        write_atomically(Path::new(file_path), &xlsx, backup).map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("Failed to save file: {}.", e)))
+    }
+
+    /// Serializes a spreadsheet as the bytes of the xlsx file it is saved as
+    fn to_xlsx(spreadsheet: &Spreadsheet) -> PyResult<Vec<u8>> {
+        let save_error = |e: String| PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("Failed to save file: {}.", e));
+
+        let mut xlsx: Vec<u8> = Vec::new();
+        writer::xlsx::write_writer(spreadsheet, &mut xlsx).map_err(|e| save_error(format!("{:?}", e)))?;
+
+        #[cfg(feature = "charts")]
+        let sparklines: Vec<Sparkline> = read_records(spreadsheet, SPARKLINES_SHEET).iter()
+            .filter_map(|record| Sparkline::from_record(record))
+            .collect();
+        #[cfg(not(feature = "charts"))]
+        let sparklines: Vec<()> = Vec::new();
+        let row_groups = read_row_groups(spreadsheet);
+        if sparklines.is_empty() && row_groups.is_empty() {
+            return Ok(xlsx);
+        }
+
+        // Parts the writer does not support are written into the saved package
+        let sheet_indexes: HashMap<String, usize> = spreadsheet.get_sheet_collection_no_check().iter()
+            .enumerate()
+            .map(|(i, sheet)| (sheet.get_name().to_string(), i))
+            .collect();
+        #[cfg(feature = "charts")]
+        let xlsx = write_sparklines(xlsx, &sheet_indexes, &sparklines).map_err(save_error)?;
+        write_row_outlines(xlsx, &sheet_indexes, &row_groups).map_err(save_error)
+    }
+
+    /// Reports what a fill would write: the columns matched to the header or not, and the cells of the data
+    ///
+    /// The checks of the fill itself are made: mismatched columns are errors with `strict`, and the cells
+    /// must be within the limits of Excel. Columns missing in the header are placed after the last one.
+    #[cfg(feature = "dataframe")]
+    #[allow(clippy::too_many_arguments)]
+    fn fill_report(
+        &self,
+        py: Python,
+        df: &DataFrame,
+        header_map: &HashMap<String, u32>,
+        mode: Mode,
+        strict: bool,
+        overwrite: bool,
+        totals: bool,
+        mut warnings: Vec<Warning>,
+        mut errors: Vec<String>,
+    ) -> PyResult<FillReport> {
+        let sheet_name = self.sheet_or_current(None)?;
+        let worksheet = self.spreadsheet.get_sheet_by_name(&sheet_name).ok_or_else(|| sheet_not_found(&sheet_name))?;
+        let (header_col, header_row) = self.current_cell_in_current_sheet.as_ref()
+            .ok_or_else(|| template_error("No cell specified. Use set_header_location to set the starting cell."))?
+            .idx();
+        let columns: Vec<String> = df.get_column_names().iter().map(|name| name.to_string()).collect();
+
+        let mut headers: Vec<(&String, &u32)> = header_map.iter().collect();
+        headers.sort_by_key(|(_, idx)| **idx);
+        let (matched, missing): (Vec<_>, Vec<_>) = headers.into_iter().partition(|(name, _)| columns.contains(name));
+        let missing_columns: Vec<String> = missing.iter().map(|(name, _)| name.to_string()).collect();
+        let extra_columns: Vec<String> = columns.iter().filter(|name| !header_map.contains_key(*name)).cloned().collect();
+        // The positions written: the matched headers, then the columns added after the last one
+        let header_cell = |idx: u32| match mode {
+            Mode::Row => index_to_excel(idx, header_row),
+            Mode::Column => index_to_excel(header_col, idx),
+        };
+        let mut positions: Vec<u32> = matched.iter().map(|(_, idx)| **idx).collect();
+
+        // Mismatched columns are errors with strict, as the fill raises, and warnings otherwise
+        for (name, idx) in &missing {
+            let message = format!("Header '{}' in {} in the ExcelTemplate is missing in the DataFrame.", name, sheet_name);
+            if strict {
+                errors.push(message);
+            } else {
+                warnings.push(Warning::new("missing_in_data", message).with_column(name).with_cell(header_cell(**idx)));
+            }
+        }
+        for name in &extra_columns {
+            let message = format!("Header '{}' is missing in {} in the ExcelTemplate.", name, sheet_name);
+            if strict {
+                errors.push(message);
+            } else {
+                let idx = worksheet.get_highest_column() + 1;
+                positions.push(idx);
+                let message = format!("{} Column added after the last one.", message);
+                warnings.push(Warning::new("column_added", message).with_column(name).with_cell(header_cell(idx)));
+            }
+        }
+        let (last_col, last_row) = worksheet.get_highest_column_and_row();
+        let height = df.height() as u32 + (totals && df.height() > 0) as u32;
+        let range = match (positions.iter().min(), positions.iter().max(), height) {
+            (Some(first), Some(last), 1..) => {
+                let ((first_col, first_row), size) = match mode {
+                    Mode::Row => ((*first, if overwrite { header_row + 1 } else { last_row + 1 }), (last - first + 1, height)),
+                    Mode::Column => ((if overwrite { header_col + 1 } else { last_col + 1 }, *first), (height, last - first + 1)),
+                };
+                if let Err(e) = check_area_limits("fill_with", &sheet_name, (first_col, first_row), size) {
+                    errors.push(e.value(py).to_string());
+                }
+                Some(format!("{}:{}", index_to_excel(first_col, first_row), index_to_excel(first_col + size.0 - 1, first_row + size.1 - 1)))
+            },
+            _ => None,
+        };
+        info!("Fill of {} validated: {} rows, {} errors", sheet_name, df.height(), errors.len());
+        Ok(FillReport {
+            sheet: sheet_name,
+            header: index_to_excel(header_col, header_row),
+            mode: mode.as_str().to_string(),
+            rows: df.height(),
+            range,
+            matched_columns: matched.into_iter().map(|(name, _)| name.clone()).collect(),
+            missing_columns,
+            extra_columns,
+            warnings,
+            errors,
+        })
     }
 
-    pub fn goto_sheet(&mut self, sheet_name: &str, cell: Option<ExcelCell>) -> PyResult<()> {
-        self.current_sheet_name = Some(sheet_name.to_string());
-        self.current_cell_in_current_sheet = cell.clone();
-        debug!("Going to sheet {} in cell {}", sheet_name, cell.map_or("None".to_string(), |c| c.range()));
+    /// Checks the data of a fill against a sheet of the workbook as read back from its saved bytes
+    #[cfg(feature = "dataframe")]
+    fn verify_saved_fill(&self, sheet_name: &str, checks: &[ColumnCheck]) -> PyResult<()> {
+        let verify_error = |e: String| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Verification of fill_with failed: {}", e));
+        let xlsx = Self::to_xlsx(&self.spreadsheet)?;
+        let saved = reader::xlsx::read_reader(std::io::Cursor::new(xlsx), true).map_err(|e| verify_error(format!("the saved file cannot be read back ({:?}).", e)))?;
+        let worksheet = saved.get_sheet_by_name(sheet_name)
+            .ok_or_else(|| verify_error(format!("sheet '{}' is missing in the saved file.", sheet_name)))?;
+        verify_checks(worksheet, checks).map_err(verify_error)?;
+        info!("Fill of {} verified in the saved file ({} columns)", sheet_name, checks.len());
         Ok(())
     }
 
-    pub fn goto_cell(&mut self, cell: ExcelCell) -> PyResult<()> {
-        self.current_cell_in_current_sheet = Some(cell.clone());
-        debug!("Going to cell {}", cell.range());
-        Ok(())
+    /// Gives the final name of each sheet to import, after the conflict policy, and takes the names
+    ///
+    /// :return: The original and final name of each sheet.
+    fn plan_sheet_names(selected: &[String], taken: &mut Vec<String>, on_conflict: &SheetConflict, source_file: Option<&str>) -> PyResult<Vec<(String, String)>> {
+        let mut names = Vec::new();
+        for sheet_name in selected {
+            let clashes = taken.iter().any(|name| name.to_lowercase() == sheet_name.to_lowercase());
+            let final_name = match (clashes, on_conflict) {
+                (false, _) | (true, SheetConflict::Overwrite) => sheet_name.clone(),
+                (true, SheetConflict::Error) => {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "Sheet '{}' already exists. Use replace=True to replace it, or another on_conflict policy.", sheet_name
+                    )));
+                },
+                (true, SheetConflict::Suffix) => unique_sheet_name(sheet_name, taken, None),
+                (true, SheetConflict::PrefixSourceFile) => {
+                    let source_file = source_file.ok_or_else(|| {
+                        PyErr::new::<pyo3::exceptions::PyValueError, _>("on_conflict='prefix_source_file' requires the path of a workbook.")
+                    })?;
+                    let stem = Path::new(source_file).file_stem().map_or(source_file.into(), |stem| stem.to_string_lossy());
+                    unique_sheet_name(sheet_name, taken, Some(&sanitize_sheet_name(&stem)))
+                },
+            };
+            if final_name != *sheet_name {
+                debug!("Sheet {} imported as {} ({})", sheet_name, final_name, on_conflict.as_str());
+            }
+            taken.push(final_name.clone());
+            names.push((sheet_name.clone(), final_name));
+        }
+        Ok(names)
     }
 
-    pub fn set_header_location(&mut self, header: ExcelHeader, mode: Mode) -> PyResult<()> {
+    /// Imports sheets of another spreadsheet under their final names, replacing the sheets with the same name
+    fn import_sheets(&mut self, source: &Spreadsheet, names: &[(String, String)]) -> PyResult<()> {
         let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
             .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
-        
-        let sheet_name = self.current_sheet_name
-            .as_ref()
-            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("No sheet specified."))?
-            .to_string(); // Clone the string to avoid borrowing self
 
-        let worksheet = spreadsheet.get_sheet_by_name_mut(&sheet_name).ok_or_else(|| {
-            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Sheet '{}' not found.", sheet_name))
-        })?;
+        for (sheet_name, final_name) in names {
+            let mut worksheet = source.get_sheet_by_name(sheet_name)
+                .ok_or_else(|| sheet_not_found(sheet_name))?
+                .clone();
+            if final_name != sheet_name {
+                worksheet.set_name(final_name.clone());
+            }
 
-        let header_location = match header {
-            ExcelHeader::ExcelRange(r) => {
-                let ((start_col, start_row), (_, _)) = r.idx();
-                ExcelCell::Tuple((start_row, start_col))
-            },
-            ExcelHeader::ExcelCell(c) => c,
-            ExcelHeader::First => ExcelCell::default(),
-            ExcelHeader::Last => {
-                let (col, row) = worksheet.get_highest_column_and_row();
-                match mode {
-                    Mode::Row => ExcelCell::Tuple((row, col)),
-                    Mode::Column => ExcelCell::Tuple((col, row)),
+            let sheets = spreadsheet.get_sheet_collection_mut();
+            let index = match sheets.iter().position(|sheet| sheet.get_name().to_lowercase() == final_name.to_lowercase()) {
+                Some(index) => index,
+                None => sheets.len(),
+            };
+            // Names local to the sheet refer to it by its position in the workbook
+            for defined_name in worksheet.get_defined_names_mut().iter_mut() {
+                if defined_name.has_local_sheet_id() {
+                    defined_name.set_local_sheet_id(index as u32);
                 }
             }
-        };
-        self.current_cell_in_current_sheet = Some(header_location.clone());
-        debug!("Headers expected in cell {} of {}", index_to_excel(header_location.idx().0, header_location.idx().1), sheet_name);
 
+            if index < sheets.len() {
+                sheets[index] = worksheet;
+                debug!("Sheet {} replaced by the included one", final_name);
+            } else {
+                spreadsheet.add_sheet(worksheet).map_err(|e| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to include sheet '{}': {:?}.", final_name, e))
+                })?;
+                debug!("Sheet {} included", final_name);
+            }
+            self.header_locations.remove(final_name);
+        }
         Ok(())
     }
 
-    pub fn set_value(&mut self, value: Value) -> PyResult<()> {
-        // Store the current sheet name and cell in variables to avoid mutable borrow conflict
-        let sheet_name = self.current_sheet_name
-            .as_ref()
-            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("No sheet specified."))?
-            .to_string(); // Clone the string to avoid borrowing self
-
-        let current_cell = self.current_cell_in_current_sheet
-            .as_ref()
-            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("No cell specified in {}.", sheet_name)))?;
-                
-        // Now call write_cell with the extracted values
-        self.write_cell(&sheet_name, current_cell.clone(), value)
-    }
-
-    /// Writes data to a specified cell in a given sheet
-    pub fn write_cell(&mut self, sheet_name: &str, cell: ExcelCell, value: Value) -> PyResult<()> {
+    /// Hides or shows sheets, the active sheet moving to the first visible one when it gets hidden
+    fn set_sheets_visibility(&mut self, sheet_name: SheetSelector, visible: bool) -> PyResult<Vec<String>> {
+        let sheet_names = sheet_name.resolve(&self.sheet_names()?)?;
         let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
             .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
-        
-        let worksheet = spreadsheet.get_sheet_by_name_mut(sheet_name).ok_or_else(|| {
-            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Sheet {} not found.", sheet_name))
-        })?;
+        if !visible && !spreadsheet.get_sheet_collection().iter().any(|ws| is_visible(ws) && !sheet_names.iter().any(|name| name == ws.get_name())) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("A workbook must keep at least one visible sheet."));
+        }
 
-        let (col, row) = cell.idx();        
-        worksheet.get_cell_mut((col, row)).set_value(&value.value());
-        debug!("Value {:?} set at {} in {}", value, cell.range(), sheet_name);
-        Ok(())
+        let active = active_sheet_name(spreadsheet);
+        for worksheet in spreadsheet.get_sheet_collection_mut().iter_mut() {
+            if sheet_names.iter().any(|name| name == worksheet.get_name()) {
+                worksheet.set_state(if visible { SheetStateValues::Visible } else { SheetStateValues::Hidden });
+            }
+        }
+        activate_sheet(spreadsheet, active.as_deref());
+        debug!("Sheets {:?} {}", sheet_names, if visible { "shown" } else { "hidden" });
+        Ok(sheet_names)
     }
 
-    pub fn remove_row_from(&mut self, sheet_name: &str, row: u32) -> PyResult<()> {
-        let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
-            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
-        
-        let worksheet = spreadsheet.get_sheet_by_name_mut(sheet_name).ok_or_else(|| {
-            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Sheet {} not found.", sheet_name))
-        })?;
+    /// Returns the names of the sheets of a spreadsheet, leaving out the hidden metadata sheets
+    fn names_of_sheets(spreadsheet: &Spreadsheet) -> Vec<String> {
+        spreadsheet.get_sheet_collection_no_check().iter()
+            .map(|sheet| sheet.get_name().to_string())
+            .filter(|name| !is_meta_sheet(name))
+            .collect()
+    }
 
-        worksheet.remove_row(&row, &1);
+    /// Writes a value in a cell of a sheet, with the write options of the template
+    fn write_value(&mut self, sheet_name: &str, cell: &ExcelCell, value: Value) -> PyResult<()> {
+        let write_options = self.write_options.clone();
+        let (col, row) = cell.idx();        
+        check_cell_limits("write_cell", sheet_name, col as u64, row as u64)?;
+        let worksheet = self.worksheet_mut(sheet_name)?;
+        write_value(worksheet.get_cell_mut((col, row)), &value, &write_options);
+        debug!("Value {:?} set at {} in {}", value, cell.range(), sheet_name);
         Ok(())
     }
 
-    pub fn remove_rows_from(&mut self, sheet_name: &str, row: u32, num: u32) -> PyResult<()> {
+    /// Returns a mutable reference to a sheet of the spreadsheet
+    fn worksheet_mut(&mut self, sheet_name: &str) -> PyResult<&mut Worksheet> {
         let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
             .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
-        
-        let worksheet = spreadsheet.get_sheet_by_name_mut(sheet_name).ok_or_else(|| {
-            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Sheet {} not found.", sheet_name))
-        })?;
-
-        worksheet.remove_row(&row, &num);
-        Ok(())
-    }
-
-    /// Returns the list of sheet names
-    pub fn sheet_names(&self) -> PyResult<Vec<String>> {
-        let sheet_count = self.spreadsheet.as_ref().get_sheet_count();
-        let mut names = Vec::with_capacity(sheet_count);
-        
-        for i in 0..sheet_count {
-            let sheet = self.spreadsheet.as_ref().get_sheet(&i).ok_or_else(|| {
-                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("No sheet found at index {}.", i))
-            })?;
-            names.push(sheet.get_name().to_string());
-        }
-        
-        Ok(names)
-    }
 
-    /// Saves the spreadsheet to a specified file path
-    pub fn save(&self, file_path: &str) -> PyResult<()> {
-        writer::xlsx::write(&self.spreadsheet, Path::new(file_path)).map_err(|e| {
-            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("Failed to save file: {:?}.", e))
+        spreadsheet.get_sheet_by_name_mut(sheet_name).ok_or_else(|| {
+            sheet_not_found(sheet_name)
         })
     }
 
-    pub fn copy_range_from(
-        &mut self,
-        source_file_path: &str,
-        source_sheet_name: &str,
-        source_range: ExcelRange,
-        transpose: Option<bool>,
-        coerce: Option<Coerce>,
-    ) -> PyResult<()> {
+    /// Enables the protection of the current sheet, leaving only unlocked cells editable
+    #[cfg(feature = "dataframe")]
+    fn protect_current_sheet(&mut self, password: Option<String>) -> PyResult<()> {
         let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
-        .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet."))?;
-        
-        let current_sheet_name = self.current_sheet_name
-            .as_ref()
-            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("No sheet specified. Use goto_sheet to set the sheet."))?
-            .to_string(); // Clone the string to avoid borrowing self
-
-        let worksheet = spreadsheet.get_sheet_by_name_mut(&current_sheet_name).ok_or_else(|| {
-            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Sheet '{}' not found", current_sheet_name))
-        })?;
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet"))?;
 
-        let current_cell = self.current_cell_in_current_sheet
+        let current_sheet_name = self.current_sheet_name
             .as_ref()
-            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("No cell specified. Use goto_cell to set the cell."))?;
+            .ok_or_else(|| template_error("No sheet specified. Use goto_sheet to set the sheet."))?;
 
-        // Read the source workbook or return an error if it doesn't exist  
-        let source_workbook = reader::xlsx::read(source_file_path).map_err(|e| {
-            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("Failed to read Excel file: {}. Error: {:?}", source_file_path, e))
-        })?;
-        let source_sheet = source_workbook.get_sheet_by_name(source_sheet_name).ok_or_else(|| {
-            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Source sheet '{}' not found.", source_sheet_name))
+        let worksheet = spreadsheet.get_sheet_by_name_mut(current_sheet_name).ok_or_else(|| {
+            sheet_not_found(current_sheet_name)
         })?;
-        debug!("Source sheet {} found in {}", source_sheet_name, source_file_path);
-        // Match on the SourceRange enum to handle both cases
-        let ((start_col, start_row), (end_col, end_row)) = source_range.idx();
 
-        let transpose = transpose.unwrap_or(false);
-        let coerce = coerce.unwrap_or(Coerce::None);
-
-        let (current_cell_col, current_cell_row) = current_cell.idx();
-        // Copy the range from the source sheet to the destination sheet
-        debug!("Copying range {} of {} to {} of {}", 
-            source_range.range(), source_sheet_name, current_cell.range(), current_sheet_name);
-        
-        for col in start_col..=end_col {
-            for row in start_row..=end_row {
-                debug!("Processing cell {}", index_to_excel(col, row));
-                if let Some(source_cell) = source_sheet.get_cell((col, row)) {
-                    let original_value = source_cell.get_value().to_string();
-                    debug!("Original value: {:?}", original_value);
-                    let value = match coerce {
-                        Coerce::None | Coerce::String => original_value.clone(),
-                        _ => match original_value.parse::<f64>() {
-                            Ok(value) => {
-                                match coerce {
-                                    Coerce::Integer => (value as i32).to_string(),
-                                    _ => value.to_string(),
-                                }
-                            },
-                            Err(_) => {
-                                warn!("Value {:?} at {} is not a number. Ignored", original_value, index_to_excel(col, row));
-                                String::new()
-                            },
-                        },
-                    };
-                    // Calculate destination cell coordinates, with optional transposing
-                    let (d_col, d_row) = if transpose {
-                        debug!("Transposing range");
-                        (current_cell_col + row - start_row, current_cell_row + col - start_col)
-                    } else {
-                        (current_cell_col + col - start_col, current_cell_row + row - start_row)
-                    };
-                    // Attempt to set the value
-                    worksheet.get_cell_mut((d_col, d_row)).set_value(&value);
-                    debug!("Value {:?} taken from {} and set to {:?} at {}", 
-                        original_value, index_to_excel(col, row), value, index_to_excel(d_col, d_row));
-                }
-                else {
-                    debug!("Cell {} is empty", index_to_excel(col, row));
-                }
-            }
+        let protection = worksheet.get_sheet_protection_mut();
+        protection.set_sheet(true);
+        if let Some(password) = password {
+            protection.set_password(&password);
         }
+        debug!("Sheet {} protected", current_sheet_name);
         Ok(())
     }
 
-    pub fn aggregate_range_from(
-        &mut self,
-        source_file_path: &str,
-        source_sheet_name: &str,
-        source_range: ExcelRange,
-        action: Action,
-        mode: Mode,        
-    ) -> PyResult<()> {
-        let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
-        .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet"))?;
-        
-        let current_sheet_name = self.current_sheet_name
-            .as_ref()
-            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("No sheet specified. Use goto_sheet to set the sheet."))?
-            .to_string(); // Clone the string to avoid borrowing self
-
-        let worksheet = spreadsheet.get_sheet_by_name_mut(&current_sheet_name).ok_or_else(|| {
-            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Sheet '{}' not found", current_sheet_name))
-        })?;
+    /// Locks or unlocks the cells of a range, the sheet defaulting to the one of the range then the current sheet
+    fn set_cells_locked(&mut self, operation: &str, range: ExcelRange, sheet_name: Option<&str>, locked: bool) -> PyResult<()> {
+        catch_panic(operation, || {
+            let range = range.resolve(&self.spreadsheet, sheet_name)?;
+            let sheet_name = self.sheet_or_current(sheet_name.or(range.sheet()))?;
+            range.check_sheet(&sheet_name)?;
+            let worksheet = self.worksheet_mut(&sheet_name)?;
+            let (first, last) = range.idx_in(worksheet);
+            check_area_limits(operation, &sheet_name, first, (last.0 - first.0 + 1, last.1 - first.1 + 1))?;
+            set_locked(worksheet, first, last, locked);
+            Ok(())
+        })
+    }
 
-        let current_cell = self.current_cell_in_current_sheet
-            .as_ref()
-            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("No cell specified. Use goto_cell to set the cell."))?;
+    /// Builds the spreadsheet to be saved, or None if it is the template spreadsheet as is
+    fn output_spreadsheet(&self, py: Python) -> PyResult<Option<Spreadsheet>> {
+        let excluded_sheets = self.excluded_sheets(py)?;
+        if excluded_sheets.is_empty() && self.stamp.is_none() {
+            return Ok(None);
+        }
 
-        // Read the source workbook or return an error if it doesn't exist  
-        let source_workbook = reader::xlsx::read(source_file_path).map_err(|_| {
-            let err_msg = format!("Failed to read Excel file: {}. Check if the file exists and is readable.", source_file_path);
-            PyErr::new::<pyo3::exceptions::PyFileNotFoundError, _>(err_msg)
-        })?;
-        let source_sheet = source_workbook.get_sheet_by_name(source_sheet_name)
-            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("Source sheet not found"))?;
-        debug!("Source sheet {} found in {}", source_sheet_name, source_file_path);
-    
-        // Match on the SourceRange enum to handle both cases
-        let ((start_col, start_row), (end_col, end_row)) = source_range.idx();
+        if excluded_sheets.len() == self.spreadsheet.get_sheet_count() {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("All sheets would be dropped. A workbook must contain at least one sheet."));
+        }
 
-        // Copy the range from the source sheet to the destination sheet
-        let results =aggregate_range(source_sheet, start_row, start_col, end_row, end_col, action, mode.clone()).map_err(|e| {
-            let err_msg = format!("Failed to aggregate range: {}", e);
-            PyErr::new::<pyo3::exceptions::PyValueError, _>(err_msg)
-        })?;
-    
-        debug!("Results: {:?}", results);
+        let mut spreadsheet = self.spreadsheet.as_ref().clone();
+        for sheet_name in &excluded_sheets {
+            debug!("Dropping sheet {} before saving", sheet_name);
+            spreadsheet.remove_sheet_by_name(sheet_name).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to drop sheet '{}': {:?}.", sheet_name, e))
+            })?;
+        }
+        if !excluded_sheets.is_empty() {
+            spreadsheet.set_active_sheet(0);
+        }
 
-        let current_cell_idx = current_cell.idx();
-        for (i, value) in results.iter().enumerate() {
-            debug!("Pasting value {}: {} to sheet", i, value);
-            match mode {
-                Mode::Row => {
-                    worksheet.get_cell_mut((current_cell_idx.0, current_cell_idx.1 + i as u32)).set_value(value.to_string());
-                    debug!("Pasted value {} to cell {}", value, index_to_excel(current_cell_idx.0, current_cell_idx.1+ i as u32));
-                },
-                Mode::Column => {
-                    worksheet.get_cell_mut((current_cell_idx.0 + i as u32, current_cell_idx.1)).set_value(value.to_string());
-                    debug!("Pasted value {} to cell {}", value, index_to_excel(current_cell_idx.0 + i as u32, current_cell_idx.1));
-                },
+        if let Some(stamp) = &self.stamp {
+            for worksheet in spreadsheet.get_sheet_collection_mut().iter_mut() {
+                stamp.apply(worksheet);
             }
         }
-        Ok(())
-    }    
-
-    fn get_header_map(&self, mode: Mode) -> PyResult<HashMap<String, u32>> {
-        let spreadsheet = Arc::as_ref(&self.spreadsheet);        
-        let current_sheet_name = match self.current_sheet_name.as_ref() {
-            Some(sheet_name) => sheet_name.clone(),
-            None => return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("No sheet specified. Use goto_sheet to set the sheet.")),
-        };
-
-        let worksheet = spreadsheet.get_sheet_by_name(&current_sheet_name).ok_or_else(|| {
-            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Sheet '{}' not found", current_sheet_name))
-        })?;
-
-        let header_location = match self.current_cell_in_current_sheet.as_ref() {
-            Some(cell) => cell,
-            None => return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("No cell specified. Use set_header_location to set the startingcell.")),
-        };
-
-        let (header_col, header_row) = header_location.idx();
-        debug!("Getting headers starting from {} in mode {}", index_to_excel(header_col, header_row), mode);
 
-        let mut header_map = HashMap::new();
-        let first = match mode {
-            Mode::Row => header_col,
-            Mode::Column => header_row,
-        };
-        let last = match mode {
-            Mode::Row => worksheet.get_highest_column(),
-            Mode::Column => worksheet.get_highest_row(),
-        };
-        debug!("From {} to {}", first, last);
-    
-        for i in first..=last {
-            let (col, row) = match mode {
-                Mode::Row => (i, header_row),
-                Mode::Column => (header_col, i),
-            };  
-            let col_name = worksheet.get_value((col, row)).to_string();
-            debug!("Header {} in {}", col_name, index_to_excel(col, row));
-            header_map.insert(col_name, col);
-        }
-    
-        Ok(header_map)
+        Ok(Some(spreadsheet))
     }
 
-    pub fn fill_with(
-        &mut self,
-        py: Python,
-        df: PyObject,
-        columns: Option<PyObject>,
-        mode: Option<Mode>,
-        strict: Option<bool>,
-        skip_null: Option<bool>,
-        overwrite: Option<bool>,
-    ) -> PyResult<()> {
-        let data_type = get_datatype(py, df.as_ref(py))?;
-
-        let df: DataFrame = convert(py, data_type, df.as_ref(py), columns)?;
-        debug!("df: {:?}", df);
-
-        // Convert the input to a Polars DataFrame
-        let mode = mode.unwrap_or(Mode::Row);
-        let skip_null = skip_null.unwrap_or(false);
-        let strict = strict.unwrap_or(false);
-        let overwrite = overwrite.unwrap_or(false);
-
-        let header_map = self.get_header_map(mode.clone())?;
-
-        let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
-            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet"))?;
-        
-        let current_sheet_name = self.current_sheet_name
-            .as_ref()
-            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("No sheet specified. Use goto_sheet to set the sheet."))?
-            .to_string(); // Clone the string to avoid borrowing self
-
-        let worksheet = spreadsheet.get_sheet_by_name_mut(&current_sheet_name).ok_or_else(|| {
-            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Sheet '{}' not found", current_sheet_name))
-        })?;
-
-        let header_location = self.current_cell_in_current_sheet
-            .as_ref()
-            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("No cell specified. Use set_header_location to set the starting cell."))?;
+    /// Returns the names of the sheets that must be left out when saving
+    fn excluded_sheets(&self, py: Python) -> PyResult<Vec<String>> {
+        let mut excluded = Vec::new();
+        for worksheet in self.spreadsheet.get_sheet_collection() {
+            let sheet_name = worksheet.get_name().to_string();
 
-        let (last_col, last_row) = worksheet.get_highest_column_and_row();
-        let (header_col, header_row) = header_location.idx();
-        let (first_col, first_row) = match mode {
-            Mode::Row => (header_col.clone(), if overwrite { header_row + 1 } else { last_row + 1 }),
-            Mode::Column => (if overwrite { header_col + 1 } else { last_col + 1 }, header_row.clone()),
-        };
+            if let Some(predicate) = self.sheet_inclusion_rules.get(&sheet_name) {
+                let predicate = predicate.as_ref(py);
+                let keep: bool = if predicate.is_callable() {
+                    predicate.call1((sheet_name.as_str(),))?.is_true()?
+                } else {
+                    predicate.is_true()?
+                };
+                if !keep {
+                    debug!("Sheet {} excluded by its inclusion rule", sheet_name);
+                    excluded.push(sheet_name);
+                    continue;
+                }
+            }
 
-        debug!("Reading {}", mode);
-        match mode {
-            Mode::Row => {
-                debug!("Header location: {}", index_to_excel(header_col, header_row));
-                debug!("Last existing row: {}", last_row);
-                debug!("First row to fill: {}", first_row);
-            },
-            Mode::Column => {
-                debug!("Header location: {}", index_to_excel(header_col, header_row));
-                debug!("Last existing column: {}", last_col);
-                debug!("First column to fill: {}", first_col);
-            },
+            if self.drop_empty_sheets && !Self::has_data(worksheet, self.header_locations.get(&sheet_name)) {
+                debug!("Sheet {} has no data", sheet_name);
+                excluded.push(sheet_name);
+            }
         }
-        self.goto_cell(ExcelCell::Tuple((first_row, first_col)))?;
-
-        self.add_df_by_column_name(&df, header_map, mode, strict, skip_null)?;
+        Ok(excluded)
+    }
 
-        Ok(())
+    /// Checks whether a sheet holds any value beyond its header (or any value at all without header)
+    fn has_data(worksheet: &Worksheet, header: Option<&(ExcelCell, Mode)>) -> bool {
+        worksheet.get_cell_collection().iter().any(|cell| {
+            if cell.get_value().is_empty() {
+                return false;
+            }
+            let coordinate = cell.get_coordinate();
+            let (col, row) = (*coordinate.get_col_num(), *coordinate.get_row_num());
+            match header {
+                Some((location, Mode::Row)) => row > location.idx().1,
+                Some((location, Mode::Column)) => col > location.idx().0,
+                None => true,
+            }
+        })
     }
-    
-}
 
-// Methods that are not available in Python
-impl ExcelTemplate {
+    #[cfg(feature = "dataframe")]
+    #[allow(clippy::too_many_arguments)]
     fn add_df_by_column_name(
         &mut self,
         df: &DataFrame,
         header_map: HashMap<String, u32>,
         mode: Mode,
+        header: (u32, u32),
         strict: bool,
         skip_null: bool,
-    ) -> Result<(), PyErr> {
+        unlock: bool,
+        defaults: &FillDefaults,
+        row_colors: &[Option<String>],
+        style_source: Option<u32>,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<HashMap<String, u32>, PyErr> {
         let mut header_map = header_map.clone();
+        let write_options = WriteOptions { date_format: defaults.date_format.clone(), ..self.write_options.clone() };
         let spreadsheet = Arc::get_mut(&mut self.spreadsheet)
         .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot modify spreadsheet"))?;
         
         let current_sheet_name = self.current_sheet_name
             .as_ref()
-            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("No sheet specified. Use goto_sheet to set the sheet."))?
+            .ok_or_else(|| template_error("No sheet specified. Use goto_sheet to set the sheet."))?
             .to_string(); // Clone the string to avoid borrowing self
 
         let worksheet = spreadsheet.get_sheet_by_name_mut(&current_sheet_name).ok_or_else(|| {
-            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Sheet '{}' not found", current_sheet_name))
+            sheet_not_found(&current_sheet_name)
         })?;
 
         let df_headers: Vec<String> = df.get_column_names().iter().map(|s| s.to_string()).collect(); // Convert to Vec<String>
         
+        let header_cell = |idx: u32| match mode {
+            Mode::Row => index_to_excel(idx, header.1),
+            Mode::Column => index_to_excel(header.0, idx),
+        };
+
         // Check for missing columns in DataFrame
-        for col_name in header_map.keys() {
+        for (col_name, idx) in &header_map {
             if !df_headers.contains(col_name) {
                 let err_msg = format!("Header '{}' in {} in the ExcelTemplate is missing in the DataFrame.", col_name, current_sheet_name);
                 warn!("{}", err_msg);
                 if strict {
-                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(err_msg));
+                    return Err(column_mismatch(err_msg, &current_sheet_name, col_name));
+                }
+                else {
+                    warnings.push(Warning::new("missing_in_data", err_msg).with_column(col_name).with_cell(header_cell(*idx)));
                 }
             }
         }
@@ -486,10 +4266,13 @@ impl ExcelTemplate {
                 let err_msg = format!("Header '{}' is missing in {} in the ExcelTemplate.", df_col, current_sheet_name);
                 warn!("{}", err_msg);
                 if strict {
-                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(err_msg));
+                    return Err(column_mismatch(err_msg, &current_sheet_name, df_col));
                 }
                 else {
-                    header_map.insert(df_col.to_string(), worksheet.get_highest_column() + 1);
+                    let idx = worksheet.get_highest_column() + 1;
+                    let message = format!("{} Column added after the last one.", err_msg);
+                    warnings.push(Warning::new("column_added", message).with_column(df_col).with_cell(header_cell(idx)));
+                    header_map.insert(df_col.to_string(), idx);
                 }
             }
         }
@@ -497,33 +4280,92 @@ impl ExcelTemplate {
         // Proceed with adding the DataFrame to the sheet for matching columns
         let current_cell = self.current_cell_in_current_sheet
             .as_ref()
-            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("No cell specified. Use set_header_location to set the startingcell."))?;
+            .ok_or_else(|| template_error("No cell specified. Use set_header_location to set the startingcell."))?;
     
         let (current_col, current_row) = current_cell.idx();
 
         let height = df.height();
-        for (header_name, idx) in header_map {
+        for idx in header_map.values() {
+            let (first, size) = match mode {
+                Mode::Row => ((*idx, current_row), (1, height as u32)),
+                Mode::Column => ((current_col, *idx), (height as u32, 1)),
+            };
+            check_area_limits("fill_with", &current_sheet_name, first, size)?;
+        }
+        // The styles of the template row are taken before it is written over
+        let source_position = |idx: u32, source: u32| match mode {
+            Mode::Row => (idx, source),
+            Mode::Column => (source, idx),
+        };
+        let template_styles: HashMap<u32, Style> = match style_source {
+            Some(source) => header_map.values()
+                .filter_map(|idx| worksheet.get_cell(source_position(*idx, source)).map(|cell| (*idx, cell.get_style().clone())))
+                .collect(),
+            None => HashMap::new(),
+        };
+
+        // Values falling on cells hidden by a merge go to its top left cell, unless the data fill that one too
+        let merges = MergedCells::of(worksheet);
+        let rows = current_row..current_row + height as u32;
+        let cols = current_col..current_col + height as u32;
+        let filled = |(col, row): (u32, u32)| match mode {
+            Mode::Row => rows.contains(&row) && header_map.values().any(|idx| *idx == col),
+            Mode::Column => cols.contains(&col) && header_map.values().any(|idx| *idx == row),
+        };
+        let mut redirected: HashSet<(u32, u32)> = HashSet::new();
+        let mut columns: Vec<(&String, &u32)> = header_map.iter().collect();
+        columns.sort_by_key(|(_, idx)| **idx);
+
+        let mut cell_log = CellLog::new("fill_with");
+        for (header_name, idx) in columns {
+            let (header_name, idx) = (header_name.as_str(), *idx);
             debug!("Header {} in {}", header_name, idx);
-            if let Some(series) = df.column(&header_name).ok() {
+            if let Ok(series) = df.column(header_name) {
                 for i in 0..height {
                     let value = series.get(i).unwrap();
+                    let (col, row) = match mode {
+                        Mode::Row => (idx, current_row + i as u32),
+                        Mode::Column => (current_col + i as u32, idx),
+                    };
+                    if let (Some(style), Some(source)) = (template_styles.get(&idx), style_source) {
+                        if source_position(idx, source) != (col, row) {
+                            worksheet.set_style((col, row), style.clone());
+                        }
+                    }
+                    if unlock {
+                        // Skipped cells are part of the data region and stay editable too
+                        worksheet.get_style_mut((col, row)).get_protection_mut().set_locked(false);
+                    }
+                    if let Some(Some(color)) = row_colors.get(i) {
+                        set_fill_color(worksheet.get_cell_mut((col, row)), color);
+                    }
                     if skip_null && value == AnyValue::Null {
                         continue;
-                    } else {    
-                        let cell_value = convert_anyvalue_to_string(value);
-                        let (col, row) = match mode {
-                            Mode::Row => (idx, current_row + i as u32),
-                            Mode::Column => (current_col + i as u32, idx),
-                        };
-                        debug!("Column: {}, Row: {}", col, row);
-                        worksheet.get_cell_mut((col, row)).set_value(cell_value.clone());
-                        debug!("{}: {} = {}", header_name, index_to_excel(col, row), cell_value);
                     }
+                    let (col, row) = match merges.hiding((col, row)) {
+                        None => (col, row),
+                        Some(_) if value == AnyValue::Null => continue,
+                        Some(merge) if filled(merge.0) || !redirected.insert(merge.0) => {
+                            let message = format!(
+                                "Value {} of '{}' at {} is hidden by the merged cells {} of {}. Not written.",
+                                convert_anyvalue_to_string(value), header_name, index_to_excel(col, row), area_range(merge), current_sheet_name
+                            );
+                            warn!("{}", message);
+                            warnings.push(Warning::new("hidden_by_merge", message).with_column(header_name).with_cell(index_to_excel(col, row)));
+                            continue;
+                        },
+                        Some(merge) => merge.0,
+                    };
+                    let cell_value = Self::write_fill_value(worksheet.get_cell_mut((col, row)), header_name, value, defaults, &write_options);
+                    cell_log.debug(|| format!("{}: {} = {}", header_name, index_to_excel(col, row), cell_value));
                 }
             }
         }
 
+        // The rows (or columns) below the data are removed, unless they hold e.g. a footer to keep
+        let truncate = defaults.truncate.unwrap_or(true);
         match mode {
+            _ if !truncate => debug!("Rows below the data kept"),
             Mode::Row => {
                 let last_row = worksheet.get_highest_row();
                 let first_row_to_remove = current_row + height as u32;
@@ -542,6 +4384,126 @@ impl ExcelTemplate {
             },
         };
     
-        Ok(())
+        Ok(header_map)
+    }
+
+    /// Writes a value of the data into a cell, with the null display and the number format of its column
+    ///
+    /// The values of the formula columns are written as formulas.
+    #[cfg(feature = "dataframe")]
+    fn write_fill_value(cell: &mut Cell, column: &str, value: AnyValue, defaults: &FillDefaults, write_options: &WriteOptions) -> String {
+        let formula = defaults.formula_columns.as_ref().is_some_and(|columns| columns.iter().any(|c| c == column));
+        let cell_value = match (&value, &defaults.null_display) {
+            // Texts of formula columns are written as formulas, with or without their leading '='
+            (AnyValue::Null, _) if formula => {
+                cell.set_blank();
+                String::new()
+            },
+            _ if formula => {
+                let text = convert_anyvalue_to_string(value);
+                let text = text.trim().trim_start_matches('=');
+                cell.set_blank();
+                match text.is_empty() {
+                    true => String::new(),
+                    false => {
+                        cell.set_formula(text);
+                        format!("={}", text)
+                    },
+                }
+            },
+            (AnyValue::Null, Some(null_display)) => {
+                cell.set_value_string(null_display.clone());
+                null_display.clone()
+            },
+            _ => write_anyvalue(cell, value, write_options),
+        };
+        if let Some(format_code) = defaults.styles.get(column) {
+            cell.get_style_mut().get_number_format_mut().set_format_code(format_code.clone());
+        }
+        cell_value
+    }
+
+    /// Writes the rows of the data whose key is already in the key column of the sheet over the row holding it
+    ///
+    /// Keys are compared as texts, the first row of the sheet holding a key being updated.
+    /// :return: The rows whose key is not in the sheet, to be appended, with their colors, and the row of the
+    ///     sheet each row of the data updated (None for the rows appended).
+    #[cfg(feature = "dataframe")]
+    #[allow(clippy::too_many_arguments)]
+    fn update_matching_rows(
+        &mut self,
+        df: &DataFrame,
+        header_map: &HashMap<String, u32>,
+        key: &str,
+        skip_null: bool,
+        defaults: &FillDefaults,
+        write_options: &WriteOptions,
+        row_colors: &[Option<String>],
+    ) -> PyResult<UpsertRows> {
+        let sheet_name = self.current_sheet_name.clone()
+            .ok_or_else(|| template_error("No sheet specified. Use goto_sheet to set the sheet."))?;
+        let key_idx = *header_map.get(key).ok_or_else(|| {
+            column_mismatch(format!("Key column '{}' of upsert_on is missing in the header of the sheet.", key), &sheet_name, key)
+        })?;
+        let keys = df.column(key).map_err(|_| {
+            column_mismatch(format!("Key column '{}' of upsert_on is missing in the DataFrame.", key), &sheet_name, key)
+        })?;
+        let (_, header_row) = self.current_cell_in_current_sheet
+            .as_ref()
+            .ok_or_else(|| template_error("No cell specified. Use set_header_location to set the starting cell."))?
+            .idx();
+        let worksheet = self.worksheet_mut(&sheet_name)?;
+
+        let mut existing_rows: HashMap<String, u32> = HashMap::new();
+        for row in header_row + 1..=worksheet.get_highest_row() {
+            let value = worksheet.get_value((key_idx, row)).trim().to_string();
+            if !value.is_empty() {
+                existing_rows.entry(value).or_insert(row);
+            }
+        }
+
+        let mut appended = Vec::with_capacity(df.height());
+        let mut updated = Vec::with_capacity(df.height());
+        let mut cell_log = CellLog::new("fill_with");
+        for i in 0..df.height() {
+            let key_value = keys.get(i).map(convert_anyvalue_to_string).unwrap_or_default();
+            let row = match existing_rows.get(key_value.trim()) {
+                Some(row) => *row,
+                None => {
+                    appended.push(true);
+                    updated.push(None);
+                    continue;
+                },
+            };
+            appended.push(false);
+            updated.push(Some(row));
+            debug!("Key {} found in row {}, row updated", key_value, row);
+            for (header_name, idx) in header_map {
+                let value = match df.column(header_name) {
+                    Ok(series) => series.get(i).unwrap_or(AnyValue::Null),
+                    Err(_) => continue,
+                };
+                if let Some(Some(color)) = row_colors.get(i) {
+                    set_fill_color(worksheet.get_cell_mut((*idx, row)), color);
+                }
+                if skip_null && value == AnyValue::Null {
+                    continue;
+                }
+                let cell_value = Self::write_fill_value(worksheet.get_cell_mut((*idx, row)), header_name, value, defaults, write_options);
+                cell_log.debug(|| format!("{}: {} = {}", header_name, index_to_excel(*idx, row), cell_value));
+            }
+        }
+        let appended_count = appended.iter().filter(|a| **a).count();
+        debug!("{} rows updated in {}, {} rows to append", appended.len() - appended_count, sheet_name, appended_count);
+
+        let row_colors = match row_colors.is_empty() {
+            true => Vec::new(),
+            false => row_colors.iter().zip(&appended).filter(|(_, a)| **a).map(|(color, _)| color.clone()).collect(),
+        };
+        let mask = BooleanChunked::from_slice("appended".into(), &appended);
+        let df = df.filter(&mask).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Failed to select the rows to append: {}.", e))
+        })?;
+        Ok((df, row_colors, updated))
     }
-}
\ No newline at end of file
+}
@@ -2,71 +2,188 @@
 use log::{debug, info, warn};
 use umya_spreadsheet::{self, Worksheet};
 
-use crate::structs::{Action, Mode};
+use crate::structs::{Action, Criteria, Mode, NonNumeric};
+use crate::utils::excel::index_to_excel;
+use crate::utils::merges::{area_range, Area};
+use crate::utils::sampling::CellLog;
+use crate::utils::warnings::Warning;
 
-// Helper to convert (col, row) into Excel-style coordinates like "A1"
-fn to_excel_coords(col: u32, row: u32) -> String {
-    let col_letter = (col as u8 + b'A' - 1) as char;
-    format!("{}{}", col_letter, row)
+/// Condition of a conditional aggregation (SUMIF, COUNTIF...): criteria tested on the aggregated cells,
+/// or on the corresponding cells of another range
+#[derive(Debug, Clone)]
+pub struct Condition {
+    criteria: Criteria,
+    origin: (u32, u32),
+    tested: Option<Area>,
+}
+
+impl Condition {
+    /// Condition on the cells of `range`, tested on `tested` if given
+    ///
+    /// The tested range has the size of the aggregated range, or is a single column (testing whole rows)
+    /// or row (testing whole columns) of the same height or width.
+    pub fn new(criteria: Criteria, range: Area, tested: Option<Area>) -> Result<Self, String> {
+        if let Some(tested) = tested {
+            let size = |area: Area| (area.1.0 - area.0.0 + 1, area.1.1 - area.0.1 + 1);
+            let ((width, height), (tested_width, tested_height)) = (size(range), size(tested));
+            if (tested_width != 1 && tested_width != width) || (tested_height != 1 && tested_height != height) {
+                return Err(format!(
+                    "The criteria range {} must have the size of the aggregated range {}, or be a row or a column of the same size.",
+                    area_range(tested), area_range(range)
+                ));
+            }
+        }
+        Ok(Condition { criteria, origin: range.0, tested })
+    }
+
+    /// Whether a cell of the aggregated range is aggregated
+    fn admits(&self, source_sheet: &Worksheet, col: u32, row: u32) -> bool {
+        let cell = match self.tested {
+            None => (col, row),
+            Some(((first_col, first_row), (last_col, last_row))) => (
+                if first_col == last_col { first_col } else { first_col + col - self.origin.0 },
+                if first_row == last_row { first_row } else { first_row + row - self.origin.1 },
+            ),
+        };
+        self.criteria.matches(&source_sheet.get_value(cell))
+    }
+}
+
+/// How the cells of an aggregated range holding no number are taken, and which cells are aggregated
+#[derive(Debug, Clone)]
+pub struct NumberPolicy {
+    pub on_non_numeric: NonNumeric,
+    pub skip_blanks: bool,
+    pub condition: Option<Condition>,
+}
+
+impl NumberPolicy {
+    /// Policy of the options given from Python, blanks and other non-numeric cells being skipped by default
+    pub fn new(on_non_numeric: Option<NonNumeric>, skip_blanks: Option<bool>) -> Self {
+        NumberPolicy { on_non_numeric: on_non_numeric.unwrap_or_default(), skip_blanks: skip_blanks.unwrap_or(true), condition: None }
+    }
+}
+
+/// Reads the number of a cell after the policy, None for a cell left out
+///
+/// Cells failing the condition are left out first. Blank cells are left out or taken as 0. Other
+/// non-numeric cells are left out or taken as 0, both reported in `warnings`, or make the aggregation fail.
+fn read_number(source_sheet: &Worksheet, col: u32, row: u32, policy: &NumberPolicy, warnings: &mut Vec<Warning>) -> Result<Option<f64>, String> {
+    if policy.condition.as_ref().is_some_and(|condition| !condition.admits(source_sheet, col, row)) {
+        return Ok(None);
+    }
+    let value = source_sheet.get_value((col, row));
+    if value.trim().is_empty() {
+        return Ok((!policy.skip_blanks).then_some(0.0));
+    }
+    if let Ok(number) = value.parse::<f64>() {
+        return Ok(Some(number));
+    }
+    let message = format!("Non-numeric value found in cell {} of {}: '{}'.", index_to_excel(col, row), source_sheet.get_name(), value);
+    let (message, number) = match policy.on_non_numeric {
+        NonNumeric::Skip => (format!("{} Skipped.", message), None),
+        NonNumeric::Zero => (format!("{} Taken as 0.", message), Some(0.0)),
+        NonNumeric::Error => return Err(message),
+    };
+    warn!("{}", message);
+    warnings.push(Warning::new("non_numeric", message).with_cell(index_to_excel(col, row)));
+    Ok(number)
 }
 
 // Aggregation function to calculate the result based on mode (Row or Column)
+// The numbers of the same range of several sheets (e.g. of several files) are pooled by row (or column)
+// Cells holding no number are taken after `policy`, those skipped or taken as 0 being reported in `warnings`
+// Returns None for the rows (or columns) with too few numbers for the action, and an error when all have too few
+#[allow(clippy::too_many_arguments)]
 pub fn aggregate_range(
-    source_sheet: &Worksheet,
+    source_sheets: &[&Worksheet],
     start_row: u32,
     start_col: u32,
     end_row: u32,
     end_col: u32,
     action: Action,
     mode: Mode,
-) -> Result<Vec<f64>, Box<dyn std::error::Error>> {
-    let mut sum_by_row: Vec<f64> = vec![0.0; (end_row - start_row + 1) as usize];   
-    let mut sum_by_col: Vec<f64> = vec![0.0; (end_col - start_col + 1) as usize];
-    let mut count_not_numeric_by_row: Vec<f64> = vec![0.0; (end_row - start_row + 1) as usize];
-    let mut count_not_numeric_by_col: Vec<f64> = vec![0.0; (end_col - start_col + 1) as usize];
+    policy: &NumberPolicy,
+    warnings: &mut Vec<Warning>,
+) -> Result<Vec<Option<f64>>, Box<dyn std::error::Error>> {
+    let mut values_by_row: Vec<Vec<f64>> = vec![Vec::new(); (end_row - start_row + 1) as usize];
+    let mut values_by_col: Vec<Vec<f64>> = vec![Vec::new(); (end_col - start_col + 1) as usize];
 
-    for row in start_row..=end_row {
-        for col in start_col..=end_col {
-            if let Some(source_cell) = source_sheet.get_cell((col, row)) {
-                let value = source_cell.get_value().to_string();
-                match value.parse::<f64>() {
-                    Ok(parsed_value) => {
-                        debug!("Row: {}, Col: {}, Value: {}", row, col, parsed_value);
-                        sum_by_row[row as usize - start_row as usize] += parsed_value;
-                        sum_by_col[col as usize - start_col as usize] += parsed_value;
-                        count_not_numeric_by_row[row as usize - start_row as usize] += 1.0;
-                        count_not_numeric_by_col[col as usize - start_col as usize] += 1.0;
-                    }
-                    Err(_) => {
-                        warn!("Non-numeric value found in cell {}: '{}'", to_excel_coords(col, row), value);
-                    }
-                }
+    let mut cell_log = CellLog::new("aggregate_range");
+    for (row, col) in (start_row..=end_row).flat_map(|row| (start_col..=end_col).map(move |col| (row, col))) {
+        for source_sheet in source_sheets {
+            if let Some(number) = read_number(source_sheet, col, row, policy, warnings)? {
+                cell_log.debug(|| format!("Row: {}, Col: {}, Value: {}", row, col, number));
+                values_by_row[row as usize - start_row as usize].push(number);
+                values_by_col[col as usize - start_col as usize].push(number);
             }
         }
     }
 
-    let sum = match mode {
-        Mode::Row => sum_by_row,
-        Mode::Column => sum_by_col,
+    let values = match mode {
+        Mode::Row => values_by_row,
+        Mode::Column => values_by_col,
     };
-    debug!("Sum: {:?}", sum);
-    let count = match mode {
-        Mode::Row => count_not_numeric_by_row,
-        Mode::Column => count_not_numeric_by_col,
-    };
-    debug!("Count: {:?}", count);
+    debug!("Values: {:?}", values);
 
     debug!("Action: {:?}", action);
-    match action {
-        Action::Sum => Ok(sum),
-        Action::Count => Ok(count),
-        Action::Average => {
-            if count.iter().any(|&c| c > 0.0) { 
-                Ok(sum.iter().zip(count.iter()).map(|(&s, &c)| s / c).collect()) 
-            } 
-            else { 
-                Err("Division by zero".into()) 
+    let results: Vec<Option<f64>> = values.iter().map(|values| action.apply(values)).collect::<Result<_, String>>()?;
+    if results.iter().all(Option::is_none) {
+        return match action {
+            Action::Custom(_) => Err("The action function returned no number".into()),
+            _ => Err(format!("Not enough numeric values to compute {:?}", action).into()),
+        };
+    }
+    Ok(results)
+}
+
+// Aggregation of the same range across several sheets, cell per cell (3-D aggregation)
+// Cells holding no number are taken after `policy`, as by aggregate_range
+// Returns the grid of results by row then column, None for the cells with too few numbers for the action
+#[allow(clippy::too_many_arguments)]
+pub fn aggregate_across_sheets(
+    source_sheets: &[&Worksheet],
+    start_row: u32,
+    start_col: u32,
+    end_row: u32,
+    end_col: u32,
+    action: Action,
+    policy: &NumberPolicy,
+    warnings: &mut Vec<Warning>,
+) -> Result<Vec<Vec<Option<f64>>>, String> {
+    let mut grid: Vec<Vec<Option<f64>>> = Vec::with_capacity((end_row - start_row + 1) as usize);
+    let mut cell_log = CellLog::new("aggregate_across_sheets");
+    for row in start_row..=end_row {
+        let mut results: Vec<Option<f64>> = Vec::with_capacity((end_col - start_col + 1) as usize);
+        for col in start_col..=end_col {
+            let mut values: Vec<f64> = Vec::with_capacity(source_sheets.len());
+            for source_sheet in source_sheets {
+                if let Some(number) = read_number(source_sheet, col, row, policy, warnings)? {
+                    values.push(number);
+                }
             }
+            cell_log.debug(|| format!("Cell {}: values {:?}", index_to_excel(col, row), values));
+            results.push(action.apply(&values)?);
         }
+        grid.push(results);
     }
+    Ok(grid)
+}
+
+// Formula of Excel computing an action over a reference, as written by aggregations kept live
+// Returns None for the Python functions, which Excel cannot compute
+pub fn aggregate_formula(action: &Action, reference: &str) -> Option<String> {
+    let function = match action {
+        Action::Sum => "SUM",
+        Action::Count => "COUNT",
+        Action::Average => "AVERAGE",
+        Action::Min => "MIN",
+        Action::Max => "MAX",
+        Action::Median => "MEDIAN",
+        Action::Variance => "VAR",
+        Action::Stddev => "STDEV",
+        Action::Product => "PRODUCT",
+        Action::Custom(_) => return None,
+    };
+    Some(format!("{}({})", function, reference))
 }
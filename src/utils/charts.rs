@@ -0,0 +1,97 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use umya_spreadsheet::drawing::charts::{CategoryAxisData, ChartText, GroupingValues, StringReference};
+use umya_spreadsheet::drawing::spreadsheet::MarkerType;
+use umya_spreadsheet::Chart;
+
+use crate::structs::ChartKind;
+use crate::utils::excel::index_to_excel_col;
+use crate::utils::refs::quote_sheet_name;
+
+/// Columns and rows a chart spans from its anchor cell when no size is given
+pub const DEFAULT_CHART_SIZE: (u32, u32) = (8, 15);
+
+type Area = ((u32, u32), (u32, u32));
+
+/// Absolute reference to an area of a sheet, as the series of a chart name their data ('Sales'!$B$2:$B$10)
+fn area_ref(sheet_name: &str, ((first_col, first_row), (last_col, last_row)): Area) -> String {
+    let absolute = |col: u32, row: u32| format!("${}${}", index_to_excel_col(col), row);
+    let area = if (first_col, first_row) == (last_col, last_row) {
+        absolute(first_col, first_row)
+    } else {
+        format!("{}:{}", absolute(first_col, first_row), absolute(last_col, last_row))
+    };
+    format!("{}!{}", quote_sheet_name(sheet_name), area)
+}
+
+/// Builds a chart over a range of data of a sheet, one series per column
+///
+/// With `header`, the first row of the data names the series. The categories (the labels of the values)
+/// are a single row or column with one cell per row of values.
+pub fn build_chart(
+    kind: &ChartKind,
+    anchor: (u32, u32),
+    size: (u32, u32),
+    sheet_name: &str,
+    data: Area,
+    categories: Option<Area>,
+    header: bool,
+) -> Result<Chart, String> {
+    let ((first_col, first_row), (last_col, last_row)) = data;
+    let values_row = if header { first_row + 1 } else { first_row };
+    if values_row > last_row {
+        return Err(format!("The data range {} holds no values below its header.", area_ref(sheet_name, data)));
+    }
+    if *kind == ChartKind::Pie && first_col != last_col {
+        return Err(format!("A pie chart takes a single column of data, not {}.", area_ref(sheet_name, data)));
+    }
+    if let Some(area @ ((cat_first_col, cat_first_row), (cat_last_col, cat_last_row))) = categories {
+        let cells = (cat_last_col - cat_first_col + 1) * (cat_last_row - cat_first_row + 1);
+        if cat_first_col != cat_last_col && cat_first_row != cat_last_row || cells != last_row - values_row + 1 {
+            return Err(format!(
+                "The categories {} must be a single row or column with one cell per row of values ({}).",
+                area_ref(sheet_name, area), last_row - values_row + 1
+            ));
+        }
+    }
+
+    let series: Vec<String> = (first_col..=last_col).map(|col| area_ref(sheet_name, ((col, values_row), (col, last_row)))).collect();
+    let mut from_marker = MarkerType::default();
+    from_marker.set_col(anchor.0 - 1).set_row(anchor.1 - 1);
+    let mut to_marker = MarkerType::default();
+    to_marker.set_col(anchor.0 - 1 + size.0).set_row(anchor.1 - 1 + size.1);
+    let chart_type = match kind {
+        ChartKind::Bar => umya_spreadsheet::ChartType::BarChart,
+        ChartKind::Line => umya_spreadsheet::ChartType::LineChart,
+        ChartKind::Pie => umya_spreadsheet::ChartType::PieChart,
+    };
+    let mut chart = Chart::default();
+    chart.new_chart(chart_type, from_marker, to_marker, series.iter().map(String::as_str).collect());
+    if *kind == ChartKind::Bar {
+        // umya stacks the bars, the series of a basic chart are rather drawn side by side
+        chart.set_grouping(GroupingValues::Standard);
+        if let Some(bar_chart) = chart.get_plot_area_mut().get_bar_chart_mut() {
+            bar_chart.get_overlap_mut().set_val(0);
+        }
+    }
+
+    let cols = first_col..=last_col;
+    for (col, series) in cols.zip(chart.get_area_chart_series_list_mut().get_area_chart_series_mut()) {
+        if header {
+            let mut name = StringReference::default();
+            name.get_formula_mut().set_address_str(area_ref(sheet_name, ((col, first_row), (col, first_row))));
+            let mut chart_text = ChartText::default();
+            chart_text.set_string_reference(name);
+            series.set_chart_text(chart_text);
+        }
+        if let Some(categories) = categories {
+            let mut labels = StringReference::default();
+            labels.get_formula_mut().set_address_str(area_ref(sheet_name, categories));
+            let mut category_axis_data = CategoryAxisData::default();
+            category_axis_data.set_string_reference(labels);
+            series.set_category_axis_data(category_axis_data);
+        }
+    }
+    debug!("{} chart of {} series built over {}", kind.as_str(), last_col - first_col + 1, area_ref(sheet_name, data));
+    Ok(chart)
+}
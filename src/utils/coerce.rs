@@ -0,0 +1,134 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use chrono::{NaiveDate, NaiveDateTime};
+use polars::prelude::*;
+
+use crate::structs::Coerce;
+use crate::utils::py2rs::convert_anyvalue_to_string;
+use crate::utils::warnings::Warning;
+
+/// Formats of the texts read as dates, the datetimes giving their date
+const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%d/%m/%Y", "%Y/%m/%d"];
+const DATETIME_FORMATS: &[&str] = &["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M:%S%.f"];
+
+/// Outcome of the values that cannot be converted, outside of strict mode
+const LEFT_EMPTY: &str = "Left empty.";
+
+/// Reads a text as a date
+fn parse_date(text: &str) -> Option<NaiveDate> {
+    let text = text.trim();
+    DATE_FORMATS.iter().find_map(|format| NaiveDate::parse_from_str(text, format).ok())
+        .or_else(|| DATETIME_FORMATS.iter().find_map(|format| NaiveDateTime::parse_from_str(text, format).ok().map(|datetime| datetime.date())))
+}
+
+/// Reads a value as a number, texts being parsed
+fn parse_number(value: &AnyValue) -> Option<f64> {
+    match value {
+        AnyValue::String(text) => text.trim().parse().ok(),
+        AnyValue::StringOwned(text) => text.trim().parse().ok(),
+        AnyValue::Boolean(_) => None,
+        value if value.is_numeric() => value.extract::<f64>(),
+        _ => None,
+    }
+}
+
+/// Values of a series, whatever its chunks
+fn values_of(series: &Series) -> impl Iterator<Item = AnyValue<'_>> {
+    (0..series.len()).map(move |i| series.get(i).unwrap_or(AnyValue::Null))
+}
+
+/// Converts the given columns of the data to the type of their coercion
+///
+/// Values that cannot be converted are left empty and reported, and integers losing their decimals
+/// are reported too. With `strict`, the first value that cannot be converted is returned as an error.
+/// :return: The warnings of the values reported.
+pub fn coerce_columns(df: &mut DataFrame, coercions: &[(String, Coerce)], strict: bool) -> Result<Vec<Warning>, String> {
+    let mut warnings = Vec::new();
+    for (name, coerce) in coercions {
+        let series = df.column(name).map_err(|_| format!("Column '{}' to coerce is missing in the DataFrame.", name))?.clone();
+        let mut report = |i: usize, value: &AnyValue, problem: &str, outcome: String| -> Result<(), String> {
+            let message = format!("Value {} of row {} of '{}' {}.", convert_anyvalue_to_string(value.clone()), i + 1, name, problem);
+            if strict {
+                return Err(message);
+            }
+            let message = format!("{} {}", message, outcome);
+            warn!("{}", message);
+            warnings.push(Warning::new("coercion", message).with_column(name).with_row(i));
+            Ok(())
+        };
+
+        let coerced = match coerce {
+            Coerce::None => continue,
+            Coerce::Integer => {
+                let mut values: Vec<Option<i64>> = Vec::with_capacity(series.len());
+                for (i, value) in values_of(&series).enumerate() {
+                    if value.dtype().is_integer() {
+                        values.push(value.extract::<i64>());
+                        continue;
+                    }
+                    values.push(match (&value, parse_number(&value)) {
+                        (AnyValue::Null, _) => None,
+                        (_, Some(number)) if number.is_finite() && number >= i64::MIN as f64 && number <= i64::MAX as f64 => {
+                            if number.fract() != 0.0 {
+                                report(i, &value, "cannot be coerced to an integer without loss", format!("Written as {}.", number.trunc()))?;
+                            }
+                            Some(number.trunc() as i64)
+                        },
+                        _ => {
+                            report(i, &value, "is not an integer", LEFT_EMPTY.to_string())?;
+                            None
+                        },
+                    });
+                }
+                Series::new(name.as_str().into(), values)
+            },
+            Coerce::Float => {
+                let mut values: Vec<Option<f64>> = Vec::with_capacity(series.len());
+                for (i, value) in values_of(&series).enumerate() {
+                    values.push(match (&value, parse_number(&value)) {
+                        (AnyValue::Null, _) => None,
+                        (_, Some(number)) => Some(number),
+                        _ => {
+                            report(i, &value, "is not a number", LEFT_EMPTY.to_string())?;
+                            None
+                        },
+                    });
+                }
+                Series::new(name.as_str().into(), values)
+            },
+            Coerce::String => {
+                let values: Vec<Option<String>> = values_of(&series)
+                    .map(|value| match value {
+                        AnyValue::Null => None,
+                        value => Some(convert_anyvalue_to_string(value)),
+                    })
+                    .collect();
+                Series::new(name.as_str().into(), values)
+            },
+            Coerce::Date => {
+                let mut values: Vec<Option<NaiveDate>> = Vec::with_capacity(series.len());
+                let dates = series.cast(&DataType::Date).ok().filter(|_| series.dtype().is_temporal());
+                for (i, value) in values_of(&series).enumerate() {
+                    let date = match (&value, &dates) {
+                        (AnyValue::Null, _) => None,
+                        (_, Some(dates)) => match dates.get(i) {
+                            Ok(AnyValue::Date(days)) => NaiveDate::from_num_days_from_ce_opt(days + 719_163),
+                            _ => None,
+                        },
+                        (AnyValue::String(text), _) => parse_date(text),
+                        (AnyValue::StringOwned(text), _) => parse_date(text),
+                        _ => None,
+                    };
+                    if date.is_none() && value != AnyValue::Null {
+                        report(i, &value, "is not a date", LEFT_EMPTY.to_string())?;
+                    }
+                    values.push(date);
+                }
+                DateChunked::from_naive_date_options(name.as_str().into(), values).into_series()
+            },
+        };
+        df.replace(name, coerced).map_err(|e| e.to_string())?;
+        debug!("Column {} coerced to {:?}", name, coerce);
+    }
+    Ok(warnings)
+}
@@ -0,0 +1,91 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use umya_spreadsheet::{Comment, RichText, Worksheet};
+
+use crate::utils::excel::index_to_excel;
+
+/// Author of the comments added without one, Excel showing the author of each note
+pub const COMMENT_AUTHOR: &str = "ez_excel_mgt";
+
+/// Comment (note) of a cell, with its author
+#[derive(Debug, Clone)]
+pub struct CellComment {
+    pub cell: String,
+    pub text: String,
+    pub author: String,
+}
+
+impl IntoPy<PyObject> for CellComment {
+    fn into_py(self, py: Python) -> PyObject {
+        let comment = PyDict::new(py);
+        // Set items can only fail on unhashable keys, which string keys are not
+        let _ = comment.set_item("cell", self.cell);
+        let _ = comment.set_item("text", self.text);
+        let _ = comment.set_item("author", self.author);
+        comment.into()
+    }
+}
+
+/// Reads back the first string printed after `value: ` in a debug output, a Rust literal ("a \"b\"\n")
+fn debug_value(debug: &str) -> Option<String> {
+    let (_, literal) = debug.split_once("value: \"")?;
+    let mut text = String::with_capacity(literal.len());
+    let mut chars = literal.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some(text),
+            '\\' => match chars.next()? {
+                'n' => text.push('\n'),
+                'r' => text.push('\r'),
+                't' => text.push('\t'),
+                '0' => text.push('\0'),
+                'u' => {
+                    let code: String = chars.by_ref().skip(1).take_while(|c| *c != '}').collect();
+                    text.extend(u32::from_str_radix(&code, 16).ok().and_then(char::from_u32));
+                },
+                c => text.push(c),
+            },
+            c => text.push(c),
+        }
+    }
+    None
+}
+
+/// Text of a comment, rich (as Excel writes them) or plain (as other tools do)
+fn comment_text(comment: &Comment) -> String {
+    match comment.get_text().get_rich_text() {
+        Some(rich_text) => rich_text.get_text().to_string(),
+        // umya keeps plain texts private, their debug output holds the value as a Rust literal
+        None => debug_value(&format!("{:?}", comment.get_text())).unwrap_or_default(),
+    }
+}
+
+/// Sets the comment of a cell, replacing the one it has: Excel shows a single note per cell
+pub fn set_comment(worksheet: &mut Worksheet, cell: (u32, u32), text: &str, author: &str) {
+    worksheet.get_comments_mut().retain(|comment| {
+        (*comment.get_coordinate().get_col_num(), *comment.get_coordinate().get_row_num()) != cell
+    });
+    let mut comment = Comment::default();
+    comment.new_comment(cell);
+    let mut rich_text = RichText::default();
+    rich_text.set_text(text);
+    comment.get_text_mut().set_rich_text(rich_text);
+    comment.set_author(author);
+    worksheet.add_comments(comment);
+    debug!("Comment set on {} of {}", index_to_excel(cell.0, cell.1), worksheet.get_name());
+}
+
+/// Returns the comments of a sheet, by row then column
+pub fn read_comments(worksheet: &Worksheet) -> Vec<CellComment> {
+    let mut comments: Vec<((u32, u32), CellComment)> = worksheet.get_comments().iter()
+        .map(|comment| {
+            let (col, row) = (*comment.get_coordinate().get_col_num(), *comment.get_coordinate().get_row_num());
+            let cell = CellComment { cell: index_to_excel(col, row), text: comment_text(comment), author: comment.get_author().to_string() };
+            ((col, row), cell)
+        })
+        .collect();
+    comments.sort_by_key(|((col, row), _)| (*row, *col));
+    comments.into_iter().map(|(_, comment)| comment).collect()
+}
@@ -0,0 +1,41 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use pyo3::prelude::*;
+use umya_spreadsheet::{DataValidation, DataValidations, Worksheet};
+
+use crate::structs::{ExcelCell, Validation, Value};
+
+/// Adds a data validation to the sheet, after the ones it already has
+pub fn add_validation(worksheet: &mut Worksheet, validation: DataValidation) {
+    match worksheet.get_data_validations_mut() {
+        Some(validations) => {
+            validations.add_data_validation_list(validation);
+        },
+        None => {
+            let mut validations = DataValidations::default();
+            validations.add_data_validation_list(validation);
+            worksheet.set_data_validations(validations);
+        },
+    }
+}
+
+/// Attaches an in-cell dropdown restricted to the given options
+///
+/// The writer has no support for form controls, so the linked cell itself carries the input.
+pub fn add_dropdown(worksheet: &mut Worksheet, cell: &ExcelCell, options: &[String], prompt: Option<String>) -> PyResult<()> {
+    let validation = Validation::list(options).map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+    add_validation(worksheet, validation.to_data_validation(&cell.range(), true, prompt, None));
+    debug!("Dropdown {:?} added at {} in {}", options, cell.range(), worksheet.get_name());
+    Ok(())
+}
+
+/// Reads the value of a control linked cell
+pub fn read_control_value(worksheet: &Worksheet, cell: &ExcelCell) -> Value {
+    let value = worksheet.get_value(cell.idx());
+    match value.as_str() {
+        "" => Value::None,
+        "TRUE" => Value::Boolean(true),
+        "FALSE" => Value::Boolean(false),
+        _ => Value::String(value),
+    }
+}
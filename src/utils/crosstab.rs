@@ -0,0 +1,149 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use polars::prelude::*;
+use std::collections::HashMap;
+use umya_spreadsheet::Worksheet;
+
+use crate::structs::Action;
+use crate::utils::py2rs::convert_anyvalue_to_string;
+use crate::utils::write::{write_anyvalue, write_text, WriteOptions};
+
+/// Values falling into a cell of a crosstab
+#[derive(Debug, Clone, Default)]
+struct Accumulator {
+    values: Vec<f64>,
+}
+
+impl Accumulator {
+    fn add(&mut self, value: Option<f64>) {
+        if let Some(value) = value {
+            self.values.push(value);
+        }
+    }
+
+    /// Result of the action, None when no value fell into the cell or too few for the action
+    fn result(&self, action: &Action) -> Option<AnyValue<'static>> {
+        match (action, self.values.len()) {
+            (Action::Count, count) => Some(AnyValue::Int64(count as i64)),
+            (_, 0) => None,
+            _ => action.compute(&self.values).map(AnyValue::Float64),
+        }
+    }
+}
+
+/// Values of a column aggregated by the values of two others, as a pivot table does
+#[derive(Debug, Clone)]
+pub struct Crosstab {
+    pub index: String,
+    pub row_keys: Vec<AnyValue<'static>>,
+    pub col_keys: Vec<AnyValue<'static>>,
+    action: Action,
+    cells: HashMap<(usize, usize), Accumulator>,
+    row_totals: Vec<Accumulator>,
+    col_totals: Vec<Accumulator>,
+    grand_total: Accumulator,
+}
+
+/// Position of a key among the distinct keys of a column, added in order of appearance
+fn key_position(keys: &mut Vec<AnyValue<'static>>, positions: &mut HashMap<String, usize>, value: AnyValue) -> usize {
+    *positions.entry(convert_anyvalue_to_string(value.clone())).or_insert_with(|| {
+        keys.push(value.into_static().unwrap_or(AnyValue::Null));
+        keys.len() - 1
+    })
+}
+
+/// Pivots a DataFrame: one row per value of `index`, one column per value of `columns`, in order of
+/// appearance, each cell aggregating the `values` of its rows. Null values are not counted.
+pub fn pivot(df: &DataFrame, index: &str, columns: &str, values: &str, action: Action) -> PolarsResult<Crosstab> {
+    let index_series = df.column(index)?;
+    let columns_series = df.column(columns)?;
+    let values_series = df.column(values)?;
+    // Counting does not need numbers, the other actions do
+    let numbers = match action {
+        Action::Count => None,
+        _ => Some(values_series.cast(&DataType::Float64)?),
+    };
+
+    let mut crosstab = Crosstab {
+        index: index.to_string(),
+        row_keys: Vec::new(),
+        col_keys: Vec::new(),
+        action,
+        cells: HashMap::new(),
+        row_totals: Vec::new(),
+        col_totals: Vec::new(),
+        grand_total: Accumulator::default(),
+    };
+    let (mut row_positions, mut col_positions) = (HashMap::new(), HashMap::new());
+    for i in 0..df.height() {
+        let row = key_position(&mut crosstab.row_keys, &mut row_positions, index_series.get(i)?);
+        let col = key_position(&mut crosstab.col_keys, &mut col_positions, columns_series.get(i)?);
+        crosstab.row_totals.resize(crosstab.row_keys.len(), Accumulator::default());
+        crosstab.col_totals.resize(crosstab.col_keys.len(), Accumulator::default());
+
+        let value = match &numbers {
+            Some(numbers) => numbers.get(i)?.extract::<f64>(),
+            None => (!values_series.get(i)?.is_null()).then_some(1.0),
+        };
+        crosstab.cells.entry((row, col)).or_default().add(value);
+        crosstab.row_totals[row].add(value);
+        crosstab.col_totals[col].add(value);
+        crosstab.grand_total.add(value);
+    }
+    debug!("{} rows of {} pivoted into {}x{}", df.height(), values, crosstab.row_keys.len(), crosstab.col_keys.len());
+    Ok(crosstab)
+}
+
+impl Crosstab {
+    /// Number of columns and rows of the written matrix, headers included
+    pub fn size(&self, totals: bool) -> (u32, u32) {
+        let extra = if totals { 2 } else { 1 };
+        (self.col_keys.len() as u32 + extra, self.row_keys.len() as u32 + extra)
+    }
+
+    /// Writes the matrix with its headers from a cell, the name of the index in the corner
+    ///
+    /// With `totals`, a last row and a last column labelled `totals` hold the action applied
+    /// to all the values of each column and row. Cells without values are left untouched.
+    pub fn write(&self, worksheet: &mut Worksheet, first_cell: (u32, u32), totals: Option<&str>, options: &WriteOptions) {
+        let (first_col, first_row) = first_cell;
+        write_text(worksheet.get_cell_mut((first_col, first_row)), &self.index);
+        for (j, key) in self.col_keys.iter().enumerate() {
+            write_anyvalue(worksheet.get_cell_mut((first_col + 1 + j as u32, first_row)), key.clone(), options);
+        }
+        for (i, key) in self.row_keys.iter().enumerate() {
+            let row = first_row + 1 + i as u32;
+            write_anyvalue(worksheet.get_cell_mut((first_col, row)), key.clone(), options);
+            for j in 0..self.col_keys.len() {
+                let value = self.cells.get(&(i, j)).and_then(|acc| acc.result(&self.action));
+                if let Some(value) = value {
+                    write_anyvalue(worksheet.get_cell_mut((first_col + 1 + j as u32, row)), value, options);
+                }
+            }
+        }
+
+        if let Some(label) = totals {
+            let total_col = first_col + 1 + self.col_keys.len() as u32;
+            let total_row = first_row + 1 + self.row_keys.len() as u32;
+            let mut write_total = |col: u32, row: u32, acc: &Accumulator| {
+                let cell = worksheet.get_cell_mut((col, row));
+                if let Some(value) = acc.result(&self.action) {
+                    write_anyvalue(cell, value, options);
+                }
+                cell.get_style_mut().get_font_mut().set_bold(true);
+            };
+            for (i, acc) in self.row_totals.iter().enumerate() {
+                write_total(total_col, first_row + 1 + i as u32, acc);
+            }
+            for (j, acc) in self.col_totals.iter().enumerate() {
+                write_total(first_col + 1 + j as u32, total_row, acc);
+            }
+            write_total(total_col, total_row, &self.grand_total);
+            for (col, row) in [(total_col, first_row), (first_col, total_row)] {
+                let cell = worksheet.get_cell_mut((col, row));
+                write_text(cell, label);
+                cell.get_style_mut().get_font_mut().set_bold(true);
+            }
+        }
+    }
+}
@@ -0,0 +1,69 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use umya_spreadsheet::Worksheet;
+
+use crate::utils::excel::excel_to_index;
+
+/// Widest column and highest row Excel accepts, in characters and points
+pub const MAX_COLUMN_WIDTH: f64 = 255.0;
+pub const MAX_ROW_HEIGHT: f64 = 409.0;
+
+/// Size of the default font, the widths of other cells being scaled to theirs
+const DEFAULT_FONT_SIZE: f64 = 11.0;
+
+/// Width, in characters of the default font, of the longest line of a text
+///
+/// Wide characters (e.g. of CJK scripts) count twice.
+fn text_width(text: &str) -> f64 {
+    text.lines()
+        .map(|line| line.chars().map(|c| if c.len_utf8() > 2 { 2.0 } else { 1.0 }).sum::<f64>())
+        .fold(0.0, f64::max)
+}
+
+/// Estimates the width a column needs to show its values as written, from the lengths of their formatted texts
+///
+/// Cells merged across several columns are left out, as their text is spread over the merge.
+/// :return: None when the column has no value.
+pub fn estimate_column_width(worksheet: &Worksheet, col: u32) -> Option<f64> {
+    let spread: Vec<((u32, u32), (u32, u32))> = worksheet.get_merge_cells().iter()
+        .filter_map(|merge| {
+            let range = merge.get_range();
+            let mut ends = range.split(':').map(excel_to_index);
+            let (first, last) = (ends.next()?, ends.next()?);
+            (first.0 != last.0).then_some((first, last))
+        })
+        .collect();
+    let width = worksheet.get_collection_by_column(&col).iter()
+        .filter(|cell| {
+            let row = *cell.get_coordinate().get_row_num();
+            !spread.iter().any(|(first, last)| (first.0..=last.0).contains(&col) && (first.1..=last.1).contains(&row))
+        })
+        .map(|cell| {
+            let size = cell.get_style().get_font().map_or(DEFAULT_FONT_SIZE, |font| *font.get_font_size().get_val());
+            text_width(&cell.get_formatted_value()) * size / DEFAULT_FONT_SIZE
+        })
+        .fold(0.0, f64::max);
+    // A character of margin on each side, as Excel keeps when fitting
+    (width > 0.0).then(|| ((width + 2.0) * 100.0).round() / 100.0)
+}
+
+/// Sizes columns of a sheet (all its used columns by default) to their content, up to `max_width`
+///
+/// Columns without values keep their width.
+/// :return: The (column, width) of the columns sized.
+pub fn autofit_columns(worksheet: &mut Worksheet, columns: Option<&[u32]>, max_width: f64) -> Vec<(u32, f64)> {
+    let columns = match columns {
+        Some(columns) => columns.to_vec(),
+        None => (1..=worksheet.get_highest_column()).collect(),
+    };
+    let mut sized = Vec::new();
+    for col in columns {
+        if let Some(width) = estimate_column_width(worksheet, col) {
+            let width = width.min(max_width);
+            worksheet.get_column_dimension_by_number_mut(&col).set_width(width);
+            sized.push((col, width));
+        }
+    }
+    debug!("{} columns of {} sized to their content", sized.len(), worksheet.get_name());
+    sized
+}
@@ -0,0 +1,173 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use umya_spreadsheet::{Pane, PaneStateValues, PaneValues, SheetView, Spreadsheet, Worksheet};
+
+use crate::structs::SheetAction;
+use crate::utils::excel::{index_to_excel, index_to_excel_col};
+use crate::utils::tables::overlapping_table;
+
+/// Name of the defined name holding the print area of a sheet
+const PRINT_AREA_NAME: &str = "_xlnm.Print_Area";
+
+/// Returns the first view of a sheet, adding one if the sheet has none
+pub fn sheet_view_mut(worksheet: &mut Worksheet) -> &mut SheetView {
+    let views = worksheet.get_sheet_views_mut().get_sheet_view_list_mut();
+    if views.is_empty() {
+        views.push(SheetView::default());
+    }
+    &mut views[0]
+}
+
+/// Hides the gridlines of a sheet
+pub fn hide_gridlines(worksheet: &mut Worksheet) {
+    sheet_view_mut(worksheet).set_show_grid_lines(false);
+}
+
+/// Freezes the rows above and the columns left of a cell, given as (col, row)
+pub fn freeze_panes(worksheet: &mut Worksheet, col: u32, row: u32) {
+    let mut pane = Pane::default();
+    pane.set_horizontal_split((col - 1) as f64);
+    pane.set_vertical_split((row - 1) as f64);
+    pane.get_top_left_cell_mut().set_coordinate(index_to_excel(col, row));
+    pane.set_active_pane(match (col > 1, row > 1) {
+        (true, true) => PaneValues::BottomRight,
+        (true, false) => PaneValues::TopRight,
+        _ => PaneValues::BottomLeft,
+    });
+    pane.set_state(PaneStateValues::Frozen);
+    sheet_view_mut(worksheet).set_pane(pane);
+    debug!("Panes of {} frozen at {}", worksheet.get_name(), index_to_excel(col, row));
+}
+
+/// Adds an autofilter to the area from `first` (its header) to `last`, as (col, row), replacing the one of the sheet
+///
+/// Excel Tables have their own filter, so an area overlapping one is refused.
+/// :return: The range filtered.
+pub fn set_autofilter(worksheet: &mut Worksheet, first: (u32, u32), last: (u32, u32)) -> Result<String, String> {
+    let range = format!("{}:{}", index_to_excel(first.0, first.1), index_to_excel(last.0, last.1));
+    if let Some(table) = overlapping_table(worksheet, first, last) {
+        return Err(format!("Range {} overlaps table '{}', which has its own filter.", range, table));
+    }
+    worksheet.set_auto_filter(range.clone());
+    debug!("Autofilter of {} set to {}", worksheet.get_name(), range);
+    Ok(range)
+}
+
+/// Sets the print area of a sheet, at the given position in the workbook, to its used range
+pub fn set_print_area(worksheet: &mut Worksheet, sheet_index: usize) {
+    let (last_col, last_row) = worksheet.get_highest_column_and_row();
+    if last_col == 0 || last_row == 0 {
+        return;
+    }
+    let address = format!(
+        "'{}'!$A$1:${}${}",
+        worksheet.get_name().replace('\'', "''"),
+        index_to_excel_col(last_col),
+        last_row
+    );
+    worksheet.get_defined_names_mut().retain(|name| name.get_name() != PRINT_AREA_NAME);
+    // Adding a defined name cannot fail. The print area is local to its sheet
+    let _ = worksheet.add_defined_name(PRINT_AREA_NAME.to_string(), address.clone());
+    if let Some(name) = worksheet.get_defined_names_mut().last_mut() {
+        name.set_local_sheet_id(sheet_index as u32);
+    }
+    debug!("Print area of {} set to {}", worksheet.get_name(), address);
+}
+
+/// Locks the formula cells of a sheet, unlocks its other cells and protects it, if it has any formula
+///
+/// :return: Whether the sheet was protected.
+pub fn protect_formulas(worksheet: &mut Worksheet, password: Option<&str>) -> bool {
+    if !worksheet.get_cell_collection().iter().any(|cell| cell.is_formula()) {
+        return false;
+    }
+    for cell in worksheet.get_cell_collection_mut() {
+        let locked = cell.is_formula();
+        cell.get_style_mut().get_protection_mut().set_locked(locked);
+    }
+    let protection = worksheet.get_sheet_protection_mut();
+    protection.set_sheet(true);
+    if let Some(password) = password {
+        protection.set_password(password);
+    }
+    debug!("Formulas of {} protected", worksheet.get_name());
+    true
+}
+
+/// Protects a sheet, leaving its unlocked cells editable and the given actions allowed
+///
+/// The actions not allowed are all refused, including the ones Excel allows by default (objects and scenarios),
+/// so that protecting a sheet again replaces its previous settings.
+pub fn protect_sheet(worksheet: &mut Worksheet, password: Option<&str>, allow: &[SheetAction]) {
+    let protection = worksheet.get_sheet_protection_mut();
+    protection.set_sheet(true);
+    if let Some(password) = password {
+        protection.set_password(password);
+    }
+    for action in SheetAction::ALL {
+        // The flags of the sheet protection tell what is refused
+        let refused = !allow.contains(&action);
+        match action {
+            SheetAction::FormatCells => protection.set_format_cells(refused),
+            SheetAction::FormatColumns => protection.set_format_columns(refused),
+            SheetAction::FormatRows => protection.set_format_rows(refused),
+            SheetAction::InsertColumns => protection.set_insert_columns(refused),
+            SheetAction::InsertRows => protection.set_insert_rows(refused),
+            SheetAction::InsertHyperlinks => protection.set_insert_hyperlinks(refused),
+            SheetAction::DeleteColumns => protection.set_delete_columns(refused),
+            SheetAction::DeleteRows => protection.set_delete_rows(refused),
+            SheetAction::Sort => protection.set_sort(refused),
+            SheetAction::AutoFilter => protection.set_auto_filter(refused),
+            SheetAction::PivotTables => protection.set_pivot_tables(refused),
+            SheetAction::Objects => protection.set_objects(refused),
+            SheetAction::Scenarios => protection.set_scenarios(refused),
+        };
+    }
+    debug!("Sheet {} protected, allowing {:?}", worksheet.get_name(), allow.iter().map(SheetAction::as_str).collect::<Vec<_>>());
+}
+
+/// Removes the protection of a sheet
+///
+/// :return: Whether the sheet was protected.
+pub fn unprotect_sheet(worksheet: &mut Worksheet) -> bool {
+    let protected = worksheet.get_sheet_protection().is_some_and(|protection| *protection.get_sheet());
+    worksheet.remove_sheet_protection();
+    debug!("Sheet {} unprotected", worksheet.get_name());
+    protected
+}
+
+/// Locks or unlocks the cells of an area, from `first` to `last` as (col, row), for when the sheet is protected
+///
+/// The empty cells are styled too, as the inputs of a collection template usually are.
+pub fn set_locked(worksheet: &mut Worksheet, first: (u32, u32), last: (u32, u32), locked: bool) {
+    for row in first.1..=last.1 {
+        for col in first.0..=last.0 {
+            worksheet.get_style_mut((col, row)).get_protection_mut().set_locked(locked);
+        }
+    }
+    debug!(
+        "Cells {}:{} of {} {}",
+        index_to_excel(first.0, first.1), index_to_excel(last.0, last.1), worksheet.get_name(), if locked { "locked" } else { "unlocked" }
+    );
+}
+
+/// Makes a sheet, at the given position, the active one with the given active cell
+pub fn set_active(spreadsheet: &mut Spreadsheet, sheet_index: usize, cell: &str) {
+    for (i, worksheet) in spreadsheet.get_sheet_collection_mut().iter_mut().enumerate() {
+        sheet_view_mut(worksheet).set_tab_selected(i == sheet_index);
+        if i == sheet_index {
+            worksheet.set_active_cell(cell);
+        }
+    }
+    spreadsheet.set_active_sheet(sheet_index as u32);
+}
+
+/// Removes the personal information of the document properties (author, last editor, manager, company)
+pub fn strip_personal_metadata(spreadsheet: &mut Spreadsheet) {
+    let properties = spreadsheet.get_properties_mut();
+    properties.set_creator("");
+    properties.set_last_modified_by("");
+    properties.set_manager("");
+    properties.set_company("");
+    debug!("Personal metadata stripped");
+}
@@ -0,0 +1,177 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use aes::cipher::{block_padding::NoPadding, BlockDecryptMut, KeyIvInit};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+use crate::utils::package::create_temporary_file;
+
+/// Signature of the compound files encrypted workbooks are stored in, xlsx files being zip archives
+const COMPOUND_FILE_SIGNATURE: &[u8] = &[0xd0, 0xcf, 0x11, 0xe0, 0xa1, 0xb1, 0x1a, 0xe1];
+
+/// Block keys of the agile encryption (ECMA-376), deriving a key per use from the hash of the password
+const BLOCK_KEY_VERIFIER_HASH_INPUT: &[u8] = &[0xfe, 0xa7, 0xd2, 0x76, 0x3b, 0x4b, 0x9e, 0x79];
+const BLOCK_KEY_VERIFIER_HASH_VALUE: &[u8] = &[0xd7, 0xaa, 0x0f, 0x6d, 0x30, 0x61, 0x34, 0x4e];
+const BLOCK_KEY_ENCRYPTED_KEY: &[u8] = &[0x14, 0x6e, 0x0b, 0xe7, 0xab, 0xac, 0xd0, 0xd6];
+
+/// The package is encrypted in segments, after the 8 bytes of its size
+const SEGMENT_SIZE: usize = 4096;
+
+static ELEMENT: Lazy<Regex> = Lazy::new(|| Regex::new(r"<(?:\w+:)?(keyData|encryptedKey)\b([^>]*)>").unwrap());
+static ATTRIBUTE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"(\w+)="([^"]*)""#).unwrap());
+
+/// Whether the bytes of a file are those of an encrypted workbook, rather than of an xlsx archive
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(COMPOUND_FILE_SIGNATURE)
+}
+
+/// The attributes of an element of the encryption info, by name
+struct Attributes(HashMap<String, String>);
+
+impl Attributes {
+    fn text(&self, name: &str) -> Result<&str, String> {
+        self.0.get(name).map(String::as_str).ok_or_else(|| format!("The encryption info has no {}.", name))
+    }
+
+    fn number(&self, name: &str) -> Result<usize, String> {
+        self.text(name)?.parse().map_err(|_| format!("The {} of the encryption info is not a number.", name))
+    }
+
+    fn bytes(&self, name: &str) -> Result<Vec<u8>, String> {
+        STANDARD.decode(self.text(name)?).map_err(|e| format!("The {} of the encryption info is invalid: {}.", name, e))
+    }
+}
+
+fn hash(algorithm: &str, parts: &[&[u8]]) -> Result<Vec<u8>, String> {
+    fn digest<D: Digest>(parts: &[&[u8]]) -> Vec<u8> {
+        let mut digest = D::new();
+        for part in parts {
+            digest.update(part);
+        }
+        digest.finalize().to_vec()
+    }
+    match algorithm {
+        "SHA512" | "SHA-512" => Ok(digest::<Sha512>(parts)),
+        "SHA384" | "SHA-384" => Ok(digest::<Sha384>(parts)),
+        "SHA256" | "SHA-256" => Ok(digest::<Sha256>(parts)),
+        _ => Err(format!("Workbooks encrypted with {} are not supported, only SHA-256 to SHA-512.", algorithm)),
+    }
+}
+
+/// Truncates a hash to a length, or pads it with 0x36 as the encryption does
+fn fit(mut bytes: Vec<u8>, len: usize) -> Vec<u8> {
+    bytes.resize(len, 0x36);
+    bytes
+}
+
+fn decrypt_cbc(key: &[u8], iv: &[u8], data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut buffer = data.to_vec();
+    let invalid = |e: String| format!("The workbook cannot be decrypted: {}.", e);
+    match key.len() {
+        16 => cbc::Decryptor::<aes::Aes128>::new_from_slices(key, iv).map_err(|e| invalid(e.to_string()))?
+            .decrypt_padded_mut::<NoPadding>(&mut buffer).map_err(|e| invalid(e.to_string()))?,
+        24 => cbc::Decryptor::<aes::Aes192>::new_from_slices(key, iv).map_err(|e| invalid(e.to_string()))?
+            .decrypt_padded_mut::<NoPadding>(&mut buffer).map_err(|e| invalid(e.to_string()))?,
+        32 => cbc::Decryptor::<aes::Aes256>::new_from_slices(key, iv).map_err(|e| invalid(e.to_string()))?
+            .decrypt_padded_mut::<NoPadding>(&mut buffer).map_err(|e| invalid(e.to_string()))?,
+        len => return Err(invalid(format!("keys of {} bits are not supported", len * 8))),
+    };
+    Ok(buffer)
+}
+
+/// Decrypts a workbook encrypted with a password (agile encryption, as Excel 2010 and later do)
+///
+/// :return: The bytes of the xlsx file.
+pub fn decrypt(bytes: &[u8], password: &str) -> Result<Vec<u8>, String> {
+    let mut compound = cfb::CompoundFile::open(Cursor::new(bytes)).map_err(|e| format!("The encrypted workbook cannot be read: {}.", e))?;
+    let mut read_stream = |name: &str| -> Result<Vec<u8>, String> {
+        let mut data = Vec::new();
+        compound.open_stream(name).and_then(|mut stream| stream.read_to_end(&mut data))
+            .map_err(|e| format!("The encrypted workbook has no readable {}: {}.", name, e))?;
+        Ok(data)
+    };
+    let info = read_stream("EncryptionInfo")?;
+    let package = read_stream("EncryptedPackage")?;
+    if info.len() < 8 || package.len() < 8 {
+        return Err("The encrypted workbook is truncated.".to_string());
+    }
+    if info[..4] != [0x04, 0x00, 0x04, 0x00] {
+        return Err(format!(
+            "Workbooks of encryption version {}.{} are not supported, only the agile encryption of Excel 2010 and later.",
+            u16::from_le_bytes([info[0], info[1]]), u16::from_le_bytes([info[2], info[3]])
+        ));
+    }
+
+    let xml = String::from_utf8_lossy(&info[8..]);
+    let mut elements: HashMap<String, Attributes> = HashMap::new();
+    for element in ELEMENT.captures_iter(&xml) {
+        let attributes = ATTRIBUTE.captures_iter(&element[2]).map(|attribute| (attribute[1].to_string(), attribute[2].to_string())).collect();
+        elements.entry(element[1].to_string()).or_insert(Attributes(attributes));
+    }
+    let (key_data, encrypted_key) = match (elements.get("keyData"), elements.get("encryptedKey")) {
+        (Some(key_data), Some(encrypted_key)) => (key_data, encrypted_key),
+        _ => return Err("The encrypted workbook has no password key.".to_string()),
+    };
+
+    // The hash of the password, spun over the salt, derives the keys of the verifier and of the package key
+    let algorithm = encrypted_key.text("hashAlgorithm")?;
+    let salt = encrypted_key.bytes("saltValue")?;
+    let key_len = encrypted_key.number("keyBits")? / 8;
+    let password: Vec<u8> = password.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    let mut password_hash = hash(algorithm, &[&salt, &password])?;
+    for i in 0..encrypted_key.number("spinCount")? as u32 {
+        password_hash = hash(algorithm, &[&i.to_le_bytes(), &password_hash])?;
+    }
+    let derived_key = |block_key: &[u8]| hash(algorithm, &[&password_hash, block_key]).map(|key| fit(key, key_len));
+
+    let verifier_input = decrypt_cbc(&derived_key(BLOCK_KEY_VERIFIER_HASH_INPUT)?, &salt, &encrypted_key.bytes("encryptedVerifierHashInput")?)?;
+    let verifier_hash = decrypt_cbc(&derived_key(BLOCK_KEY_VERIFIER_HASH_VALUE)?, &salt, &encrypted_key.bytes("encryptedVerifierHashValue")?)?;
+    let expected = hash(algorithm, &[&verifier_input[..salt.len().min(verifier_input.len())]])?;
+    if verifier_hash.len() < expected.len() || verifier_hash[..expected.len()] != expected[..] {
+        return Err("Wrong password for the encrypted workbook.".to_string());
+    }
+    let package_key = decrypt_cbc(&derived_key(BLOCK_KEY_ENCRYPTED_KEY)?, &salt, &encrypted_key.bytes("encryptedKeyValue")?)?;
+    let package_key = fit(package_key, key_data.number("keyBits")? / 8);
+
+    // Each segment of the package has its own initialization vector, from the salt and its index
+    let package_algorithm = key_data.text("hashAlgorithm")?;
+    let package_salt = key_data.bytes("saltValue")?;
+    let block_size = key_data.number("blockSize")?;
+    if block_size == 0 || block_size % 16 != 0 {
+        return Err(format!("The blockSize of the encryption info is not a multiple of 16: {}.", block_size));
+    }
+    let mut size = [0u8; 8];
+    size.copy_from_slice(&package[..8]);
+    let size = u64::from_le_bytes(size) as usize;
+    let mut xlsx = Vec::with_capacity(package.len());
+    for (i, segment) in package[8..].chunks(SEGMENT_SIZE).enumerate() {
+        let iv = fit(hash(package_algorithm, &[&package_salt, &(i as u32).to_le_bytes()])?, block_size);
+        let complete = segment.len() - segment.len() % block_size;
+        xlsx.extend(decrypt_cbc(&package_key, &iv, &segment[..complete])?);
+    }
+    if xlsx.len() < size {
+        return Err("The encrypted workbook is truncated.".to_string());
+    }
+    xlsx.truncate(size);
+    debug!("Workbook of {} bytes decrypted", size);
+    Ok(xlsx)
+}
+
+/// Encrypts the bytes of an xlsx file with a password, as Excel does (agile encryption, AES-256 and SHA-512)
+///
+/// The encryption of umya-spreadsheet writes a file, a temporary one created in `directory` (that of the
+/// file saved) under a name no other file has, whose bytes are read back.
+pub fn encrypt(xlsx: &[u8], password: &str, directory: &Path) -> std::io::Result<Vec<u8>> {
+    let (temporary, file) = create_temporary_file(directory, "ezxl")?;
+    drop(file);
+    umya_spreadsheet::helper::crypt::encrypt(&temporary, xlsx, password);
+    let encrypted = std::fs::read(&temporary);
+    let _ = std::fs::remove_file(&temporary);
+    debug!("Workbook of {} bytes encrypted", xlsx.len());
+    encrypted
+}
@@ -0,0 +1,55 @@
+// create_exception! expands to cfg conditions of pyo3 0.18 unknown to recent compilers
+#![allow(unexpected_cfgs)]
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use pyo3::create_exception;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::PyTypeInfo;
+
+// Base of the errors of the templates, a ValueError so that existing handlers keep catching them
+create_exception!(ez_excel_mgt, TemplateError, PyValueError);
+// A sheet named or matched by a pattern is not in the workbook
+create_exception!(ez_excel_mgt, SheetNotFoundError, TemplateError);
+// Columns of the data and headers of the sheet do not match
+create_exception!(ez_excel_mgt, ColumnMismatchError, TemplateError);
+// A cell or range is invalid or outside the limits of Excel
+create_exception!(ez_excel_mgt, RangeError, TemplateError);
+
+/// Builds an error of the given type with attributes set on the exception, None when not known
+fn with_attributes<T: PyTypeInfo>(message: String, attributes: &[(&str, Option<&str>)]) -> PyErr {
+    Python::with_gil(|py| {
+        let err = PyErr::new::<T, _>(message);
+        for (name, value) in attributes {
+            // Attributes of an exception instance can always be set
+            let _ = err.value(py).setattr(*name, *value);
+        }
+        err
+    })
+}
+
+/// Error of a template used in a state it does not support, e.g. no sheet selected yet
+pub fn template_error(message: impl Into<String>) -> PyErr {
+    PyErr::new::<TemplateError, _>(message.into())
+}
+
+/// Error of a sheet not found, with its `sheet_name`
+pub fn sheet_not_found(sheet_name: &str) -> PyErr {
+    with_attributes::<SheetNotFoundError>(format!("Sheet '{}' not found.", sheet_name), &[("sheet_name", Some(sheet_name))])
+}
+
+/// Error of a source sheet, of another workbook, not found, with its `sheet_name`
+pub fn source_sheet_not_found(sheet_name: &str) -> PyErr {
+    with_attributes::<SheetNotFoundError>(format!("Source sheet '{}' not found.", sheet_name), &[("sheet_name", Some(sheet_name))])
+}
+
+/// Error of a column of the data or header of the sheet without its match, with the `sheet_name` and the `column`
+#[cfg(feature = "dataframe")]
+pub fn column_mismatch(message: String, sheet_name: &str, column: &str) -> PyErr {
+    with_attributes::<ColumnMismatchError>(message, &[("sheet_name", Some(sheet_name)), ("column", Some(column))])
+}
+
+/// Error of an invalid or out of bounds cell or range, with the `sheet_name` and the `cell` when known
+pub fn range_error(message: String, sheet_name: Option<&str>, cell: Option<&str>) -> PyErr {
+    with_attributes::<RangeError>(message, &[("sheet_name", sheet_name), ("cell", cell)])
+}
@@ -2,6 +2,8 @@ use log::*;
 use pyo3::prelude::*;
 use pyo3::types::PyAny;
 
+use crate::utils::errors::range_error;
+
 
 pub fn excel_col_to_index(col: &str) -> u32 {
     col.chars().rev().enumerate().fold(0, |acc, (i, c)| {
@@ -22,17 +24,31 @@ pub fn index_to_excel_col(col: u32) -> String {
     col_str.chars().rev().collect::<String>()
 }
 
+/// Parses an Excel cell reference (e.g. "B5", "$B$5" or "b5") into (col, row)
+pub fn parse_excel_cell(cell: &str) -> Result<(u32, u32), String> {
+    let reference = cell.trim().replace('$', "").to_uppercase();
+    let split = reference.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(reference.len());
+    let (col_str, row_str) = reference.split_at(split);
+
+    if col_str.is_empty() || col_str.len() > 3 || row_str.is_empty() || row_str.len() > 7 || !row_str.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("Invalid cell reference {:?}. Expected column letters followed by a row number (e.g. B5).", cell));
+    }
+    let row = row_str.parse::<u32>().map_err(|_| format!("Invalid row in cell reference {:?}.", cell))?;
+    if row == 0 {
+        return Err(format!("Invalid cell reference {:?}. Row numbering starts at 1.", cell));
+    }
+
+    Ok((excel_col_to_index(col_str), row))
+}
+
+/// Converts an Excel cell reference into (col, row), (0, 0) for an invalid reference
+///
+/// References coming from Python are validated when extracted, and (0, 0) is rejected by the limit checks.
 pub fn excel_to_index(cell: &str) -> (u32, u32) {
-    let col_str = cell.chars().filter(|c| c.is_alphabetic()).collect::<String>();
-    let row_str = cell.chars().filter(|c| c.is_numeric()).collect::<String>();
-    
-    // Convert column letters to a number
-    let col = excel_col_to_index(&col_str);
-    
-    // Parse the row number
-    let row = row_str.parse::<u32>().unwrap();
-    
-    (col, row)
+    parse_excel_cell(cell).unwrap_or_else(|e| {
+        warn!("{}", e);
+        (0, 0)
+    })
 }
 
 #[allow(dead_code)]
@@ -53,6 +69,59 @@ pub fn tuple_to_excel(row: u32, col: u32) -> String {
 }
 
 
+/// Checks that a text is a valid defined name (e.g. "SalesData"): a letter, underscore or backslash
+/// followed by letters, digits, underscores and periods, and not a cell reference
+pub fn is_defined_name(name: &str) -> bool {
+    let name = name.trim();
+    let mut chars = name.chars();
+    let valid_start = matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_' || c == '\\');
+    valid_start
+        && name.len() <= 255
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+        && parse_excel_cell(name).is_err()
+        && !["R", "C"].contains(&name.to_uppercase().as_str())
+}
+
+/// Maximum number of rows of an Excel sheet
+pub const EXCEL_MAX_ROWS: u32 = 1_048_576;
+
+/// Maximum number of columns of an Excel sheet (XFD)
+pub const EXCEL_MAX_COLUMNS: u32 = 16_384;
+
+/// Checks that a cell is within the limits of an Excel sheet before writing to it.
+///
+/// :param operation: The operation writing to the cell, for the error message.
+/// :param sheet_name: The sheet written to.
+/// :param col: The 1-based column of the cell.
+/// :param row: The 1-based row of the cell.
+/// :return: An error naming the operation, sheet and offending cell if it is out of bounds.
+pub fn check_cell_limits(operation: &str, sheet_name: &str, col: u64, row: u64) -> PyResult<()> {
+    if col == 0 || row == 0 || col > EXCEL_MAX_COLUMNS as u64 || row > EXCEL_MAX_ROWS as u64 {
+        let cell = if col > 0 && col <= u32::MAX as u64 && row <= u32::MAX as u64 {
+            index_to_excel(col as u32, row as u32)
+        } else {
+            format!("(row {}, column {})", row, col)
+        };
+        let message = format!(
+            "{}: cell {} in '{}' is outside the limits of Excel ({} rows, {} columns).",
+            operation, cell, sheet_name, EXCEL_MAX_ROWS, EXCEL_MAX_COLUMNS,
+        );
+        return Err(range_error(message, Some(sheet_name), Some(&cell)));
+    }
+    Ok(())
+}
+
+/// Checks that a rectangular area, given by its first cell and size, is within the limits of an Excel sheet.
+pub fn check_area_limits(operation: &str, sheet_name: &str, first: (u32, u32), size: (u32, u32)) -> PyResult<()> {
+    let (col, row) = (first.0 as u64, first.1 as u64);
+    check_cell_limits(operation, sheet_name, col, row)?;
+    if size.0 > 0 && size.1 > 0 {
+        check_cell_limits(operation, sheet_name, col + size.0 as u64 - 1, row + size.1 as u64 - 1)?;
+    }
+    Ok(())
+}
+
+
 /// Determine the row.
 ///
 /// :param which_row: The row input from Python.
@@ -92,3 +161,54 @@ pub fn get_header_row(py: Python, which_row: Option<PyObject>, rows: (u32, u32,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::{any_cell, check};
+    use rand::Rng;
+
+    #[test]
+    fn column_letters_round_trip() {
+        for col in 1..=EXCEL_MAX_COLUMNS {
+            let letters = index_to_excel_col(col);
+            assert!(letters.len() <= 3 && letters.chars().all(|c| c.is_ascii_uppercase()), "{} for {}", letters, col);
+            assert_eq!(excel_col_to_index(&letters), col);
+        }
+    }
+
+    #[test]
+    fn cells_round_trip() {
+        check(|rng| {
+            let (col, row) = any_cell(rng);
+            let cell = index_to_excel(col, row);
+            assert_eq!(parse_excel_cell(&cell), Ok((col, row)), "{}", cell);
+            assert_eq!(excel_to_index(&cell), (col, row), "{}", cell);
+        });
+    }
+
+    #[test]
+    fn cells_are_parsed_whatever_their_case_and_anchors() {
+        check(|rng| {
+            let (col, row) = any_cell(rng);
+            let letters = index_to_excel_col(col);
+            let letters = if rng.gen() { letters.to_lowercase() } else { letters };
+            let col_anchor = if rng.gen() { "$" } else { "" };
+            let row_anchor = if rng.gen() { "$" } else { "" };
+            let cell = format!(" {}{}{}{} ", col_anchor, letters, row_anchor, row);
+            assert_eq!(parse_excel_cell(&cell), Ok((col, row)), "{:?}", cell);
+        });
+    }
+
+    #[test]
+    fn invalid_cells_are_rejected() {
+        check(|rng| {
+            let (col, row) = any_cell(rng);
+            let letters = index_to_excel_col(col);
+            for cell in [format!("{}0", letters), letters.clone(), row.to_string(), format!("{}{}", row, letters), format!("{}{}x", letters, row)].iter() {
+                assert!(parse_excel_cell(cell).is_err(), "{:?}", cell);
+                assert_eq!(excel_to_index(cell), (0, 0), "{:?}", cell);
+            }
+        });
+    }
+}
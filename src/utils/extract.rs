@@ -0,0 +1,81 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use polars::prelude::*;
+use umya_spreadsheet::{CellRawValue, Worksheet};
+
+use crate::utils::excel::index_to_excel;
+
+/// Reads a table from a worksheet into a Polars DataFrame.
+///
+/// Column names are read on the header row from the header column up to `last_col` (or the highest
+/// column), blank headers being skipped. Data is read below the header up to `last_row`, or up to
+/// the first empty row if not given. Columns holding only numbers become Float64, only booleans
+/// become Boolean, anything else becomes String.
+pub fn extract_table(
+    worksheet: &Worksheet,
+    header_col: u32,
+    header_row: u32,
+    last_col: Option<u32>,
+    last_row: Option<u32>,
+) -> Result<DataFrame, String> {
+    let last_col = last_col.unwrap_or_else(|| worksheet.get_highest_column());
+
+    let mut headers: Vec<(String, u32)> = Vec::new();
+    for col in header_col..=last_col {
+        let name = worksheet.get_value((col, header_row)).trim().to_string();
+        if name.is_empty() {
+            debug!("Blank header at {} skipped", index_to_excel(col, header_row));
+            continue;
+        }
+        if headers.iter().any(|(n, _)| *n == name) {
+            return Err(format!("Header '{}' appears more than once in row {}.", name, header_row));
+        }
+        headers.push((name, col));
+    }
+
+    let last_row = match last_row {
+        Some(row) => row,
+        None => {
+            let highest_row = worksheet.get_highest_row();
+            let mut row = header_row;
+            while row < highest_row && headers.iter().any(|(_, col)| !worksheet.get_value((*col, row + 1)).is_empty()) {
+                row += 1;
+            }
+            row
+        }
+    };
+    debug!("Table read from {} to row {}", index_to_excel(header_col, header_row), last_row);
+
+    let columns: Vec<Series> = headers.iter()
+        .map(|(name, col)| {
+            let values: Vec<CellRawValue> = ((header_row + 1)..=last_row)
+                .map(|row| worksheet.get_cell((*col, row)).map_or(CellRawValue::Empty, |c| c.get_raw_value().clone()))
+                .collect();
+            raw_values_to_series(name, &values)
+        })
+        .collect();
+
+    DataFrame::new(columns).map_err(|e| format!("Failed to create DataFrame: {}.", e))
+}
+
+/// Builds a Series from raw cell values, based on the types found in the cells
+pub fn raw_values_to_series(name: &str, values: &[CellRawValue]) -> Series {
+    let non_empty = || values.iter().filter(|v| !matches!(v, CellRawValue::Empty));
+
+    if non_empty().all(|v| matches!(v, CellRawValue::Numeric(_))) {
+        let numbers: Vec<Option<f64>> = values.iter()
+            .map(|v| match v { CellRawValue::Numeric(n) => Some(*n), _ => None })
+            .collect();
+        Series::new(name.into(), numbers)
+    } else if non_empty().all(|v| matches!(v, CellRawValue::Bool(_))) {
+        let booleans: Vec<Option<bool>> = values.iter()
+            .map(|v| match v { CellRawValue::Bool(b) => Some(*b), _ => None })
+            .collect();
+        Series::new(name.into(), booleans)
+    } else {
+        let strings: Vec<Option<String>> = values.iter()
+            .map(|v| match v { CellRawValue::Empty => None, _ => Some(v.to_string()) })
+            .collect();
+        Series::new(name.into(), strings)
+    }
+}
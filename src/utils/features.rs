@@ -0,0 +1,31 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use pyo3::prelude::*;
+#[allow(unused_imports)]
+use pyo3::types::{PyDict, PyTuple};
+
+/// Cargo features this binary can be built with, and whether they are compiled in
+pub const CARGO_FEATURES: &[(&str, bool)] = &[
+    ("aggregate", cfg!(feature = "aggregate")),
+    ("charts", cfg!(feature = "charts")),
+    ("dataframe", cfg!(feature = "dataframe")),
+    ("styles", cfg!(feature = "styles")),
+];
+
+/// Error raised when an API of a feature left out of the build is called
+pub fn missing_feature(operation: &str, feature: &str) -> PyErr {
+    warn!("{} called but the '{}' feature is not compiled in", operation, feature);
+    PyErr::new::<pyo3::exceptions::PyNotImplementedError, _>(format!(
+        "{} requires the '{}' feature, which this build of ez_excel_mgt was compiled without. \
+        Rebuild it with `--features {}` (or the default features).",
+        operation, feature, feature
+    ))
+}
+
+/// Stands in for collect when the 'dataframe' feature is left out
+#[cfg(not(feature = "dataframe"))]
+#[pyfunction]
+#[pyo3(signature = (*_args, **_kwargs))]
+pub fn collect(_args: &PyTuple, _kwargs: Option<&PyDict>) -> PyResult<()> {
+    Err(missing_feature("collect", "dataframe"))
+}
@@ -0,0 +1,91 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use polars::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use umya_spreadsheet::Worksheet;
+
+use crate::structs::{Fx, Mode};
+use crate::utils::py2rs::convert_anyvalue_to_string;
+
+/// Currency of each row of the data, empty when it is null
+fn row_currencies(df: &DataFrame, fx: &Fx) -> Result<Vec<String>, String> {
+    let currencies = df.column(&fx.currency)
+        .map_err(|_| format!("Currency column '{}' is missing in the DataFrame.", fx.currency))?;
+    (0..df.height())
+        .map(|i| currencies.get(i).map(|value| convert_anyvalue_to_string(value).trim().to_string()).map_err(|e| e.to_string()))
+        .collect()
+}
+
+/// Adds the converted columns to the data, each value multiplied by the rate of the currency of its row
+///
+/// Rows whose currency has no rate get an empty converted value.
+/// :return: The warnings on the currencies without a rate.
+pub fn add_converted_columns(df: &mut DataFrame, fx: &Fx) -> Result<Vec<String>, String> {
+    let currencies = row_currencies(df, fx)?;
+    let mut missing: BTreeMap<String, usize> = BTreeMap::new();
+    let rates: Vec<Option<f64>> = currencies.iter()
+        .map(|currency| {
+            let rate = fx.rates.get(currency).copied();
+            if rate.is_none() {
+                *missing.entry(currency.clone()).or_default() += 1;
+            }
+            rate
+        })
+        .collect();
+
+    for (column, converted) in &fx.columns {
+        let series = df.column(column).map_err(|_| format!("Column '{}' to convert is missing in the DataFrame.", column))?;
+        if !series.dtype().is_numeric() {
+            return Err(format!("Column '{}' to convert needs numbers, not {}.", column, series.dtype()));
+        }
+        let amounts = series.cast(&DataType::Float64).map_err(|e| e.to_string())?;
+        let amounts = amounts.f64().map_err(|e| e.to_string())?;
+        let values: Vec<Option<f64>> = amounts.into_iter().zip(&rates)
+            .map(|(amount, rate)| Some(amount? * (*rate)?))
+            .collect();
+        df.with_column(Series::new(converted.as_str().into(), values)).map_err(|e| e.to_string())?;
+        debug!("Column {} converted into {}", column, converted);
+    }
+
+    Ok(missing.into_iter()
+        .map(|(currency, rows)| match currency.as_str() {
+            "" => format!("No currency in {} row(s): their converted values are left empty.", rows),
+            _ => format!("No fx rate for currency '{}' in {} row(s): their converted values are left empty.", currency, rows),
+        })
+        .collect())
+}
+
+/// Formats the original amounts of the written rows with the code of their currency, e.g. `#,##0.00 "USD"`
+///
+/// Columns with a number format of their own (set_defaults styles) are left alone.
+pub fn set_currency_formats(
+    worksheet: &mut Worksheet,
+    df: &DataFrame,
+    fx: &Fx,
+    header_map: &HashMap<String, u32>,
+    first_cell: (u32, u32),
+    mode: &Mode,
+    styles: &HashMap<String, String>,
+) -> Result<(), String> {
+    let currencies = row_currencies(df, fx)?;
+    let (first_col, first_row) = first_cell;
+    for (column, _) in &fx.columns {
+        let idx = match (header_map.get(column), styles.contains_key(column)) {
+            (Some(idx), false) => *idx,
+            _ => continue,
+        };
+        let series = df.column(column).map_err(|e| e.to_string())?;
+        for (i, currency) in currencies.iter().enumerate() {
+            if currency.is_empty() || series.get(i).map_or(true, |value| value.is_null()) {
+                continue;
+            }
+            let (col, row) = match mode {
+                Mode::Row => (idx, first_row + i as u32),
+                Mode::Column => (first_col + i as u32, idx),
+            };
+            let format_code = format!("{} \"{}\"", fx.format, currency.replace('"', ""));
+            worksheet.get_style_mut((col, row)).get_number_format_mut().set_format_code(format_code);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,177 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use chrono::{Datelike, Duration, Months, NaiveDate};
+use polars::prelude::*;
+use umya_spreadsheet::Worksheet;
+
+use crate::structs::Period;
+use crate::utils::py2rs::convert_anyvalue_to_string;
+use crate::utils::styles::{parse_color, set_fill_color};
+use crate::utils::warnings::Warning;
+use crate::utils::write::{write_anyvalue, write_text, WriteOptions};
+
+/// Day 0 of the serial dates of Excel
+fn excel_epoch() -> NaiveDate {
+    NaiveDate::from_ymd_opt(1899, 12, 30).unwrap()
+}
+
+/// Reads a date from a Polars value: a date, a datetime or a text starting with YYYY-MM-DD
+pub fn to_date(value: AnyValue) -> Option<NaiveDate> {
+    let unix_epoch = NaiveDate::from_ymd_opt(1970, 1, 1)?;
+    match value {
+        AnyValue::Date(days) => unix_epoch.checked_add_signed(Duration::days(days as i64)),
+        AnyValue::Datetime(value, unit, _) => {
+            let per_day = match unit {
+                TimeUnit::Nanoseconds => 86_400_000_000_000,
+                TimeUnit::Microseconds => 86_400_000_000,
+                TimeUnit::Milliseconds => 86_400_000,
+            };
+            unix_epoch.checked_add_signed(Duration::days(value.div_euclid(per_day)))
+        },
+        AnyValue::String(text) => parse_date(text),
+        AnyValue::StringOwned(text) => parse_date(&text),
+        _ => None,
+    }
+}
+
+/// Reads the YYYY-MM-DD date a text starts with, ignoring a time after it
+fn parse_date(text: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(text.trim().get(..10)?, "%Y-%m-%d").ok()
+}
+
+impl Period {
+    /// First day of the period holding a date, weeks starting on Monday
+    fn start_of(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Day => date,
+            Period::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+            Period::Month => date.with_day(1).unwrap_or(date),
+        }
+    }
+
+    /// First day of the next period
+    fn next(&self, start: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Day => start + Duration::days(1),
+            Period::Week => start + Duration::days(7),
+            Period::Month => start + Months::new(1),
+        }
+    }
+
+    /// Number format of the date headers
+    fn format_code(&self) -> &'static str {
+        match self {
+            Period::Day | Period::Week => "yyyy-mm-dd",
+            Period::Month => "mmm yyyy",
+        }
+    }
+}
+
+/// A task of the chart, with its dates when they are valid and the color of its bar
+#[derive(Debug, Clone)]
+pub struct Bar {
+    pub label: AnyValue<'static>,
+    pub span: Option<(NaiveDate, NaiveDate)>,
+    pub color: Option<String>,
+}
+
+/// Tasks laid out on a grid of date columns, a row per task
+#[derive(Debug, Clone)]
+pub struct Gantt {
+    pub task: String,
+    pub period: Period,
+    pub bars: Vec<Bar>,
+    pub periods: Vec<NaiveDate>,
+}
+
+/// Lays out the tasks of a DataFrame over the periods running from the first start to the last end
+///
+/// Tasks without valid dates, or ending before they start, keep their row without a bar and are
+/// reported in `warnings` with their row of the data, as are invalid colors of `color_from`.
+pub fn plan_gantt(
+    df: &DataFrame,
+    task: &str,
+    start: &str,
+    end: &str,
+    period: Period,
+    color_from: Option<&str>,
+    warnings: &mut Vec<Warning>,
+) -> PolarsResult<Gantt> {
+    let (tasks, starts, ends) = (df.column(task)?, df.column(start)?, df.column(end)?);
+    let colors = color_from.map(|column| df.column(column)).transpose()?;
+
+    let mut bars = Vec::with_capacity(df.height());
+    for i in 0..df.height() {
+        let label = tasks.get(i)?.into_static()?;
+        let name = convert_anyvalue_to_string(label.clone());
+        let span = match (to_date(starts.get(i)?), to_date(ends.get(i)?)) {
+            (Some(first), Some(last)) if first <= last => Some((first, last)),
+            (Some(_), Some(_)) => {
+                warnings.push(Warning::new("no_bar", format!("Task '{}' ends before it starts. Left without a bar.", name)).with_column(end).with_row(i));
+                None
+            },
+            _ => {
+                warnings.push(Warning::new("no_bar", format!("Task '{}' has no valid '{}' and '{}' dates. Left without a bar.", name, start, end)).with_column(start).with_row(i));
+                None
+            },
+        };
+        let color = match colors.map(|colors| colors.get(i)).transpose()? {
+            None | Some(AnyValue::Null) => None,
+            Some(value) => match parse_color(&convert_anyvalue_to_string(value)) {
+                Ok(argb) => Some(argb),
+                Err(e) => {
+                    warnings.push(Warning::new("invalid_color", format!("{} Default color used for row {}.", e, i + 1)).with_column(color_from.unwrap_or_default()).with_row(i));
+                    None
+                },
+            },
+        };
+        bars.push(Bar { label, span, color });
+    }
+
+    let mut periods = Vec::new();
+    let first = bars.iter().filter_map(|bar| bar.span.map(|(first, _)| first)).min();
+    let last = bars.iter().filter_map(|bar| bar.span.map(|(_, last)| last)).max();
+    if let (Some(first), Some(last)) = (first, last) {
+        let mut current = period.start_of(first);
+        while current <= last {
+            periods.push(current);
+            current = period.next(current);
+        }
+    }
+    debug!("{} tasks laid out over {} periods of a {}", bars.len(), periods.len(), period.as_str());
+    Ok(Gantt { task: task.to_string(), period, bars, periods })
+}
+
+impl Gantt {
+    /// Number of columns and rows of the chart, headers included
+    pub fn size(&self) -> (u32, u32) {
+        (self.periods.len() as u32 + 1, self.bars.len() as u32 + 1)
+    }
+
+    /// Writes the chart from a cell: the tasks down the first column, the first day of each period
+    /// across the first row and the periods of each task filled with its color (ARGB)
+    pub fn write(&self, worksheet: &mut Worksheet, first_cell: (u32, u32), color: &str, options: &WriteOptions) {
+        let (first_col, first_row) = first_cell;
+        write_text(worksheet.get_cell_mut((first_col, first_row)), &self.task);
+        for (j, start) in self.periods.iter().enumerate() {
+            let cell = worksheet.get_cell_mut((first_col + 1 + j as u32, first_row));
+            cell.set_value_number((*start - excel_epoch()).num_days() as f64);
+            cell.get_style_mut().get_number_format_mut().set_format_code(self.period.format_code());
+        }
+
+        for (i, bar) in self.bars.iter().enumerate() {
+            let row = first_row + 1 + i as u32;
+            write_anyvalue(worksheet.get_cell_mut((first_col, row)), bar.label.clone(), options);
+            let (first, last) = match bar.span {
+                Some(span) => span,
+                None => continue,
+            };
+            for (j, start) in self.periods.iter().enumerate() {
+                // A period is part of the bar when it overlaps the dates of the task
+                if *start <= last && self.period.next(*start) > first {
+                    set_fill_color(worksheet.get_cell_mut((first_col + 1 + j as u32, row)), bar.color.as_deref().unwrap_or(color));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,22 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use polars::prelude::*;
+
+use crate::structs::GeneratedColumn;
+
+/// Adds the generated columns to the data, which must not have columns of the same names
+pub fn add_generated_columns(df: &mut DataFrame, columns: &[(String, GeneratedColumn)]) -> Result<(), String> {
+    let height = df.height();
+    for (name, generator) in columns {
+        if df.get_column_index(name).is_some() {
+            return Err(format!("Generated column '{}' is already a column of the DataFrame.", name));
+        }
+        let series = match generator {
+            GeneratedColumn::RowNumber(start) => Series::new(name.as_str().into(), (0..height as i64).map(|i| start + i).collect::<Vec<i64>>()),
+            GeneratedColumn::Constant(value) => Series::new(name.as_str().into(), vec![value.as_str(); height]),
+        };
+        df.with_column(series).map_err(|e| e.to_string())?;
+        debug!("Column {} generated as {:?}", name, generator);
+    }
+    Ok(())
+}
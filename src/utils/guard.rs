@@ -0,0 +1,23 @@
+#[allow(unused_imports)]
+use log::{debug, error, info, warn};
+use pyo3::prelude::*;
+use std::panic::{self, AssertUnwindSafe};
+
+/// Runs an entry point, converting any internal panic into a Python RuntimeError naming the operation
+///
+/// Without it, a panic surfaces as a PanicException, which derives from BaseException and escapes
+/// the usual `except Exception` handlers.
+pub fn catch_panic<T>(operation: &str, f: impl FnOnce() -> PyResult<T>) -> PyResult<T> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(payload) => {
+            let message = payload.downcast_ref::<&str>().map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown error".to_string());
+            error!("Panic in {}: {}", operation, message);
+            Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(
+                "{}: internal error ({}). Please report it with the arguments used.", operation, message,
+            )))
+        },
+    }
+}
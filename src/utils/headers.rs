@@ -0,0 +1,51 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use std::collections::HashMap;
+
+use crate::structs::HeaderMatching;
+
+/// Maps the columns of the data to the headers of the sheet they match, keyed by column name
+///
+/// Columns matching a header exactly are mapped first, then the `aliases` (column name to header),
+/// then the headers matching under the policy, the leftmost header winning when several match. The
+/// headers left unmatched are kept under their own name, to be reported as missing in the data.
+pub fn match_headers(
+    header_map: HashMap<String, u32>,
+    columns: &[String],
+    matching: &HeaderMatching,
+    aliases: &HashMap<String, String>,
+) -> HashMap<String, u32> {
+    let mut headers = header_map;
+    let mut matched: HashMap<String, u32> = HashMap::new();
+    for column in columns {
+        if let Some(idx) = headers.remove(column) {
+            matched.insert(column.clone(), idx);
+        }
+    }
+
+    for column in columns {
+        if matched.contains_key(column) {
+            continue;
+        }
+        let header = match aliases.get(column) {
+            Some(alias) if headers.contains_key(alias) => Some(alias.clone()),
+            _ if *matching == HeaderMatching::Exact => None,
+            _ => {
+                let key = matching.key(column);
+                headers.iter()
+                    .filter(|(header, _)| matching.key(header) == key)
+                    .min_by_key(|(_, idx)| **idx)
+                    .map(|(header, _)| header.clone())
+            },
+        };
+        if let Some(header) = header {
+            if let Some(idx) = headers.remove(&header) {
+                debug!("Column {} matched to header {}", column, header);
+                matched.insert(column.clone(), idx);
+            }
+        }
+    }
+
+    matched.extend(headers);
+    matched
+}
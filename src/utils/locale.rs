@@ -0,0 +1,57 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+
+/// Separators used to render numbers as text
+#[derive(Debug, Clone)]
+pub struct NumberLocale {
+    pub decimal_separator: String,
+    pub thousands_separator: Option<String>,
+}
+
+impl NumberLocale {
+    /// Renders a number with the separators of the locale, e.g. 1234.5 as "1 234,5"
+    pub fn format(&self, value: f64) -> String {
+        self.format_digits(&value.to_string())
+    }
+
+    /// Renders an integer with the separators of the locale, all its digits kept whatever its size
+    pub fn format_integer(&self, value: i128) -> String {
+        self.format_digits(&value.to_string())
+    }
+
+    /// Renders the decimal digits of a number ("-1234.5") with the separators of the locale
+    fn format_digits(&self, text: &str) -> String {
+        let (sign, digits) = match text.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", text),
+        };
+        let (integer, fraction) = match digits.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (digits, None),
+        };
+
+        let integer = match &self.thousands_separator {
+            Some(separator) if integer.chars().all(|c| c.is_ascii_digit()) => {
+                let mut grouped = String::new();
+                for (i, c) in integer.chars().enumerate() {
+                    if i > 0 && (integer.len() - i) % 3 == 0 {
+                        grouped.push_str(separator);
+                    }
+                    grouped.push(c);
+                }
+                grouped
+            },
+            _ => integer.to_string(),
+        };
+
+        match fraction {
+            Some(fraction) => format!("{}{}{}{}", sign, integer, self.decimal_separator, fraction),
+            None => format!("{}{}", sign, integer),
+        }
+    }
+
+    /// Renders a numeric string with the separators of the locale, or None if it is not a number
+    pub fn format_str(&self, value: &str) -> Option<String> {
+        value.trim().parse::<f64>().ok().map(|number| self.format(number))
+    }
+}
@@ -0,0 +1,53 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+use std::path::Path;
+use umya_spreadsheet::Spreadsheet;
+use zip::ZipArchive;
+
+use crate::utils::package::patch_parts;
+
+/// Content type of the workbook part of an xlsx file, and of a macro-enabled (xlsm) one
+const WORKBOOK_CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml";
+const MACRO_WORKBOOK_CONTENT_TYPE: &str = "application/vnd.ms-excel.sheet.macroEnabled.main+xml";
+
+static CODE_NAME: Lazy<Regex> = Lazy::new(|| Regex::new(r#"<workbookPr\b[^>]*\bcodeName="([^"]*)""#).unwrap());
+
+/// Whether a file is saved as a macro-enabled workbook, from its extension (.xlsm)
+pub fn is_macro_path(file_path: &str) -> bool {
+    Path::new(file_path).extension().is_some_and(|extension| extension.eq_ignore_ascii_case("xlsm"))
+}
+
+/// Keeps the code name the macros of a workbook refer it by (ThisWorkbook, DieseArbeitsmappe...)
+///
+/// The reader leaves it out, and the writer would name the workbook ThisWorkbook, breaking the macros
+/// of workbooks created by a localized Excel.
+pub fn restore_code_name<R: Read + Seek>(spreadsheet: &mut Spreadsheet, xlsx: R) {
+    if !spreadsheet.get_has_macros() {
+        return;
+    }
+    let mut workbook_xml = String::new();
+    let read = ZipArchive::new(xlsx).ok()
+        .and_then(|mut archive| archive.by_name("xl/workbook.xml").ok()?.read_to_string(&mut workbook_xml).ok());
+    if read.is_none() {
+        return;
+    }
+    if let Some(code_name) = CODE_NAME.captures(&workbook_xml).map(|captures| captures[1].to_string()) {
+        debug!("Workbook code name {} kept for its macros", code_name);
+        spreadsheet.set_code_name(code_name);
+    }
+}
+
+/// Declares the workbook of an xlsx file as macro-enabled, as Excel requires for a file saved as .xlsm
+///
+/// The writer only does so for workbooks with macros, a workbook without any saved as .xlsm being refused.
+pub fn enable_macros(xlsx: Vec<u8>) -> Result<Vec<u8>, String> {
+    let patches: HashMap<String, _> = HashMap::from([(
+        "[Content_Types].xml".to_string(),
+        |xml: &str| Ok(xml.replace(WORKBOOK_CONTENT_TYPE, MACRO_WORKBOOK_CONTENT_TYPE)),
+    )]);
+    patch_parts(xlsx, &patches)
+}
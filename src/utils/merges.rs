@@ -0,0 +1,92 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use umya_spreadsheet::Worksheet;
+
+use crate::utils::excel::{index_to_excel, parse_excel_cell};
+
+/// Corners of an area of a sheet, as ((col, row), (col, row)) from the top left to the bottom right
+pub type Area = ((u32, u32), (u32, u32));
+
+/// Returns the range of an area, as in Excel
+pub fn area_range(area: Area) -> String {
+    format!("{}:{}", index_to_excel(area.0.0, area.0.1), index_to_excel(area.1.0, area.1.1))
+}
+
+/// Whether two areas have a cell in common
+fn overlap(a: Area, b: Area) -> bool {
+    a.0.0 <= b.1.0 && b.0.0 <= a.1.0 && a.0.1 <= b.1.1 && b.0.1 <= a.1.1
+}
+
+/// The merged cells of a sheet, read once to look up the cells they hide
+pub struct MergedCells(Vec<Area>);
+
+impl MergedCells {
+    /// Reads the merged cells of a sheet
+    pub fn of(worksheet: &Worksheet) -> Self {
+        let areas = worksheet.get_merge_cells().iter()
+            .filter_map(|merge| {
+                let range = merge.get_range();
+                let (first, last) = range.split_once(':')?;
+                Some((parse_excel_cell(first).ok()?, parse_excel_cell(last).ok()?))
+            })
+            .collect();
+        MergedCells(areas)
+    }
+
+    /// Returns the merge hiding a cell, None for the cells outside merges and for the anchors (top left cells) of merges
+    pub fn hiding(&self, cell: (u32, u32)) -> Option<Area> {
+        self.0.iter().copied().find(|area| area.0 != cell && overlap(*area, (cell, cell)))
+    }
+
+    /// Returns the merges lying entirely within some rows
+    pub fn within_rows(&self, first_row: u32, last_row: u32) -> Vec<Area> {
+        self.0.iter().copied().filter(|area| area.0.1 >= first_row && area.1.1 <= last_row).collect()
+    }
+
+    /// Returns the first merge having a cell in the given area
+    pub fn overlapping(&self, area: Area) -> Option<Area> {
+        self.0.iter().copied().find(|merge| overlap(*merge, area))
+    }
+}
+
+/// Merges the cells of an area of a sheet, the way Excel does: only the value of the top left cell is kept
+///
+/// :return: The cells whose values were cleared, or an error when the area overlaps a merge already there.
+pub fn merge_cells(worksheet: &mut Worksheet, area: Area) -> Result<Vec<String>, String> {
+    if let Some(merge) = MergedCells::of(worksheet).overlapping(area) {
+        return Err(format!("Range {} overlaps the merged cells {}.", area_range(area), area_range(merge)));
+    }
+    let mut hidden: Vec<(u32, u32)> = worksheet.get_cell_collection().iter()
+        .filter(|cell| !cell.get_value().is_empty() || cell.is_formula())
+        .map(|cell| (*cell.get_coordinate().get_col_num(), *cell.get_coordinate().get_row_num()))
+        .filter(|cell| *cell != area.0 && overlap(area, (*cell, *cell)))
+        .collect();
+    hidden.sort_by_key(|(col, row)| (*row, *col));
+    let mut cleared = Vec::new();
+    for cell in hidden {
+        worksheet.get_cell_mut(cell).set_blank();
+        cleared.push(index_to_excel(cell.0, cell.1));
+    }
+    worksheet.add_merge_cells(area_range(area));
+    debug!("Cells {} of {} merged, {} values cleared", area_range(area), worksheet.get_name(), cleared.len());
+    Ok(cleared)
+}
+
+/// Splits the merges of a sheet having a cell in an area back into their cells
+///
+/// :return: The ranges of the merges split.
+pub fn unmerge_cells(worksheet: &mut Worksheet, area: Area) -> Vec<String> {
+    let split: Vec<String> = MergedCells::of(worksheet).0.into_iter()
+        .filter(|merge| overlap(*merge, area))
+        .map(area_range)
+        .collect();
+    worksheet.get_merge_cells_mut().retain(|merge| {
+        let range = merge.get_range();
+        match range.split_once(':').map(|(first, last)| (parse_excel_cell(first), parse_excel_cell(last))) {
+            Some((Ok(first), Ok(last))) => !overlap((first, last), area),
+            _ => true,
+        }
+    });
+    debug!("{} merges split in {} of {}", split.len(), area_range(area), worksheet.get_name());
+    split
+}
@@ -0,0 +1,175 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use chrono::{SecondsFormat, Utc};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use umya_spreadsheet::{SheetStateValues, Spreadsheet, Worksheet};
+
+use crate::structs::Mode;
+
+/// Prefix of the hidden sheets holding the metadata written by this module
+pub const META_SHEET_PREFIX: &str = "_ezxl_";
+
+/// Checks whether a sheet is one of the hidden metadata sheets
+pub fn is_meta_sheet(sheet_name: &str) -> bool {
+    sheet_name.starts_with(META_SHEET_PREFIX)
+}
+
+/// Appends a record (one row of strings) to a hidden metadata sheet, creating the sheet if needed
+pub fn append_record(spreadsheet: &mut Spreadsheet, sheet_name: &str, record: &[String]) -> Result<(), String> {
+    if spreadsheet.get_sheet_by_name(sheet_name).is_none() {
+        let worksheet = spreadsheet.new_sheet(sheet_name).map_err(|e| e.to_string())?;
+        worksheet.set_state(SheetStateValues::VeryHidden);
+        debug!("Metadata sheet {} created", sheet_name);
+    }
+    let worksheet = spreadsheet.get_sheet_by_name_mut(sheet_name)
+        .ok_or_else(|| format!("Sheet '{}' not found.", sheet_name))?;
+
+    let row = worksheet.get_highest_row() + 1;
+    for (i, value) in record.iter().enumerate() {
+        worksheet.get_cell_mut((i as u32 + 1, row)).set_value_string(value.clone());
+    }
+    debug!("Record {:?} appended to {}", record, sheet_name);
+    Ok(())
+}
+
+/// Reads all records of a hidden metadata sheet, if it exists
+pub fn read_records(spreadsheet: &Spreadsheet, sheet_name: &str) -> Vec<Vec<String>> {
+    match spreadsheet.get_sheet_by_name(sheet_name) {
+        Some(worksheet) => {
+            let (last_col, last_row) = worksheet.get_highest_column_and_row();
+            (1..=last_row)
+                .map(|row| (1..=last_col).map(|col| worksheet.get_value((col, row))).collect())
+                .collect()
+        },
+        None => Vec::new(),
+    }
+}
+
+/// Renames the sheet the records of the hidden metadata sheets belong to, given in their first column
+///
+/// :return: The number of records renamed.
+pub fn rename_in_records(spreadsheet: &mut Spreadsheet, sheet_name: &str, new_name: &str) -> usize {
+    let mut renamed = 0;
+    for worksheet in spreadsheet.get_sheet_collection_mut().iter_mut().filter(|ws| is_meta_sheet(ws.get_name())) {
+        for row in 1..=worksheet.get_highest_row() {
+            if worksheet.get_value((1, row)) == sheet_name {
+                worksheet.get_cell_mut((1, row)).set_value_string(new_name);
+                renamed += 1;
+            }
+        }
+    }
+    renamed
+}
+
+/// Removes the records of the hidden metadata sheets belonging to a sheet, given in their first column
+///
+/// :return: The number of records removed.
+pub fn remove_records_of(spreadsheet: &mut Spreadsheet, sheet_name: &str) -> usize {
+    let mut removed = 0;
+    for worksheet in spreadsheet.get_sheet_collection_mut().iter_mut().filter(|ws| is_meta_sheet(ws.get_name())) {
+        // From the last record, so that the rows of the records left to check do not move
+        for row in (1..=worksheet.get_highest_row()).rev() {
+            if worksheet.get_value((1, row)) == sheet_name {
+                worksheet.remove_row(&row, &1);
+                removed += 1;
+            }
+        }
+    }
+    removed
+}
+
+/// Hidden sheet holding the registry of the regions filled in the workbook
+pub const REGISTRY_SHEET: &str = "_ezxl_meta";
+
+/// A region filled in the workbook, persisted so later runs can find it again
+#[derive(Debug, Clone)]
+pub struct Region {
+    pub sheet: String,
+    pub header: String,
+    pub mode: Mode,
+    pub range: String,
+    pub filled_at: String,
+}
+
+impl Region {
+    /// Builds a region filled now, with the header and data range as Excel references
+    pub fn filled_now(sheet: &str, header: String, mode: Mode, range: String) -> Self {
+        Region {
+            sheet: sheet.to_string(),
+            header,
+            mode,
+            range,
+            filled_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        }
+    }
+
+    #[cfg(feature = "dataframe")]
+    fn to_record(&self) -> Vec<String> {
+        vec![self.sheet.clone(), self.header.clone(), self.mode.as_str().to_string(), self.range.clone(), self.filled_at.clone()]
+    }
+
+    fn from_record(record: &[String]) -> Option<Self> {
+        match record {
+            [sheet, header, mode, range, filled_at, ..] => Some(Region {
+                sheet: sheet.clone(),
+                header: header.clone(),
+                mode: mode.parse().ok()?,
+                range: range.clone(),
+                filled_at: filled_at.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl IntoPy<PyObject> for Region {
+    fn into_py(self, py: Python) -> PyObject {
+        let region = PyDict::new(py);
+        // Set items can only fail on unhashable keys, which string keys are not
+        let _ = region.set_item("sheet", self.sheet);
+        let _ = region.set_item("header", self.header);
+        let _ = region.set_item("mode", self.mode.as_str());
+        let _ = region.set_item("range", self.range);
+        let _ = region.set_item("filled_at", self.filled_at);
+        region.into()
+    }
+}
+
+/// Appends a region to the registry of the workbook
+#[cfg(feature = "dataframe")]
+pub fn register_region(spreadsheet: &mut Spreadsheet, region: &Region) -> Result<(), String> {
+    append_record(spreadsheet, REGISTRY_SHEET, &region.to_record())
+}
+
+/// Reads the regions of the registry of the workbook, oldest first, skipping unreadable records
+pub fn read_regions(spreadsheet: &Spreadsheet) -> Vec<Region> {
+    read_records(spreadsheet, REGISTRY_SHEET).iter()
+        .filter_map(|record| {
+            let region = Region::from_record(record);
+            if region.is_none() {
+                warn!("Unreadable region {:?} in {}", record, REGISTRY_SHEET);
+            }
+            region
+        })
+        .collect()
+}
+
+/// Clears the values and formulas of the cells of a range, keeping their styles
+///
+/// :return: The number of cells cleared.
+pub fn clear_values(worksheet: &mut Worksheet, bounds: ((u32, u32), (u32, u32))) -> usize {
+    let ((first_col, first_row), (last_col, last_row)) = bounds;
+    let mut cleared = 0;
+    for cell in worksheet.get_cell_collection_mut() {
+        let (col, row) = (*cell.get_coordinate().get_col_num(), *cell.get_coordinate().get_row_num());
+        if col < first_col || col > last_col || row < first_row || row > last_row {
+            continue;
+        }
+        if !cell.get_value().is_empty() || cell.is_formula() {
+            cell.set_blank();
+            cleared += 1;
+        }
+    }
+    cleared
+}
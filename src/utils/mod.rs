@@ -1,3 +1,62 @@
+#[cfg(feature = "aggregate")]
 pub mod aggregate;
+#[cfg(feature = "dataframe")]
+pub mod coerce;
+#[cfg(feature = "charts")]
+pub mod charts;
+pub mod comments;
+pub mod controls;
+#[cfg(feature = "dataframe")]
+pub mod crosstab;
+pub mod dimensions;
+pub mod distribution;
+pub mod encryption;
+pub mod errors;
 pub mod excel;
+#[cfg(feature = "dataframe")]
+pub mod extract;
+pub mod features;
+#[cfg(feature = "dataframe")]
+pub mod fx;
+#[cfg(all(feature = "dataframe", feature = "styles"))]
+pub mod gantt;
+#[cfg(feature = "dataframe")]
+pub mod generated;
+pub mod guard;
+#[cfg(feature = "dataframe")]
+pub mod headers;
+pub mod locale;
+pub mod macros;
+pub mod merges;
+pub mod meta;
+pub mod optimize;
+pub mod outline;
+pub mod properties;
+pub mod package;
+pub mod parallel;
+#[cfg(feature = "dataframe")]
 pub mod py2rs;
+pub mod refs;
+pub mod render;
+#[cfg(feature = "dataframe")]
+pub mod rs2py;
+pub mod sampling;
+pub mod search;
+pub mod select;
+pub mod sheets;
+#[cfg(feature = "dataframe")]
+pub mod sources;
+#[cfg(feature = "charts")]
+pub mod sparkline;
+pub mod stamp;
+pub mod stats;
+pub mod styles;
+#[cfg(feature = "dataframe")]
+pub mod subtotals;
+pub mod tables;
+#[cfg(test)]
+pub mod testing;
+#[cfg(feature = "dataframe")]
+pub mod verify;
+pub mod warnings;
+pub mod write;
\ No newline at end of file
@@ -0,0 +1,170 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use std::io::Cursor;
+use umya_spreadsheet::{reader, writer, Spreadsheet, Worksheet};
+
+use crate::utils::package::patch_parts;
+use crate::utils::refs::check_formula;
+
+const SHARED_STRINGS_PART: &str = "xl/sharedStrings.xml";
+const STYLES_PART: &str = "xl/styles.xml";
+
+/// Table of shared strings without any string, filled again from the cells when saved
+const EMPTY_SHARED_STRINGS: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    "\r\n",
+    r#"<sst xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" count="0" uniqueCount="0"/>"#,
+);
+
+/// Rewriting of a part of the package
+type Patch<'a> = &'a dyn Fn(&str) -> Result<String, String>;
+
+/// What optimize removed from the workbook, and the size of the file before and after
+#[derive(Debug, Clone, Default)]
+pub struct OptimizeReport {
+    pub styles_removed: u64,
+    pub strings_removed: u64,
+    pub defined_names_removed: Vec<String>,
+    pub size_before: u64,
+    pub size_after: u64,
+}
+
+impl IntoPy<PyObject> for OptimizeReport {
+    fn into_py(self, py: Python) -> PyObject {
+        let report = PyDict::new(py);
+        // Set items can only fail on unhashable keys, which string keys are not
+        let _ = report.set_item("styles_removed", self.styles_removed);
+        let _ = report.set_item("strings_removed", self.strings_removed);
+        let _ = report.set_item("defined_names_removed", self.defined_names_removed);
+        let _ = report.set_item("size_before", self.size_before);
+        let _ = report.set_item("size_after", self.size_after);
+        report.into()
+    }
+}
+
+/// Serializes a workbook with the writer only, as the reader expects it back
+fn to_bytes(spreadsheet: &Spreadsheet) -> Result<Vec<u8>, String> {
+    let mut xlsx: Vec<u8> = Vec::new();
+    writer::xlsx::write_writer(spreadsheet, &mut xlsx).map_err(|e| format!("{:?}", e))?;
+    Ok(xlsx)
+}
+
+/// Count attribute of the lists of the stylesheet
+static COUNT: Lazy<Regex> = Lazy::new(|| Regex::new(r#"\bcount="\d+""#).unwrap());
+
+/// Reads a numeric attribute of an element
+fn attribute(element: &str, name: &str) -> Option<usize> {
+    let regex = Regex::new(&format!(r#"\b{}="(\d+)""#, name)).ok()?;
+    regex.captures(element)?.get(1)?.as_str().parse().ok()
+}
+
+/// Elements of a list of the stylesheet, e.g. the `<font>` of `<fonts>`
+fn list_items<'a>(xml: &'a str, list: &str, item: &str) -> Vec<&'a str> {
+    let list = Regex::new(&format!(r"(?s)<{0}\b[^>]*>(.*?)</{0}>", list)).unwrap();
+    let item = Regex::new(&format!(r"(?s)<{0}\b[^>]*?/>|<{0}\b[^>]*>.*?</{0}>", item)).unwrap();
+    match list.captures(xml).and_then(|caps| caps.get(1)) {
+        Some(content) => item.find_iter(content.as_str()).map(|m| m.as_str()).collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Keeps the elements of a list of the stylesheet for which `keep` is true, given their position, updating its count
+fn retain_items<F: Fn(usize, &str) -> bool>(xml: &str, list: &str, item: &str, keep: F) -> String {
+    let items = list_items(xml, list, item);
+    let kept: Vec<&str> = items.iter().enumerate().filter(|(i, element)| keep(*i, element)).map(|(_, element)| *element).collect();
+    if kept.len() == items.len() {
+        return xml.to_string();
+    }
+    let regex = Regex::new(&format!(r"(?s)(<{0}\b[^>]*>)(.*?)(</{0}>)", list)).unwrap();
+    let rewritten = regex.replace(xml, |caps: &Captures| {
+        let open = COUNT.replace(&caps[1], format!(r#"count="{}""#, kept.len()).as_str()).into_owned();
+        format!("{}{}{}", open, kept.concat(), &caps[3])
+    });
+    debug!("{} of {} {} kept", kept.len(), items.len(), list);
+    rewritten.into_owned()
+}
+
+/// Drops the cell formats of a stylesheet but the default one, and the number formats, fonts, fills
+/// and borders only they used
+///
+/// The formats of the named cell styles, and what they use, are kept. Saving adds the formats the
+/// cells use again.
+fn prune_stylesheet(xml: &str) -> String {
+    let cell_formats = list_items(xml, "cellXfs", "xf");
+    let style_formats = list_items(xml, "cellStyleXfs", "xf");
+    let kept: Vec<&str> = cell_formats.iter().take(1).chain(style_formats.iter()).copied().collect();
+    let used = |id: &str| kept.iter().filter_map(|xf| attribute(xf, id)).max().map_or(1, |max| max + 1);
+    let number_formats: Vec<usize> = kept.iter().filter_map(|xf| attribute(xf, "numFmtId")).collect();
+
+    let xml = retain_items(xml, "cellXfs", "xf", |i, _| i == 0);
+    let xml = retain_items(&xml, "numFmts", "numFmt", |_, element| {
+        attribute(element, "numFmtId").is_some_and(|id| number_formats.contains(&id))
+    });
+    let xml = retain_items(&xml, "fonts", "font", |i, _| i < used("fontId"));
+    // Excel requires the two first fills, none and gray125
+    let xml = retain_items(&xml, "fills", "fill", |i, _| i < used("fillId").max(2));
+    retain_items(&xml, "borders", "border", |i, _| i < used("borderId"))
+}
+
+/// Rebuilds the stylesheet and the table of shared strings of a workbook from what its cells use
+///
+/// The tables of a loaded workbook are kept as they were when saved, so the styles and strings
+/// no cell uses anymore pile up over fill and copy cycles. The sheets, with the styles and texts of
+/// their cells, are moved into the workbook reloaded with pruned tables, the saving filling them again.
+pub fn rebuild_tables(spreadsheet: &Spreadsheet) -> Result<Spreadsheet, String> {
+    let mut model = spreadsheet.clone();
+    model.read_sheet_collection();
+    let xlsx = to_bytes(&model)?;
+
+    let pruned_styles = |xml: &str| Ok(prune_stylesheet(xml));
+    let empty_strings = |_: &str| Ok(EMPTY_SHARED_STRINGS.to_string());
+    let mut patches: HashMap<String, Patch> = HashMap::new();
+    patches.insert(STYLES_PART.to_string(), &pruned_styles);
+    patches.insert(SHARED_STRINGS_PART.to_string(), &empty_strings);
+    let xlsx = patch_parts(xlsx, &patches)?;
+
+    // The workbook keeps its theme, properties and defined names, its sheets are replaced
+    let mut rebuilt = reader::xlsx::read_reader(Cursor::new(xlsx), false).map_err(|e| format!("{:?}", e))?;
+    while !rebuilt.get_sheet_collection_no_check().is_empty() {
+        rebuilt.remove_sheet(0)?;
+    }
+    let sheets: Vec<Worksheet> = std::mem::take(model.get_sheet_collection_mut()).into_iter().collect();
+    for worksheet in sheets {
+        rebuilt.add_sheet(worksheet)?;
+    }
+    Ok(rebuilt)
+}
+
+/// Removes the defined names that point nowhere anymore: to #REF! or to sheets that do not exist
+///
+/// :return: The names removed, prefixed with their sheet for the names local to a sheet.
+pub fn remove_broken_names(spreadsheet: &mut Spreadsheet) -> Vec<String> {
+    let sheet_names: Vec<String> = spreadsheet.get_sheet_collection_no_check().iter().map(|ws| ws.get_name().to_string()).collect();
+    let mut removed = Vec::new();
+
+    for worksheet in spreadsheet.get_sheet_collection_mut().iter_mut() {
+        let sheet = worksheet.get_name().to_string();
+        worksheet.get_defined_names_mut().retain(|defined_name| match check_formula(&defined_name.get_address(), &sheet_names) {
+            Some(reason) => {
+                debug!("Defined name {} of {} removed: {}", defined_name.get_name(), sheet, reason);
+                removed.push(format!("{}!{}", sheet, defined_name.get_name()));
+                false
+            },
+            None => true,
+        });
+    }
+    spreadsheet.get_defined_names_mut().retain(|defined_name| match check_formula(&defined_name.get_address(), &sheet_names) {
+        Some(reason) => {
+            debug!("Defined name {} removed: {}", defined_name.get_name(), reason);
+            removed.push(defined_name.get_name().to_string());
+            false
+        },
+        None => true,
+    });
+    removed
+}
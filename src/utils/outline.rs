@@ -0,0 +1,101 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use umya_spreadsheet::Spreadsheet;
+
+use crate::utils::meta::read_records;
+#[cfg(feature = "dataframe")]
+use crate::utils::meta::append_record;
+use crate::utils::package::{patch_parts, sheet_part};
+
+/// Hidden sheet holding the outline levels of rows, written into the saved files as the writer does not support them
+pub const OUTLINE_SHEET: &str = "_ezxl_outline";
+
+/// Opening tag of a row of a sheet
+static ROW_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r#"<row r="(\d+)""#).unwrap());
+
+/// Rows of a sheet grouped at an outline level, so they can be collapsed
+#[derive(Debug, Clone)]
+pub struct RowGroup {
+    pub sheet: String,
+    pub first_row: u32,
+    pub last_row: u32,
+    pub level: u8,
+}
+
+impl RowGroup {
+    #[cfg(feature = "dataframe")]
+    pub fn to_record(&self) -> Vec<String> {
+        vec![self.sheet.clone(), self.first_row.to_string(), self.last_row.to_string(), self.level.to_string()]
+    }
+
+    pub fn from_record(record: &[String]) -> Option<Self> {
+        match record {
+            [sheet, first_row, last_row, level, ..] => Some(RowGroup {
+                sheet: sheet.clone(),
+                first_row: first_row.parse().ok()?,
+                last_row: last_row.parse().ok()?,
+                level: level.parse().ok()?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Groups rows of a sheet, kept in the hidden outline sheet until the file is written
+#[cfg(feature = "dataframe")]
+pub fn add_row_group(spreadsheet: &mut Spreadsheet, group: &RowGroup) -> Result<(), String> {
+    let worksheet = spreadsheet.get_sheet_by_name_mut(&group.sheet)
+        .ok_or_else(|| format!("Sheet '{}' not found.", group.sheet))?;
+    let properties = worksheet.get_sheet_format_properties_mut();
+    if *properties.get_outline_level_row() < group.level {
+        properties.set_outline_level_row(group.level);
+    }
+    append_record(spreadsheet, OUTLINE_SHEET, &group.to_record())?;
+    debug!("Rows {} to {} of {} grouped at level {}", group.first_row, group.last_row, group.sheet, group.level);
+    Ok(())
+}
+
+/// Reads the row groups of the workbook
+pub fn read_row_groups(spreadsheet: &Spreadsheet) -> Vec<RowGroup> {
+    read_records(spreadsheet, OUTLINE_SHEET).iter()
+        .filter_map(|record| RowGroup::from_record(record))
+        .collect()
+}
+
+/// Sets the outline level of the rows of a sheet XML, the highest level of a row winning
+fn outline_rows(sheet_xml: &str, levels: &HashMap<u32, u8>) -> String {
+    ROW_TAG.replace_all(sheet_xml, |caps: &Captures| {
+        let row: u32 = caps[1].parse().unwrap_or(0);
+        match levels.get(&row) {
+            Some(level) => format!("{} outlineLevel=\"{}\"", &caps[0], level),
+            None => caps[0].to_string(),
+        }
+    }).into_owned()
+}
+
+/// Writes the outline levels of rows into a workbook, given as the bytes of the xlsx file
+///
+/// :param sheet_indexes: The position of each sheet name in the workbook, as written.
+pub fn write_row_outlines(xlsx: Vec<u8>, sheet_indexes: &HashMap<String, usize>, groups: &[RowGroup]) -> Result<Vec<u8>, String> {
+    let mut by_part: HashMap<String, HashMap<u32, u8>> = HashMap::new();
+    for group in groups {
+        match sheet_indexes.get(&group.sheet) {
+            Some(index) => {
+                let levels = by_part.entry(sheet_part(*index)).or_default();
+                for row in group.first_row..=group.last_row {
+                    let level = levels.entry(row).or_default();
+                    *level = (*level).max(group.level);
+                }
+            },
+            None => debug!("Row group in {} left out with its sheet", group.sheet),
+        }
+    }
+
+    let patches: HashMap<String, _> = by_part.into_iter()
+        .map(|(part, levels)| (part, move |sheet_xml: &str| Ok(outline_rows(sheet_xml, &levels))))
+        .collect();
+    patch_parts(xlsx, &patches)
+}
@@ -0,0 +1,122 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Write};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Name of the part of the package holding the sheet at a position of the workbook
+pub fn sheet_part(index: usize) -> String {
+    format!("xl/worksheets/sheet{}.xml", index + 1)
+}
+
+/// Escapes the characters of a text that are special in XML
+#[cfg(feature = "charts")]
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Rewrites some XML parts of a workbook, given as the bytes of the xlsx file, the others being copied as is
+///
+/// :param patches: The function rewriting each part to change, by part name.
+pub fn patch_parts<F>(xlsx: Vec<u8>, patches: &HashMap<String, F>) -> Result<Vec<u8>, String>
+where
+    F: Fn(&str) -> Result<String, String>,
+{
+    if patches.is_empty() {
+        return Ok(xlsx);
+    }
+
+    let mut archive = ZipArchive::new(Cursor::new(xlsx)).map_err(|e| e.to_string())?;
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).map_err(|e| e.to_string())?;
+        match patches.get(file.name()) {
+            Some(patch) => {
+                let name = file.name().to_string();
+                let mut xml = String::new();
+                file.read_to_string(&mut xml).map_err(|e| e.to_string())?;
+                let xml = patch(&xml)?;
+                writer.start_file(name.as_str(), options).map_err(|e| e.to_string())?;
+                writer.write_all(xml.as_bytes()).map_err(|e| e.to_string())?;
+                debug!("{} rewritten", name);
+            },
+            None => writer.raw_copy_file(file).map_err(|e| e.to_string())?,
+        }
+    }
+    Ok(writer.finish().map_err(|e| e.to_string())?.into_inner())
+}
+
+/// Counter of the temporary files, for the saves of several threads
+static TEMPORARY_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// Creates a temporary file in a directory, under a name no other file has (`.{prefix}.{pid}.{n}.tmp`)
+///
+/// The file is created with `create_new`, so that two saves, of two threads or two processes, never share one.
+pub fn create_temporary_file(directory: &Path, prefix: &str) -> std::io::Result<(PathBuf, File)> {
+    loop {
+        let temporary = directory.join(format!(
+            ".{}.{}.{}.tmp", prefix, std::process::id(), TEMPORARY_FILES.fetch_add(1, Ordering::Relaxed)
+        ));
+        match std::fs::OpenOptions::new().write(true).create_new(true).open(&temporary) {
+            Ok(file) => return Ok((temporary, file)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Writes the bytes of a file atomically: to a temporary file next to it, then renamed over it
+///
+/// A process dying mid-write leaves the previous file whole. With `backup`, the previous file, if any,
+/// is first copied to the same path with `.bak` appended (e.g. report.xlsx.bak).
+pub fn write_atomically(path: &Path, bytes: &[u8], backup: bool) -> std::io::Result<()> {
+    let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let directory = path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+    let (temporary, mut file) = create_temporary_file(directory, &file_name)?;
+    let written = file.write_all(bytes).and_then(|_| file.sync_all());
+    drop(file);
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&temporary);
+        return Err(e);
+    }
+    if backup && path.exists() {
+        let backup_path = path.with_file_name(format!("{}.bak", file_name));
+        if let Err(e) = std::fs::copy(path, &backup_path) {
+            let _ = std::fs::remove_file(&temporary);
+            return Err(e);
+        }
+        debug!("Previous version of {} kept as {}", path.display(), backup_path.display());
+    }
+    std::fs::rename(&temporary, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temporary);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concurrent_saves_never_share_a_temporary_file() {
+        let directory = std::env::temp_dir().join(format!("ezxl-package-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("report.xlsx");
+        let contents: Vec<Vec<u8>> = (0..8u8).map(|i| vec![i; 1 << 16]).collect();
+        std::thread::scope(|scope| {
+            for bytes in &contents {
+                let path = &path;
+                scope.spawn(move || write_atomically(path, bytes, false).unwrap());
+            }
+        });
+        let written = std::fs::read(&path).unwrap();
+        assert!(contents.contains(&written), "The saved file mixes the bytes of several saves");
+        let left: Vec<_> = std::fs::read_dir(&directory).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+        assert_eq!(left, vec![std::ffi::OsString::from("report.xlsx")], "Temporary files left behind");
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
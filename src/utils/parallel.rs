@@ -0,0 +1,30 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+/// Reads files in worker threads, the GIL being released meanwhile so that Python threads keep running
+///
+/// At most `max_workers` files are read at once (default: one per core), and the results come in the
+/// order of the files whatever the order they are read in.
+pub fn read_files<T, F>(py: Python, files: &[String], max_workers: Option<usize>, read: F) -> PyResult<Vec<T>>
+where
+    T: Send,
+    F: Fn(&str) -> T + Send + Sync,
+{
+    let pool = match max_workers {
+        Some(0) => return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("max_workers must be at least 1.")),
+        Some(max_workers) => Some(rayon::ThreadPoolBuilder::new().num_threads(max_workers).build().map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Failed to start the worker threads: {}", e))
+        })?),
+        None => None,
+    };
+    debug!("Reading {} files with {} workers", files.len(), pool.as_ref().map_or_else(rayon::current_num_threads, |pool| pool.current_num_threads()));
+    Ok(py.allow_threads(|| {
+        let read_all = || -> Vec<T> { files.par_iter().map(|file_path| read(file_path)).collect() };
+        match &pool {
+            Some(pool) => pool.install(read_all),
+            None => read_all(),
+        }
+    }))
+}
@@ -0,0 +1,116 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use umya_spreadsheet::custom_properties::CustomDocumentProperty;
+use umya_spreadsheet::Spreadsheet;
+
+use crate::structs::{CustomProperty, PropertyDate};
+
+/// Core and custom properties of a workbook, as File > Info shows them in Excel
+#[derive(Debug, Clone, Default)]
+pub struct DocumentProperties {
+    pub title: String,
+    pub subject: String,
+    pub author: String,
+    pub last_modified_by: String,
+    pub company: String,
+    pub manager: String,
+    pub category: String,
+    pub keywords: String,
+    pub description: String,
+    pub created: String,
+    pub modified: String,
+    pub custom: Vec<(String, CustomProperty)>,
+}
+
+impl IntoPy<PyObject> for DocumentProperties {
+    fn into_py(self, py: Python) -> PyObject {
+        let properties = PyDict::new(py);
+        let custom = PyDict::new(py);
+        // Set items can only fail on unhashable keys, which string keys are not
+        for (name, value) in self.custom {
+            let _ = custom.set_item(name, value.into_py(py));
+        }
+        let _ = properties.set_item("title", self.title);
+        let _ = properties.set_item("subject", self.subject);
+        let _ = properties.set_item("author", self.author);
+        let _ = properties.set_item("last_modified_by", self.last_modified_by);
+        let _ = properties.set_item("company", self.company);
+        let _ = properties.set_item("manager", self.manager);
+        let _ = properties.set_item("category", self.category);
+        let _ = properties.set_item("keywords", self.keywords);
+        let _ = properties.set_item("description", self.description);
+        let _ = properties.set_item("created", self.created);
+        let _ = properties.set_item("modified", self.modified);
+        let _ = properties.set_item("custom", custom);
+        properties.into()
+    }
+}
+
+/// Value of a custom property as it was stored
+fn custom_value(property: &CustomDocumentProperty) -> CustomProperty {
+    if let Some(number) = property.get_value_number() {
+        return CustomProperty::Integer(number);
+    }
+    if let Some(boolean) = property.get_value_bool() {
+        return CustomProperty::Boolean(boolean);
+    }
+    // umya keeps the type of the other values private, their debug output tells dates apart
+    let value = property.get_value().to_string();
+    if format!("{:?}", property).contains("custom_document_property_value: Date(") {
+        CustomProperty::Date(PropertyDate(value))
+    } else {
+        CustomProperty::Text(value)
+    }
+}
+
+/// Returns the core and custom properties of a workbook, the custom ones in the order of the file
+pub fn read_properties(spreadsheet: &Spreadsheet) -> DocumentProperties {
+    let properties = spreadsheet.get_properties();
+    DocumentProperties {
+        title: properties.get_title().to_string(),
+        subject: properties.get_subject().to_string(),
+        author: properties.get_creator().to_string(),
+        last_modified_by: properties.get_last_modified_by().to_string(),
+        company: properties.get_company().to_string(),
+        manager: properties.get_manager().to_string(),
+        category: properties.get_category().to_string(),
+        keywords: properties.get_keywords().to_string(),
+        description: properties.get_description().to_string(),
+        created: properties.get_created().to_string(),
+        modified: properties.get_modified().to_string(),
+        custom: properties.get_custom_properties().get_custom_document_property_list().iter()
+            .map(|property| (property.get_name().to_string(), custom_value(property)))
+            .collect(),
+    }
+}
+
+/// Sets a custom property of a workbook, replacing the one of the same name, or removes it without a value
+pub fn set_custom_property(spreadsheet: &mut Spreadsheet, name: &str, value: Option<&CustomProperty>) {
+    let list = spreadsheet.get_properties_mut().get_custom_properties_mut().get_custom_document_property_list_mut();
+    let position = list.iter().position(|property| property.get_name() == name);
+    let value = match value {
+        Some(value) => value,
+        None => {
+            if let Some(position) = position {
+                list.remove(position);
+                debug!("Custom property {} removed", name);
+            }
+            return;
+        },
+    };
+    let mut property = CustomDocumentProperty::default();
+    property.set_name(name);
+    match value {
+        CustomProperty::Text(text) => property.set_value_string(text),
+        CustomProperty::Integer(number) => property.set_value_number(*number),
+        CustomProperty::Boolean(boolean) => property.set_value_bool(*boolean),
+        CustomProperty::Date(date) => property.set_value_date_manual(&date.0),
+    };
+    match position {
+        Some(position) => list[position] = property,
+        None => list.push(property),
+    }
+    debug!("Custom property {} set to {:?}", name, value);
+}
@@ -1,10 +1,14 @@
 #[allow(unused_imports)]
 use log::{debug, info, warn, error};
 use pyo3::prelude::*;
-use pyo3::types::PyAny;
+use pyo3::types::{PyAny, PyCapsule};
 use pyo3::exceptions::{PyImportError, PyRuntimeError, PyTypeError, PyValueError};
 use pyo3::{PyErr, PyTypeInfo};
 use polars::prelude::*;
+use polars::export::arrow::array::{new_empty_array, Array, StructArray};
+use polars::export::arrow::datatypes::ArrowDataType;
+use polars::export::arrow::ffi::{ArrowArrayStream, ArrowArrayStreamReader};
+use chrono::{NaiveDate, NaiveDateTime};
 use std::collections::HashMap;
 use std::fmt;
 
@@ -22,6 +26,7 @@ pub fn convert_anyvalue_to_string(value: AnyValue) -> String {
     match value {
         AnyValue::Null => "".to_string(),        // For null values, return an empty string
         AnyValue::String(val) => val.to_string(),
+        AnyValue::StringOwned(val) => val.to_string(),
         AnyValue::Int32(val) => val.to_string(),
         AnyValue::Int64(val) => val.to_string(),
         AnyValue::Float64(val) => val.to_string(),
@@ -30,53 +35,107 @@ pub fn convert_anyvalue_to_string(value: AnyValue) -> String {
     }
 }
 
+/// Import the Arrow data of a Python object as a Rust Polars DataFrame, without copying its buffers.
+///
+/// The object exports an Arrow C stream through the Arrow PyCapsule interface (`__arrow_c_stream__`),
+/// as Polars DataFrames, pyarrow tables and other Arrow libraries do. The record batches of the stream
+/// become the chunks of the columns, their buffers being shared rather than copied.
+///
+/// :param py_df: The Python object to import.
+/// :return: A Rust Polars DataFrame, or None if the object does not export an Arrow stream.
+fn arrow_stream_to_rust_polars_df(py_df: &PyAny) -> PyResult<Option<DataFrame>> {
+    if !py_df.hasattr("__arrow_c_stream__")? {
+        return Ok(None);
+    }
+    let capsule: &PyCapsule = py_df.call_method0("__arrow_c_stream__")?.downcast().map_err(|_| {
+        py_err::<PyTypeError>("__arrow_c_stream__ did not return a PyCapsule.".to_string())
+    })?;
+    if capsule.name()?.is_none_or(|name| name.to_bytes() != b"arrow_array_stream") {
+        return Err(py_err::<PyTypeError>("__arrow_c_stream__ did not return an Arrow stream capsule.".to_string()));
+    }
+
+    // The stream is moved out of the capsule, which then holds a released stream its destructor leaves alone
+    let stream = unsafe { std::ptr::replace(capsule.pointer() as *mut ArrowArrayStream, ArrowArrayStream::empty()) };
+    let import_error = |e: PolarsError| py_err::<PyRuntimeError>(format!("Failed to import the Arrow stream: {}.", e));
+    let mut reader = unsafe { ArrowArrayStreamReader::try_new(Box::new(stream)) }.map_err(import_error)?;
+    let fields = match reader.field().dtype() {
+        ArrowDataType::Struct(fields) => fields.clone(),
+        dtype => return Err(py_err::<PyTypeError>(format!("The Arrow stream holds {:?} rather than record batches.", dtype))),
+    };
+
+    let mut chunks: Vec<Vec<Box<dyn Array>>> = vec![Vec::new(); fields.len()];
+    while let Some(batch) = unsafe { reader.next() } {
+        let batch = batch.map_err(import_error)?;
+        let batch = batch.as_any().downcast_ref::<StructArray>().ok_or_else(|| {
+            py_err::<PyTypeError>("The Arrow stream holds a batch that is not a record batch.".to_string())
+        })?;
+        for (column, values) in chunks.iter_mut().zip(batch.values()) {
+            column.push(values.clone());
+        }
+    }
+    let columns = fields.iter().zip(chunks).map(|(field, mut column)| {
+        if column.is_empty() {
+            column.push(new_empty_array(field.dtype().clone()));
+        }
+        <Series as std::convert::TryFrom<_>>::try_from((field, column))
+    }).collect::<PolarsResult<Vec<Series>>>().map_err(import_error)?;
+    let df = DataFrame::new(columns).map_err(import_error)?;
+    debug!("{} rows of {} columns imported from the Arrow stream", df.height(), df.width());
+    Ok(Some(df))
+}
+
 /// Convert a Python Polars DataFrame to a Rust Polars DataFrame.
 ///
-/// This function serializes a Python Polars DataFrame into Arrow format using `pyarrow`
-/// and deserializes it back into a Rust Polars DataFrame using Polars' `IpcReader`.
+/// The DataFrame is imported through the Arrow PyCapsule interface, without copying it. Older Polars
+/// versions, which do not export it, are serialized into Arrow format using `pyarrow` and deserialized
+/// back into a Rust Polars DataFrame using Polars' `IpcReader`.
 ///
 /// :param py: The Python interpreter instance.
 /// :param py_df: The Python Polars DataFrame to convert.
 /// :return: A Rust Polars DataFrame.
 fn py_polars_df_to_rust_polars_df(py: Python, py_df: &PyAny) -> PyResult<DataFrame> {
+    if let Some(df) = arrow_stream_to_rust_polars_df(py_df)? {
+        return Ok(df);
+    }
+
     let pyarrow: &PyModule = py.import("pyarrow").map_err(|_| {
-        py_err::<PyImportError>(format!("Failed to import pyarrow module."))
+        py_err::<PyImportError>("Failed to import pyarrow module.".to_string())
     })?;
 
     let arrow_table: &PyAny = py_df.call_method0("to_arrow").map_err(|_| {
-        py_err::<PyRuntimeError>(format!("Failed to convert DataFrame to Arrow format."))
+        py_err::<PyRuntimeError>("Failed to convert DataFrame to Arrow format.".to_string())
     })?;
 
     // Create an in-memory output stream
     let buffer: &PyAny = pyarrow.call_method0("BufferOutputStream").map_err(|_| {
-        py_err::<PyRuntimeError>(format!("Failed to create buffer stream."))
+        py_err::<PyRuntimeError>("Failed to create buffer stream.".to_string())
     })?;
 
     // Use RecordBatchFileWriter to serialize the Arrow table into the buffer
     let writer: &PyAny = pyarrow
         .call_method1("RecordBatchFileWriter", (buffer, arrow_table.getattr("schema").unwrap()))
         .map_err(|_| {
-            py_err::<PyRuntimeError>(format!("Failed to create Arrow RecordBatchFileWriter."))
+            py_err::<PyRuntimeError>("Failed to create Arrow RecordBatchFileWriter.".to_string())
         })?;
     writer.call_method1("write_table", (arrow_table,)).map_err(|_| {
-            py_err::<PyRuntimeError>(format!("Failed to write Arrow table."))
+            py_err::<PyRuntimeError>("Failed to write Arrow table.".to_string())
         })?;
     writer.call_method0("close").map_err(|_| {
-        py_err::<PyRuntimeError>(format!("Failed to close Arrow writer."))
+        py_err::<PyRuntimeError>("Failed to close Arrow writer.".to_string())
     })?;
 
     // Extract the buffer's contents as bytes
     let buffer_bytes: Vec<u8> = buffer
         .call_method0("getvalue")
-        .map_err(|_| py_err::<PyRuntimeError>(format!("Failed to extract buffer.")))?
+        .map_err(|_| py_err::<PyRuntimeError>("Failed to extract buffer.".to_string()))?
         .extract()
-        .map_err(|_| py_err::<PyRuntimeError>(format!("Failed to extract buffer bytes.")))?;
+        .map_err(|_| py_err::<PyRuntimeError>("Failed to extract buffer bytes.".to_string()))?;
 
     // Deserialize into Rust Polars DataFrame using IpcReader
     let cursor = std::io::Cursor::new(buffer_bytes);
     IpcReader::new(cursor)
         .finish()
-        .map_err(|_| py_err::<PyRuntimeError>(format!("Failed to deserialize Arrow data.")))
+        .map_err(|_| py_err::<PyRuntimeError>("Failed to deserialize Arrow data.".to_string()))
 }
 
 /// Convert a Pandas DataFrame to a Polars DataFrame in Rust.
@@ -89,26 +148,56 @@ fn py_polars_df_to_rust_polars_df(py: Python, py_df: &PyAny) -> PyResult<DataFra
 /// :return: A Rust Polars DataFrame.
 fn py_pandas_df_to_rust_polars_df(py: Python, df: &PyAny) -> PyResult<DataFrame> {
     let polars: &PyModule = py.import("polars").map_err(|_| {
-        py_err::<PyImportError>(format!("Failed to import polars module."))
+        py_err::<PyImportError>("Failed to import polars module.".to_string())
     })?;
     let df_polars: &PyAny = polars.call_method1("DataFrame", (df,)).map_err(|_| {
-        py_err::<PyTypeError>(format!("Failed to convert Pandas DataFrame to Polars."))
+        py_err::<PyTypeError>("Failed to convert Pandas DataFrame to Polars.".to_string())
     })?;
     py_polars_df_to_rust_polars_df(py, df_polars)
 }
 
+/// Temporal types of Python values
+enum Temporal {
+    Date,
+    Datetime,
+}
+
+/// Tells whether a Python value is a datetime or a date, datetimes being dates too
+fn temporal_kind(py: Python, value: &PyAny) -> PyResult<Option<Temporal>> {
+    let datetime = py.import("datetime")?;
+    if value.is_instance(datetime.getattr("datetime")?.downcast()?)? {
+        Ok(Some(Temporal::Datetime))
+    } else if value.is_instance(datetime.getattr("date")?.downcast()?)? {
+        Ok(Some(Temporal::Date))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Reads a Python date (or the date of a datetime) from its attributes
+fn extract_naive_date(value: &PyAny) -> Option<NaiveDate> {
+    let part = |attribute: &str| value.getattr(attribute).ok()?.extract::<u32>().ok();
+    NaiveDate::from_ymd_opt(value.getattr("year").ok()?.extract().ok()?, part("month")?, part("day")?)
+}
+
+/// Reads a Python datetime from its attributes, as the naive datetime it shows
+fn extract_naive_datetime(value: &PyAny) -> Option<NaiveDateTime> {
+    let part = |attribute: &str| value.getattr(attribute).ok()?.extract::<u32>().ok();
+    extract_naive_date(value)?.and_hms_micro_opt(part("hour")?, part("minute")?, part("second")?, part("microsecond")?)
+}
+
 /// Extracts a Polars Series from a vector of optional Python objects.
 ///
 /// This function takes a vector of optional Python objects and infers the type of the first
 /// non-None value to determine the appropriate Rust type for the Series. It handles
-/// String, integer, float, and boolean types, returning a Series containing the extracted values.
+/// String, date, datetime, integer, float, and boolean types, returning a Series containing the extracted values.
 ///
 /// :param py: The Python interpreter instance.
 /// :param column: A vector of optional PyObject values representing the column data.
 /// :param name: The name of the Series to be created.
 /// :param max_column_len: The maximum length of the column, used to ensure consistent Series length.
 /// :return: A PyResult containing the constructed Series or an error if the type is unsupported.
-fn extract_series_from_vec_of_optional_py_objects(py: Python, column: &Vec<Option<PyObject>>, name: &str, max_column_len: usize) -> PyResult<Series> {
+fn extract_series_from_vec_of_optional_py_objects(py: Python, column: &[Option<PyObject>], name: &str, max_column_len: usize) -> PyResult<Series> {
     // Find the first non-null value to infer the column type
     let first_non_null = column.iter().flatten().next(); // Find the first non-None value
 
@@ -122,16 +211,39 @@ fn extract_series_from_vec_of_optional_py_objects(py: Python, column: &Vec<Optio
                         .and_then(|val| val.as_ref().and_then(|v| v.extract::<Option<String>>(py).ok()).flatten())
                 })
                 .collect();
-            return Ok(Series::new(name.into(), extracted_values));
+            Ok(Series::new(name.into(), extracted_values))
+        } else if let Some(kind) = temporal_kind(py, first_value)? {
+            // Handle dates and datetimes of the datetime module
+            match kind {
+                Temporal::Datetime => {
+                    let extracted_values = (0..max_column_len)
+                        .map(|idx| column.get(idx).and_then(|val| val.as_ref().and_then(|v| extract_naive_datetime(v.as_ref(py)))));
+                    Ok(DatetimeChunked::from_naive_datetime_options(name.into(), extracted_values, TimeUnit::Microseconds).into_series())
+                },
+                Temporal::Date => {
+                    let extracted_values = (0..max_column_len)
+                        .map(|idx| column.get(idx).and_then(|val| val.as_ref().and_then(|v| extract_naive_date(v.as_ref(py)))));
+                    Ok(DateChunked::from_naive_date_options(name.into(), extracted_values).into_series())
+                },
+            }
+        } else if first_value.is_instance(py.get_type::<pyo3::types::PyBool>())? {
+            // Handle boolean type, before integers as Python booleans are integers
+            let extracted_values: Vec<Option<bool>> = (0..max_column_len)
+                .map(|idx| {
+                    column.get(idx)
+                        .and_then(|val| val.as_ref().and_then(|v| v.extract::<Option<bool>>(py).ok()).flatten())
+                })
+                .collect();
+            Ok(Series::new(name.into(), extracted_values))
         } else if first_value.is_instance(py.get_type::<pyo3::types::PyInt>())? {
             // Handle integer type
-            let extracted_values: Vec<Option<i32>> = (0..max_column_len)
+            let extracted_values: Vec<Option<i64>> = (0..max_column_len)
                 .map(|idx| {
                     column.get(idx)
-                        .and_then(|val| val.as_ref().and_then(|v| v.extract::<Option<i32>>(py).ok()).flatten())
+                        .and_then(|val| val.as_ref().and_then(|v| v.extract::<Option<i64>>(py).ok()).flatten())
                 })
                 .collect();
-            return Ok(Series::new(name.into(), extracted_values));
+            Ok(Series::new(name.into(), extracted_values))
         } else if first_value.is_instance(py.get_type::<pyo3::types::PyFloat>())? {
             // Handle float type
             let extracted_values: Vec<Option<f64>> = (0..max_column_len)
@@ -140,18 +252,9 @@ fn extract_series_from_vec_of_optional_py_objects(py: Python, column: &Vec<Optio
                         .and_then(|val| val.as_ref().and_then(|v| v.extract::<Option<f64>>(py).ok()).flatten())
                 })
                 .collect();
-            return Ok(Series::new(name.into(), extracted_values));
-        } else if first_value.is_instance(py.get_type::<pyo3::types::PyBool>())? {
-            // Handle boolean type
-            let extracted_values: Vec<Option<bool>> = (0..max_column_len)
-                .map(|idx| {
-                    column.get(idx)
-                        .and_then(|val| val.as_ref().and_then(|v| v.extract::<Option<bool>>(py).ok()).flatten())
-                })
-                .collect();
-            return Ok(Series::new(name.into(), extracted_values));
+            Ok(Series::new(name.into(), extracted_values))
         } else {
-            Err(py_err::<PyTypeError>(format!("Unsupported value type in column")))
+            Err(py_err::<PyTypeError>("Unsupported value type in column".to_string()))
         }
     } else {
         Err(py_err::<PyTypeError>(format!("Column '{}' contains only None values or is empty", name)))
@@ -169,20 +272,20 @@ fn extract_series_from_vec_of_optional_py_objects(py: Python, column: &Vec<Optio
 fn py_dict_of_lists_to_rust_polars_df(py: Python, dict_of_lists: &PyAny) -> PyResult<DataFrame> {
     // Check if df is a HashMap<String, Vec<Option<PyObject>>>
     let dict_of_lists: HashMap<String, Vec<Option<PyObject>>> = dict_of_lists.extract().map_err(|_| {
-        py_err::<PyTypeError>(format!("Structure of dictionary of lists is not correct."))
+        py_err::<PyTypeError>("Structure of dictionary of lists is not correct.".to_string())
     })?;
 
     // Create a vector to store the columns
     let mut columns: Vec<Series> = Vec::with_capacity(dict_of_lists.len());
 
     // Determine the maximum column length (since empty lists may exist)
-    let max_column_len = dict_of_lists.iter().map(|(_, values)| values.len()).max().unwrap_or(0);
+    let max_column_len = dict_of_lists.values().map(|values| values.len()).max().unwrap_or(0);
 
     // Iterate over each key-value pair in the dictionary
     for (name, values) in dict_of_lists {
         //
         if values.len() != max_column_len {
-            return Err(py_err::<PyValueError>(format!("At least one list in the dictionary of lists has a different length than the others.")));
+            return Err(py_err::<PyValueError>("At least one list in the dictionary of lists has a different length than the others.".to_string()));
         }
 
         // Extract the series from the list of optional PyObject
@@ -208,17 +311,17 @@ fn py_dict_of_lists_to_rust_polars_df(py: Python, dict_of_lists: &PyAny) -> PyRe
 fn py_list_of_lists_to_rust_polars_df(py: Python, list_of_lists: &PyAny, columns: &PyAny) -> PyResult<DataFrame> {
     // Extract column names from the Python list
     let columns: Vec<String> = columns.extract().map_err(|_| {
-        py_err::<PyTypeError>(format!("List of columns is not correct."))
+        py_err::<PyTypeError>("List of columns is not correct.".to_string())
     })?;
     
     // Extract the list of lists from Python
     let list_of_lists: Vec<Vec<Option<PyObject>>> = list_of_lists.extract().map_err(|_| {
-        py_err::<PyTypeError>(format!("Structure of list of lists is not correct."))
+        py_err::<PyTypeError>("Structure of list of lists is not correct.".to_string())
     })?;
     
     // Check if the number of columns and number of lists match
     if columns.len() != list_of_lists.len() {
-        return Err(py_err::<PyValueError>(format!("List of columns and list of lists have different lengths.")))
+        return Err(py_err::<PyValueError>("List of columns and list of lists have different lengths.".to_string()))
         }
 
     // Create a vector to store the columns
@@ -239,11 +342,11 @@ fn py_list_of_lists_to_rust_polars_df(py: Python, list_of_lists: &PyAny, columns
 
         // Check if the length of the current list matches the max length
         else if values.len() != max_column_len {
-            return Err(py_err::<PyValueError>(format!("At least one list in the list of lists has a different length than the others.")));
+            return Err(py_err::<PyValueError>("At least one list in the list of lists has a different length than the others.".to_string()));
         }
 
         // Convert the list of optional PyObject values into a Polars Series
-        let series = extract_series_from_vec_of_optional_py_objects(py, &values, name.as_str(), max_column_len)?;
+        let series = extract_series_from_vec_of_optional_py_objects(py, values, name.as_str(), max_column_len)?;
         df_columns.push(series);
     }
 
@@ -278,6 +381,7 @@ pub enum OriginalDataType {
     Polars,
     DictionaryOfLists,
     ListOfLists,
+    Arrow,
 }
 
 impl fmt::Display for OriginalDataType {
@@ -287,6 +391,7 @@ impl fmt::Display for OriginalDataType {
             OriginalDataType::Polars => write!(f, "Polars DataFrame"),
             OriginalDataType::DictionaryOfLists => write!(f, "Dictionary of Lists"),
             OriginalDataType::ListOfLists => write!(f, "List of Lists"),
+            OriginalDataType::Arrow => write!(f, "Arrow table"),
         }
     }
 }
@@ -316,8 +421,11 @@ pub fn get_datatype(py: Python, df: &PyAny) -> PyResult<OriginalDataType> {
     } else if df.is_instance(py.get_type::<pyo3::types::PyList>())? {
         debug!("List of lists found");
         Ok(OriginalDataType::ListOfLists)
+    } else if df.hasattr("__arrow_c_stream__")? {
+        debug!("Arrow table found");
+        Ok(OriginalDataType::Arrow)
     } else {
-        let err_msg = format!("Input must be a Pandas or Polars DataFrame, dictionary of lists or list of lists with column names.");
+        let err_msg = "Input must be a Pandas or Polars DataFrame, an Arrow table, dictionary of lists or list of lists with column names.".to_string();
         error!("{}", err_msg);
         Err(py_err::<PyTypeError>(err_msg))
     }
@@ -347,15 +455,19 @@ pub fn convert(py: Python, data_type: OriginalDataType, df: &PyAny, columns: Opt
             // Convert Python Polars DataFrame to Rust Polars DataFrame
             Ok(py_polars_df_to_rust_polars_df(py, df)?)
         },
+        (OriginalDataType::Arrow, None) => {
+            // Import a pyarrow table, or the data of any library exporting an Arrow stream, without copying it
+            arrow_stream_to_rust_polars_df(df)?.ok_or_else(|| py_err::<PyTypeError>("The Arrow table exports no Arrow stream.".to_string()))
+        },
         (OriginalDataType::DictionaryOfLists, None) => {
             // Convert Dict[str, List[Any]] to Polars DataFrame
             Ok(py_dict_of_lists_to_rust_polars_df(py, df)?)
         },
         (_, Some(_)) => {
-            Err(py_err::<PyValueError>(format!("Column names should not be provided for Pandas, Polars and Dict of Lists.")))
+            Err(py_err::<PyValueError>("Column names should not be provided for Pandas, Polars, Arrow tables and Dict of Lists.".to_string()))
         },
         (OriginalDataType::ListOfLists, None) => {
-            Err(py_err::<PyValueError>(format!("Column names must be provided for List of Lists.")))
+            Err(py_err::<PyValueError>("Column names must be provided for List of Lists.".to_string()))
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,413 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use regex::{Captures, Regex};
+use umya_spreadsheet::Spreadsheet;
+
+use crate::utils::excel::{excel_col_to_index, index_to_excel_col, parse_excel_cell};
+
+/// Marker Excel writes in place of a reference to a deleted area
+const REF_ERROR: &str = "#REF!";
+
+/// Cell or range reference, optionally prefixed with a sheet name (quoted or not)
+static REFERENCE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:'((?:[^']|'')+)'!|([A-Za-z_][A-Za-z0-9_.]*)!)?\$?[A-Z]{1,3}(\$?)(\d+)(?::\$?[A-Z]{1,3}(\$?)(\d+))?").unwrap()
+});
+
+/// Cell or range reference with the anchors ($) and the column and row of each cell
+static ANCHORED_REFERENCE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"((?:'(?:[^']|'')+'|[A-Za-z_][A-Za-z0-9_.]*)!)?(\$?)([A-Z]{1,3})(\$?)(\d+)(?::(\$?)([A-Z]{1,3})(\$?)(\d+))?").unwrap()
+});
+
+/// Sheet qualifier of a reference, quoted or not ('My data'!, Data!)
+static SHEET_QUALIFIER: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:'((?:[^']|'')+)'|([A-Za-z_][A-Za-z0-9_.]*))!").unwrap()
+});
+
+/// Last column of an Excel sheet (XFD)
+const MAX_COLUMN: i64 = 16_384;
+
+/// Last row of an Excel sheet
+const MAX_ROW: i64 = 1_048_576;
+
+/// Reference of a formula that is broken, in a cell or in a defined name
+#[derive(Debug, Clone)]
+pub struct BrokenRef {
+    pub sheet: Option<String>,
+    pub cell: Option<String>,
+    pub name: Option<String>,
+    pub formula: String,
+    pub reason: String,
+}
+
+impl IntoPy<PyObject> for BrokenRef {
+    fn into_py(self, py: Python) -> PyObject {
+        let broken = PyDict::new(py);
+        // Set items can only fail on unhashable keys, which string keys are not
+        let _ = broken.set_item("sheet", self.sheet);
+        let _ = broken.set_item("cell", self.cell);
+        let _ = broken.set_item("name", self.name);
+        let _ = broken.set_item("formula", self.formula);
+        let _ = broken.set_item("reason", self.reason);
+        broken.into()
+    }
+}
+
+/// Replaces the references of a formula for which `replace` returns a text, leaving string literals alone
+///
+/// `replace` gets the sheet of the reference (None for the sheet of the formula) and its first and last rows.
+fn map_refs<F>(formula: &str, mut replace: F) -> String
+where
+    F: FnMut(Option<&str>, u32, u32) -> Option<String>,
+{
+    formula.split('"').enumerate()
+        .map(|(i, part)| {
+            // Odd parts are inside string literals
+            if i % 2 == 1 {
+                return part.to_string();
+            }
+            REFERENCE.replace_all(part, |caps: &Captures| {
+                let whole = caps.get(0).unwrap();
+                if !stands_alone(part, whole.start(), whole.end()) {
+                    return whole.as_str().to_string();
+                }
+                let sheet = caps.get(1).map(|m| m.as_str().replace("''", "'")).or_else(|| caps.get(2).map(|m| m.as_str().to_string()));
+                let first_row: u32 = caps[4].parse().unwrap_or(0);
+                let last_row: u32 = caps.get(6).and_then(|m| m.as_str().parse().ok()).unwrap_or(first_row);
+                replace(sheet.as_deref(), first_row.min(last_row), first_row.max(last_row))
+                    .unwrap_or_else(|| whole.as_str().to_string())
+            }).into_owned()
+        })
+        .collect::<Vec<String>>()
+        .join("\"")
+}
+
+/// Whether a match of a reference pattern is a reference: function names (LOG10) and parts of numbers (1E10) are not
+fn stands_alone(text: &str, start: usize, end: usize) -> bool {
+    let before = text[..start].chars().last();
+    let after = text[end..].chars().next();
+    !(before.is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '.') || after.is_some_and(|c| c == '(' || c.is_alphanumeric()))
+}
+
+/// Moves a column or row by an offset unless it is anchored with $, None when it leaves the sheet
+fn translate_part(anchor: &str, index: i64, offset: i64, max: i64) -> Option<i64> {
+    let index = if anchor.is_empty() { index + offset } else { index };
+    (1..=max).contains(&index).then_some(index)
+}
+
+/// Moves the relative references of a formula by an offset of columns and rows, as Excel does when a
+/// formula is pasted elsewhere
+///
+/// Anchored columns and rows ($A, $1) stay, and references moved out of the sheet become #REF!.
+pub fn translate_formula(formula: &str, col_offset: i64, row_offset: i64) -> String {
+    formula.split('"').enumerate()
+        .map(|(i, part)| {
+            // Odd parts are inside string literals
+            if i % 2 == 1 {
+                return part.to_string();
+            }
+            ANCHORED_REFERENCE.replace_all(part, |caps: &Captures| {
+                let whole = caps.get(0).unwrap();
+                if !stands_alone(part, whole.start(), whole.end()) {
+                    return whole.as_str().to_string();
+                }
+                let sheet = caps.get(1).map_or("", |m| m.as_str());
+                let cell = |first: usize| -> Option<String> {
+                    let col = translate_part(&caps[first], excel_col_to_index(&caps[first + 1]) as i64, col_offset, MAX_COLUMN)?;
+                    let row = translate_part(&caps[first + 2], caps[first + 3].parse().ok()?, row_offset, MAX_ROW)?;
+                    Some(format!("{}{}{}{}", &caps[first], index_to_excel_col(col as u32), &caps[first + 2], row))
+                };
+                let translated = match caps.get(6) {
+                    Some(_) => cell(2).zip(cell(6)).map(|(first, last)| format!("{}:{}", first, last)),
+                    None => cell(2),
+                };
+                format!("{}{}", sheet, translated.unwrap_or_else(|| REF_ERROR.to_string()))
+            }).into_owned()
+        })
+        .collect::<Vec<String>>()
+        .join("\"")
+}
+
+/// Whether a formula holds a #REF! outside of its string literals
+fn has_ref_error(formula: &str) -> bool {
+    formula.split('"').step_by(2).any(|part| part.contains(REF_ERROR))
+}
+
+/// Replaces with #REF! the references to rows about to be removed from a sheet, the way Excel does
+///
+/// References entirely within the removed rows would otherwise silently point to the rows below.
+/// :return: The number of formulas changed.
+pub fn break_removed_refs(spreadsheet: &mut Spreadsheet, sheet_name: &str, row: u32, num: u32) -> usize {
+    let last_removed = row + num.saturating_sub(1);
+    let mut changed = 0;
+    for worksheet in spreadsheet.get_sheet_collection_mut() {
+        let own_sheet = worksheet.get_name().eq_ignore_ascii_case(sheet_name);
+        for cell in worksheet.get_cell_collection_mut() {
+            if !cell.is_formula() {
+                continue;
+            }
+            let formula = cell.get_formula().to_string();
+            let rewritten = map_refs(&formula, |sheet, first_row, last_row| {
+                let targets_sheet = sheet.map_or(own_sheet, |s| s.eq_ignore_ascii_case(sheet_name));
+                (targets_sheet && first_row >= row && last_row <= last_removed).then(|| REF_ERROR.to_string())
+            });
+            if rewritten != formula {
+                debug!("Formula {} of {} becomes {}", formula, cell.get_coordinate().get_coordinate(), rewritten);
+                cell.set_formula(rewritten);
+                changed += 1;
+            }
+        }
+    }
+    changed
+}
+
+/// Name of the sheet of a qualifier ('My data'! or Data!), unquoted
+fn qualified_name(qualifier: &str) -> String {
+    let name = qualifier.trim_end_matches('!');
+    name.strip_prefix('\'').and_then(|n| n.strip_suffix('\'')).map_or_else(|| name.to_string(), |n| n.replace("''", "'"))
+}
+
+/// Moves down the references of a formula to the rows of a sheet from `row` on, by `num` rows
+///
+/// Only references qualified with the sheet name are moved, ranges spanning `row` growing, and the ones
+/// moved out of the sheet become #REF!.
+fn shift_sheet_rows(formula: &str, sheet_name: &str, row: u32, num: u32) -> String {
+    formula.split('"').enumerate()
+        .map(|(i, part)| {
+            // Odd parts are inside string literals
+            if i % 2 == 1 {
+                return part.to_string();
+            }
+            ANCHORED_REFERENCE.replace_all(part, |caps: &Captures| {
+                let whole = caps.get(0).unwrap();
+                let sheet = match caps.get(1) {
+                    Some(sheet) if stands_alone(part, whole.start(), whole.end()) => sheet.as_str(),
+                    _ => return whole.as_str().to_string(),
+                };
+                if !qualified_name(sheet).eq_ignore_ascii_case(sheet_name) {
+                    return whole.as_str().to_string();
+                }
+                // Inserted rows move anchored references too
+                let cell = |first: usize| -> Option<String> {
+                    let index: i64 = caps[first + 3].parse().ok()?;
+                    let index = if index >= row as i64 { index + num as i64 } else { index };
+                    (index <= MAX_ROW).then(|| format!("{}{}{}{}", &caps[first], &caps[first + 1], &caps[first + 2], index))
+                };
+                let shifted = match caps.get(6) {
+                    Some(_) => cell(2).zip(cell(6)).map(|(first, last)| format!("{}:{}", first, last)),
+                    None => cell(2),
+                };
+                format!("{}{}", sheet, shifted.unwrap_or_else(|| REF_ERROR.to_string()))
+            }).into_owned()
+        })
+        .collect::<Vec<String>>()
+        .join("\"")
+}
+
+/// Moves down the references of the other sheets to the rows of a sheet from `row` on, the way Excel does
+/// when `num` rows are inserted there
+///
+/// The formulas of the sheet itself are moved by umya along with its rows.
+/// :return: The number of formulas changed.
+pub fn shift_inserted_refs(spreadsheet: &mut Spreadsheet, sheet_name: &str, row: u32, num: u32) -> usize {
+    let mut changed = 0;
+    for worksheet in spreadsheet.get_sheet_collection_mut() {
+        if worksheet.get_name().eq_ignore_ascii_case(sheet_name) {
+            continue;
+        }
+        for cell in worksheet.get_cell_collection_mut() {
+            if !cell.is_formula() {
+                continue;
+            }
+            let formula = cell.get_formula().to_string();
+            let rewritten = shift_sheet_rows(&formula, sheet_name, row, num);
+            if rewritten != formula {
+                debug!("Formula {} of {} becomes {}", formula, cell.get_coordinate().get_coordinate(), rewritten);
+                cell.set_formula(rewritten);
+                changed += 1;
+            }
+        }
+    }
+    changed
+}
+
+/// Grows by `num` rows the ranges to a sheet ending on `last_row` and starting on `first_row` or above, so the
+/// totals of rows repeated below `last_row` cover them all
+///
+/// The formulas of rows `first_row` to `last_row` of the sheet itself are left alone, the rows repeated
+/// taking copies of them.
+/// :return: The number of formulas changed.
+pub fn grow_ranges_ending_at(spreadsheet: &mut Spreadsheet, sheet_name: &str, first_row: u32, last_row: u32, num: u32) -> usize {
+    let mut changed = 0;
+    for worksheet in spreadsheet.get_sheet_collection_mut() {
+        let own_sheet = worksheet.get_name().eq_ignore_ascii_case(sheet_name);
+        for cell in worksheet.get_cell_collection_mut() {
+            let row = *cell.get_coordinate().get_row_num();
+            if !cell.is_formula() || (own_sheet && (first_row..=last_row).contains(&row)) {
+                continue;
+            }
+            let formula = cell.get_formula().to_string();
+            let rewritten = formula.split('"').enumerate()
+                .map(|(i, part)| {
+                    // Odd parts are inside string literals
+                    if i % 2 == 1 {
+                        return part.to_string();
+                    }
+                    ANCHORED_REFERENCE.replace_all(part, |caps: &Captures| {
+                        let whole = caps.get(0).unwrap();
+                        let targets_sheet = caps.get(1).map_or(own_sheet, |sheet| qualified_name(sheet.as_str()).eq_ignore_ascii_case(sheet_name));
+                        let rows = caps.get(9).and_then(|end| Some((caps[5].parse::<u32>().ok()?, end.as_str().parse::<u32>().ok()?)));
+                        match rows {
+                            Some((start, end)) if targets_sheet && start <= first_row && end == last_row && stands_alone(part, whole.start(), whole.end()) => format!(
+                                "{}{}{}{}{}:{}{}{}{}",
+                                caps.get(1).map_or("", |m| m.as_str()), &caps[2], &caps[3], &caps[4], start, &caps[6], &caps[7], &caps[8], end + num,
+                            ),
+                            _ => whole.as_str().to_string(),
+                        }
+                    }).into_owned()
+                })
+                .collect::<Vec<String>>()
+                .join("\"");
+            if rewritten != formula {
+                debug!("Formula {} of {} becomes {}", formula, cell.get_coordinate().get_coordinate(), rewritten);
+                cell.set_formula(rewritten);
+                changed += 1;
+            }
+        }
+    }
+    changed
+}
+
+/// Quotes a sheet name for a reference where Excel needs it ('My data', '2024', 'A1')
+pub fn quote_sheet_name(sheet_name: &str) -> String {
+    let plain = sheet_name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && sheet_name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+        && parse_excel_cell(sheet_name).is_err();
+    match plain {
+        true => sheet_name.to_string(),
+        false => format!("'{}'", sheet_name.replace('\'', "''")),
+    }
+}
+
+/// Qualifies a sheet of another workbook for a reference ('reports/[q1.xlsx]Sales'), as Excel writes it
+#[cfg(feature = "aggregate")]
+pub fn external_sheet_name(file_path: &str, sheet_name: &str) -> String {
+    let path = std::path::Path::new(file_path);
+    let file_name = path.file_name().map_or(file_path.to_string(), |name| name.to_string_lossy().to_string());
+    let directory = match path.parent().map(|parent| parent.to_string_lossy().to_string()) {
+        Some(parent) if !parent.is_empty() => format!("{}{}", parent, std::path::MAIN_SEPARATOR),
+        _ => String::new(),
+    };
+    let qualified = format!("{}[{}]{}", directory, file_name, sheet_name);
+    format!("'{}'", qualified.replace('\'', "''"))
+}
+
+/// Renames the sheet of the references of a formula qualified with `sheet_name` (compared case-insensitively)
+pub fn rename_sheet_in_formula(formula: &str, sheet_name: &str, new_name: &str) -> String {
+    formula.split('"').enumerate()
+        .map(|(i, part)| {
+            // Odd parts are inside string literals
+            if i % 2 == 1 {
+                return part.to_string();
+            }
+            SHEET_QUALIFIER.replace_all(part, |caps: &Captures| {
+                let whole = caps.get(0).unwrap();
+                let follows_name = part[..whole.start()].chars().last().is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '.');
+                let name = caps.get(1).map(|m| m.as_str().replace("''", "'")).or_else(|| caps.get(2).map(|m| m.as_str().to_string()));
+                match name {
+                    Some(name) if !follows_name && name.eq_ignore_ascii_case(sheet_name) => format!("{}!", quote_sheet_name(new_name)),
+                    _ => whole.as_str().to_string(),
+                }
+            }).into_owned()
+        })
+        .collect::<Vec<String>>()
+        .join("\"")
+}
+
+/// Renames the sheet of the references of the formulas of the workbook, the way Excel does when a sheet is renamed
+///
+/// :return: The number of formulas changed.
+pub fn rename_sheet_refs(spreadsheet: &mut Spreadsheet, sheet_name: &str, new_name: &str) -> usize {
+    let mut changed = 0;
+    for worksheet in spreadsheet.get_sheet_collection_mut() {
+        for cell in worksheet.get_cell_collection_mut() {
+            if !cell.is_formula() {
+                continue;
+            }
+            let formula = cell.get_formula().to_string();
+            let rewritten = rename_sheet_in_formula(&formula, sheet_name, new_name);
+            if rewritten != formula {
+                debug!("Formula {} of {} becomes {}", formula, cell.get_coordinate().get_coordinate(), rewritten);
+                cell.set_formula(rewritten);
+                changed += 1;
+            }
+        }
+    }
+    changed
+}
+
+/// Checks a formula for #REF! and references to sheets that do not exist
+pub fn check_formula(formula: &str, sheet_names: &[String]) -> Option<String> {
+    if has_ref_error(formula) {
+        return Some("Invalid reference (#REF!).".to_string());
+    }
+    let mut missing: Option<String> = None;
+    map_refs(formula, |sheet, _, _| {
+        // References to other workbooks ('[q1.xlsx]Sales') are not checked
+        if let Some(sheet) = sheet.filter(|sheet| !sheet.contains('[')) {
+            if missing.is_none() && !sheet_names.iter().any(|s| s.eq_ignore_ascii_case(sheet)) {
+                missing = Some(sheet.to_string());
+            }
+        }
+        None
+    });
+    missing.map(|sheet| format!("Reference to the missing sheet '{}'.", sheet))
+}
+
+/// Finds the formulas of the workbook, in cells and defined names, with a broken reference
+pub fn find_broken_refs(spreadsheet: &Spreadsheet) -> Vec<BrokenRef> {
+    let sheet_names: Vec<String> = spreadsheet.get_sheet_collection().iter().map(|ws| ws.get_name().to_string()).collect();
+    let mut broken = Vec::new();
+
+    for worksheet in spreadsheet.get_sheet_collection() {
+        let mut cells: Vec<_> = worksheet.get_cell_collection().into_iter().filter(|cell| cell.is_formula()).collect();
+        cells.sort_by_key(|cell| (*cell.get_coordinate().get_row_num(), *cell.get_coordinate().get_col_num()));
+        for cell in cells {
+            if let Some(reason) = check_formula(cell.get_formula(), &sheet_names) {
+                broken.push(BrokenRef {
+                    sheet: Some(worksheet.get_name().to_string()),
+                    cell: Some(cell.get_coordinate().get_coordinate()),
+                    name: None,
+                    formula: cell.get_formula().to_string(),
+                    reason,
+                });
+            }
+        }
+        for defined_name in worksheet.get_defined_names() {
+            if let Some(reason) = check_formula(&defined_name.get_address(), &sheet_names) {
+                broken.push(BrokenRef {
+                    sheet: Some(worksheet.get_name().to_string()),
+                    cell: None,
+                    name: Some(defined_name.get_name().to_string()),
+                    formula: defined_name.get_address(),
+                    reason,
+                });
+            }
+        }
+    }
+    for defined_name in spreadsheet.get_defined_names() {
+        if let Some(reason) = check_formula(&defined_name.get_address(), &sheet_names) {
+            broken.push(BrokenRef {
+                sheet: None,
+                cell: None,
+                name: Some(defined_name.get_name().to_string()),
+                formula: defined_name.get_address(),
+                reason,
+            });
+        }
+    }
+    debug!("{} broken references found", broken.len());
+    broken
+}
@@ -0,0 +1,312 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use umya_spreadsheet::{Cell, Spreadsheet, Worksheet};
+
+use crate::structs::{ContextValue, Value};
+use crate::utils::excel::index_to_excel;
+use crate::utils::merges::{area_range, MergedCells};
+use crate::utils::refs::{break_removed_refs, grow_ranges_ending_at, shift_inserted_refs, translate_formula};
+use crate::utils::tables::shift_tables_for_inserted_rows;
+use crate::utils::warnings::Warning;
+use crate::utils::write::{write_text, write_value, WriteOptions};
+
+/// Placeholder of a template: a name of the context or a path into it ({{client.name}}, {{items.0}})
+static TOKEN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_]*(?:\.[A-Za-z0-9_]+)*)\s*\}\}").unwrap()
+});
+
+/// What a token stands for in the context
+enum Lookup<'a> {
+    Found(&'a ContextValue),
+    // Left for another pass: the tokens of the lists repeating rows, or the others within a block
+    Skipped,
+    Missing,
+}
+
+/// Looks a path (client.name) up in a value of the context, the numbers of the path indexing lists
+fn lookup<'a>(value: &'a ContextValue, path: &[&str]) -> Option<&'a ContextValue> {
+    path.iter().try_fold(value, |value, key| match value {
+        ContextValue::Record(fields) => fields.get(*key),
+        ContextValue::List(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+        ContextValue::Value(_) => None,
+    })
+}
+
+/// Returns the name of the list a token repeats the rows of: its first name, when that is a list of the context
+fn repeated_list<'a>(context: &ContextValue, token: &'a str) -> Option<&'a str> {
+    let (name, rest) = token.split_once('.').map_or((token, None), |(name, rest)| (name, Some(rest)));
+    // A number after the list picks one of its items ({{items.0.name}}) rather than repeating them
+    let indexed = rest.is_some_and(|rest| rest.split('.').next().is_some_and(|key| key.parse::<usize>().is_ok()));
+    match lookup(context, &[name]) {
+        Some(ContextValue::List(_)) if !indexed => Some(name),
+        _ => None,
+    }
+}
+
+/// Replaces the tokens of a text found in the context, `escape` preparing the values for the text
+///
+/// :return: The text, None when no token was replaced, and the tokens that could not be, with why.
+fn replace_tokens<'a, F, E>(text: &str, resolve: F, escape: E) -> (Option<String>, Vec<(String, &'static str)>)
+where
+    F: Fn(&str) -> Lookup<'a>,
+    E: Fn(String) -> String,
+{
+    let mut issues = Vec::new();
+    let mut replaced = false;
+    let rendered = TOKEN.replace_all(text, |caps: &Captures| match resolve(&caps[1]) {
+        Lookup::Found(ContextValue::Value(value)) => {
+            replaced = true;
+            escape(value.value())
+        },
+        Lookup::Found(_) => {
+            issues.push((caps[1].to_string(), "not_a_value"));
+            caps[0].to_string()
+        },
+        Lookup::Skipped => caps[0].to_string(),
+        Lookup::Missing => {
+            issues.push((caps[1].to_string(), "missing_in_context"));
+            caps[0].to_string()
+        },
+    });
+    (replaced.then(|| rendered.into_owned()), issues)
+}
+
+/// Replaces the tokens of the value of a cell, a value made of a single token taking the value of the context
+/// as is (numbers stay numbers)
+fn render_text<'a, F>(text: &str, resolve: F) -> (Option<Value>, Vec<(String, &'static str)>)
+where
+    F: Fn(&str) -> Lookup<'a>,
+{
+    if let Some(caps) = TOKEN.captures(text.trim()) {
+        if caps[0].len() == text.trim().len() {
+            match resolve(&caps[1]) {
+                Lookup::Found(ContextValue::Value(value)) => return (Some(value.clone()), Vec::new()),
+                Lookup::Skipped => return (None, Vec::new()),
+                _ => {},
+            }
+        }
+    }
+    let (rendered, issues) = replace_tokens(text, resolve, |value| value);
+    (rendered.map(Value::String), issues)
+}
+
+/// The warning of a token that could not be rendered
+fn render_warning(sheet_name: &str, place: &str, token: &str, code: &'static str) -> Warning {
+    let message = match code {
+        "not_a_value" => format!("{{{{{}}}}} in {} of {} is a list or a record of the context, not a value.", token, place, sheet_name),
+        _ => format!("{{{{{}}}}} in {} of {} is not in the context and was left as is.", token, place, sheet_name),
+    };
+    warn!("{}", message);
+    Warning::new(code, message).with_column(token)
+}
+
+/// Writes the value rendered in a cell, after the write options
+fn write_rendered(cell: &mut Cell, value: &Value, options: &WriteOptions) {
+    match value {
+        Value::String(text) => {
+            write_text(cell, text);
+        },
+        _ => write_value(cell, value, options),
+    }
+}
+
+/// Renders the tokens of the cells of a sheet (of some rows only when given), formulas left alone
+///
+/// :return: The number of cells rendered.
+fn render_cells<'a, F>(worksheet: &mut Worksheet, rows: Option<(u32, u32)>, resolve: F, options: &WriteOptions, warnings: &mut Vec<Warning>) -> usize
+where
+    F: Fn(&str) -> Lookup<'a>,
+{
+    let mut cells: Vec<((u32, u32), String)> = worksheet.get_cell_collection().iter()
+        .filter(|cell| !cell.is_formula())
+        .map(|cell| ((*cell.get_coordinate().get_col_num(), *cell.get_coordinate().get_row_num()), cell.get_value().to_string()))
+        .filter(|((_, row), value)| rows.is_none_or(|(first, last)| (first..=last).contains(row)) && value.contains("{{"))
+        .collect();
+    cells.sort_by_key(|((col, row), _)| (*row, *col));
+    let sheet_name = worksheet.get_name().to_string();
+    let mut rendered = 0;
+    for ((col, row), text) in cells {
+        let (value, issues) = render_text(&text, &resolve);
+        warnings.extend(issues.into_iter().map(|(token, code)| {
+            render_warning(&sheet_name, &index_to_excel(col, row), &token, code).with_cell(index_to_excel(col, row))
+        }));
+        if let Some(value) = value {
+            write_rendered(worksheet.get_cell_mut((col, row)), &value, options);
+            rendered += 1;
+        }
+    }
+    rendered
+}
+
+/// Resolves the tokens of the context, leaving the lists repeating rows for their blocks
+fn resolve_in_context<'a>(context: &'a ContextValue) -> impl Fn(&str) -> Lookup<'a> {
+    move |token: &str| {
+        if repeated_list(context, token).is_some() {
+            return Lookup::Skipped;
+        }
+        let path: Vec<&str> = token.split('.').collect();
+        lookup(context, &path).map_or(Lookup::Missing, Lookup::Found)
+    }
+}
+
+/// Resolves the tokens of a list repeating rows from one of its items ({{items.name}}, {{items}} for a list of
+/// values), the other tokens being rendered afterwards
+fn resolve_in_item<'a>(list: &'a str, item: &'a ContextValue) -> impl Fn(&str) -> Lookup<'a> + 'a {
+    move |token: &str| {
+        let mut path = token.split('.');
+        if path.next() != Some(list) {
+            return Lookup::Skipped;
+        }
+        let path: Vec<&str> = path.collect();
+        lookup(item, &path).map_or(Lookup::Missing, Lookup::Found)
+    }
+}
+
+/// A block of rows repeated for each item of a list: the consecutive rows whose tokens refer to the list
+struct Block {
+    list: String,
+    first_row: u32,
+    last_row: u32,
+}
+
+/// Finds the blocks of rows of a sheet repeated for the lists of the context, from the top
+///
+/// :return: The blocks, or an error when a row refers to two lists.
+fn find_blocks(worksheet: &Worksheet, context: &ContextValue) -> Result<Vec<Block>, String> {
+    let mut lists: Vec<(u32, String)> = Vec::new();
+    for cell in worksheet.get_cell_collection() {
+        if cell.is_formula() {
+            continue;
+        }
+        let row = *cell.get_coordinate().get_row_num();
+        let value = cell.get_value();
+        for caps in TOKEN.captures_iter(&value) {
+            let Some(list) = repeated_list(context, caps.get(1).unwrap().as_str()) else {
+                continue;
+            };
+            match lists.iter().find(|(r, _)| *r == row) {
+                Some((_, other)) if other != list => {
+                    return Err(format!("Row {} of {} refers to two lists, {} and {}.", row, worksheet.get_name(), other, list));
+                },
+                Some(_) => {},
+                None => lists.push((row, list.to_string())),
+            }
+        }
+    }
+    lists.sort();
+    let mut blocks: Vec<Block> = Vec::new();
+    for (row, list) in lists {
+        match blocks.last_mut() {
+            Some(block) if block.list == list && block.last_row + 1 == row => block.last_row = row,
+            _ => blocks.push(Block { list, first_row: row, last_row: row }),
+        }
+    }
+    Ok(blocks)
+}
+
+/// Repeats a block of rows of a sheet for each item of its list, rendering each copy from its item
+///
+/// Rows are inserted below the block as Excel does (formulas, merges and tables below moving down), the copies
+/// taking the values, formulas (moved to their row), styles, merges and heights of the block. Ranges ending on
+/// the last row of the block grow to the copies, so its totals cover all the items. An empty list removes the block.
+fn repeat_block(
+    spreadsheet: &mut Spreadsheet,
+    sheet_name: &str,
+    block: &Block,
+    items: &[ContextValue],
+    options: &WriteOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<(), String> {
+    let height = block.last_row - block.first_row + 1;
+    if items.is_empty() {
+        break_removed_refs(spreadsheet, sheet_name, block.first_row, height);
+        let worksheet = spreadsheet.get_sheet_by_name_mut(sheet_name).ok_or_else(|| format!("Sheet {} not found.", sheet_name))?;
+        worksheet.remove_row(&block.first_row, &height);
+        debug!("Rows {} to {} of {} removed, {} is empty", block.first_row, block.last_row, sheet_name, block.list);
+        return Ok(());
+    }
+
+    let inserted = height * (items.len() as u32 - 1);
+    if inserted > 0 {
+        let worksheet = spreadsheet.get_sheet_by_name_mut(sheet_name).ok_or_else(|| format!("Sheet {} not found.", sheet_name))?;
+        worksheet.insert_new_row(&(block.last_row + 1), &inserted);
+        shift_tables_for_inserted_rows(worksheet, block.last_row + 1, inserted);
+        shift_inserted_refs(spreadsheet, sheet_name, block.last_row + 1, inserted);
+        grow_ranges_ending_at(spreadsheet, sheet_name, block.first_row, block.last_row, inserted);
+    }
+
+    let worksheet = spreadsheet.get_sheet_by_name_mut(sheet_name).ok_or_else(|| format!("Sheet {} not found.", sheet_name))?;
+    let cells: Vec<Cell> = worksheet.get_cell_collection().into_iter()
+        .filter(|cell| (block.first_row..=block.last_row).contains(cell.get_coordinate().get_row_num()))
+        .cloned()
+        .collect();
+    let heights: Vec<(u32, f64)> = (block.first_row..=block.last_row)
+        .filter_map(|row| worksheet.get_row_dimension(&row).map(|dimension| (row, *dimension.get_height())))
+        .filter(|(_, height)| *height > 0.0)
+        .collect();
+    let merges = MergedCells::of(worksheet).within_rows(block.first_row, block.last_row);
+    for i in 1..items.len() as u32 {
+        let offset = i * height;
+        for cell in &cells {
+            let (col, row) = (*cell.get_coordinate().get_col_num(), *cell.get_coordinate().get_row_num());
+            let target = worksheet.get_cell_mut((col, row + offset));
+            target.set_style(cell.get_style().clone());
+            if cell.is_formula() {
+                target.set_formula(translate_formula(cell.get_formula(), 0, offset as i64));
+            } else {
+                target.set_cell_value(cell.get_cell_value().clone());
+            }
+        }
+        for (row, row_height) in &heights {
+            worksheet.get_row_dimension_mut(&(row + offset)).set_height(*row_height);
+        }
+        for ((first_col, first_row), (last_col, last_row)) in &merges {
+            worksheet.add_merge_cells(area_range(((*first_col, first_row + offset), (*last_col, last_row + offset))));
+        }
+    }
+    for (i, item) in items.iter().enumerate() {
+        let first_row = block.first_row + i as u32 * height;
+        render_cells(worksheet, Some((first_row, first_row + height - 1)), resolve_in_item(&block.list, item), options, warnings);
+    }
+    debug!("Rows {} to {} of {} repeated for the {} items of {}", block.first_row, block.last_row, sheet_name, items.len(), block.list);
+    Ok(())
+}
+
+/// Renders the placeholders ({{name}}) of a sheet from the context: its cells, header and footer
+///
+/// Blocks of rows referring to a list ({{items.name}}) are repeated for each item of the list first, the lowest
+/// first so the rows of the blocks above stay where they are, and the other placeholders are rendered in the
+/// rows where they end up.
+/// :return: The warnings about the placeholders that could not be rendered, or an error when a row refers to two lists.
+pub fn render_sheet(spreadsheet: &mut Spreadsheet, sheet_name: &str, context: &ContextValue, options: &WriteOptions) -> Result<Vec<Warning>, String> {
+    let mut warnings = Vec::new();
+    let worksheet = spreadsheet.get_sheet_by_name(sheet_name).ok_or_else(|| format!("Sheet {} not found.", sheet_name))?;
+    let blocks = find_blocks(worksheet, context)?;
+    for block in blocks.iter().rev() {
+        let Some(ContextValue::List(items)) = lookup(context, &[block.list.as_str()]) else {
+            continue;
+        };
+        repeat_block(spreadsheet, sheet_name, block, items, options, &mut warnings)?;
+    }
+
+    let worksheet = spreadsheet.get_sheet_by_name_mut(sheet_name).ok_or_else(|| format!("Sheet {} not found.", sheet_name))?;
+    let rendered = render_cells(worksheet, None, resolve_in_context(context), options, &mut warnings);
+
+    // The header and footer hold texts only, where '&' starts a formatting code and is doubled to be printed
+    let resolve = |token: &str| lookup(context, &token.split('.').collect::<Vec<&str>>()).map_or(Lookup::Missing, Lookup::Found);
+    let header_footer = worksheet.get_header_footer_mut();
+    let (header, issues) = replace_tokens(header_footer.get_odd_header().get_value(), resolve, |value| value.replace('&', "&&"));
+    warnings.extend(issues.into_iter().map(|(token, code)| render_warning(sheet_name, "the header", &token, code)));
+    if let Some(header) = header {
+        header_footer.get_odd_header_mut().set_value(header);
+    }
+    let (footer, issues) = replace_tokens(header_footer.get_odd_footer().get_value(), resolve, |value| value.replace('&', "&&"));
+    warnings.extend(issues.into_iter().map(|(token, code)| render_warning(sheet_name, "the footer", &token, code)));
+    if let Some(footer) = footer {
+        header_footer.get_odd_footer_mut().set_value(footer);
+    }
+    debug!("{} cells and {} blocks of rows rendered in {}", rendered, blocks.len(), sheet_name);
+    Ok(warnings)
+}
@@ -0,0 +1,46 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn, error};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use pyo3::exceptions::{PyImportError, PyRuntimeError};
+use pyo3::{PyErr, PyTypeInfo};
+use polars::prelude::*;
+
+fn py_err<E>(err_msg: String) -> PyErr
+where
+    E: PyTypeInfo,
+{
+    error!("{}", err_msg);
+    PyErr::new::<E, _>(err_msg)
+}
+
+/// Convert a Rust Polars DataFrame to a Python Polars DataFrame.
+///
+/// This function serializes a Rust Polars DataFrame into Arrow IPC format using Polars' `IpcWriter`
+/// and deserializes it in Python with `polars.read_ipc`, mirroring the conversion of py2rs.
+///
+/// :param py: The Python interpreter instance.
+/// :param df: The Rust Polars DataFrame to convert.
+/// :return: A Python Polars DataFrame.
+pub fn rust_polars_df_to_py_polars_df(py: Python, df: &mut DataFrame) -> PyResult<PyObject> {
+    let mut buffer: Vec<u8> = Vec::new();
+    IpcWriter::new(&mut buffer)
+        .finish(df)
+        .map_err(|e| py_err::<PyRuntimeError>(format!("Failed to serialize DataFrame to Arrow: {}.", e)))?;
+
+    let polars: &PyModule = py.import("polars").map_err(|_| {
+        py_err::<PyImportError>("Failed to import polars module.".to_string())
+    })?;
+    let io: &PyModule = py.import("io").map_err(|_| {
+        py_err::<PyImportError>("Failed to import io module.".to_string())
+    })?;
+
+    let stream: &PyAny = io.call_method1("BytesIO", (PyBytes::new(py, &buffer),)).map_err(|_| {
+        py_err::<PyRuntimeError>("Failed to create buffer stream.".to_string())
+    })?;
+    let py_df: &PyAny = polars.call_method1("read_ipc", (stream,)).map_err(|_| {
+        py_err::<PyRuntimeError>("Failed to deserialize Arrow data.".to_string())
+    })?;
+
+    Ok(py_df.into())
+}
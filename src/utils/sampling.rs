@@ -0,0 +1,59 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use log::{log_enabled, Level};
+use once_cell::sync::Lazy;
+use std::env;
+
+/// Cells logged per operation before the others are only counted
+pub const DEFAULT_LOGGED_CELLS: usize = 20;
+
+/// Environment variable changing the number of cells logged per operation (0 for none)
+pub const LOGGED_CELLS_VAR: &str = "EZXL_LOG_CELLS";
+
+static LOGGED_CELLS: Lazy<usize> = Lazy::new(|| {
+    env::var(LOGGED_CELLS_VAR).ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(DEFAULT_LOGGED_CELLS)
+});
+
+/// Debug log of the cells of a loop: the first cells are logged, the others only counted
+///
+/// Messages are built only for the cells logged, so a debug level set by accident does not slow
+/// down large fills. The number of cells left out is logged when the log is dropped.
+pub struct CellLog {
+    operation: &'static str,
+    enabled: bool,
+    logged: usize,
+    skipped: usize,
+}
+
+impl CellLog {
+    pub fn new(operation: &'static str) -> Self {
+        CellLog { operation, enabled: log_enabled!(Level::Debug), logged: 0, skipped: 0 }
+    }
+
+    /// Logs the message of a cell, built by `message` if the cell is among the first ones
+    #[inline]
+    pub fn debug<F: FnOnce() -> String>(&mut self, message: F) {
+        if !self.enabled {
+            return;
+        }
+        if self.logged < *LOGGED_CELLS {
+            debug!("{}", message());
+            self.logged += 1;
+        } else {
+            self.skipped += 1;
+        }
+    }
+}
+
+impl Drop for CellLog {
+    fn drop(&mut self) {
+        if self.skipped > 0 {
+            debug!(
+                "{}: {} cells logged, {} more not logged (set {} to log more)",
+                self.operation, self.logged, self.skipped, LOGGED_CELLS_VAR
+            );
+        }
+    }
+}
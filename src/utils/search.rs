@@ -0,0 +1,147 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use regex::{NoExpand, Regex};
+use umya_spreadsheet::{CellRawValue, Worksheet};
+
+use crate::structs::SearchPattern;
+
+/// A cell found or replaced, with its value (the new one for a replacement)
+#[derive(Debug, Clone)]
+pub struct CellMatch {
+    pub sheet: String,
+    pub cell: String,
+    pub value: String,
+}
+
+impl IntoPy<PyObject> for CellMatch {
+    fn into_py(self, py: Python) -> PyObject {
+        let found = PyDict::new(py);
+        // Set items can only fail on unhashable keys, which string keys are not
+        let _ = found.set_item("sheet", self.sheet);
+        let _ = found.set_item("cell", self.cell);
+        let _ = found.set_item("value", self.value);
+        found.into()
+    }
+}
+
+/// The cells whose value matches a pattern, compiled once for a find or a replace over many cells
+pub struct Matcher {
+    regex: Regex,
+    literal: bool,
+}
+
+impl Matcher {
+    /// Compiles a pattern, matching the whole value of a cell only with `whole_cell`
+    ///
+    /// A text is matched as is, a regular expression with the flags it was compiled with in Python.
+    pub fn new(pattern: &SearchPattern, whole_cell: bool, case_sensitive: bool) -> Result<Self, String> {
+        let (expression, literal, ignore_case) = match pattern {
+            SearchPattern::Text(text) => {
+                if text.is_empty() {
+                    return Err("The text to find cannot be empty.".to_string());
+                }
+                (regex::escape(text), true, !case_sensitive)
+            },
+            SearchPattern::Regex { pattern, ignore_case } => (pattern.clone(), false, *ignore_case || !case_sensitive),
+        };
+        let expression = if whole_cell { format!("^(?:{})$", expression) } else { expression };
+        let expression = if ignore_case { format!("(?i){}", expression) } else { expression };
+        let regex = Regex::new(&expression).map_err(|e| format!("Invalid pattern {:?}: {}.", pattern.as_str(), e))?;
+        Ok(Matcher { regex, literal })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+
+    /// Replaces the matches in a text, the groups of a regular expression being referred to as in Python (\1, \g<name>)
+    pub fn replace(&self, text: &str, replacement: &str) -> String {
+        if self.literal {
+            self.regex.replace_all(text, NoExpand(replacement)).into_owned()
+        } else {
+            self.regex.replace_all(text, python_replacement(replacement).as_str()).into_owned()
+        }
+    }
+}
+
+/// Translates a replacement of Python's re.sub into the syntax of the regex crate
+///
+/// \1 and \g<1> or \g<name> refer to groups, \\ is a backslash, and the dollars are escaped.
+fn python_replacement(replacement: &str) -> String {
+    let mut translated = String::with_capacity(replacement.len());
+    let mut chars = replacement.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek().copied()) {
+            ('$', _) => translated.push_str("$$"),
+            ('\\', Some('\\')) => {
+                chars.next();
+                translated.push('\\');
+            },
+            ('\\', Some(d)) if d.is_ascii_digit() => {
+                let mut group = String::new();
+                while let Some(d) = chars.peek().copied().filter(|d| d.is_ascii_digit()) {
+                    group.push(d);
+                    chars.next();
+                }
+                translated.push_str(&format!("${{{}}}", group));
+            },
+            ('\\', Some('g')) => {
+                chars.next();
+                if chars.peek() == Some(&'<') {
+                    chars.next();
+                    let group: String = chars.by_ref().take_while(|c| *c != '>').collect();
+                    translated.push_str(&format!("${{{}}}", group));
+                } else {
+                    translated.push_str("\\g");
+                }
+            },
+            ('\\', Some('n')) => {
+                chars.next();
+                translated.push('\n');
+            },
+            ('\\', Some('t')) => {
+                chars.next();
+                translated.push('\t');
+            },
+            (c, _) => translated.push(c),
+        }
+    }
+    translated
+}
+
+/// Returns the cells of a sheet whose value matches, by row then column, with their values
+///
+/// Formulas are left out, their value being computed by Excel.
+pub fn find_cells(worksheet: &Worksheet, matcher: &Matcher) -> Vec<((u32, u32), String)> {
+    let mut found: Vec<((u32, u32), String)> = worksheet.get_cell_collection().iter()
+        .filter(|cell| !cell.is_formula())
+        .map(|cell| ((*cell.get_coordinate().get_col_num(), *cell.get_coordinate().get_row_num()), cell.get_value().to_string()))
+        .filter(|(_, value)| !value.is_empty() && matcher.is_match(value))
+        .collect();
+    found.sort_by_key(|((col, row), _)| (*row, *col));
+    found
+}
+
+/// Replaces the matches in the values of the cells of a sheet, keeping the type of the numbers and booleans replaced
+///
+/// Texts stay texts, even when they read as a number after the replacement. Rich texts lose their formatting.
+/// :return: The cells changed, by row then column, with their new values.
+pub fn replace_in_cells(worksheet: &mut Worksheet, matcher: &Matcher, replacement: &str) -> Vec<((u32, u32), String)> {
+    let mut replaced = Vec::new();
+    for (cell, value) in find_cells(worksheet, matcher) {
+        let new_value = matcher.replace(&value, replacement);
+        if new_value == value {
+            continue;
+        }
+        let target = worksheet.get_cell_mut(cell);
+        match target.get_raw_value() {
+            CellRawValue::Numeric(_) | CellRawValue::Bool(_) => target.set_value(&new_value),
+            _ => target.set_value_string(&new_value),
+        };
+        replaced.push((cell, new_value));
+    }
+    debug!("{} cells replaced in {}", replaced.len(), worksheet.get_name());
+    replaced
+}
@@ -0,0 +1,143 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use regex::Regex;
+#[cfg(any(feature = "dataframe", feature = "aggregate"))]
+use std::path::Path;
+
+use crate::utils::meta::is_meta_sheet;
+
+/// Prefix of the patterns given as regular expressions
+pub const REGEX_PREFIX: &str = "re:";
+
+/// Checks whether a pattern is a glob pattern (with *, ? or [...])
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '['])
+}
+
+/// Translates a glob pattern into an anchored regular expression
+pub fn glob_to_regex(pattern: &str) -> String {
+    let mut regex = String::from("^");
+    let mut in_class = false;
+    for c in pattern.chars() {
+        match c {
+            '*' if !in_class => regex.push_str(".*"),
+            '?' if !in_class => regex.push('.'),
+            '[' if !in_class => {
+                in_class = true;
+                regex.push('[');
+            },
+            ']' if in_class => {
+                in_class = false;
+                regex.push(']');
+            },
+            '!' if in_class && regex.ends_with('[') => regex.push('^'),
+            _ if in_class => regex.push(c),
+            _ => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+/// Lists the files whose name matches a glob pattern (e.g. "returns/*.xlsx"), sorted by path
+///
+/// Only the file name can hold wildcards, not the directories. The lock files Excel leaves ("~$...") are left out.
+#[cfg(any(feature = "dataframe", feature = "aggregate"))]
+pub fn glob_files(pattern: &str) -> Result<Vec<String>, String> {
+    let path = Path::new(pattern);
+    let name = path.file_name().and_then(|name| name.to_str())
+        .ok_or_else(|| format!("Invalid file pattern {:?}.", pattern))?;
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+    if dir.is_some_and(|dir| is_glob(&dir.to_string_lossy())) {
+        return Err(format!("Invalid file pattern {:?}. Only file names can hold wildcards.", pattern));
+    }
+    let regex = Regex::new(&glob_to_regex(name)).map_err(|e| format!("Invalid file pattern {:?}: {}.", pattern, e))?;
+    let entries = std::fs::read_dir(dir.unwrap_or_else(|| Path::new(".")))
+        .map_err(|e| format!("Failed to list the files of {:?}: {}.", pattern, e))?;
+    let mut files: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| entry.file_name().to_str().map(|name| name.to_string()))
+        .filter(|name| regex.is_match(name) && !name.starts_with("~$"))
+        .map(|name| match dir {
+            Some(dir) => dir.join(name).to_string_lossy().to_string(),
+            None => name,
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Selects the names matching a pattern: an exact name first, then a regular expression
+/// (prefixed with "re:") or a glob pattern
+pub fn select_names(names: &[String], pattern: &str) -> Result<Vec<String>, String> {
+    if names.iter().any(|name| name == pattern) {
+        return Ok(vec![pattern.to_string()]);
+    }
+
+    let regex = match pattern.strip_prefix(REGEX_PREFIX) {
+        Some(regex) => format!("^(?:{})$", regex),
+        None if is_glob(pattern) => glob_to_regex(pattern),
+        None => return Ok(Vec::new()),
+    };
+    let regex = Regex::new(&regex).map_err(|e| format!("Invalid sheet pattern '{}': {}.", pattern, e))?;
+    let selected: Vec<String> = names.iter().filter(|name| regex.is_match(name)).cloned().collect();
+    debug!("Pattern {} matches {:?}", pattern, selected);
+    Ok(selected)
+}
+
+/// Maximum length of a sheet name in Excel
+pub const MAX_SHEET_NAME_LEN: usize = 31;
+
+/// Characters Excel does not allow in sheet names
+const INVALID_SHEET_NAME_CHARS: [char; 7] = ['[', ']', ':', '*', '?', '/', '\\'];
+
+/// Truncates a sheet name to a number of characters
+fn truncate_name(name: &str, max_len: usize) -> String {
+    name.chars().take(max_len).collect()
+}
+
+/// Turns any text (e.g. a file name) into a valid sheet name
+pub fn sanitize_sheet_name(text: &str) -> String {
+    let name: String = text.chars().map(|c| if INVALID_SHEET_NAME_CHARS.contains(&c) { '_' } else { c }).collect();
+    truncate_name(name.trim_matches('\''), MAX_SHEET_NAME_LEN)
+}
+
+/// Checks that a name can be given to a sheet, other than the `taken` ones (compared without case, as in Excel)
+pub fn check_sheet_name(name: &str, taken: &[String]) -> Result<(), String> {
+    if name.trim().is_empty() || name.chars().count() > MAX_SHEET_NAME_LEN {
+        return Err(format!("Invalid sheet name {:?}. Expected 1 to {} characters.", name, MAX_SHEET_NAME_LEN));
+    }
+    if name.contains(INVALID_SHEET_NAME_CHARS) || name.starts_with('\'') || name.ends_with('\'') {
+        return Err(format!("Invalid sheet name {:?}. Sheet names cannot hold [ ] : * ? / \\ nor start or end with '.", name));
+    }
+    if is_meta_sheet(name) {
+        return Err(format!("Invalid sheet name {:?}. The names of the metadata sheets are reserved.", name));
+    }
+    if taken.iter().any(|t| t.to_lowercase() == name.to_lowercase()) {
+        return Err(format!("Sheet '{}' already exists.", name));
+    }
+    Ok(())
+}
+
+/// Returns a sheet name not taken yet, Excel comparing names without case
+///
+/// The name is first prefixed (e.g. with the source file) if a prefix is given, then suffixed with
+/// " (2)", " (3)"... until it is free, truncated so it stays within the length Excel allows.
+pub fn unique_sheet_name(name: &str, taken: &[String], prefix: Option<&str>) -> String {
+    let is_taken = |candidate: &str| taken.iter().any(|t| t.to_lowercase() == candidate.to_lowercase());
+    let base = match prefix {
+        Some(prefix) => truncate_name(&format!("{} {}", prefix, name), MAX_SHEET_NAME_LEN),
+        None => name.to_string(),
+    };
+    if !is_taken(&base) {
+        return base;
+    }
+    (2..)
+        .map(|i| {
+            let suffix = format!(" ({})", i);
+            format!("{}{}", truncate_name(&base, MAX_SHEET_NAME_LEN - suffix.len()), suffix)
+        })
+        .find(|candidate| !is_taken(candidate))
+        .unwrap_or(base)
+}
@@ -0,0 +1,73 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use umya_spreadsheet::{SheetStateValues, Spreadsheet, Worksheet};
+
+use crate::utils::distribution::sheet_view_mut;
+
+/// Parses the given sheets of a workbook read lazily, the others being left as read from the file
+///
+/// Parsing the cells is most of the time spent reading a large workbook, so a workbook only read from
+/// parses the sheets it is read from; the sheets left unparsed cannot be accessed.
+pub fn parse_sheets(spreadsheet: &mut Spreadsheet, sheet_names: &[String]) {
+    for sheet_name in sheet_names {
+        if spreadsheet.get_sheet_collection_no_check().iter().any(|ws| ws.get_name() == sheet_name) {
+            spreadsheet.read_sheet_by_name(sheet_name);
+        }
+    }
+    debug!("{} of {} sheets parsed", sheet_names.len(), spreadsheet.get_sheet_count());
+}
+
+/// Whether a sheet is visible, neither hidden nor very hidden
+pub fn is_visible(worksheet: &Worksheet) -> bool {
+    matches!(worksheet.get_state(), SheetStateValues::Visible)
+}
+
+/// Returns the name of the active sheet, None when the active tab is out of the workbook
+pub fn active_sheet_name(spreadsheet: &Spreadsheet) -> Option<String> {
+    let index = *spreadsheet.get_workbook_view().get_active_tab() as usize;
+    spreadsheet.get_sheet_collection().get(index).map(|worksheet| worksheet.get_name().to_string())
+}
+
+/// Makes a sheet the active and selected one, the first visible sheet when it is gone or hidden
+///
+/// The active tab is a position, so it must be set again whenever sheets are moved, removed or hidden.
+pub fn activate_sheet(spreadsheet: &mut Spreadsheet, sheet_name: Option<&str>) {
+    let sheets = spreadsheet.get_sheet_collection_mut();
+    let index = sheets.iter().position(|ws| Some(ws.get_name()) == sheet_name && is_visible(ws))
+        .or_else(|| sheets.iter().position(is_visible))
+        .unwrap_or(0);
+    for (i, worksheet) in sheets.iter_mut().enumerate() {
+        sheet_view_mut(worksheet).set_tab_selected(i == index);
+    }
+    spreadsheet.set_active_sheet(index as u32);
+    debug!("Sheet {} active", index);
+}
+
+/// Sets the names scoped to a sheet to its position again, after sheets were moved, inserted or removed
+pub fn reindex_local_names(spreadsheet: &mut Spreadsheet) {
+    for (index, worksheet) in spreadsheet.get_sheet_collection_mut().iter_mut().enumerate() {
+        for defined_name in worksheet.get_defined_names_mut().iter_mut() {
+            if defined_name.has_local_sheet_id() {
+                defined_name.set_local_sheet_id(index as u32);
+            }
+        }
+    }
+}
+
+/// Renames the tables of a copy of a sheet ("Sales" to "Sales_2"), as table names are unique in a workbook
+pub fn rename_copied_tables(spreadsheet: &Spreadsheet, worksheet: &mut Worksheet) {
+    let mut taken: Vec<String> = spreadsheet.get_sheet_collection().iter()
+        .flat_map(|ws| ws.get_tables().iter().map(|table| table.get_name().to_lowercase()))
+        .collect();
+    for table in worksheet.get_tables_mut().iter_mut() {
+        let name = table.get_name().to_string();
+        let new_name = (2..)
+            .map(|n| format!("{}_{}", name, n))
+            .find(|candidate| !taken.contains(&candidate.to_lowercase()))
+            .unwrap_or_default();
+        table.set_name(&new_name);
+        table.set_display_name(&new_name);
+        debug!("Table {} of the copy renamed to {}", name, new_name);
+        taken.push(new_name.to_lowercase());
+    }
+}
@@ -0,0 +1,116 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use polars::prelude::*;
+use polars_parquet::read::{column_iter_to_arrays, infer_schema, read_metadata, BasicDecompressor, PageReader};
+use polars_utils::mmap::{MemReader, MemSlice};
+use std::convert::TryFrom;
+use std::fs::File;
+
+/// Reads a CSV file straight into a DataFrame, the column types being inferred from its values
+///
+/// `columns` names the columns of a file without a header row, which polars names column_1, column_2...
+pub fn read_csv(csv_path: &str, delimiter: u8, has_header: bool, columns: Option<&[String]>) -> Result<DataFrame, String> {
+    let mut df = CsvReadOptions::default()
+        .with_has_header(has_header)
+        .map_parse_options(|options| options.with_separator(delimiter))
+        .try_into_reader_with_file_path(Some(csv_path.into()))
+        .and_then(|reader| reader.finish())
+        .map_err(|e| e.to_string())?;
+    if let Some(columns) = columns {
+        if columns.len() != df.width() {
+            return Err(format!("{} column names given for the {} columns of the file.", columns.len(), df.width()));
+        }
+        df.set_column_names(columns).map_err(|e| e.to_string())?;
+    }
+    debug!("{} rows of {} columns read from {}", df.height(), df.width(), csv_path);
+    Ok(df)
+}
+
+/// Reads an Arrow IPC (Feather v2) file straight into a DataFrame, with its column types
+///
+/// With `columns`, only these columns are read from the file.
+pub fn read_ipc(ipc_path: &str, columns: Option<Vec<String>>) -> Result<DataFrame, String> {
+    let file = File::open(ipc_path).map_err(|e| e.to_string())?;
+    let df = IpcReader::new(file)
+        .with_columns(columns)
+        .finish()
+        .map_err(|e| e.to_string())?;
+    debug!("{} rows of {} columns read from {}", df.height(), df.width(), ipc_path);
+    Ok(df)
+}
+
+/// Reads a Parquet file straight into a DataFrame, with its column types
+///
+/// With `columns`, only these columns are read from the file. The pages may be uncompressed or compressed
+/// with zstd, gzip or lz4: the snappy and brotli codecs are not part of this build.
+pub fn read_parquet(parquet_path: &str, columns: Option<Vec<String>>) -> Result<DataFrame, String> {
+    let file = File::open(parquet_path).map_err(|e| e.to_string())?;
+    let data = MemSlice::from_file(&file).map_err(|e| e.to_string())?;
+    let metadata = read_metadata(&mut MemReader::new(data.clone())).map_err(|e| e.to_string())?;
+    let schema = infer_schema(&metadata).map_err(|e| e.to_string())?;
+    let fields: Vec<&ArrowField> = match &columns {
+        Some(columns) => columns.iter()
+            .map(|name| schema.get(name).ok_or_else(|| format!("Column {:?} not found in the file.", name)))
+            .collect::<Result<_, _>>()?,
+        None => schema.iter_values().collect(),
+    };
+    let series = fields.into_iter().map(|field| {
+        let chunks = metadata.row_groups.iter().map(|row_group| {
+            let (columns, types): (Vec<_>, Vec<_>) = row_group.columns_under_root_iter(&field.name).map(|column| {
+                let range = column.byte_range();
+                let chunk = MemReader::new(data.slice(range.start as usize..range.end as usize));
+                let pages = PageReader::new(chunk, column, vec![], usize::MAX);
+                (BasicDecompressor::new(pages, vec![]), &column.descriptor().descriptor.primitive_type)
+            }).unzip();
+            column_iter_to_arrays(columns, types, field.clone(), None)
+        }).collect::<PolarsResult<Vec<ArrayRef>>>()?;
+        Series::try_from((field, chunks))
+    }).collect::<PolarsResult<Vec<Series>>>().map_err(|e| e.to_string())?;
+    let df = DataFrame::new(series).map_err(|e| e.to_string())?;
+    debug!("{} rows of {} columns read from {}", df.height(), df.width(), parquet_path);
+    Ok(df)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars_parquet::write::{CompressionOptions, Encoding, FileWriter, RowGroupIterator, StatisticsOptions, Version, WriteOptions, ZstdLevel};
+
+    /// Writes a DataFrame to a Parquet file compressed with zstd, a row group per `rows` rows
+    fn write_parquet(df: &DataFrame, path: &std::path::Path, rows: usize) {
+        let schema = df.schema().to_arrow(CompatLevel::newest());
+        let options = WriteOptions {
+            statistics: StatisticsOptions::default(),
+            version: Version::V2,
+            compression: CompressionOptions::Zstd(Some(ZstdLevel::try_new(3).unwrap())),
+            data_page_size: None,
+        };
+        let chunks: Vec<DataFrame> = (0..df.height()).step_by(rows).map(|offset| df.slice(offset as i64, rows)).collect();
+        let batches = chunks.iter().flat_map(|chunk| chunk.iter_chunks(CompatLevel::newest(), false)).map(Ok);
+        let encodings = schema.iter_values().map(|_| vec![Encoding::Plain]).collect();
+        let row_groups = RowGroupIterator::try_new(batches, &schema, options, encodings).unwrap();
+        let mut writer = FileWriter::try_new(File::create(path).unwrap(), schema, options).unwrap();
+        for row_group in row_groups {
+            writer.write(row_group.unwrap()).unwrap();
+        }
+        writer.end(None).unwrap();
+    }
+
+    #[test]
+    fn parquet_files_are_read_with_their_column_types_across_row_groups() {
+        let path = std::env::temp_dir().join(format!("ezxl-sources-{}.parquet", std::process::id()));
+        let df = df!(
+            "Name" => ["Alice", "Bob", "Carol"],
+            "Age" => [Some(25i64), Some(30), None],
+            "Score" => [1.5, 2.5, 3.5],
+        ).unwrap();
+        write_parquet(&df, &path, 2);
+        let path_str = path.to_str().unwrap();
+
+        assert!(read_parquet(path_str, None).unwrap().equals_missing(&df));
+        let columns = read_parquet(path_str, Some(vec!["Score".to_string(), "Name".to_string()])).unwrap();
+        assert!(columns.equals_missing(&df.select(["Score", "Name"]).unwrap()));
+        assert_eq!(read_parquet(path_str, Some(vec!["Missing".to_string()])).unwrap_err(), "Column \"Missing\" not found in the file.");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,191 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use std::collections::HashMap;
+
+use crate::structs::SparklineType;
+use crate::utils::excel::index_to_excel;
+use crate::utils::package::{escape_xml, patch_parts, sheet_part};
+use crate::utils::refs::rename_sheet_in_formula;
+use umya_spreadsheet::Spreadsheet;
+
+/// Hidden sheet holding the sparklines, written into the saved files as the writer does not support them
+pub const SPARKLINES_SHEET: &str = "_ezxl_sparklines";
+
+/// Identifier of the extension of the sheets holding sparkline groups
+const SPARKLINE_EXT_URI: &str = "{05C60535-1F16-4fd2-B633-F4F36F0B64E0}";
+
+/// A sparkline drawn in a cell from a row or a column of data
+#[derive(Debug, Clone)]
+pub struct Sparkline {
+    pub sheet: String,
+    pub cell: String,
+    pub data: String,
+    pub kind: SparklineType,
+}
+
+impl Sparkline {
+    pub fn to_record(&self) -> Vec<String> {
+        vec![self.sheet.clone(), self.cell.clone(), self.data.clone(), self.kind.as_str().to_string()]
+    }
+
+    pub fn from_record(record: &[String]) -> Option<Self> {
+        match record {
+            [sheet, cell, data, kind, ..] => Some(Sparkline {
+                sheet: sheet.clone(),
+                cell: cell.clone(),
+                data: data.clone(),
+                kind: kind.parse().ok()?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Quotes a sheet name for use in a formula
+fn quote_sheet_name(sheet_name: &str) -> String {
+    format!("'{}'!", sheet_name.replace('\'', "''"))
+}
+
+/// Renames the sheet of the recorded sparklines drawn in a sheet or reading their data from it
+///
+/// :return: The number of records changed.
+pub fn rename_sparkline_refs(spreadsheet: &mut Spreadsheet, sheet_name: &str, new_name: &str) -> usize {
+    let worksheet = match spreadsheet.get_sheet_by_name_mut(SPARKLINES_SHEET) {
+        Some(worksheet) => worksheet,
+        None => return 0,
+    };
+    let mut changed = 0;
+    for row in 1..=worksheet.get_highest_row() {
+        // The sheet of the cells is renamed with the other records, the data is a reference to rename
+        let data = worksheet.get_value((3, row));
+        let renamed = rename_sheet_in_formula(&data, sheet_name, new_name);
+        if renamed != data {
+            worksheet.get_cell_mut((3, row)).set_value_string(renamed);
+            changed += 1;
+        }
+    }
+    changed
+}
+
+/// Splits a range of data into one sparkline per cell of the location range (a single row or column)
+///
+/// A vertical location takes one row of data per cell, a horizontal one a column of data per cell, and a
+/// single cell the whole data range, which must then be a single row or column.
+pub fn build_sparklines(
+    sheet_name: &str,
+    location: ((u32, u32), (u32, u32)),
+    data_sheet_name: &str,
+    data: ((u32, u32), (u32, u32)),
+    kind: SparklineType,
+) -> Result<Vec<Sparkline>, String> {
+    let ((first_col, first_row), (last_col, last_row)) = location;
+    let ((data_first_col, data_first_row), (data_last_col, data_last_row)) = data;
+    let location_range = format!("{}:{}", index_to_excel(first_col, first_row), index_to_excel(last_col, last_row));
+    let data_range = format!("{}:{}", index_to_excel(data_first_col, data_first_row), index_to_excel(data_last_col, data_last_row));
+    let prefix = quote_sheet_name(data_sheet_name);
+    let sparkline = |col: u32, row: u32, data: String| Sparkline {
+        sheet: sheet_name.to_string(),
+        cell: index_to_excel(col, row),
+        data: format!("{}{}", prefix, data),
+        kind: kind.clone(),
+    };
+
+    let data_rows = data_last_row - data_first_row + 1;
+    let data_cols = data_last_col - data_first_col + 1;
+    if first_col == last_col && first_row == last_row {
+        if data_rows > 1 && data_cols > 1 {
+            return Err(format!("The data range {} of the sparkline in {} must be a single row or column.", data_range, location_range));
+        }
+        return Ok(vec![sparkline(first_col, first_row, data_range)]);
+    }
+
+    if first_col == last_col {
+        if data_rows != last_row - first_row + 1 {
+            return Err(format!("The data range {} must have one row per cell of {}.", data_range, location_range));
+        }
+        Ok((0..data_rows).map(|i| {
+            let row = data_first_row + i;
+            sparkline(first_col, first_row + i, format!("{}:{}", index_to_excel(data_first_col, row), index_to_excel(data_last_col, row)))
+        }).collect())
+    } else if first_row == last_row {
+        if data_cols != last_col - first_col + 1 {
+            return Err(format!("The data range {} must have one column per cell of {}.", data_range, location_range));
+        }
+        Ok((0..data_cols).map(|i| {
+            let col = data_first_col + i;
+            sparkline(first_col + i, first_row, format!("{}:{}", index_to_excel(col, data_first_row), index_to_excel(col, data_last_row)))
+        }).collect())
+    } else {
+        Err(format!("The sparklines location {} must be a single row or column.", location_range))
+    }
+}
+
+/// Builds the extension of a sheet holding its sparklines, one group per type
+fn sparkline_groups_xml(sparklines: &[&Sparkline]) -> String {
+    let mut xml = format!(
+        "<ext uri=\"{}\" xmlns:x14=\"http://schemas.microsoft.com/office/spreadsheetml/2009/9/main\">\
+        <x14:sparklineGroups xmlns:xm=\"http://schemas.microsoft.com/office/excel/2006/main\">",
+        SPARKLINE_EXT_URI
+    );
+    for kind in [SparklineType::Line, SparklineType::Column, SparklineType::WinLoss] {
+        let group: Vec<&&Sparkline> = sparklines.iter().filter(|s| s.kind == kind).collect();
+        if group.is_empty() {
+            continue;
+        }
+        let type_attribute = match kind {
+            SparklineType::Line => "",
+            SparklineType::Column => " type=\"column\"",
+            SparklineType::WinLoss => " type=\"stacked\"",
+        };
+        xml.push_str(&format!(
+            "<x14:sparklineGroup{} displayEmptyCellsAs=\"gap\">\
+            <x14:colorSeries rgb=\"FF376092\"/><x14:colorNegative rgb=\"FFD00000\"/><x14:colorAxis rgb=\"FF000000\"/>\
+            <x14:colorMarkers rgb=\"FFD00000\"/><x14:colorFirst rgb=\"FFD00000\"/><x14:colorLast rgb=\"FFD00000\"/>\
+            <x14:colorHigh rgb=\"FFD00000\"/><x14:colorLow rgb=\"FFD00000\"/><x14:sparklines>",
+            type_attribute
+        ));
+        for sparkline in group {
+            xml.push_str(&format!(
+                "<x14:sparkline><xm:f>{}</xm:f><xm:sqref>{}</xm:sqref></x14:sparkline>",
+                escape_xml(&sparkline.data),
+                sparkline.cell
+            ));
+        }
+        xml.push_str("</x14:sparklines></x14:sparklineGroup>");
+    }
+    xml.push_str("</x14:sparklineGroups></ext>");
+    xml
+}
+
+/// Adds an extension to the XML of a sheet, in its existing extension list or in a new one
+fn add_sheet_extension(sheet_xml: &str, extension: &str) -> Result<String, String> {
+    if let Some(position) = sheet_xml.rfind("</extLst>") {
+        return Ok(format!("{}{}{}", &sheet_xml[..position], extension, &sheet_xml[position..]));
+    }
+    match sheet_xml.rfind("</worksheet>") {
+        Some(position) => Ok(format!("{}<extLst>{}</extLst>{}", &sheet_xml[..position], extension, &sheet_xml[position..])),
+        None => Err("Invalid sheet XML: no closing worksheet tag.".to_string()),
+    }
+}
+
+/// Writes sparklines into a workbook, given as the bytes of the xlsx file
+///
+/// :param sheet_indexes: The position of each sheet name in the workbook, as written.
+pub fn write_sparklines(xlsx: Vec<u8>, sheet_indexes: &HashMap<String, usize>, sparklines: &[Sparkline]) -> Result<Vec<u8>, String> {
+    let mut by_part: HashMap<String, Vec<&Sparkline>> = HashMap::new();
+    for sparkline in sparklines {
+        match sheet_indexes.get(&sparkline.sheet) {
+            Some(index) => by_part.entry(sheet_part(*index)).or_default().push(sparkline),
+            None => debug!("Sparkline in {} left out with its sheet", sparkline.sheet),
+        }
+    }
+
+    let patches: HashMap<String, _> = by_part.into_iter()
+        .map(|(part, sheet_sparklines)| {
+            let extension = sparkline_groups_xml(&sheet_sparklines);
+            debug!("{} sparklines written in {}", sheet_sparklines.len(), part);
+            (part, move |sheet_xml: &str| add_sheet_extension(sheet_xml, &extension))
+        })
+        .collect();
+    patch_parts(xlsx, &patches)
+}
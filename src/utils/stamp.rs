@@ -0,0 +1,71 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+#[cfg(feature = "styles")]
+use pyo3::prelude::*;
+#[cfg(feature = "styles")]
+use std::path::Path;
+use umya_spreadsheet::drawing::spreadsheet::MarkerType;
+use umya_spreadsheet::{Color, Image, Worksheet};
+
+use crate::structs::{ExcelCell, StampMode};
+use crate::utils::excel::index_to_excel;
+
+/// Stamp applied to every sheet of a saved workbook
+#[derive(Debug, Clone)]
+pub struct Stamp {
+    pub text: String,
+    pub mode: StampMode,
+    pub image_path: Option<String>,
+    pub cell: Option<ExcelCell>,
+}
+
+impl Stamp {
+    /// Checks that everything needed by the stamp mode is available
+    #[cfg(feature = "styles")]
+    pub fn validate(&self) -> PyResult<()> {
+        if let StampMode::BackgroundImage = self.mode {
+            match &self.image_path {
+                Some(path) if Path::new(path).exists() => {},
+                Some(path) => {
+                    return Err(PyErr::new::<pyo3::exceptions::PyFileNotFoundError, _>(format!("Image not found: {:?}", path)));
+                },
+                None => {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("An image path is required in background_image mode."));
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the stamp to a worksheet
+    pub fn apply(&self, worksheet: &mut Worksheet) {
+        match self.mode {
+            StampMode::Header => {
+                // '&' starts a formatting code in headers, it must be doubled to be printed
+                let header = format!("&C&\"-,Bold\"&K{}{}", &Color::COLOR_RED[2..], self.text.replace('&', "&&"));
+                worksheet.get_header_footer_mut().get_odd_header_mut().set_value(header);
+                debug!("Header stamp {:?} set in {}", self.text, worksheet.get_name());
+            },
+            StampMode::BackgroundImage => {
+                let path = self.image_path.as_deref().unwrap_or_default();
+                let mut marker = MarkerType::default();
+                marker.set_coordinate(self.cell.as_ref().map_or("A1".to_string(), |c| c.range()));
+                let mut image = Image::default();
+                image.new_image(path, marker);
+                worksheet.add_image(image);
+                debug!("Image stamp {} set in {}", path, worksheet.get_name());
+            },
+            StampMode::CellOverlay => {
+                let (col, row) = match &self.cell {
+                    Some(cell) => cell.idx(),
+                    None => (worksheet.get_highest_column() + 1, 1),
+                };
+                worksheet.get_cell_mut((col, row)).set_value(self.text.clone());
+                let font = worksheet.get_style_mut((col, row)).get_font_mut();
+                font.set_bold(true);
+                font.get_color_mut().set_argb(Color::COLOR_RED);
+                debug!("Cell stamp {:?} set at {} in {}", self.text, index_to_excel(col, row), worksheet.get_name());
+            },
+        }
+    }
+}
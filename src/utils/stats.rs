@@ -0,0 +1,143 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use regex::Regex;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use umya_spreadsheet::Spreadsheet;
+use zip::ZipArchive;
+
+use crate::utils::package::sheet_part;
+
+const SHARED_STRINGS_PART: &str = "xl/sharedStrings.xml";
+const STYLES_PART: &str = "xl/styles.xml";
+
+/// Counts of the table of shared strings: references and distinct strings
+static SST_COUNTS: Lazy<Regex> = Lazy::new(|| Regex::new(r#"<sst[^>]*\bcount="(\d+)"[^>]*\buniqueCount="(\d+)""#).unwrap());
+
+/// Number of cell formats (styles) of the stylesheet
+static CELL_XFS_COUNT: Lazy<Regex> = Lazy::new(|| Regex::new(r#"<cellXfs[^>]*\bcount="(\d+)""#).unwrap());
+
+/// Size of a part of the package, compressed as in the file and uncompressed
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PartSize {
+    pub size: u64,
+    pub xml_size: u64,
+}
+
+/// Cell counts and size of a sheet
+#[derive(Debug, Clone)]
+pub struct SheetStats {
+    pub name: String,
+    pub cells: usize,
+    pub formulas: usize,
+    pub strings: usize,
+    pub part: PartSize,
+}
+
+/// Statistics of a workbook as saved, to find what makes a file large
+#[derive(Debug, Clone)]
+pub struct WorkbookStats {
+    pub file_size: u64,
+    pub sheets: Vec<SheetStats>,
+    pub string_count: u64,
+    pub unique_strings: u64,
+    pub shared_strings: PartSize,
+    pub style_count: u64,
+    pub styles: PartSize,
+    pub other_size: u64,
+}
+
+fn part_dict(py: Python, part: &PartSize) -> PyObject {
+    let dict = PyDict::new(py);
+    // Set items can only fail on unhashable keys, which string keys are not
+    let _ = dict.set_item("size", part.size);
+    let _ = dict.set_item("xml_size", part.xml_size);
+    dict.into()
+}
+
+impl IntoPy<PyObject> for WorkbookStats {
+    fn into_py(self, py: Python) -> PyObject {
+        let sheets: Vec<PyObject> = self.sheets.iter()
+            .map(|sheet| {
+                let dict = PyDict::new(py);
+                let _ = dict.set_item("name", &sheet.name);
+                let _ = dict.set_item("cells", sheet.cells);
+                let _ = dict.set_item("formulas", sheet.formulas);
+                let _ = dict.set_item("strings", sheet.strings);
+                let _ = dict.set_item("size", sheet.part.size);
+                let _ = dict.set_item("xml_size", sheet.part.xml_size);
+                dict.into()
+            })
+            .collect();
+        let shared_strings = part_dict(py, &self.shared_strings);
+        let _ = shared_strings.as_ref(py).set_item("count", self.string_count);
+        let _ = shared_strings.as_ref(py).set_item("unique", self.unique_strings);
+        let styles = part_dict(py, &self.styles);
+        let _ = styles.as_ref(py).set_item("count", self.style_count);
+
+        let stats = PyDict::new(py);
+        let _ = stats.set_item("file_size", self.file_size);
+        let _ = stats.set_item("sheets", sheets);
+        let _ = stats.set_item("shared_strings", shared_strings);
+        let _ = stats.set_item("styles", styles);
+        let _ = stats.set_item("other_size", self.other_size);
+        stats.into()
+    }
+}
+
+/// Reads the first number captured by a regex in a part, 0 without a match
+fn captured_count(xml: &str, regex: &Regex, group: usize) -> u64 {
+    regex.captures(xml).and_then(|caps| caps.get(group)?.as_str().parse().ok()).unwrap_or(0)
+}
+
+/// Computes the statistics of a workbook from its model and the bytes of the xlsx file it is saved as
+pub fn workbook_stats(spreadsheet: &Spreadsheet, xlsx: &[u8]) -> Result<WorkbookStats, String> {
+    let mut archive = ZipArchive::new(Cursor::new(xlsx)).map_err(|e| e.to_string())?;
+    let mut parts: HashMap<String, PartSize> = HashMap::new();
+    let (mut sst_xml, mut styles_xml) = (String::new(), String::new());
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).map_err(|e| e.to_string())?;
+        let name = file.name().to_string();
+        parts.insert(name.clone(), PartSize { size: file.compressed_size(), xml_size: file.size() });
+        match name.as_str() {
+            SHARED_STRINGS_PART => file.read_to_string(&mut sst_xml).map_err(|e| e.to_string())?,
+            STYLES_PART => file.read_to_string(&mut styles_xml).map_err(|e| e.to_string())?,
+            _ => 0,
+        };
+    }
+
+    let mut accounted = 0;
+    let mut sheets = Vec::new();
+    for (i, worksheet) in spreadsheet.get_sheet_collection_no_check().iter().enumerate() {
+        let part = parts.get(&sheet_part(i)).copied().unwrap_or_default();
+        accounted += part.size;
+        let cells = worksheet.get_cell_collection();
+        sheets.push(SheetStats {
+            name: worksheet.get_name().to_string(),
+            cells: cells.len(),
+            formulas: cells.iter().filter(|cell| cell.is_formula()).count(),
+            strings: cells.iter().filter(|cell| cell.get_data_type() == "s").count(),
+            part,
+        });
+    }
+    let shared_strings = parts.get(SHARED_STRINGS_PART).copied().unwrap_or_default();
+    let styles = parts.get(STYLES_PART).copied().unwrap_or_default();
+    accounted += shared_strings.size + styles.size;
+
+    let file_size = xlsx.len() as u64;
+    let stats = WorkbookStats {
+        file_size,
+        sheets,
+        string_count: captured_count(&sst_xml, &SST_COUNTS, 1),
+        unique_strings: captured_count(&sst_xml, &SST_COUNTS, 2),
+        shared_strings,
+        style_count: captured_count(&styles_xml, &CELL_XFS_COUNT, 1),
+        styles,
+        other_size: file_size.saturating_sub(accounted),
+    };
+    debug!("Workbook of {} bytes: {} sheets, {} strings, {} styles", stats.file_size, stats.sheets.len(), stats.unique_strings, stats.style_count);
+    Ok(stats)
+}
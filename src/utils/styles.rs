@@ -0,0 +1,88 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use umya_spreadsheet::Worksheet;
+#[cfg(feature = "dataframe")]
+use umya_spreadsheet::Cell;
+
+use crate::utils::excel::{index_to_excel, parse_excel_cell};
+
+/// Colors that can be given by name, as ARGB
+#[cfg(all(feature = "dataframe", feature = "styles"))]
+const NAMED_COLORS: &[(&str, &str)] = &[
+    ("black", "FF000000"),
+    ("blue", "FF0000FF"),
+    ("gray", "FF808080"),
+    ("green", "FF00FF00"),
+    ("grey", "FF808080"),
+    ("orange", "FFFFA500"),
+    ("red", "FFFF0000"),
+    ("white", "FFFFFFFF"),
+    ("yellow", "FFFFFF00"),
+];
+
+/// Parses a color given as a name ("red"), RGB ("#FF0000", "FF0000") or ARGB ("FFFF0000") into ARGB
+#[cfg(all(feature = "dataframe", feature = "styles"))]
+pub fn parse_color(color: &str) -> Result<String, String> {
+    let color = color.trim();
+    if let Some((_, argb)) = NAMED_COLORS.iter().find(|(name, _)| name.eq_ignore_ascii_case(color)) {
+        return Ok(argb.to_string());
+    }
+
+    let hex = color.strip_prefix('#').unwrap_or(color);
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid color: '{}'. Use a name, RRGGBB or AARRGGBB.", color));
+    }
+    match hex.len() {
+        6 => Ok(format!("FF{}", hex.to_uppercase())),
+        8 => Ok(hex.to_uppercase()),
+        _ => Err(format!("Invalid color: '{}'. Use a name, RRGGBB or AARRGGBB.", color)),
+    }
+}
+
+/// Fills the background of a cell with a solid ARGB color
+#[cfg(feature = "dataframe")]
+pub fn set_fill_color(cell: &mut Cell, argb: &str) {
+    cell.get_style_mut().set_background_color(argb);
+}
+
+/// Copies the merged cells lying inside a range of a sheet, and the widths of its columns, to another sheet
+///
+/// The first cell of the range goes to `first_cell`. Transposed, the merges are transposed too and the
+/// column widths, which would apply to rows, are not copied. Returns the number of merges copied.
+pub fn copy_layout(source: &Worksheet, target: &mut Worksheet, bounds: ((u32, u32), (u32, u32)), first_cell: (u32, u32), transpose: bool) -> usize {
+    let ((start_col, start_row), (end_col, end_row)) = bounds;
+    let destination = |(col, row): (u32, u32)| match transpose {
+        true => (first_cell.0 + row - start_row, first_cell.1 + col - start_col),
+        false => (first_cell.0 + col - start_col, first_cell.1 + row - start_row),
+    };
+    let inside = |(col, row): (u32, u32)| (start_col..=end_col).contains(&col) && (start_row..=end_row).contains(&row);
+
+    let mut merges = Vec::new();
+    for merge in source.get_merge_cells() {
+        let range = merge.get_range();
+        let corners = match range.split_once(':') {
+            Some((first, last)) => (parse_excel_cell(first), parse_excel_cell(last)),
+            None => continue,
+        };
+        if let (Ok(first), Ok(last)) = corners {
+            if inside(first) && inside(last) {
+                let ((first_col, first_row), (last_col, last_row)) = (destination(first), destination(last));
+                merges.push(format!("{}:{}", index_to_excel(first_col, first_row), index_to_excel(last_col, last_row)));
+            }
+        }
+    }
+    for merge in &merges {
+        debug!("Merged cells {} copied", merge);
+        target.add_merge_cells(merge.clone());
+    }
+
+    if !transpose {
+        for col in start_col..=end_col {
+            if let Some(column) = source.get_column_dimension_by_number(&col) {
+                let width = *column.get_width();
+                target.get_column_dimension_by_number_mut(&(first_cell.0 + col - start_col)).set_width(width);
+            }
+        }
+    }
+    merges.len()
+}
@@ -0,0 +1,239 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use polars::prelude::*;
+use std::collections::HashMap;
+use umya_spreadsheet::Worksheet;
+
+use crate::structs::{Action, Subtotals};
+use crate::utils::excel::index_to_excel_col;
+use crate::utils::outline::RowGroup;
+use crate::utils::py2rs::convert_anyvalue_to_string;
+
+/// A row of the grouped layout, data rows giving their row in the DataFrame and the others their group
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayoutRow {
+    GroupHeader(usize),
+    Data(usize),
+    Subtotal(usize),
+    GrandTotal,
+}
+
+/// A run of consecutive rows with the same value in the grouping column
+#[derive(Debug, Clone)]
+pub struct Group {
+    pub label: String,
+    pub first: usize,
+    pub len: usize,
+}
+
+/// Number of the SUBTOTAL function computing an action, ignoring the nested subtotals
+///
+/// SUBTOTAL has no median, whose subtotals are written as values.
+fn subtotal_function(action: &Action) -> Option<u32> {
+    match action {
+        Action::Average => Some(1),
+        Action::Count => Some(3),
+        Action::Max => Some(4),
+        Action::Min => Some(5),
+        Action::Product => Some(6),
+        Action::Stddev => Some(7),
+        Action::Sum => Some(9),
+        Action::Variance => Some(10),
+        Action::Median | Action::Custom(_) => None,
+    }
+}
+
+/// Computes an action over the values of a column
+fn aggregate(series: &Series, action: &Action) -> Option<f64> {
+    match action {
+        Action::Count => Some((series.len() - series.null_count()) as f64),
+        _ => {
+            let values: Vec<f64> = series.cast(&DataType::Float64).ok()?.f64().ok()?.into_iter().flatten().collect();
+            action.compute(&values)
+        },
+    }
+}
+
+/// Splits the rows of a DataFrame into runs of the same value of a column, in order, as Excel's Subtotal does
+pub fn group_runs(df: &DataFrame, by: &str) -> PolarsResult<Vec<Group>> {
+    let series = df.column(by)?;
+    let mut groups: Vec<Group> = Vec::new();
+    for i in 0..df.height() {
+        let label = convert_anyvalue_to_string(series.get(i)?);
+        match groups.last_mut() {
+            Some(group) if group.label == label => group.len += 1,
+            _ => groups.push(Group { label, first: i, len: 1 }),
+        }
+    }
+    Ok(groups)
+}
+
+/// Lays out the groups: an optional header row, the data rows and a subtotal row each, then the grand total
+pub fn layout(groups: &[Group], subtotals: &Subtotals) -> Vec<LayoutRow> {
+    let mut rows = Vec::new();
+    for (g, group) in groups.iter().enumerate() {
+        if subtotals.group_headers {
+            rows.push(LayoutRow::GroupHeader(g));
+        }
+        rows.extend((group.first..group.first + group.len).map(LayoutRow::Data));
+        rows.push(LayoutRow::Subtotal(g));
+    }
+    if subtotals.grand_total.is_some() && !groups.is_empty() {
+        rows.push(LayoutRow::GrandTotal);
+    }
+    rows
+}
+
+/// Spreads the rows of a DataFrame over the layout, with empty rows in place of the header and subtotal rows
+pub fn interleave(df: &DataFrame, layout: &[LayoutRow]) -> PolarsResult<DataFrame> {
+    let indices: IdxCa = layout.iter()
+        .map(|row| match row {
+            LayoutRow::Data(i) => Some(*i as IdxSize),
+            _ => None,
+        })
+        .collect();
+    df.take(&indices)
+}
+
+/// Writes the header, subtotal and grand total rows of the layout, starting at a row of the sheet
+///
+/// Subtotals are SUBTOTAL formulas (with their computed value) unless `formulas` is off, in which case
+/// the values only are written. The label goes in the column of 'by', or the first one if it is not filled.
+/// :return: The row groups of the outline, if it is on.
+pub fn write_subtotal_rows(
+    worksheet: &mut Worksheet,
+    df: &DataFrame,
+    groups: &[Group],
+    layout: &[LayoutRow],
+    header_map: &HashMap<String, u32>,
+    first_row: u32,
+    subtotals: &Subtotals,
+) -> PolarsResult<Vec<RowGroup>> {
+    let label_col = header_map.get(&subtotals.by).copied()
+        .or_else(|| header_map.values().copied().min())
+        .unwrap_or(1);
+    // Sheet row of each DataFrame row
+    let mut data_rows = vec![0; df.height()];
+    for (k, row) in layout.iter().enumerate() {
+        if let LayoutRow::Data(i) = row {
+            data_rows[*i] = first_row + k as u32;
+        }
+    }
+
+    for (k, row_kind) in layout.iter().enumerate() {
+        let row = first_row + k as u32;
+        let (label, rows, span) = match row_kind {
+            LayoutRow::Data(_) => continue,
+            LayoutRow::GroupHeader(g) => (groups[*g].label.clone(), None, None),
+            LayoutRow::Subtotal(g) => {
+                let group = &groups[*g];
+                let rows = (data_rows[group.first], data_rows[group.first + group.len - 1]);
+                (subtotals.label.replace("{}", &group.label), Some(rows), Some((group.first, group.len)))
+            },
+            LayoutRow::GrandTotal => {
+                let rows = (data_rows.first().copied().unwrap_or(row), data_rows.last().copied().unwrap_or(row));
+                (subtotals.grand_total.clone().unwrap_or_default(), Some(rows), Some((0, df.height())))
+            },
+        };
+
+        for col in header_map.values() {
+            let cell = worksheet.get_cell_mut((*col, row));
+            cell.set_blank();
+            cell.get_style_mut().get_font_mut().set_bold(true);
+        }
+        worksheet.get_cell_mut((label_col, row)).set_value_string(label.clone());
+        worksheet.get_cell_mut((label_col, row)).get_style_mut().get_font_mut().set_bold(true);
+
+        if let (Some((first, last)), Some((offset, len))) = (rows, span) {
+            for (column, action) in &subtotals.agg {
+                let col = match header_map.get(column) {
+                    Some(col) => *col,
+                    None => continue,
+                };
+                let value = aggregate(&df.column(column)?.slice(offset as i64, len), action);
+                let cell = worksheet.get_cell_mut((col, row));
+                match (subtotals.formulas.then(|| subtotal_function(action)).flatten(), value) {
+                    (Some(function), value) => {
+                        // The computed value is kept as the cached result of the formula
+                        if let Some(value) = value {
+                            cell.set_value_number(value);
+                        }
+                        let letter = index_to_excel_col(col);
+                        cell.set_formula(format!("SUBTOTAL({},{}{}:{}{})", function, letter, first, letter, last));
+                    },
+                    (None, Some(value)) => {
+                        cell.set_value_number(value);
+                    },
+                    (None, None) => {},
+                }
+            }
+        }
+        debug!("{} row written in row {}", label, row);
+    }
+
+    if !subtotals.outline {
+        return Ok(Vec::new());
+    }
+    // Data rows sit one level below their subtotal row, itself below the grand total if any
+    let data_level = if subtotals.grand_total.is_some() { 2 } else { 1 };
+    let sheet = worksheet.get_name().to_string();
+    let mut row_groups = Vec::new();
+    let mut group_first = first_row;
+    for (k, row_kind) in layout.iter().enumerate() {
+        let row = first_row + k as u32;
+        match row_kind {
+            // Group headers stay visible above their collapsed rows
+            LayoutRow::GroupHeader(_) => group_first = row + 1,
+            LayoutRow::Subtotal(_) => {
+                row_groups.push(RowGroup { sheet: sheet.clone(), first_row: group_first, last_row: row - 1, level: data_level });
+                group_first = row + 1;
+            },
+            _ => {},
+        }
+    }
+    if subtotals.grand_total.is_some() && layout.len() > 1 {
+        row_groups.push(RowGroup { sheet, first_row, last_row: first_row + layout.len() as u32 - 2, level: 1 });
+    }
+    Ok(row_groups)
+}
+
+/// Writes a totals row below the data, in bold, with a formula per column of `totals` over the rows of the data
+///
+/// Formulas are SUBTOTAL ones, leaving out the rows hidden by a filter, except for the medians, which
+/// SUBTOTAL does not compute. Their value is computed from the cells of the sheet and kept as their
+/// cached result. The 'Total' label goes in the first column filled, unless it has a total itself.
+pub fn write_totals_row(worksheet: &mut Worksheet, header_map: &HashMap<String, u32>, totals: &[(String, Action)], rows: (u32, u32), row: u32) {
+    let (first, last) = rows;
+    for col in header_map.values() {
+        let cell = worksheet.get_cell_mut((*col, row));
+        cell.set_blank();
+        cell.get_style_mut().get_font_mut().set_bold(true);
+    }
+    let label_col = header_map.values().copied().min().unwrap_or(1);
+    if !totals.iter().any(|(column, _)| header_map.get(column) == Some(&label_col)) {
+        worksheet.get_cell_mut((label_col, row)).set_value_string("Total");
+    }
+
+    for (column, action) in totals {
+        let col = match header_map.get(column) {
+            Some(col) => *col,
+            None => continue,
+        };
+        let texts: Vec<String> = (first..=last).map(|r| worksheet.get_value((col, r))).filter(|text| !text.is_empty()).collect();
+        let value = match action {
+            Action::Count => Some(texts.len() as f64),
+            _ => action.compute(&texts.iter().filter_map(|text| text.parse().ok()).collect::<Vec<f64>>()),
+        };
+        let letter = index_to_excel_col(col);
+        let formula = match subtotal_function(action) {
+            Some(function) => format!("SUBTOTAL({},{}{}:{}{})", function, letter, first, letter, last),
+            None => format!("MEDIAN({}{}:{}{})", letter, first, letter, last),
+        };
+        let cell = worksheet.get_cell_mut((col, row));
+        if let Some(value) = value {
+            cell.set_value_number(value);
+        }
+        cell.set_formula(formula);
+    }
+    debug!("Totals row written in row {} of {}", row, worksheet.get_name());
+}
@@ -0,0 +1,74 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use umya_spreadsheet::Worksheet;
+
+use crate::utils::excel::index_to_excel;
+
+/// Resizes the Excel Table (ListObject) whose header holds a cell so its data ends at the given row
+///
+/// Appended rows are then part of the table, keeping its banding, structured references and connected
+/// pivots working. Tables with a totals row are left as is, as the data would be written below it.
+///
+/// :param header: The header cell of the filled data, as (col, row).
+/// :param last_row: The last row of data.
+/// :return: The messages about the tables that could not be resized.
+#[cfg(feature = "dataframe")]
+pub fn fit_table_to_data(worksheet: &mut Worksheet, header: (u32, u32), last_row: u32) -> Vec<String> {
+    let (header_col, header_row) = header;
+    let sheet_name = worksheet.get_name().to_string();
+    let mut warnings = Vec::new();
+
+    for table in worksheet.get_tables_mut().iter_mut() {
+        let (start, end) = table.get_area();
+        let (first_col, first_row, last_col, table_last_row) = (*start.get_col_num(), *start.get_row_num(), *end.get_col_num(), *end.get_row_num());
+        if first_row != header_row || header_col < first_col || header_col > last_col {
+            continue;
+        }
+
+        if *table.get_totals_row_count() > 0 || *table.get_totals_row_shown() {
+            let message = format!(
+                "Table '{}' in {} has a totals row and was not resized to the filled data.",
+                table.get_name(), sheet_name
+            );
+            warn!("{}", message);
+            warnings.push(message);
+            continue;
+        }
+
+        // A table keeps at least one data row
+        let new_last_row = last_row.max(first_row + 1);
+        if new_last_row != table_last_row {
+            table.set_area(((first_col, first_row), (last_col, new_last_row)));
+            debug!("Table {} in {} resized to {}:{}", table.get_name(), sheet_name,
+                index_to_excel(first_col, first_row), index_to_excel(last_col, new_last_row));
+        }
+    }
+    warnings
+}
+
+/// Moves down the tables of a sheet below `row` by `num` rows and grows the ones spanning it, the way Excel
+/// does when rows are inserted there
+pub fn shift_tables_for_inserted_rows(worksheet: &mut Worksheet, row: u32, num: u32) {
+    let sheet_name = worksheet.get_name().to_string();
+    for table in worksheet.get_tables_mut().iter_mut() {
+        let (start, end) = table.get_area();
+        let (first_col, first_row, last_col, last_row) = (*start.get_col_num(), *start.get_row_num(), *end.get_col_num(), *end.get_row_num());
+        if last_row < row {
+            continue;
+        }
+        let first_row = if first_row >= row { first_row + num } else { first_row };
+        table.set_area(((first_col, first_row), (last_col, last_row + num)));
+        debug!("Table {} in {} moved to {}:{}", table.get_name(), sheet_name,
+            index_to_excel(first_col, first_row), index_to_excel(last_col, last_row + num));
+    }
+}
+
+/// Returns the name of a table of the sheet overlapping the area from `first` to `last`, as (col, row), if any
+pub fn overlapping_table(worksheet: &Worksheet, first: (u32, u32), last: (u32, u32)) -> Option<String> {
+    worksheet.get_tables().iter()
+        .find(|table| {
+            let (start, end) = table.get_area();
+            *start.get_col_num() <= last.0 && first.0 <= *end.get_col_num() && *start.get_row_num() <= last.1 && first.1 <= *end.get_row_num()
+        })
+        .map(|table| table.get_name().to_string())
+}
@@ -0,0 +1,36 @@
+//! Property tests over random inputs, reproducible from the seed of each case
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+
+use crate::utils::excel::{EXCEL_MAX_COLUMNS, EXCEL_MAX_ROWS};
+
+/// Seed of the first case, unless EZXL_TEST_SEED gives another one
+const DEFAULT_SEED: u64 = 1269;
+
+/// Number of cases of a property, unless EZXL_TEST_CASES gives another number
+const DEFAULT_CASES: u64 = 2_000;
+
+fn env_or(name: &str, default: u64) -> u64 {
+    std::env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
+
+/// Checks a property over random cases, case `i` drawing its inputs from a generator seeded with the seed plus `i`
+///
+/// A failing case prints its seed, replayed alone with `EZXL_TEST_SEED=<seed> EZXL_TEST_CASES=1 cargo test <name>`.
+pub fn check<F: Fn(&mut StdRng)>(property: F) {
+    let first_seed = env_or("EZXL_TEST_SEED", DEFAULT_SEED);
+    for case in 0..env_or("EZXL_TEST_CASES", DEFAULT_CASES) {
+        let seed = first_seed.wrapping_add(case);
+        let mut rng = StdRng::seed_from_u64(seed);
+        if let Err(panic) = catch_unwind(AssertUnwindSafe(|| property(&mut rng))) {
+            eprintln!("Property failed for the seed {}, replay it with EZXL_TEST_SEED={} EZXL_TEST_CASES=1", seed, seed);
+            resume_unwind(panic);
+        }
+    }
+}
+
+/// A random cell of a sheet, as (col, row)
+pub fn any_cell(rng: &mut StdRng) -> (u32, u32) {
+    (rng.gen_range(1..=EXCEL_MAX_COLUMNS), rng.gen_range(1..=EXCEL_MAX_ROWS))
+}
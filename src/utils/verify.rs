@@ -0,0 +1,147 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use polars::prelude::*;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use umya_spreadsheet::{Cell, Worksheet};
+
+use crate::structs::Mode;
+use crate::utils::excel::index_to_excel;
+use crate::utils::warnings::Warning;
+use crate::utils::write::{write_anyvalue, WriteOptions};
+
+/// Texts a column of the data must read as in the saved file, with their cells
+#[derive(Debug, Clone)]
+pub struct ColumnCheck {
+    pub column: String,
+    pub cells: Vec<((u32, u32), String)>,
+}
+
+/// What a fill would write, as checked by fill_with in validate_only mode, without writing it
+#[derive(Debug, Clone, Default)]
+pub struct FillReport {
+    pub sheet: String,
+    pub header: String,
+    pub mode: String,
+    pub rows: usize,
+    pub range: Option<String>,
+    pub matched_columns: Vec<String>,
+    pub missing_columns: Vec<String>,
+    pub extra_columns: Vec<String>,
+    pub warnings: Vec<Warning>,
+    pub errors: Vec<String>,
+}
+
+impl IntoPy<PyObject> for FillReport {
+    fn into_py(self, py: Python) -> PyObject {
+        let report = PyDict::new(py);
+        // Set items can only fail on unhashable keys, which string keys are not
+        let _ = report.set_item("valid", self.errors.is_empty());
+        let _ = report.set_item("sheet", self.sheet);
+        let _ = report.set_item("header", self.header);
+        let _ = report.set_item("mode", self.mode);
+        let _ = report.set_item("rows", self.rows);
+        let _ = report.set_item("range", self.range);
+        let _ = report.set_item("matched_columns", self.matched_columns);
+        let _ = report.set_item("missing_columns", self.missing_columns);
+        let _ = report.set_item("extra_columns", self.extra_columns);
+        let _ = report.set_item("warnings", self.warnings.into_py(py));
+        let _ = report.set_item("errors", self.errors);
+        report.into()
+    }
+}
+
+/// Number of non-empty texts and checksum of a column
+fn summary<'a>(texts: impl Iterator<Item = &'a str>) -> (usize, u64) {
+    let mut hasher = DefaultHasher::new();
+    let mut count = 0;
+    for text in texts {
+        if !text.is_empty() {
+            count += 1;
+        }
+        text.hash(&mut hasher);
+    }
+    (count, hasher.finish())
+}
+
+/// Computes the text each value of the data is written as, in the cells it is written to
+///
+/// Rows for which `checked_rows` is false (subtotal rows) are left out, as are the null values
+/// skipped with `skip_null`.
+#[allow(clippy::too_many_arguments)]
+pub fn expected_checks(
+    df: &DataFrame,
+    header_map: &HashMap<String, u32>,
+    first_cell: (u32, u32),
+    mode: &Mode,
+    skip_null: bool,
+    null_display: Option<&str>,
+    options: &WriteOptions,
+    checked_rows: &[bool],
+) -> PolarsResult<Vec<ColumnCheck>> {
+    let (first_col, first_row) = first_cell;
+    let mut checks = Vec::new();
+    for (column, idx) in header_map {
+        let series = match df.column(column) {
+            Ok(series) => series,
+            Err(_) => continue,
+        };
+        let mut cells = Vec::with_capacity(df.height());
+        for i in 0..df.height() {
+            if !checked_rows.get(i).copied().unwrap_or(true) {
+                continue;
+            }
+            let value = series.get(i)?;
+            let text = match (&value, null_display) {
+                (AnyValue::Null, _) if skip_null => continue,
+                (AnyValue::Null, Some(null_display)) => null_display.to_string(),
+                _ => {
+                    // The value goes through the writer, so the check does not depend on how it renders values
+                    let mut cell = Cell::default();
+                    write_anyvalue(&mut cell, value, options);
+                    cell.get_value().to_string()
+                },
+            };
+            let position = match mode {
+                Mode::Row => (*idx, first_row + i as u32),
+                Mode::Column => (first_col + i as u32, *idx),
+            };
+            cells.push((position, text));
+        }
+        checks.push(ColumnCheck { column: column.clone(), cells });
+    }
+    checks.sort_by(|a, b| a.column.cmp(&b.column));
+    Ok(checks)
+}
+
+/// Compares the columns read back from the saved file with the data: count of values, then checksum
+///
+/// :return: The first mismatch found, naming the column and the first cell that differs.
+pub fn verify_checks(worksheet: &Worksheet, checks: &[ColumnCheck]) -> Result<(), String> {
+    for check in checks {
+        let saved: Vec<String> = check.cells.iter().map(|(position, _)| worksheet.get_value(*position)).collect();
+        let expected = summary(check.cells.iter().map(|(_, text)| text.as_str()));
+        let actual = summary(saved.iter().map(|text| text.as_str()));
+        if expected == actual {
+            debug!("Column {} verified: {} values, checksum {:x}", check.column, expected.0, expected.1);
+            continue;
+        }
+        if expected.0 != actual.0 {
+            return Err(format!(
+                "Column '{}' has {} values in the saved file instead of {}.", check.column, actual.0, expected.0
+            ));
+        }
+        let ((col, row), text, saved) = check.cells.iter().zip(&saved)
+            .find(|((_, text), saved)| text != *saved)
+            .map(|((position, text), saved)| (*position, text.as_str(), saved.as_str()))
+            .unwrap_or(((0, 0), "", ""));
+        return Err(format!(
+            "Column '{}' differs from the data in the saved file (checksum {:x} instead of {:x}), first at {}: '{}' instead of '{}'.",
+            check.column, actual.1, expected.1, index_to_excel(col, row), saved, text
+        ));
+    }
+    Ok(())
+}
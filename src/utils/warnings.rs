@@ -0,0 +1,78 @@
+// create_exception! expands to cfg conditions of pyo3 0.18 unknown to recent compilers
+#![allow(unexpected_cfgs)]
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+use pyo3::create_exception;
+use pyo3::exceptions::PyUserWarning;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+// Warning raised in Python for non-fatal conditions that may silently lose data
+create_exception!(ez_excel_mgt, DataLossWarning, PyUserWarning);
+
+/// Emits each message as a DataLossWarning through the Python warnings module
+///
+/// With a warnings filter set to "error", the first message is raised as an exception.
+pub fn emit_warnings(py: Python, messages: &[String]) -> PyResult<()> {
+    for message in messages {
+        PyErr::warn(py, py.get_type::<DataLossWarning>(), message, 1)?;
+    }
+    Ok(())
+}
+
+/// A warning of a fill or a copy, with the column and cell it is about, returned to Python callers
+///
+/// The code names the kind of warning, e.g. 'column_added' for a column of the data missing in the header.
+#[derive(Debug, Clone)]
+pub struct Warning {
+    pub code: &'static str,
+    pub message: String,
+    pub column: Option<String>,
+    pub cell: Option<String>,
+    // Row of the data the warning is about, to find its cell once the data is written (not returned)
+    pub row: Option<usize>,
+}
+
+impl Warning {
+    pub fn new(code: &'static str, message: String) -> Self {
+        Warning { code, message, column: None, cell: None, row: None }
+    }
+
+    /// Names the column of the data the warning is about
+    pub fn with_column(mut self, column: &str) -> Self {
+        self.column = Some(column.to_string());
+        self
+    }
+
+    /// Gives the row of the data (from 0) the warning is about
+    pub fn with_row(mut self, row: usize) -> Self {
+        self.row = Some(row);
+        self
+    }
+
+    /// Names the cell the warning is about, as in Excel (e.g. "B4")
+    pub fn with_cell(mut self, cell: String) -> Self {
+        self.cell = Some(cell);
+        self
+    }
+}
+
+impl IntoPy<PyObject> for Warning {
+    fn into_py(self, py: Python) -> PyObject {
+        let warning = PyDict::new(py);
+        // Set items can only fail on unhashable keys, which string keys are not
+        let _ = warning.set_item("code", self.code);
+        let _ = warning.set_item("message", self.message);
+        let _ = warning.set_item("column", self.column);
+        let _ = warning.set_item("cell", self.cell);
+        warning.into()
+    }
+}
+
+/// Emits the message of each warning as a DataLossWarning, as emit_warnings, and returns the warnings
+pub fn emit_structured_warnings(py: Python, warnings: Vec<Warning>) -> PyResult<Vec<Warning>> {
+    for warning in &warnings {
+        PyErr::warn(py, py.get_type::<DataLossWarning>(), &warning.message, 1)?;
+    }
+    Ok(warnings)
+}
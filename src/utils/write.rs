@@ -0,0 +1,256 @@
+#[allow(unused_imports)]
+use log::{debug, info, warn};
+#[cfg(feature = "dataframe")]
+use polars::prelude::*;
+use umya_spreadsheet::{Cell, NumberingFormat};
+
+use crate::structs::{Operation, Value};
+use crate::utils::locale::NumberLocale;
+#[cfg(feature = "dataframe")]
+use crate::utils::py2rs::convert_anyvalue_to_string;
+
+/// Number of significant digits Excel keeps for numbers
+const EXCEL_PRECISION_DIGITS: u32 = 15;
+
+/// Number format of the dates written without a date_format
+#[cfg(feature = "dataframe")]
+const DEFAULT_DATE_FORMAT: &str = "yyyy-mm-dd";
+
+/// Number format of the durations, in hours beyond 24
+#[cfg(feature = "dataframe")]
+const DURATION_FORMAT: &str = "[h]:mm:ss";
+
+/// Serial date of 1970-01-01, the epoch of the Polars dates
+#[cfg(feature = "dataframe")]
+const UNIX_EPOCH_SERIAL: f64 = 25569.0;
+
+/// Number format applied to large integers so they are not displayed in scientific notation
+#[derive(Debug, Clone)]
+pub struct IntegerFormat {
+    pub format_code: String,
+    pub min_digits: u32,
+}
+
+impl Default for IntegerFormat {
+    fn default() -> Self {
+        IntegerFormat {
+            format_code: NumberingFormat::FORMAT_NUMBER.to_string(),
+            min_digits: 12,
+        }
+    }
+}
+
+/// Options applied when writing values into cells
+#[derive(Debug, Clone, Default)]
+pub struct WriteOptions {
+    pub number_locale: Option<NumberLocale>,
+    pub integer_format: IntegerFormat,
+    /// Number format of the dates, the datetimes adding the time to it
+    #[cfg(feature = "dataframe")]
+    pub date_format: Option<String>,
+}
+
+/// Counts the digits of an integer, ignoring the sign
+fn count_digits(value: i128) -> u32 {
+    value.unsigned_abs().checked_ilog10().map_or(1, |d| d + 1)
+}
+
+/// Writes an integer into a cell, keeping all its digits visible
+///
+/// Integers beyond Excel's precision are written as text, as they could not be stored as numbers.
+pub fn write_integer(cell: &mut Cell, value: i128, options: &WriteOptions) -> String {
+    let text = value.to_string();
+    if let Some(locale) = &options.number_locale {
+        let text = locale.format_integer(value);
+        cell.set_value_string(text.clone());
+        return text;
+    }
+
+    let digits = count_digits(value);
+    if digits > EXCEL_PRECISION_DIGITS {
+        debug!("Integer {} exceeds Excel's precision, written as text", text);
+        cell.set_value_string(text.clone());
+    } else {
+        cell.set_value_number(value as f64);
+        if digits >= options.integer_format.min_digits && options.integer_format.format_code != NumberingFormat::FORMAT_GENERAL {
+            cell.get_style_mut().get_number_format_mut().set_format_code(options.integer_format.format_code.clone());
+        }
+    }
+    text
+}
+
+/// Whether `_xHHHH_` starts the text, which Excel would read as an escaped character
+fn starts_with_escape(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() >= 7 && bytes.starts_with(b"_x") && bytes[2..6].iter().all(|b| b.is_ascii_hexdigit()) && bytes[6] == b'_'
+}
+
+/// Normalizes the newlines of a text to `\n` and escapes the characters XML cannot hold
+///
+/// CRLF, lone CR and the `_x000D_` artifacts of earlier round trips become `\n`. Control characters
+/// are escaped the way Excel does (`_x0001_`), and literal `_xHHHH_` sequences are protected with
+/// `_x005F_` so Excel does not decode them.
+pub fn normalize_text(text: &str) -> String {
+    let text = text.replace("_x000D_\n", "\n").replace("\r\n", "\n").replace("_x000D_", "\n").replace('\r', "\n");
+    let mut normalized = String::with_capacity(text.len());
+    for (i, c) in text.char_indices() {
+        if c == '_' && starts_with_escape(&text[i..]) {
+            normalized.push_str("_x005F");
+        }
+        if (c.is_control() && c != '\n' && c != '\t') || c == '\u{FFFE}' || c == '\u{FFFF}' {
+            normalized.push_str(&format!("_x{:04X}_", c as u32));
+        } else {
+            normalized.push(c);
+        }
+    }
+    normalized
+}
+
+/// Writes a text into a cell, with normalized newlines and wrapping enabled when it holds several lines
+///
+/// The text stays a text even when it reads like a number or a boolean ("007", "TRUE").
+pub fn write_text(cell: &mut Cell, text: &str) -> String {
+    let text = normalize_text(text);
+    if text.contains('\n') {
+        cell.get_style_mut().get_alignment_mut().set_wrap_text(true);
+    }
+    if text.is_empty() {
+        cell.set_blank();
+    } else {
+        cell.set_value_string(text.clone());
+    }
+    text
+}
+
+/// Number of units of a Polars time unit in a day
+#[cfg(feature = "dataframe")]
+fn units_per_day(unit: &TimeUnit) -> f64 {
+    match unit {
+        TimeUnit::Nanoseconds => 86_400_000_000_000.0,
+        TimeUnit::Microseconds => 86_400_000_000.0,
+        TimeUnit::Milliseconds => 86_400_000.0,
+    }
+}
+
+/// Excel serial number and number format of a temporal Polars value
+///
+/// Dates are days since 1899-12-30 with the time of day as a fraction, datetimes being taken as
+/// stored, without converting their time zone. Durations are fractions of days.
+#[cfg(feature = "dataframe")]
+fn temporal_serial(value: &AnyValue, options: &WriteOptions) -> Option<(f64, String)> {
+    let date_format = options.date_format.as_deref().unwrap_or(DEFAULT_DATE_FORMAT);
+    match value {
+        AnyValue::Date(days) => Some((UNIX_EPOCH_SERIAL + *days as f64, date_format.to_string())),
+        AnyValue::Datetime(value, unit, _) => {
+            Some((UNIX_EPOCH_SERIAL + *value as f64 / units_per_day(unit), format!("{} hh:mm:ss", date_format)))
+        },
+        AnyValue::Duration(value, unit) => Some((*value as f64 / units_per_day(unit), DURATION_FORMAT.to_string())),
+        AnyValue::Time(nanoseconds) => Some((*nanoseconds as f64 / units_per_day(&TimeUnit::Nanoseconds), "hh:mm:ss".to_string())),
+        _ => None,
+    }
+}
+
+/// Writes a Polars value into a cell and returns its text representation
+#[cfg(feature = "dataframe")]
+pub fn write_anyvalue(cell: &mut Cell, value: AnyValue, options: &WriteOptions) -> String {
+    if value.dtype().is_integer() {
+        if let Some(integer) = value.extract::<i128>() {
+            return write_integer(cell, integer, options);
+        }
+    }
+    if let (Some(locale), true) = (&options.number_locale, value.is_numeric()) {
+        if let Some(number) = value.extract::<f64>() {
+            let text = locale.format(number);
+            cell.set_value_string(text.clone());
+            return text;
+        }
+    }
+    match &value {
+        AnyValue::String(text) => return write_text(cell, text),
+        AnyValue::StringOwned(text) => return write_text(cell, text),
+        _ => {},
+    }
+    let text = convert_anyvalue_to_string(value.clone());
+    // Booleans and numbers are written with their type, not guessed back from their text
+    match &value {
+        AnyValue::Boolean(boolean) => {
+            cell.set_value_bool(*boolean);
+        },
+        AnyValue::Date(_) | AnyValue::Datetime(..) | AnyValue::Duration(..) | AnyValue::Time(_) => match temporal_serial(&value, options) {
+            // Excel displays no date before 1900
+            Some((serial, _)) if serial < 0.0 && !matches!(value, AnyValue::Duration(..)) => {
+                cell.set_value_string(text.clone());
+            },
+            Some((serial, format_code)) => {
+                cell.set_value_number(serial);
+                // A date format of the template is kept unless one is given
+                let general = cell.get_style().get_number_format().is_none_or(|format| format.get_number_format_id() == &0);
+                if general || options.date_format.is_some() {
+                    cell.get_style_mut().get_number_format_mut().set_format_code(format_code);
+                }
+            },
+            None => {
+                cell.set_value(text.clone());
+            },
+        },
+        AnyValue::Float32(_) | AnyValue::Float64(_) => match value.extract::<f64>() {
+            Some(number) if number.is_finite() => {
+                cell.set_value_number(number);
+            },
+            // NaN and infinities have no Excel number
+            _ => {
+                cell.set_value_string(text.clone());
+            },
+        },
+        _ => {
+            cell.set_value(text.clone());
+        },
+    }
+    text
+}
+
+/// Writes a value into a cell, after the write options
+///
+/// Integers keep their digits, numbers follow the locale and formulas replace the cell value.
+pub fn write_value(cell: &mut Cell, value: &Value, options: &WriteOptions) {
+    match (&options.number_locale, value) {
+        (_, Value::Int(v)) => {
+            write_integer(cell, *v as i128, options);
+        },
+        (Some(locale), Value::Float(v)) => {
+            cell.set_value_string(locale.format(*v));
+        },
+        (_, Value::String(v)) => {
+            write_text(cell, v);
+        },
+        (_, Value::Formula(v)) => {
+            cell.set_blank().set_formula(v.clone());
+        },
+        (_, Value::None) => {
+            cell.set_blank();
+        },
+        _ => {
+            cell.set_value(value.value());
+        },
+    };
+}
+
+/// Combines a pasted number with the value of a cell, blank cells counting as 0
+///
+/// A formula is wrapped in the operation (`=(A1*2)+5`), as Excel does.
+/// :return: The value written, or why the cell was kept: it holds no number or the division is by zero.
+pub fn combine_value(cell: &mut Cell, operation: &Operation, number: f64, options: &WriteOptions) -> Result<String, String> {
+    if cell.is_formula() {
+        let formula = format!("({}){}{}", cell.get_formula(), operation.operator(), number);
+        cell.set_blank().set_formula(formula.clone());
+        return Ok(format!("={}", formula));
+    }
+    let existing = cell.get_value().to_string();
+    let existing_number = match existing.as_str() {
+        "" => 0.0,
+        _ => existing.parse::<f64>().map_err(|_| format!("{:?} is not a number", existing))?,
+    };
+    let result = operation.apply(existing_number, number).ok_or_else(|| "division by zero".to_string())?;
+    write_value(cell, &Value::Float(result), options);
+    Ok(result.to_string())
+}
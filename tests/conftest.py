@@ -1,5 +1,9 @@
 import pytest
 import openpyxl
+from openpyxl.styles import Font
+from openpyxl.workbook.defined_name import DefinedName
+from openpyxl.worksheet.datavalidation import DataValidation
+from openpyxl.worksheet.table import Table, TableStyleInfo
 
 
 @pytest.fixture(scope="function")
@@ -157,3 +161,90 @@ def create_test_excel_with_3_sheets(tmp_path):
     return str(excel_path), ["New Sheet 1", "New Sheet 2", "New Sheet 3"], 3
 
 
+
+
+@pytest.fixture(scope="function")
+def create_test_excel_with_table(tmp_path):
+    """Fixture to create a temporary Excel file whose data is an Excel Table."""
+    excel_path = tmp_path / "table.xlsx"
+
+    workbook = openpyxl.Workbook()
+    sheet = workbook.active
+    sheet.title = "People"
+    sheet.append(["Name", "Age", "City"])
+    sheet.append(["Irène", 25, "Lyon"])
+    sheet.append(["Matthieu", 26, "Paris"])
+    table = Table(displayName="People", ref="A1:C3")
+    table.tableStyleInfo = TableStyleInfo(name="TableStyleMedium9", showRowStripes=True)
+    sheet.add_table(table)
+
+    workbook.save(excel_path)
+
+    return str(excel_path), sheet.title, 1
+
+
+@pytest.fixture(scope="function")
+def create_component_library(tmp_path):
+    """Fixture to create a library workbook of standard sheets, with styles, a defined name and a validation."""
+    excel_path = tmp_path / "components.xlsx"
+
+    workbook = openpyxl.Workbook()
+    cover = workbook.active
+    cover.title = "Cover"
+    cover["A1"] = "Quarterly report"
+    cover["A1"].font = Font(bold=True)
+    workbook.defined_names["ReportTitle"] = DefinedName("ReportTitle", attr_text="Cover!$A$1")
+
+    status = workbook.create_sheet("Status")
+    status["A1"] = "Status"
+    validation = DataValidation(type="list", formula1='"Open,Closed"')
+    validation.add("A2:A10")
+    status.add_data_validation(validation)
+    workbook.create_sheet("Notes")
+
+    workbook.save(excel_path)
+
+    return str(excel_path), ["Cover", "Status", "Notes"]
+
+
+@pytest.fixture(scope="function")
+def create_test_excel_with_monthly_sheets(tmp_path):
+    """Fixture to create a temporary Excel file with the same layout on one sheet per month."""
+    # Create a temporary file path for the Excel file
+    excel_path = tmp_path / "months.xlsx"
+
+    # Create a new workbook with one sheet per month
+    workbook = openpyxl.Workbook()
+    months = ["Jan", "Feb", "Mar"]
+    workbook.active.title = months[0]
+    for month in months[1:]:
+        workbook.create_sheet(month)
+
+    # Prepopulate each sheet with the same grid, scaled by the month number
+    for i, month in enumerate(months, start=1):
+        sheet = workbook[month]
+        sheet["A1"] = "Sales"
+        sheet["B1"] = 10 * i
+        sheet["C1"] = 1 * i
+        sheet["A2"] = "Costs"
+        sheet["B2"] = 5 * i
+        sheet["C2"] = 2 * i
+
+    # Write to the temporary file
+    workbook.save(excel_path)
+
+    return str(excel_path), months
+
+
+def pytest_addoption(parser):
+    parser.addoption("--update-golden", action="store_true", default=False,
+                     help="Rewrite the golden snapshots in tests/golden from the workbooks produced by the tests.")
+
+
+@pytest.fixture(scope="function")
+def golden(request):
+    """Fixture comparing a workbook with its golden snapshot (or updating it with --update-golden)."""
+    from workbooks import assert_matches_golden
+
+    update = request.config.getoption("--update-golden", default=False)
+    return lambda path, name: assert_matches_golden(path, name, update=update)
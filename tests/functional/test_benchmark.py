@@ -0,0 +1,55 @@
+# This project uses Poetry for dependency management.
+import time
+import openpyxl
+import polars as pl
+from ez_excel_mgt import ExcelTemplate, capture_log
+
+
+ROWS = 20000
+
+
+def fill(excel_path, sheet_name, header_row, df):
+    """Fills the sheet with the data and returns the time taken."""
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    start = time.perf_counter()
+    template.fill_with(df, overwrite=True)
+    return time.perf_counter() - start
+
+
+def test_debug_logging_of_fill_is_sampled(create_test_excel):
+    """Benchmark guarding against formatting a debug line per cell when the debug level is on."""
+    excel_path, sheet_name, header_row = create_test_excel
+    df = pl.DataFrame({
+        "Name": [f"Name {i}" for i in range(ROWS)],
+        "Age": list(range(ROWS)),
+        "Gender": ["F", "M"] * (ROWS // 2),
+    })
+
+    quiet = min(fill(excel_path, sheet_name, header_row, df) for _ in range(2))
+    with capture_log("debug") as log:
+        verbose = fill(excel_path, sheet_name, header_row, df)
+
+    # The first cells are logged, the others only counted in a summary record
+    assert len(log) < 200
+    assert any("more not logged" in r["message"] for r in log.records)
+    assert verbose < quiet * 3 + 0.5
+
+
+def test_debug_logging_of_copy_is_sampled(tmp_path, create_test_excel):
+    """Test that copying a large range at debug level logs a bounded number of records."""
+    excel_path, sheet_name, _ = create_test_excel
+    source_path = tmp_path / "source.xlsx"
+    workbook = openpyxl.Workbook()
+    for row in range(1, 1001):
+        workbook.active.append([row * col for col in range(1, 21)])
+    workbook.save(source_path)
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name, cell="E1")
+    with capture_log("debug") as log:
+        template.copy_range_from(str(source_path), "Sheet", "A1:T1000")
+
+    assert len(log) < 100
+    assert any("copy_range_from: 20 cells logged, 19980 more not logged" in r["message"] for r in log.records)
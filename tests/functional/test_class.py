@@ -1,10 +1,19 @@
 # This project uses Poetry for dependency management.
+import datetime
+import io
+import logging
 import pytest
+import random
+import re
+import zipfile
 from pathlib import Path
 import polars as pl
 import pandas as pd
 import openpyxl
-from ez_excel_mgt import ExcelTemplate
+from openpyxl.workbook.defined_name import DefinedName
+from ez_excel_mgt import ExcelTemplate, DataLossWarning, capabilities, capture_log, refresh_log_levels, set_log_level
+from ez_excel_mgt import clear_workbook_cache, set_workbook_cache, workbook_cache_info
+from ez_excel_mgt import ColumnMismatchError, RangeError, SheetNotFoundError, TemplateError
 
 
 def test_open_and_save_as(create_test_excel):
@@ -23,6 +32,277 @@ def test_open_and_save_as(create_test_excel):
     path.with_suffix(".new.xlsx").unlink()
 
 
+def test_context_manager_saves_on_exit(create_test_excel):
+    """Test that a with block saves the template on a clean exit only."""
+    file_path, _, _ = create_test_excel
+    autosave_path = Path(file_path).with_suffix(".auto.xlsx")
+
+    with ExcelTemplate(file_path, autosave_path=str(autosave_path)) as template:
+        template.add_sheet("Saved")
+    assert "Saved" in openpyxl.load_workbook(autosave_path).sheetnames
+
+    with pytest.raises(KeyError):
+        with ExcelTemplate(file_path) as template:
+            template.add_sheet("Lost")
+            raise KeyError("failure")
+    assert "Lost" not in openpyxl.load_workbook(file_path).sheetnames
+
+    with ExcelTemplate(file_path) as template:
+        template.add_sheet("InPlace")
+    assert "InPlace" in openpyxl.load_workbook(file_path).sheetnames
+
+
+def test_transaction_rolls_back(create_test_excel):
+    """Test that rollback restores the template and that a failing transaction block rolls back."""
+    file_path, _, _ = create_test_excel
+    template = ExcelTemplate(file_path)
+
+    template.begin()
+    template.add_sheet("Draft")
+    template.rollback()
+    assert "Draft" not in template.sheet_names()
+
+    template.begin()
+    template.add_sheet("Kept")
+    template.commit()
+    assert "Kept" in template.sheet_names()
+
+    with pytest.raises(KeyError):
+        with template.transaction():
+            template.add_sheet("Lost")
+            raise KeyError("failure")
+    assert "Lost" not in template.sheet_names()
+
+    with template.transaction():
+        template.add_sheet("Committed")
+    assert "Committed" in template.sheet_names()
+
+    with template.transaction():
+        template.add_sheet("Ended")
+        template.commit()
+    with pytest.raises(KeyError):
+        with template.transaction():
+            template.rollback()
+            raise KeyError("failure")
+    assert "Ended" in template.sheet_names()
+
+    with pytest.raises(TemplateError):
+        template.commit()
+    template.begin()
+    with pytest.raises(TemplateError):
+        template.begin()
+
+
+def test_bytes_round_trip(create_test_excel):
+    """Test loading a template from bytes and saving it back to bytes."""
+    file_path, _, _ = create_test_excel
+    template = ExcelTemplate.from_bytes(Path(file_path).read_bytes())
+    template.add_sheet("InMemory")
+    data = template.to_bytes()
+
+    assert isinstance(data, bytes)
+    workbook = openpyxl.load_workbook(io.BytesIO(data))
+    assert workbook.sheetnames == ["Sheet1", "InMemory"]
+    assert workbook["Sheet1"]["A4"].value == "Irène"
+
+    with pytest.raises(ValueError):
+        ExcelTemplate.from_bytes(b"not a workbook")
+
+
+def test_password_protected_workbooks(create_test_excel, tmp_path):
+    """Test saving a workbook encrypted with a password, and opening it again with this password."""
+    file_path, sheet_name, _ = create_test_excel
+    encrypted_path = tmp_path / "encrypted.xlsx"
+    ExcelTemplate(file_path).save_as(str(encrypted_path), password="s3cret")
+    assert encrypted_path.read_bytes()[:8] == bytes.fromhex("d0cf11e0a1b11ae1")
+
+    template = ExcelTemplate(str(encrypted_path), password="s3cret")
+    workbook = openpyxl.load_workbook(io.BytesIO(template.to_bytes()))
+    assert workbook[sheet_name]["A4"].value == "Irène"
+    assert ExcelTemplate.from_bytes(encrypted_path.read_bytes(), password="s3cret").sheet_names() == [sheet_name]
+
+    with pytest.raises(ValueError, match="password"):
+        ExcelTemplate(str(encrypted_path))
+    with pytest.raises(ValueError, match="Wrong password"):
+        ExcelTemplate(str(encrypted_path), password="wrong")
+
+    # Saved on exit, the template is encrypted again with the password it was opened with
+    with ExcelTemplate(str(encrypted_path), password="s3cret") as template:
+        template.write_cell(sheet_name, "B4", 30)
+    assert encrypted_path.read_bytes()[:8] == bytes.fromhex("d0cf11e0a1b11ae1")
+    template = ExcelTemplate(str(encrypted_path), password="s3cret")
+    assert openpyxl.load_workbook(io.BytesIO(template.to_bytes()))[sheet_name]["B4"].value == 30
+
+
+def test_workbook_cache(create_test_excel, tmp_path):
+    """Test reusing the workbooks parsed from unchanged files, each template modifying its own copy."""
+    file_path, sheet_name, _ = create_test_excel
+    set_workbook_cache(4)
+    try:
+        first = ExcelTemplate(file_path)
+        first.write_cell(sheet_name, "A4", "Secret")
+        second = ExcelTemplate(file_path)
+        assert workbook_cache_info()["hits"] == 1
+        assert workbook_cache_info()["workbooks"] == [str(Path(file_path).resolve())]
+
+        # Saving a copy adds its strings to the file written, not to the other copies
+        second.save_as(str(tmp_path / "second.xlsx"))
+        with zipfile.ZipFile(tmp_path / "second.xlsx") as archive:
+            assert b"Secret" not in archive.read("xl/sharedStrings.xml")
+        assert openpyxl.load_workbook(tmp_path / "second.xlsx")[sheet_name]["A4"].value == "Irène"
+
+        # A file modified is read again
+        first.save(file_path)
+        assert openpyxl.load_workbook(io.BytesIO(ExcelTemplate(file_path).to_bytes()))[sheet_name]["A4"].value == "Secret"
+        assert workbook_cache_info()["misses"] == 2
+
+        target = ExcelTemplate.create()
+        target.goto_sheet("Sheet1", cell="A1")
+        target.copy_range_from(file_path, sheet_name, "A4:B4")
+        assert workbook_cache_info()["hits"] == 2
+        clear_workbook_cache()
+        assert workbook_cache_info() == {"max_workbooks": 4, "workbooks": [], "hits": 0, "misses": 0}
+    finally:
+        set_workbook_cache(0)
+
+
+def test_macro_enabled_workbooks(create_test_excel, tmp_path):
+    """Test keeping the macros of an xlsm template, and leaving them out of xlsx files."""
+    file_path, sheet_name, _ = create_test_excel
+    macro_path = tmp_path / "template.xlsm"
+    with zipfile.ZipFile(file_path) as source, zipfile.ZipFile(macro_path, "w") as target:
+        for name in source.namelist():
+            data = source.read(name).decode()
+            if name == "[Content_Types].xml":
+                data = data.replace(
+                    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml", "application/vnd.ms-excel.sheet.macroEnabled.main+xml"
+                ).replace("</Types>", '<Default Extension="bin" ContentType="application/vnd.ms-office.vbaProject"/></Types>')
+            elif name == "xl/_rels/workbook.xml.rels":
+                data = data.replace("</Relationships>", '<Relationship Id="rIdVba" Type="http://schemas.microsoft.com/office/2006/relationships/vbaProject" Target="vbaProject.bin"/></Relationships>')
+            elif name == "xl/workbook.xml":
+                data = re.sub(r"<workbookPr\b", '<workbookPr codeName="DieseArbeitsmappe"', data, count=1)
+            target.writestr(name, data)
+        target.writestr("xl/vbaProject.bin", b"macros")
+
+    template = ExcelTemplate(str(macro_path))
+    template.write_cell(sheet_name, "B4", 30)
+    template.save_as(str(tmp_path / "report.xlsm"))
+    with zipfile.ZipFile(tmp_path / "report.xlsm") as report:
+        assert report.read("xl/vbaProject.bin") == b"macros"
+        assert "macroEnabled.main+xml" in report.read("[Content_Types].xml").decode()
+        assert 'codeName="DieseArbeitsmappe"' in report.read("xl/workbook.xml").decode()
+    assert openpyxl.load_workbook(tmp_path / "report.xlsm", keep_vba=True)[sheet_name]["B4"].value == 30
+
+    with pytest.warns(DataLossWarning, match="macros"):
+        template.save_as(str(tmp_path / "report.xlsx"))
+    with zipfile.ZipFile(tmp_path / "report.xlsx") as report:
+        assert "xl/vbaProject.bin" not in report.namelist()
+        assert "macroEnabled" not in report.read("[Content_Types].xml").decode()
+
+    # A workbook without macros saved as .xlsm is still declared macro-enabled, as Excel requires
+    ExcelTemplate(file_path).save_as(str(tmp_path / "plain.xlsm"))
+    with zipfile.ZipFile(tmp_path / "plain.xlsm") as report:
+        assert "macroEnabled.main+xml" in report.read("[Content_Types].xml").decode()
+
+
+def test_create_blank_workbooks(tmp_path):
+    """Test creating blank workbooks, with the default sheet or given sheets."""
+    output_path = tmp_path / "blank.xlsx"
+    ExcelTemplate.create().save_as(str(output_path))
+    assert openpyxl.load_workbook(output_path).sheetnames == ["Sheet1"]
+
+    ExcelTemplate.create_from_sheets(["Summary", "Data"]).save_as(str(output_path))
+    assert openpyxl.load_workbook(output_path).sheetnames == ["Summary", "Data"]
+
+    with pytest.raises(ValueError):
+        ExcelTemplate.create_from_sheets([])
+
+
+def test_manage_sheets(tmp_path):
+    """Test renaming, cloning, moving, hiding and deleting sheets."""
+    output_path = tmp_path / "sheets.xlsx"
+    workbook = openpyxl.Workbook()
+    workbook.active.title = "Data"
+    workbook["Data"]["A1"] = 5
+    workbook.create_sheet("Summary")["A1"] = "=Data!A1*2"
+    workbook.save(output_path)
+
+    template = ExcelTemplate(str(output_path))
+    template.rename_sheet("Data", "My data")
+    template.clone_sheet("My data", "Copy")
+    template.move_sheet("Summary", 0)
+    assert template.hide_sheet("Co*") == ["Copy"]
+    with pytest.raises(ValueError):
+        template.rename_sheet("Summary", "copy")
+    with pytest.raises(SheetNotFoundError):
+        template.delete_sheet("Data")
+    template.save(str(output_path))
+
+    workbook = openpyxl.load_workbook(output_path)
+    assert workbook.sheetnames == ["Summary", "My data", "Copy"]
+    assert workbook["Summary"]["A1"].value == "='My data'!A1*2"
+    assert workbook["Copy"]["A1"].value == 5
+    assert workbook["Copy"].sheet_state == "hidden"
+
+    template.show_sheet("Copy")
+    template.delete_sheet("Summary")
+    with pytest.raises(ValueError):
+        template.hide_sheet(["My data", "Copy"])
+    assert template.sheet_names() == ["My data", "Copy"]
+
+
+def test_save_as_keeps_the_template(create_test_excel):
+    """Test that save_as writes a new file and refuses to overwrite the template."""
+    file_path, _, _ = create_test_excel
+    template = ExcelTemplate(str(file_path))
+    template.add_sheet("Output")
+
+    with pytest.raises(ValueError):
+        template.save_as(str(file_path))
+    assert "Output" not in openpyxl.load_workbook(file_path).sheetnames
+
+    output_path = Path(file_path).with_suffix(".out.xlsx")
+    template.save_as(str(output_path))
+    assert "Output" in openpyxl.load_workbook(output_path).sheetnames
+
+
+def test_save_with_backup(create_test_excel):
+    """Test that save keeps the previous version of the file with backup and leaves no temporary file."""
+    file_path, _, _ = create_test_excel
+    template = ExcelTemplate(str(file_path))
+    template.add_sheet("Saved")
+
+    template.save(str(file_path), backup=True)
+    backup_path = Path(str(file_path) + ".bak")
+    assert "Saved" in openpyxl.load_workbook(file_path).sheetnames
+    assert "Saved" not in openpyxl.load_workbook(backup_path).sheetnames
+    assert [p.name for p in Path(file_path).parent.iterdir() if p.name.endswith(".tmp")] == []
+    backup_path.unlink()
+
+
+def test_errors_carry_attributes(create_test_excel):
+    """Test that errors are raised with the exception types of the crate and their attributes."""
+    file_path, sheet_name, header_row = create_test_excel
+    template = ExcelTemplate(str(file_path))
+
+    with pytest.raises(TemplateError):
+        template.set_header_location((header_row, 1), 'row')
+    with pytest.raises(SheetNotFoundError) as error:
+        template.goto_sheet("Missing")
+    assert error.value.sheet_name == "Missing"
+    with pytest.raises(RangeError) as error:
+        template.write_cell(sheet_name, (1048577, 1), "value")
+    assert (error.value.sheet_name, error.value.cell) == (sheet_name, "A1048577")
+
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    with pytest.raises(ColumnMismatchError) as error:
+        template.fill_with(pl.DataFrame({"Name": ["Alice"], "Age": [30]}), strict=True)
+    assert (error.value.sheet_name, error.value.column) == (sheet_name, "Gender")
+    # The errors stay ValueErrors for existing handlers
+    assert issubclass(ColumnMismatchError, ValueError)
+
+
 def test_add_sheet(create_test_excel):
     """Test adding a sheet."""
     file_path, _, _ = create_test_excel
@@ -87,3 +367,793 @@ def test_set_cell(create_test_excel):
     # Assert that data is inserted with a header row (named columns)
     assert sheet["B5"].value == "Hello, World!"
     assert sheet["B6"].value == True
+
+
+def test_drop_empty_sheets(create_test_excel_commented):
+    """Test dropping sheets without data when saving."""
+    file_path, sheet_name, header_row = create_test_excel_commented
+    template = ExcelTemplate(file_path)
+    template.add_sheet("Empty")
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.drop_empty_sheets()
+    template.save(file_path)
+
+    workbook = openpyxl.load_workbook(file_path)
+
+    assert workbook.sheetnames == [sheet_name]
+
+
+def test_include_sheet_if(create_test_excel_with_3_sheets):
+    """Test conditional inclusion of sheets when saving."""
+    file_path, sheet_names, _ = create_test_excel_with_3_sheets
+    template = ExcelTemplate(file_path)
+    template.include_sheet_if(sheet_names[1], False)
+    template.include_sheet_if(sheet_names[2], lambda name: name.endswith("3"))
+    template.save(file_path)
+
+    workbook = openpyxl.load_workbook(file_path)
+
+    assert workbook.sheetnames == [sheet_names[0], sheet_names[2]]
+    # The template itself keeps all its sheets
+    assert template.sheet_names() == sheet_names
+
+
+def test_stamp_header(create_test_excel_with_3_sheets):
+    """Test stamping all sheets in their page header."""
+    file_path, sheet_names, _ = create_test_excel_with_3_sheets
+    template = ExcelTemplate(file_path)
+    template.stamp("DRAFT")
+    template.save(file_path)
+
+    workbook = openpyxl.load_workbook(file_path)
+
+    for sheet_name in sheet_names:
+        assert workbook[sheet_name].oddHeader.center.text == "DRAFT"
+
+
+def test_stamp_cell_overlay_and_remove(create_test_excel):
+    """Test stamping a cell and removing the stamp for the final run."""
+    file_path, sheet_name, _ = create_test_excel
+    path = Path(file_path)
+    template = ExcelTemplate(file_path)
+    template.stamp("PREVIEW", "cell_overlay", cell="E1")
+    template.save(str(path.with_suffix(".preview.xlsx")))
+    template.remove_stamp()
+    template.save(file_path)
+
+    preview = openpyxl.load_workbook(path.with_suffix(".preview.xlsx"))[sheet_name]
+    final = openpyxl.load_workbook(file_path)[sheet_name]
+
+    assert preview["E1"].value == "PREVIEW"
+    assert preview["E1"].font.b
+    assert final["E1"].value is None
+
+
+def test_checkbox_and_option_buttons(create_test_excel):
+    """Test adding boolean and choice inputs and reading them back."""
+    file_path, sheet_name, _ = create_test_excel
+    template = ExcelTemplate(file_path)
+    template.add_checkbox("E1", checked=True, label="Approved?", sheet_name=sheet_name)
+    template.add_option_buttons("E2", ["Low", "Medium", "High"], selected="Medium", sheet_name=sheet_name)
+    template.save(file_path)
+
+    workbook = openpyxl.load_workbook(file_path)
+    sheet = workbook[sheet_name]
+    validations = {str(v.sqref): v.formula1 for v in sheet.data_validations.dataValidation}
+
+    assert sheet["E1"].value is True
+    assert sheet["E2"].value == "Medium"
+    assert validations["E1"] == '"TRUE,FALSE"'
+    assert validations["E2"] == '"Low,Medium,High"'
+
+    template = ExcelTemplate(file_path)
+
+    assert template.get_control_value("E1", sheet_name=sheet_name) is True
+    assert template.get_control_value("E2", sheet_name=sheet_name) == "Medium"
+    assert template.get_control_value("E3", sheet_name=sheet_name) is None
+
+
+def test_add_data_validation(create_test_excel):
+    """Test adding dropdowns, numeric bounds and date constraints to ranges."""
+    file_path, sheet_name, _ = create_test_excel
+    template = ExcelTemplate(file_path)
+    template.add_data_validation("C4:C10", values=["F", "M"], prompt="Gender", sheet_name=sheet_name)
+    template.add_data_validation("D4:D10", source_range="A4:A5", sheet_name=sheet_name)
+    template.add_data_validation("B4:B10", kind="whole", minimum=0, maximum=120, error_message="Invalid age", sheet_name=sheet_name)
+    template.add_data_validation("E:E", kind="date", minimum=datetime.date(2024, 1, 1), sheet_name=sheet_name)
+    template.add_data_validation("F4", kind="decimal", operator="less_than", maximum="B4", sheet_name=sheet_name)
+
+    with pytest.raises(ValueError):
+        template.add_data_validation("G4", kind="whole", operator="between", minimum=1, sheet_name=sheet_name)
+    with pytest.raises(ValueError):
+        template.add_data_validation("G4", values=["a,b"], sheet_name=sheet_name)
+    with pytest.raises(ValueError):
+        template.add_data_validation("G4", kind="whole", values=["1"], sheet_name=sheet_name)
+    template.save(file_path)
+
+    workbook = openpyxl.load_workbook(file_path)
+    validations = {str(v.sqref): v for v in workbook[sheet_name].data_validations.dataValidation}
+
+    assert validations["C4:C10"].type == "list"
+    assert validations["C4:C10"].formula1 == '"F,M"'
+    assert validations["C4:C10"].prompt == "Gender"
+    assert validations["D4:D10"].formula1 == f"{sheet_name}!$A$4:$A$5"
+    assert validations["B4:B10"].type == "whole"
+    assert validations["B4:B10"].operator == "between"
+    assert (validations["B4:B10"].formula1, validations["B4:B10"].formula2) == ("0", "120")
+    assert validations["B4:B10"].error == "Invalid age"
+    assert validations["E1:E1048576"].type == "date"
+    assert validations["E1:E1048576"].operator == "greaterThanOrEqual"
+    assert validations["E1:E1048576"].formula1 == "45292"
+    assert validations["F4"].operator == "lessThan"
+    assert validations["F4"].formula1 == "B4"
+
+
+def test_column_widths_and_row_heights(create_test_excel):
+    """Test setting column widths and row heights and sizing columns to their content."""
+    file_path, sheet_name, _ = create_test_excel
+    template = ExcelTemplate(file_path)
+    template.set_column_width("D", 20, sheet_name=sheet_name)
+    template.goto_sheet(sheet_name)
+    template.set_row_height(3, 30)
+    widths = template.autofit_columns(columns=["A", "B", "D"], sheet_name=sheet_name)
+
+    with pytest.raises(ValueError):
+        template.set_column_width("E", 300, sheet_name=sheet_name)
+    template.save(file_path)
+
+    workbook = openpyxl.load_workbook(file_path)
+    sheet = workbook[sheet_name]
+
+    # "Second row" is the longest value of A, and D keeps its width as it has no value
+    assert widths == {"A": 12.0, "B": 5.0}
+    assert sheet.column_dimensions["A"].width == 12.0
+    assert sheet.column_dimensions["D"].width == 20
+    assert sheet.row_dimensions[3].height == 30
+
+
+def test_merge_and_unmerge_cells(create_test_excel):
+    """Test merging cells, keeping the top left value, and splitting them back."""
+    file_path, sheet_name, _ = create_test_excel
+    template = ExcelTemplate(file_path)
+    with pytest.warns(DataLossWarning, match="A2"):
+        warnings = template.merge_cells("A1:B2", sheet_name=sheet_name)
+    assert [(w["code"], w["cell"]) for w in warnings] == [("cleared_by_merge", "A2")]
+    with pytest.raises(RangeError):
+        template.merge_cells("B2:C2", sheet_name=sheet_name)
+    template.merge_cells("D1:E1", sheet_name=sheet_name)
+    template.save(file_path)
+
+    sheet = openpyxl.load_workbook(file_path)[sheet_name]
+    assert {str(merge) for merge in sheet.merged_cells.ranges} == {"A1:B2", "D1:E1"}
+    assert sheet["A1"].value == "First row"
+    assert sheet["A2"].value is None
+
+    template = ExcelTemplate(file_path)
+    assert template.unmerge_cells("A1", sheet_name=sheet_name) == ["A1:B2"]
+    template.save(file_path)
+
+    sheet = openpyxl.load_workbook(file_path)[sheet_name]
+    assert {str(merge) for merge in sheet.merged_cells.ranges} == {"D1:E1"}
+
+    template = ExcelTemplate(file_path)
+    assert template.unmerge_cells("D:E", sheet_name=sheet_name) == ["D1:E1"]
+
+
+def test_protect_sheet_and_lock_cells(create_test_excel):
+    """Test protecting a sheet with editable input cells and some actions allowed."""
+    file_path, sheet_name, _ = create_test_excel
+    template = ExcelTemplate(file_path)
+    template.unlock_cells("B4:C6", sheet_name=sheet_name)
+    template.lock_cells("C6", sheet_name=sheet_name)
+    template.protect_sheet(password="secret", allow=("format_cells", "sort"), sheet_name=sheet_name)
+    with pytest.raises(ValueError):
+        template.protect_sheet(allow=["fly"], sheet_name=sheet_name)
+    template.save(file_path)
+
+    sheet = openpyxl.load_workbook(file_path)[sheet_name]
+    assert sheet.protection.sheet
+    assert not sheet.protection.formatCells and not sheet.protection.sort
+    assert sheet.protection.insertRows and sheet.protection.deleteRows
+    assert not sheet["B4"].protection.locked
+    assert not sheet["C5"].protection.locked
+    assert sheet["C6"].protection.locked
+    assert sheet["A4"].protection.locked
+    assert sheet["B4"].value == 25
+
+    template = ExcelTemplate(file_path)
+    assert template.unprotect_sheet(sheet_name)
+    assert not template.unprotect_sheet(sheet_name)
+    template.save(file_path)
+    assert not openpyxl.load_workbook(file_path)[sheet_name].protection.sheet
+
+
+def test_set_formula(create_test_excel):
+    """Test writing formulas, with or without their leading '='."""
+    file_path, sheet_name, _ = create_test_excel
+    template = ExcelTemplate(file_path)
+    template.set_formula("B6", "=SUM(B4:B5)", sheet_name=sheet_name)
+    template.goto_sheet(sheet_name)
+    template.set_formula("C6", "AVERAGE(B4:B5)")
+    template.write_cell(sheet_name, "D6", "=not a formula")
+
+    with pytest.raises(ValueError):
+        template.set_formula("E6", "=")
+    template.save(file_path)
+
+    sheet = openpyxl.load_workbook(file_path)[sheet_name]
+    assert sheet["B6"].value == "=SUM(B4:B5)"
+    assert sheet["C6"].value == "=AVERAGE(B4:B5)"
+    assert sheet["D6"].data_type == "s"
+
+
+def test_capabilities():
+    """Test the description of the supported features."""
+    caps = capabilities()
+
+    assert caps["version"]
+    assert "avg" in caps["actions"]
+    assert caps["modes"] == ["row", "col"]
+    assert "integer" in caps["coercions"]
+    assert "xlsx" in caps["file_formats"]
+    assert "polars" in caps["data_formats"]
+    assert "collect" in caps["features"]
+    assert set(caps["cargo_features"]) == {"aggregate", "charts", "dataframe", "styles"}
+
+
+def test_capture_log(create_test_excel, caplog):
+    """Test capturing the Rust log records of a single operation."""
+    file_path, sheet_name, _ = create_test_excel
+    template = ExcelTemplate(file_path)
+
+    with capture_log() as log:
+        template.goto_sheet(sheet_name)
+        template.set_header_location((3, 1), 'row')
+
+    assert len(log) > 0
+    assert all(r["level"] in ("ERROR", "WARN", "INFO", "DEBUG") for r in log.records)
+    assert any("Header" in r["message"] for r in log.records)
+
+    # Records logged after the block are not captured
+    count = len(log)
+    template.set_header_location((3, 1), 'row')
+    assert len(log) == count
+
+    with caplog.at_level("DEBUG", logger="ez_excel_mgt"):
+        log.replay()
+    assert len(caplog.records) == count
+
+    with pytest.raises(ValueError):
+        capture_log("verbose")
+
+
+def test_log_records_reach_python_logging(create_test_excel, caplog):
+    """Test that the Rust log records go to the ez_excel_mgt logger, at the levels set in Python."""
+    file_path, sheet_name, _ = create_test_excel
+    template = ExcelTemplate(file_path)
+
+    with caplog.at_level("DEBUG", logger="ez_excel_mgt"):
+        refresh_log_levels()
+        template.goto_sheet(sheet_name)
+    assert any(r.name.startswith("ez_excel_mgt.") and "Going to sheet" in r.message for r in caplog.records)
+
+    caplog.clear()
+    with caplog.at_level("WARNING", logger="ez_excel_mgt"):
+        refresh_log_levels()
+        template.goto_sheet(sheet_name)
+    assert caplog.records == []
+
+    # Levels changed from Python are only seen once read again
+    logging.getLogger("ez_excel_mgt.template").setLevel(logging.DEBUG)
+    with caplog.at_level("WARNING", logger="ez_excel_mgt"):
+        template.goto_sheet(sheet_name)
+        assert caplog.records == []
+        refresh_log_levels()
+        template.goto_sheet(sheet_name)
+    assert any(r.name == "ez_excel_mgt.template" for r in caplog.records)
+    logging.getLogger("ez_excel_mgt.template").setLevel(logging.NOTSET)
+    refresh_log_levels()
+
+
+def test_set_log_level_and_verbose(create_test_excel, caplog):
+    """Test changing the log level at runtime and the debug records of a verbose call."""
+    file_path, sheet_name, _ = create_test_excel
+    template = ExcelTemplate(file_path)
+    template.goto_sheet(sheet_name, cell="E1")
+
+    caplog.set_level("DEBUG")
+    set_log_level("warn")
+    template.copy_range_from(file_path, sheet_name, "A1:A2")
+    assert not any(r.levelname == "DEBUG" for r in caplog.records)
+
+    template.copy_range_from(file_path, sheet_name, "A1:A2", verbose=True)
+    assert any("Copying range" in r.message for r in caplog.records)
+
+    caplog.clear()
+    template.copy_range_from(file_path, sheet_name, "A1:A2")
+    assert not any(r.levelname == "DEBUG" for r in caplog.records)
+
+    set_log_level("debug")
+    template.copy_range_from(file_path, sheet_name, "A1:A2")
+    assert any("Copying range" in r.message for r in caplog.records)
+    logging.getLogger("ez_excel_mgt").setLevel(logging.NOTSET)
+    refresh_log_levels()
+
+    with pytest.raises(ValueError):
+        set_log_level("loud")
+
+
+def test_document_properties(create_test_excel):
+    """Test setting and reading back the core and custom document properties."""
+    file_path, _, _ = create_test_excel
+    template = ExcelTemplate(file_path)
+    template.set_properties(
+        title="Report", author="Jane Doe", company="ACME", created="2024-03-01",
+        modified=datetime.datetime(2024, 3, 2, 10, 30, tzinfo=datetime.timezone(datetime.timedelta(hours=2))),
+        custom={"run_id": "abc", "rows": 42, "final": True, "as_of": datetime.date(2024, 2, 29)},
+    )
+    template.set_properties(custom={"rows": 43, "final": None})
+    with pytest.raises(ValueError):
+        template.set_properties(custom={"ratio": 1.5})
+    with pytest.raises(ValueError):
+        template.set_properties(created="yesterday")
+    template.save(file_path)
+
+    properties = ExcelTemplate(file_path).get_properties()
+    assert properties["title"] == "Report"
+    assert properties["author"] == "Jane Doe"
+    assert properties["company"] == "ACME"
+    assert properties["created"] == "2024-03-01T00:00:00Z"
+    assert properties["modified"] == "2024-03-02T08:30:00Z"
+    assert properties["custom"] == {"run_id": "abc", "rows": 43, "as_of": "2024-02-29T00:00:00Z"}
+    workbook = openpyxl.load_workbook(file_path)
+    assert workbook.properties.title == "Report"
+    assert workbook.properties.creator == "Jane Doe"
+
+
+def test_stats(create_test_excel):
+    """Test the statistics of the workbook as it would be saved."""
+    file_path, sheet_name, _ = create_test_excel
+    template = ExcelTemplate(file_path)
+    template.write_cell(sheet_name, "E1", "Repeated")
+    template.write_cell(sheet_name, "E2", "Repeated")
+
+    stats = template.stats()
+    sheet = next(s for s in stats["sheets"] if s["name"] == sheet_name)
+    assert sheet["cells"] == 11
+    assert sheet["strings"] == 9
+    assert 0 < sheet["size"] < sheet["xml_size"]
+    assert stats["shared_strings"]["unique"] < stats["shared_strings"]["count"]
+    assert stats["styles"]["count"] >= 1
+    total = sum(s["size"] for s in stats["sheets"]) + stats["shared_strings"]["size"] + stats["styles"]["size"] + stats["other_size"]
+    assert total == stats["file_size"]
+
+
+def test_optimize(tmp_path):
+    """Test pruning the styles, strings and defined names no longer used."""
+    file_path = tmp_path / "cycles.xlsx"
+    workbook = openpyxl.Workbook()
+    sheet = workbook.active
+    sheet.title = "Data"
+    for row in range(1, 21):
+        sheet.cell(row=row, column=1, value=f"Label {row}")
+        sheet.cell(row=row, column=2, value=row).number_format = f'0.00 "unit {row}"'
+    workbook.defined_names["Labels"] = DefinedName("Labels", attr_text="Data!$A$1:$A$20")
+    workbook.defined_names["Old"] = DefinedName("Old", attr_text="Missing!$A$1")
+    workbook.save(file_path)
+
+    template = ExcelTemplate(str(file_path))
+    template.remove_rows_from("Data", 2, 19)
+    report = template.optimize()
+    assert report["styles_removed"] == 19
+    assert report["strings_removed"] == 19
+    assert report["defined_names_removed"] == ["Old"]
+    assert report["size_after"] < report["size_before"]
+    template.save(str(file_path))
+
+    workbook = openpyxl.load_workbook(file_path)
+    assert workbook["Data"]["A1"].value == "Label 1"
+    assert workbook["Data"]["B1"].number_format == '0.00 "unit 1"'
+    assert "Labels" in workbook.defined_names or "Labels" in workbook["Data"].defined_names
+    assert "Old" not in workbook.defined_names
+
+
+def test_select_sheets_with_patterns(create_test_excel_with_3_sheets):
+    """Test selecting sheets with glob patterns and regular expressions."""
+    file_path, sheet_names, _ = create_test_excel_with_3_sheets
+    template = ExcelTemplate(file_path)
+    template.add_sheet("Summary")
+
+    assert template.select_sheets("New Sheet *") == sheet_names
+    assert template.select_sheets("re:New Sheet [23]") == sheet_names[1:]
+    assert template.select_sheets(["Summary", "New Sheet 1"]) == ["Summary", "New Sheet 1"]
+
+    with pytest.raises(ValueError):
+        template.select_sheets("Region_*")
+    with pytest.raises(ValueError):
+        template.goto_sheet("New Sheet *")
+
+    written = template.write_cell("New Sheet ?", "A1", "Header")
+    assert written == sheet_names
+    assert template.include_sheet_if("re:.*[12]$", False) == sheet_names[:2]
+    template.save(file_path)
+
+    workbook = openpyxl.load_workbook(file_path)
+
+    assert workbook.sheetnames == ["New Sheet 3", "Summary"]
+    assert workbook["New Sheet 3"]["A1"].value == "Header"
+
+
+def test_write_outside_excel_limits(create_test_excel):
+    """Test that writes beyond the last row or column of Excel are rejected with a precise error."""
+    file_path, sheet_name, _ = create_test_excel
+    template = ExcelTemplate(file_path)
+
+    with pytest.raises(ValueError, match="write_cell: cell XFE1 in 'Sheet1'"):
+        template.write_cell(sheet_name, "XFE1", 1)
+    with pytest.raises(ValueError, match="A1048577"):
+        template.write_cell(sheet_name, (1048577, 1), 1)
+
+    template.write_cell(sheet_name, "XFD1048576", "last")
+    template.goto_sheet(sheet_name, cell="XFD1048576")
+    with pytest.raises(ValueError, match="add_checkbox"):
+        template.add_checkbox("XFE1", sheet_name=sheet_name)
+
+
+@pytest.mark.parametrize("cell", ["", "A", "1", "A0", "1A", "A1B", "AAAA1", "A-1", "é1", "A 1 1", (0, 1), (1, 0), (-1, 1), 1.5])
+def test_malformed_cells_raise_value_errors(create_test_excel, cell):
+    """Test that malformed cell references raise ValueError instead of panicking."""
+    file_path, sheet_name, _ = create_test_excel
+    template = ExcelTemplate(file_path)
+
+    with pytest.raises(ValueError):
+        template.write_cell(sheet_name, cell, 1)
+    with pytest.raises(ValueError):
+        template.goto_sheet(sheet_name, cell=cell)
+
+
+def malformed_workbooks(file_path):
+    """Yields truncated, corrupted and incomplete copies of a workbook."""
+    data = Path(file_path).read_bytes()
+    yield b""
+    yield b"not a workbook"
+    for length in (10, 100, len(data) // 2, len(data) - 10):
+        yield data[:length]
+    rnd = random.Random(0)
+    for _ in range(20):
+        corrupted = bytearray(data)
+        for _ in range(20):
+            corrupted[rnd.randrange(len(corrupted))] = rnd.randrange(256)
+        yield bytes(corrupted)
+    with zipfile.ZipFile(io.BytesIO(data)) as archive:
+        parts = {name: archive.read(name) for name in archive.namelist()}
+    for name, part in parts.items():
+        for content in (None, b"", b"<broken", part[:len(part) // 2]):
+            buffer = io.BytesIO()
+            with zipfile.ZipFile(buffer, "w") as archive:
+                for other, other_part in parts.items():
+                    if other != name:
+                        archive.writestr(other, other_part)
+                    elif content is not None:
+                        archive.writestr(other, content)
+            yield buffer.getvalue()
+
+
+def test_malformed_files_raise_errors(create_test_excel, tmp_path):
+    """Test that opening malformed files raises exceptions instead of panicking."""
+    file_path, _, _ = create_test_excel
+    malformed_path = tmp_path / "malformed.xlsx"
+
+    for data in malformed_workbooks(file_path):
+        malformed_path.write_bytes(data)
+        try:
+            ExcelTemplate(str(malformed_path))
+        except Exception:
+            pass
+
+
+@pytest.mark.parametrize("source_range", ["", ":", "A1:", ":B2", "A1:B2:C3", "A1-B2", "first", ((0, 1), (2, 2)), ((1, 1),)])
+def test_malformed_ranges_raise_value_errors(create_test_excel, create_empty_test_excel, source_range):
+    """Test that malformed ranges raise ValueError instead of panicking."""
+    source_file_path, source_sheet_name, _ = create_test_excel
+    dest_file_path, dest_sheet_name, _ = create_empty_test_excel
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell=(1, 1))
+
+    with pytest.raises(ValueError):
+        template.copy_range_from(source_file_path, source_sheet_name, source_range)
+    with pytest.raises(ValueError):
+        template.aggregate_range_from(source_file_path, source_sheet_name, source_range, action='sum', mode='row')
+
+
+@pytest.mark.parametrize("source_range", ["b5:a3", "$A$3:$B$5", "A3", ((5, 2), (3, 1))])
+def test_lenient_ranges(create_test_excel, create_empty_test_excel, source_range):
+    """Test that ranges given in any corner order, absolute or as a single cell are accepted."""
+    source_file_path, source_sheet_name, _ = create_test_excel
+    dest_file_path, dest_sheet_name, _ = create_empty_test_excel
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell=(1, 1))
+    template.copy_range_from(source_file_path, source_sheet_name, source_range)
+    template.save(dest_file_path)
+
+    workbook = openpyxl.load_workbook(dest_file_path)
+    assert workbook[dest_sheet_name]["A1"].value == "Name"
+
+
+def test_tag_cells(create_test_excel):
+    """Test tagging cells with metadata kept in a hidden sheet."""
+    file_path, sheet_name, _ = create_test_excel
+    template = ExcelTemplate(file_path)
+    template.goto_sheet(sheet_name)
+    template.tag_cell("B4", "lineage", "Q42")
+    template.tag_cell((4, 2), "status", "pending")
+    template.tag_cell("B4", "status", "validated")
+    template.tag_cell("$D$10", "lineage", "Q43")
+    template.save(file_path)
+
+    template = ExcelTemplate(file_path)
+    template.goto_sheet(sheet_name)
+    assert template.get_tags() == {
+        "B4": {"lineage": "Q42", "status": "validated"},
+        "D10": {"lineage": "Q43"},
+    }
+    assert template.get_tags("A1:C5", sheet_name) == {"B4": {"lineage": "Q42", "status": "validated"}}
+    assert template.sheet_names() == [sheet_name]
+    assert openpyxl.load_workbook(file_path)["_ezxl_tags"].sheet_state == "veryHidden"
+
+    with pytest.raises(ValueError):
+        template.tag_cell("A1", "lineage", "Q44", "Missing")
+
+
+def test_include_sheets_of_a_library(create_test_excel, create_component_library):
+    """Test importing standard sheets from a library workbook."""
+    file_path, sheet_name, _ = create_test_excel
+    library_path, library_sheets = create_component_library
+    template = ExcelTemplate(file_path)
+
+    assert template.include(library_path, sheets=["Cover", "Stat*"]) == ["Cover", "Status"]
+    with pytest.raises(ValueError):
+        template.include(ExcelTemplate(library_path), sheets="Cover")
+    assert template.include(ExcelTemplate(library_path), replace=True) == library_sheets
+    template.save(file_path)
+
+    workbook = openpyxl.load_workbook(file_path)
+    assert workbook.sheetnames == [sheet_name] + library_sheets
+    assert workbook["Cover"]["A1"].value == "Quarterly report"
+    assert workbook["Cover"]["A1"].font.bold
+    assert "ReportTitle" in workbook.defined_names
+    validations = workbook["Status"].data_validations.dataValidation
+    assert [v.formula1 for v in validations] == ['"Open,Closed"']
+
+    with pytest.raises(TypeError):
+        template.include(42)
+
+
+def test_consolidate_with_sheet_conflicts(create_test_excel, create_component_library):
+    """Test the naming policies of sheets whose names are already taken."""
+    file_path, sheet_name, _ = create_test_excel
+    library_path, library_sheets = create_component_library
+    template = ExcelTemplate(file_path)
+
+    assert template.consolidate([library_path], sheets="Cover") == {library_path: {"Cover": "Cover"}}
+    assert template.consolidate([library_path], sheets="Cover") == {library_path: {"Cover": "Cover (2)"}}
+    assert template.sheet_names() == [sheet_name, "Cover", "Cover (2)"]
+
+    prefix = Path(library_path).stem
+    mapping = template.consolidate([library_path], sheets="Notes", on_conflict="prefix_source_file")
+    assert mapping == {library_path: {"Notes": "Notes"}}
+    mapping = template.consolidate([library_path], sheets="Notes", on_conflict="prefix_source_file")
+    assert mapping == {library_path: {"Notes": f"{prefix} Notes"[:31]}}
+
+    with pytest.raises(ValueError, match="already exists"):
+        template.consolidate([library_path], on_conflict="error")
+    assert template.include(library_path, sheets="Status", on_conflict="suffix") == ["Status"]
+    assert template.include(library_path, sheets="Status", on_conflict="suffix") == ["Status (2)"]
+    assert template.include(library_path, sheets="Cover", on_conflict="overwrite") == ["Cover"]
+    with pytest.raises(ValueError):
+        template.consolidate([library_path], on_conflict="rename")
+
+
+def test_add_sparklines(create_test_excel):
+    """Test adding sparklines, kept across a reopen and save."""
+    file_path, sheet_name, _ = create_test_excel
+    template = ExcelTemplate(file_path)
+    template.goto_sheet(sheet_name)
+    template.add_sparkline("D4:D5", "B4:C5")
+    template.add_sparkline("B7:C7", "B4:C5", type="column")
+    template.save(file_path)
+    ExcelTemplate(file_path).save(file_path)
+
+    sheet_xml = zipfile.ZipFile(file_path).read("xl/worksheets/sheet1.xml").decode()
+    assert "<xm:f>'Sheet1'!B4:C4</xm:f><xm:sqref>D4</xm:sqref>" in sheet_xml
+    assert "<xm:f>'Sheet1'!C4:C5</xm:f><xm:sqref>C7</xm:sqref>" in sheet_xml
+    assert sheet_xml.count("<x14:sparklineGroup ") == 2
+    assert openpyxl.load_workbook(file_path)[sheet_name]["B4"].value == 25
+
+    with pytest.raises(ValueError):
+        template.add_sparkline("D4:E5", "B4:C5")
+    with pytest.raises(ValueError):
+        template.add_sparkline("D4", "B4:C5")
+    with pytest.raises(ValueError):
+        template.add_sparkline("D4", "B4:C4", type="pie")
+
+
+def test_add_charts(create_test_excel):
+    """Test adding native charts over the data, kept across a reopen and save."""
+    file_path, sheet_name, _ = create_test_excel
+    template = ExcelTemplate(file_path)
+    template.goto_sheet(sheet_name)
+    template.add_chart("E3", "bar", "B3:B5", categories_range="A4:A5", title="Ages", header=True)
+    template.add_chart("E20", "pie", "B4:B5", categories_range="A4:A5", size=(4, 6))
+    template.save(file_path)
+    ExcelTemplate(file_path).save(file_path)
+
+    charts = sorted(name for name in zipfile.ZipFile(file_path).namelist() if name.startswith("xl/charts/chart"))
+    assert len(charts) == 2
+    chart_xml = zipfile.ZipFile(file_path).read(charts[0]).decode()
+    assert "<c:barChart>" in chart_xml and "Ages" in chart_xml
+    assert "<c:f>Sheet1!$B$3</c:f>" in chart_xml
+    assert "<c:f>Sheet1!$A$4:$A$5</c:f>" in chart_xml
+    assert "<c:f>Sheet1!$B$4:$B$5</c:f>" in chart_xml
+    assert openpyxl.load_workbook(file_path)[sheet_name]["B4"].value == 25
+
+    with pytest.raises(ValueError):
+        template.add_chart("E3", "pie", "B4:C5")
+    with pytest.raises(ValueError):
+        template.add_chart("E3", "line", "B4:B5", categories_range="A3:A5")
+    with pytest.raises(ValueError):
+        template.add_chart("E3", "donut", "B4:B5")
+
+
+def test_finalize_for_distribution(create_test_excel):
+    """Test the print-ready bundle applied before distributing a workbook."""
+    file_path, sheet_name, header_row = create_test_excel
+    workbook = openpyxl.load_workbook(file_path)
+    workbook[sheet_name]["B6"] = "=SUM(B4:B5)"
+    workbook.properties.creator = "Jane Doe"
+    workbook.create_sheet("Notes")
+    workbook.save(file_path)
+
+    template = ExcelTemplate(file_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.finalize_for_distribution({"password": "secret", "active_cell": "B4"})
+    template.save(file_path)
+
+    workbook = openpyxl.load_workbook(file_path)
+    sheet = workbook[sheet_name]
+    assert sheet.sheet_view.showGridLines is False
+    assert sheet.freeze_panes == "A4"
+    assert sheet.print_area == f"'{sheet_name}'!$A$1:$C$6"
+    assert sheet.protection.sheet
+    assert sheet["B6"].protection.locked
+    assert not sheet["B4"].protection.locked
+    # Sheets without formulas are not protected
+    assert not workbook["Notes"].protection.sheet
+    assert workbook.active.title == sheet_name
+    assert not workbook.properties.creator
+
+    with pytest.raises(ValueError):
+        template.finalize_for_distribution({"gridlines": False})
+
+
+def test_finalize_for_distribution_with_preset(create_test_excel_with_3_sheets):
+    """Test turning steps off and activating another sheet."""
+    file_path, sheet_names, _ = create_test_excel_with_3_sheets
+    template = ExcelTemplate(file_path)
+    template.finalize_for_distribution({"hide_gridlines": False, "active_sheet": sheet_names[1]}, sheets=sheet_names[1:])
+    template.save(file_path)
+
+    workbook = openpyxl.load_workbook(file_path)
+    assert workbook.active.title == sheet_names[1]
+    assert all(workbook[name].sheet_view.showGridLines is not False for name in sheet_names)
+
+
+def test_find_broken_refs_after_removing_rows(tmp_path):
+    """Test that references to removed rows become #REF! and are reported."""
+    file_path = tmp_path / "formulas.xlsx"
+    workbook = openpyxl.Workbook()
+    sheet = workbook.active
+    sheet.title = "Data"
+    for row in range(1, 8):
+        sheet.cell(row=row, column=1, value=row)
+    sheet["B1"] = "=A5*2"
+    sheet["B2"] = "=SUM(A3:A6)"
+    summary = workbook.create_sheet("Summary")
+    summary["A1"] = "=Data!A5+1"
+    summary["A2"] = "=Missing!A1"
+    workbook.save(file_path)
+
+    template = ExcelTemplate(str(file_path))
+    assert [(b["sheet"], b["cell"]) for b in template.find_broken_refs()] == [("Summary", "A2")]
+
+    template.remove_row_from("Data", 5)
+    broken = template.find_broken_refs()
+    assert [(b["sheet"], b["cell"]) for b in broken] == [("Data", "B1"), ("Summary", "A1"), ("Summary", "A2")]
+    assert "#REF!" in broken[0]["formula"]
+    assert "Missing" in broken[2]["reason"]
+    template.save(str(file_path))
+
+    workbook = openpyxl.load_workbook(file_path)
+    assert workbook["Data"]["B2"].value == "=SUM(A3:A5)"
+
+
+def test_find_and_replace(create_test_excel):
+    """Test finding and replacing cell values with texts and regular expressions."""
+    file_path, sheet_name, _ = create_test_excel
+    template = ExcelTemplate(file_path)
+    assert [(f["sheet"], f["cell"]) for f in template.find("row")] == [(sheet_name, "A1"), (sheet_name, "A2")]
+    assert template.find("ROW") == []
+    assert [f["cell"] for f in template.find("ROW", case_sensitive=False)] == ["A1", "A2"]
+    assert [f["cell"] for f in template.find("Name", whole_cell=True)] == ["A3"]
+    assert [f["cell"] for f in template.find(re.compile(r"^\d+$"))] == ["B4", "B5"]
+
+    changed = template.replace(re.compile(r"(\w+) row"), r"\1 line")
+    assert [(c["cell"], c["value"]) for c in changed] == [("A1", "First line"), ("A2", "Second line")]
+    assert template.replace("25", "27", whole_cell=True) == [{"sheet": sheet_name, "cell": "B4", "value": "27"}]
+    with pytest.raises(ValueError):
+        template.find("")
+    template.save(file_path)
+
+    sheet = openpyxl.load_workbook(file_path)[sheet_name]
+    assert sheet["A1"].value == "First line"
+    assert sheet["B4"].value == 27
+
+
+def test_render_placeholders(tmp_path):
+    """Test rendering placeholders, with a block of rows repeated for a list."""
+    file_path = tmp_path / "invoice.xlsx"
+    workbook = openpyxl.Workbook()
+    sheet = workbook.active
+    sheet.title = "Invoice"
+    sheet["A1"] = "Invoice {{number}} for {{client.name}}"
+    sheet["B1"] = "{{number}}"
+    sheet["A3"] = "{{items.name}}"
+    sheet["B3"] = "{{items.qty}}"
+    sheet["C3"] = "=B3*2"
+    sheet["A4"] = "Total"
+    sheet["B4"] = "=SUM(B3:B3)"
+    sheet["A5"] = "{{notes}}"
+    sheet["A6"] = "{{unknown}}"
+    sheet.oddFooter.center.text = "{{client.name}}"
+    workbook.save(file_path)
+
+    template = ExcelTemplate(str(file_path))
+    context = {"number": 42, "client": {"name": "ACME"}, "items": [{"name": "Bolt", "qty": 3}, {"name": "Nut", "qty": 5}], "notes": []}
+    with pytest.warns(DataLossWarning):
+        warnings = template.render(context)
+    assert [(w["code"], w["cell"]) for w in warnings] == [("missing_in_context", "A6")]
+    with pytest.raises(TemplateError):
+        template.render({"unknown": {}}, strict=True)
+    template.save(str(file_path))
+
+    sheet = openpyxl.load_workbook(file_path)["Invoice"]
+    assert sheet["A1"].value == "Invoice 42 for ACME"
+    assert sheet["B1"].value == 42
+    assert [sheet.cell(row=row, column=1).value for row in range(3, 6)] == ["Bolt", "Nut", "Total"]
+    assert sheet["B4"].value == 5
+    assert sheet["C4"].value == "=B4*2"
+    assert sheet["B5"].value == "=SUM(B3:B4)"
+    assert sheet["A6"].value == "{{unknown}}"
+    assert sheet.oddFooter.center.text == "ACME"
+
+
+def test_add_and_read_comments(create_test_excel):
+    """Test adding comments to cells and reading them back."""
+    file_path, sheet_name, _ = create_test_excel
+    template = ExcelTemplate(file_path)
+    template.goto_sheet(sheet_name)
+    template.add_comment("B4", "Checked", author="Ann")
+    template.add_comment("A1", "Draft")
+    template.add_comment("B4", "Checked twice", author="Bob")
+    assert template.read_comments() == [
+        {"cell": "A1", "text": "Draft", "author": "ez_excel_mgt"},
+        {"cell": "B4", "text": "Checked twice", "author": "Bob"},
+    ]
+    template.save(file_path)
+
+    sheet = openpyxl.load_workbook(file_path)[sheet_name]
+    assert sheet["B4"].comment.text == "Checked twice"
+    assert sheet["B4"].comment.author == "Bob"
+    assert [c["cell"] for c in ExcelTemplate(file_path).read_comments(sheet_name)] == ["A1", "B4"]
@@ -0,0 +1,119 @@
+# This project uses Poetry for dependency management.
+import openpyxl
+import polars as pl
+import pytest
+from ez_excel_mgt import ExcelTemplate, collect
+
+
+def create_returned_excel(path, entity, rows, headers=("Name", "Amount")):
+    """Utility to create a workbook as returned by a recipient."""
+    workbook = openpyxl.Workbook()
+    sheet = workbook.active
+    sheet.title = "Data"
+    sheet["A1"] = "Entity"
+    sheet["B1"] = entity
+    for j, header in enumerate(headers):
+        sheet.cell(row=3, column=j + 1, value=header)
+    for i, row in enumerate(rows):
+        for j, value in enumerate(row):
+            sheet.cell(row=4 + i, column=j + 1, value=value)
+    workbook.save(path)
+    return str(path)
+
+
+def test_collect(tmp_path):
+    """Test collecting the data of returned workbooks into one DataFrame."""
+    first = create_returned_excel(tmp_path / "first.xlsx", "North", [("Alice", 10), ("Bob", 20)])
+    second = create_returned_excel(tmp_path / "second.xlsx", "South", [("Carol", 30)])
+    invalid = create_returned_excel(tmp_path / "invalid.xlsx", "East", [("Dave", 40)], headers=("Name", "Total"))
+
+    spec = {
+        "sheet": "Data",
+        "header": "A3",
+        "columns": ["Name", "Amount"],
+        "named_cells": {"Entity": "B1"},
+    }
+    df, errors = collect(spec, [first, second, invalid, str(tmp_path / "missing.xlsx")])
+
+    assert isinstance(df, pl.DataFrame)
+    assert df["Name"].to_list() == ["Alice", "Bob", "Carol"]
+    assert df["Amount"].to_list() == [10, 20, 30]
+    assert df["Entity"].to_list() == ["North", "North", "South"]
+    assert df["source_file"].to_list() == [first, first, second]
+    assert list(errors) == [invalid, str(tmp_path / "missing.xlsx")]
+    assert errors[invalid] == ["Header 'Amount' is missing in Data."]
+
+
+def test_to_polars(tmp_path):
+    """Test reading a sheet, or a range of it, back into a DataFrame."""
+    path = create_returned_excel(tmp_path / "report.xlsx", "North", [("Alice", 10), ("Bob", 20), ("Carol", 30)])
+    template = ExcelTemplate(path)
+
+    df = template.to_polars(header_row=3, sheet_name="Data")
+    assert isinstance(df, pl.DataFrame)
+    assert df.columns == ["Name", "Amount"]
+    assert df["Name"].to_list() == ["Alice", "Bob", "Carol"]
+    assert df["Amount"].to_list() == [10, 20, 30]
+
+    df = template.to_polars(range="A3:B5", sheet_name="Data")
+    assert df["Name"].to_list() == ["Alice", "Bob"]
+
+    template.goto_sheet("Data")
+    template.set_header_location("A3", "row")
+    template.fill_with(pl.DataFrame({"Name": ["Dave"], "Amount": [40]}), overwrite=True)
+    assert template.to_polars(sheet_name="Data")["Name"].to_list() == ["Dave", "Bob", "Carol"]
+
+
+def test_consolidate_files(tmp_path):
+    """Test appending the tables of several workbooks, matched by a glob pattern, to one sheet."""
+    returns = tmp_path / "returns"
+    returns.mkdir()
+    first = create_returned_excel(returns / "first.xlsx", "North", [("Alice", 10), ("Bob", 20)])
+    second = create_returned_excel(returns / "second.xlsx", "South", [("Carol", 30)])
+
+    path = tmp_path / "summary.xlsx"
+    workbook = openpyxl.Workbook()
+    workbook.active.title = "All"
+    workbook.active.append(["Name", "Amount", "File"])
+    workbook.save(path)
+
+    template = ExcelTemplate(str(path))
+    template.goto_sheet("All")
+    template.set_header_location("A1", "row")
+    rows = template.consolidate_files(str(returns / "*.xlsx"), "Data", header="A3", source_column="File")
+    assert rows == {first: 2, second: 1}
+    assert template.to_polars(sheet_name="All")["Name"].to_list() == ["Alice", "Bob", "Carol"]
+    assert template.to_polars(sheet_name="All")["File"].to_list() == [first, first, second]
+
+    assert template.consolidate_files([first], "Data", range="A3:B4") == {first: 1}
+    assert template.to_polars(sheet_name="All")["Amount"].to_list() == [10, 20, 30, 10]
+
+
+def test_read_files_in_worker_threads(tmp_path):
+    """Test reading many workbooks in worker threads, the results keeping the order of the files."""
+    returns = tmp_path / "returns"
+    returns.mkdir()
+    files = [create_returned_excel(returns / f"{i:02}.xlsx", f"Entity {i}", [(f"Item {i}", i)]) for i in range(12)]
+    spec = {"sheet": "Data", "header": "A3", "named_cells": {"Entity": "B1"}}
+
+    for max_workers in (None, 1, 4):
+        df, errors = collect(spec, files, max_workers=max_workers)
+        assert df["Amount"].to_list() == list(range(12))
+        assert df["source_file"].to_list() == files
+        assert errors == {}
+    with pytest.raises(ValueError, match="max_workers"):
+        collect(spec, files, max_workers=0)
+
+    path = tmp_path / "summary.xlsx"
+    workbook = openpyxl.Workbook()
+    workbook.active.title = "All"
+    workbook.active.append(["Name", "Amount"])
+    workbook.save(path)
+    template = ExcelTemplate(str(path))
+    template.goto_sheet("All")
+    template.set_header_location("A1", "row")
+    rows = template.consolidate_files(str(returns / "*.xlsx"), "Data", header="A3", max_workers=3)
+    assert rows == {file_path: 1 for file_path in files}
+    assert template.to_polars(sheet_name="All")["Name"].to_list() == [f"Item {i}" for i in range(12)]
+    with pytest.raises(FileNotFoundError):
+        template.consolidate_files(files + [str(returns / "missing.xlsx")], "Data", header="A3", max_workers=3)
@@ -1,7 +1,9 @@
 # This project uses Poetry for dependency management.
 from pathlib import Path
 import openpyxl
-from ez_excel_mgt import ExcelTemplate
+from openpyxl.styles import Font
+import pytest
+from ez_excel_mgt import ExcelTemplate, DataLossWarning, RangeError
 
 
 def test_copy_range_between_files(create_test_excel, create_empty_test_excel):
@@ -72,3 +74,298 @@ def test_copy_range_between_files_and_coerce(create_test_excel_float, create_emp
     assert sheet["A1"].value == 1234
     assert sheet["A2"].value == 9876
 
+
+
+def test_copy_range_and_coerce_warns_on_loss(create_test_excel_float, create_empty_test_excel):
+    """Test that lossy and failed integer coercions are reported as warnings."""
+    source_file_path, source_sheet_name, _header_row = create_test_excel_float
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell=(1, 1))
+
+    with pytest.warns(DataLossWarning) as record:
+        template.copy_range_from(source_file_path, source_sheet_name, ((3, 4), (5, 4)), None, 'int')
+
+    messages = [str(w.message) for w in record]
+    assert any("D3" in m and "not a number" in m for m in messages)
+    assert any("D4" in m and "without loss" in m for m in messages)
+
+
+def test_copy_range_outside_excel_limits(create_test_excel, create_empty_test_excel):
+    """Test that a copy overflowing the last row of Excel is rejected before writing."""
+    source_file_path, source_sheet_name, _header_row = create_test_excel
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell=(1048575, 1))
+
+    with pytest.raises(ValueError, match="copy_range_from: cell B1048577"):
+        template.copy_range_from(source_file_path, source_sheet_name, "A3:B5")
+
+
+@pytest.mark.parametrize("source_range, expected", [
+    ("Sheet1!A4:B5", {"A1": "Irène", "B2": 26}),
+    ("A4:A", {"A1": "Irène", "A2": "Matthieu", "A3": None}),
+    ("B:B", {"A4": 25, "A5": 26, "A6": None}),
+    ("4:5", {"A1": "Irène", "B2": 26, "C1": None}),
+])
+def test_copy_range_with_a1_references(create_test_excel, create_empty_test_excel, source_range, expected):
+    """Test copying sheet-qualified, whole column, whole row and open-ended ranges."""
+    source_file_path, source_sheet_name, _header_row = create_test_excel
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell="A1")
+    template.copy_range_from(source_file_path, source_sheet_name, source_range)
+    with pytest.raises(RangeError):
+        template.copy_range_from(source_file_path, source_sheet_name, "Other!A1:B2")
+    template.save(dest_file_path)
+
+    sheet = openpyxl.load_workbook(dest_file_path)[dest_sheet_name]
+    for cell, value in expected.items():
+        assert sheet[cell].value == value
+
+
+def test_copy_range_with_defined_names(create_test_excel, create_empty_test_excel):
+    """Test defining names and copying the ranges they refer to."""
+    source_file_path, source_sheet_name, _header_row = create_test_excel
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    source = ExcelTemplate(source_file_path)
+    source.goto_sheet(source_sheet_name)
+    source.define_name("People", "A3:A4")
+    # A new definition replaces the previous one
+    source.define_name("People", "A4:B5")
+    with pytest.raises(ValueError, match="Invalid name"):
+        source.define_name("B5", "A4:B5")
+    source.save(source_file_path)
+
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell="A1")
+    template.copy_range_from(source_file_path, source_sheet_name, "people")
+    with pytest.raises(RangeError, match="not defined"):
+        template.copy_range_from(source_file_path, source_sheet_name, "Missing")
+    template.save(dest_file_path)
+
+    sheet = openpyxl.load_workbook(dest_file_path)[dest_sheet_name]
+    assert [[cell.value for cell in row] for row in sheet["A1:B2"]] == [["Irène", 25], ["Matthieu", 26]]
+    destinations = openpyxl.load_workbook(source_file_path).defined_names["People"].destinations
+    assert list(destinations) == [(source_sheet_name, "$A$4:$B$5")]
+
+
+def test_copy_range_from_one_sheet_of_many(create_test_excel_with_monthly_sheets, create_empty_test_excel):
+    """Test copying from one sheet of a workbook of several, the names of the workbook resolving as before."""
+    source_file_path, months = create_test_excel_with_monthly_sheets
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    source = ExcelTemplate(source_file_path)
+    source.goto_sheet("Mar")
+    source.define_name("MarchSales", "B1:C1")
+    source.save(source_file_path)
+
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell="A1")
+    template.copy_range_from(source_file_path, "F*", "B1:C2")
+    template.goto_cell("A3")
+    template.copy_range_from(source_file_path, "Mar", "MarchSales")
+    with pytest.raises(RangeError, match="'Mar'"):
+        template.copy_range_from(source_file_path, "Jan", "MarchSales")
+    template.goto_cell("A4")
+    template.aggregate_across_sheets(source_file_path, ["Jan", "Mar"], "B1:C1", action="sum")
+    template.save(dest_file_path)
+
+    sheet = openpyxl.load_workbook(dest_file_path)[dest_sheet_name]
+    assert [[cell.value for cell in row] for row in sheet["A1:B4"]] == [[20, 2], [10, 4], [30, 3], [40, 4]]
+
+
+def test_copy_range_with_styles(create_test_excel, create_empty_test_excel):
+    """Test that styles, merges inside the range and column widths are copied with the values."""
+    source_file_path, source_sheet_name, _header_row = create_test_excel
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    workbook = openpyxl.load_workbook(source_file_path)
+    sheet = workbook[source_sheet_name]
+    sheet["A3"].font = Font(bold=True)
+    sheet["B4"].number_format = "0.00"
+    sheet.merge_cells("A1:C1")
+    sheet.merge_cells("A5:D5")
+    sheet.column_dimensions["B"].width = 30
+    workbook.save(source_file_path)
+
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell=(2, 2))
+    template.copy_range_from(source_file_path, source_sheet_name, "A1:C5", with_styles=True)
+    template.save(dest_file_path)
+
+    sheet = openpyxl.load_workbook(dest_file_path)[dest_sheet_name]
+    assert sheet["B2"].value == "First row"
+    assert sheet["B4"].font.bold
+    assert sheet["C5"].value == 25
+    assert sheet["C5"].number_format == "0.00"
+    assert [str(merge) for merge in sheet.merged_cells.ranges] == ["B2:D2"]
+    assert sheet.column_dimensions["C"].width == 30
+
+
+def test_copy_range_pastes_formulas(create_test_excel, create_empty_test_excel):
+    """Test that formulas are pasted with their relative references moved to the destination."""
+    source_file_path, source_sheet_name, _header_row = create_test_excel
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    workbook = openpyxl.load_workbook(source_file_path)
+    sheet = workbook[source_sheet_name]
+    sheet["D4"] = "=B4*2"
+    sheet["D5"] = '=$B$4+B5&"B5"'
+    workbook.save(source_file_path)
+
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell=(10, 1))
+    template.copy_range_from(source_file_path, source_sheet_name, "A3:D5", paste="formulas")
+    template.goto_cell("A1")
+    template.copy_range_from(source_file_path, source_sheet_name, "D4:D4", paste="formulas")
+    template.save(dest_file_path)
+
+    sheet = openpyxl.load_workbook(dest_file_path)[dest_sheet_name]
+    assert sheet["A10"].value == "Name"
+    assert sheet["D11"].value == "=B11*2"
+    assert sheet["D12"].value == '=$B$4+B12&"B5"'
+    assert sheet["A1"].value == "=#REF!*2"
+
+    with pytest.raises(ValueError, match="Invalid paste"):
+        template.copy_range_from(source_file_path, source_sheet_name, "A3:D5", paste="links")
+
+
+def test_copy_range_with_transform(create_test_excel, create_empty_test_excel):
+    """Test that the transform gets each value with its source and destination cells, and gives the value written."""
+    source_file_path, source_sheet_name, _header_row = create_test_excel
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    calls = []
+
+    def transform(value, source, dest):
+        calls.append((value, source, dest))
+        if isinstance(value, str):
+            return None if value == "Gender" else value.upper()
+        return value * 12
+
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell=(2, 2))
+    template.copy_range_from(source_file_path, source_sheet_name, "A3:C5", transform=transform)
+    template.save(dest_file_path)
+
+    assert ("Name", "A3", "B2") in calls
+    assert (26, "B5", "C4") in calls
+    sheet = openpyxl.load_workbook(dest_file_path)[dest_sheet_name]
+    assert [[cell.value for cell in row] for row in sheet["B2:D4"]] == [
+        ["NAME", "AGE", None], ["IRÈNE", 300, None], ["MATTHIEU", 312, None]
+    ]
+
+    with pytest.raises(ZeroDivisionError):
+        template.copy_range_from(source_file_path, source_sheet_name, "A3:C5", transform=lambda value, source, dest: 1 / 0)
+
+
+def test_copy_range_paste_special(create_test_excel, create_empty_test_excel):
+    """Test pasting the formats only, or everything, and skipping the blank cells of the source."""
+    source_file_path, source_sheet_name, _header_row = create_test_excel
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    workbook = openpyxl.load_workbook(source_file_path)
+    sheet = workbook[source_sheet_name]
+    sheet["C4"].font = Font(bold=True)
+    sheet["D4"] = "=B4*2"
+    workbook.save(source_file_path)
+    workbook = openpyxl.load_workbook(dest_file_path)
+    sheet = workbook[dest_sheet_name]
+    sheet["C2"] = "kept"
+    sheet["C12"] = "overwritten"
+    workbook.save(dest_file_path)
+
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell=(1, 1))
+    template.copy_range_from(source_file_path, source_sheet_name, "A3:D5", skip_blanks=True)
+    template.goto_cell("F1")
+    template.copy_range_from(source_file_path, source_sheet_name, "A3:D5", paste="formats")
+    template.goto_cell("A11")
+    template.copy_range_from(source_file_path, source_sheet_name, "A3:D5", paste="all")
+    template.save(dest_file_path)
+
+    sheet = openpyxl.load_workbook(dest_file_path)[dest_sheet_name]
+    assert sheet["A2"].value == "Irène"
+    assert sheet["C2"].value == "kept"
+    assert sheet["F1"].value is None
+    assert sheet["H2"].font.bold
+    assert sheet["C12"].value is None
+    assert sheet["C12"].font.bold
+    assert sheet["D12"].value == "=B12*2"
+
+    with pytest.raises(ValueError, match="Invalid paste"):
+        template.copy_range_from(source_file_path, source_sheet_name, "A3:D5", paste="comments")
+
+
+def test_copy_range_with_operation(create_test_excel, create_empty_test_excel):
+    """Test that the numbers copied are combined with the values of the destination."""
+    source_file_path, source_sheet_name, _header_row = create_test_excel
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    workbook = openpyxl.load_workbook(dest_file_path)
+    sheet = workbook[dest_sheet_name]
+    sheet["B1"] = 100
+    sheet["B2"] = "n/a"
+    sheet["A1"] = "Someone"
+    workbook.save(dest_file_path)
+
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell=(1, 1))
+    with pytest.warns(DataLossWarning, match="n/a"):
+        template.copy_range_from(source_file_path, source_sheet_name, "A4:B5", operation="add")
+    template.goto_cell("B1")
+    template.copy_range_from(source_file_path, source_sheet_name, "B5:B5", operation="multiply")
+    template.save(dest_file_path)
+
+    sheet = openpyxl.load_workbook(dest_file_path)[dest_sheet_name]
+    assert sheet["A1"].value == "Irène"
+    assert sheet["A2"].value == "Matthieu"
+    assert sheet["B1"].value == (100 + 25) * 26
+    assert sheet["B2"].value == "n/a"
+
+    with pytest.raises(ValueError, match="Invalid operation"):
+        template.copy_range_from(source_file_path, source_sheet_name, "B4:B5", operation="modulo")
+
+
+def test_copy_many_ranges(create_test_excel, create_empty_test_excel):
+    """Test copying several blocks of a sheet in one call, each to its own cell."""
+    source_file_path, source_sheet_name, _header_row = create_test_excel
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name)
+    template.copy_range_from(source_file_path, source_sheet_name, [("A4:A5", "B2"), (((4, 2), (5, 2)), (2, 5)), ("A1", "H1")], transpose=False)
+    with pytest.raises(RangeError):
+        template.copy_range_from(source_file_path, source_sheet_name, [("A1", "A1"), ("A4:B5", "XFD1")])
+    with pytest.raises(ValueError, match="pairs"):
+        template.copy_range_from(source_file_path, source_sheet_name, [("A4:A5",)])
+    template.save(dest_file_path)
+
+    sheet = openpyxl.load_workbook(dest_file_path)[dest_sheet_name]
+    assert [sheet["B2"].value, sheet["B3"].value] == ["Irène", "Matthieu"]
+    assert [sheet["E2"].value, sheet["E3"].value] == [25, 26]
+    assert sheet["H1"].value == "First row"
+    assert sheet["A1"].value is None
+
+
+def test_copy_range_from_template(create_test_excel, create_empty_test_excel):
+    """Test copying from another template, as it is in memory, rather than from a file."""
+    source_file_path, source_sheet_name, _header_row = create_test_excel
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    source = ExcelTemplate(source_file_path)
+    source.write_cell(source_sheet_name, "B5", 27)
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell=(1, 1))
+    template.copy_range_from(source, source_sheet_name, "A4:B5")
+    with pytest.raises(TypeError, match="ExcelTemplate"):
+        template.copy_range_from(template, dest_sheet_name, "A1:B2")
+    template.save(dest_file_path)
+
+    sheet = openpyxl.load_workbook(dest_file_path)[dest_sheet_name]
+    assert [[cell.value for cell in row] for row in sheet["A1:B2"]] == [["Irène", 25], ["Matthieu", 27]]
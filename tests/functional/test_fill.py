@@ -1,10 +1,13 @@
 # This project uses Poetry for dependency management.
  
+import datetime
 import polars as pl
 import pandas as pd
+import pyarrow as pa
 import pytest
 import openpyxl
-from ez_excel_mgt import ExcelTemplate
+from openpyxl.styles import Border, PatternFill, Side
+from ez_excel_mgt import ColumnMismatchError, ExcelTemplate, DataLossWarning
 
 
 def generate_test_data(data, data_type="polars"):
@@ -14,6 +17,8 @@ def generate_test_data(data, data_type="polars"):
         return pl.DataFrame(data)
     elif data_type == "pandas":
         return pd.DataFrame(data)
+    elif data_type == "arrow":
+        return pa.table(data)
     elif data_type == "dict":
         return data
     elif data_type == "list":
@@ -22,7 +27,7 @@ def generate_test_data(data, data_type="polars"):
         raise ValueError(f"Unsupported data type: {data_type}")
     
 
-@pytest.mark.parametrize("data_type", ["polars", "pandas", "dict", "list"])
+@pytest.mark.parametrize("data_type", ["polars", "pandas", "arrow", "dict", "list"])
 def test_fill_sheet(create_test_excel, data_type):
     """Test inserting data at the end of the sheet."""
     excel_path, sheet_name, header_row = create_test_excel
@@ -376,3 +381,1211 @@ def test_fill_sheet_with_multiple_overwrite(create_test_excel, data_type):
     assert sheet["A6"].value == "Tom"
     assert sheet["B6"].value == 35
     assert sheet["C6"].value == "M"
+
+
+def test_fill_sheet_and_protect(create_test_excel):
+    """Test unlocking the filled cells and protecting the rest of the sheet."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({
+        "Name": ["Alice", "Bob"],
+        "Age": [25, None],
+    }, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df, overwrite=True, skip_null=True, protect=True, password="secret")
+    template.save(excel_path)
+
+    workbook = openpyxl.load_workbook(excel_path)
+    sheet = workbook[sheet_name]
+
+    assert sheet.protection.sheet
+    assert not sheet["A4"].protection.locked
+    assert not sheet["B5"].protection.locked
+    assert sheet["A3"].protection.locked
+    assert sheet["A1"].protection.locked
+
+
+def test_fill_sheet_with_provenance(create_test_excel):
+    """Test recording where each filled column comes from."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({
+        "Name": ["Alice", "Bob"],
+        "Age": [25, 30],
+    }, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df, overwrite=True, provenance={"file": "people.csv", "query_id": "Q42"})
+    template.save(excel_path)
+
+    template = ExcelTemplate(excel_path)
+    provenance = sorted(template.get_provenance(sheet_name), key=lambda p: p["range"])
+
+    assert provenance == [
+        {"sheet": sheet_name, "range": "A4:A5", "column": "Name", "source_column": "Name", "file": "people.csv", "query_id": "Q42"},
+        {"sheet": sheet_name, "range": "B4:B5", "column": "Age", "source_column": "Age", "file": "people.csv", "query_id": "Q42"},
+    ]
+    assert template.sheet_names() == [sheet_name]
+    assert openpyxl.load_workbook(excel_path)["_ezxl_provenance"].sheet_state == "veryHidden"
+
+
+def test_fill_sheet_rediscovers_registered_regions(create_test_excel):
+    """Test that a later run finds the header location registered by a previous fill."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(generate_test_data({"Name": ["Alice", "Bob"], "Age": [25, 30]}), overwrite=True)
+    template.save(excel_path)
+
+    # No header location given: the registered one is used and the data is appended
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.fill_with(generate_test_data({"Name": ["Carol"], "Age": [35]}))
+    template.save(excel_path)
+
+    regions = ExcelTemplate(excel_path).get_regions(sheet_name)
+    assert [(r["header"], r["mode"], r["range"]) for r in regions] == [("A3", "row", "A4:C5"), ("A3", "row", "A6:C6")]
+    assert all(r["filled_at"].endswith("Z") for r in regions)
+
+    workbook = openpyxl.load_workbook(excel_path)
+    sheet = workbook[sheet_name]
+    assert [sheet.cell(row=row, column=1).value for row in range(4, 7)] == ["Alice", "Bob", "Carol"]
+    assert workbook["_ezxl_meta"].sheet_state == "veryHidden"
+
+
+def test_reset_data_reuses_template(create_test_excel, tmp_path):
+    """Test clearing the filled regions to fill the same template instance again."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    for i, names in enumerate([["Alice", "Bob", "Carol"], ["Dave"]]):
+        cleared = template.reset_data()
+        assert len(cleared) == i
+        template.fill_with(generate_test_data({"Name": names, "Age": [30] * len(names)}), overwrite=True)
+        template.save(str(tmp_path / f"report_{i}.xlsx"))
+
+    assert [r["range"] for r in template.reset_data(sheet_name)] == ["A4:C6", "A4:C4"]
+
+    sheet = openpyxl.load_workbook(tmp_path / "report_1.xlsx")[sheet_name]
+    assert sheet["A1"].value == "First row"
+    assert sheet["A3"].value == "Name"
+    assert [sheet.cell(row=row, column=1).value for row in range(4, 7)] == ["Dave", None, None]
+
+
+def test_fill_sheet_with_row_colors(create_test_excel):
+    """Test coloring each written row from a column of the data."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({
+        "Name": ["Alice", "Bob", "Carol"],
+        "Age": [25, 30, 35],
+        "status_color": ["red", None, "#00FF00"],
+    }, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df, overwrite=True, style_from="status_color")
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert [sheet.cell(row=4, column=col).fill.fgColor.rgb for col in (1, 2)] == ["FFFF0000", "FFFF0000"]
+    assert sheet["A5"].fill.fill_type is None
+    assert sheet["B6"].fill.fgColor.rgb == "FF00FF00"
+    # The style column is not written
+    assert all(sheet.cell(row=row, column=4).value is None for row in range(3, 7))
+
+
+def test_fill_sheet_with_invalid_row_colors(create_test_excel):
+    """Test that invalid colors leave their row uncolored with a warning."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({"Name": ["Alice"], "Age": [25], "status_color": ["reddish"]}, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    with pytest.warns(DataLossWarning, match="reddish"):
+        template.fill_with(df, overwrite=True, style_from="status_color")
+    with pytest.raises(ValueError):
+        template.fill_with(df, overwrite=True, style_from="missing_column")
+
+
+def test_fill_sheet_with_subtotals(create_test_excel):
+    """Test interleaving subtotal rows, with SUBTOTAL formulas and an outline, between the groups."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({
+        "Name": ["Alice", "Bob", "Carol", "Dave", "Eve"],
+        "Age": [30, 35, 40, 45, 50],
+        "Gender": ["F", "F", "M", "M", "M"],
+    }, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df, overwrite=True, subtotals={"by": "Gender", "agg": {"Age": "sum"}})
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert [sheet.cell(row=row, column=1).value for row in range(4, 12)] == [
+        "Alice", "Bob", None, "Carol", "Dave", "Eve", None, None,
+    ]
+    assert sheet["C6"].value == "F Total"
+    assert sheet["B6"].value == "=SUBTOTAL(9,B4:B5)"
+    assert sheet["C10"].value == "M Total"
+    assert sheet["B10"].value == "=SUBTOTAL(9,B7:B9)"
+    assert sheet["C11"].value == "Grand Total"
+    assert sheet["B11"].value == "=SUBTOTAL(9,B4:B9)"
+    assert sheet["C6"].font.bold
+    assert sheet.row_dimensions[4].outline_level == 2
+    assert sheet.row_dimensions[6].outline_level == 1
+    assert not sheet.row_dimensions[11].outline_level
+
+    # Values instead of formulas, with group headers and no grand total
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df, overwrite=True, subtotals={
+        "by": "Gender", "agg": {"Age": "avg"}, "formulas": False, "group_headers": True, "grand_total": False, "outline": False,
+    })
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert sheet["C4"].value == "F"
+    assert sheet["B7"].value == 32.5
+    assert sheet["C12"].value == "M Total"
+    assert sheet["B12"].value == 45
+    assert sheet.max_row == 12
+
+    with pytest.raises(ValueError):
+        template.fill_with(df, subtotals={"by": "Gender", "agg": {"Name": "sum"}})
+
+
+def test_fill_sheet_with_fx(create_test_excel):
+    """Test converting amounts with the rate of the currency of their row, keeping the original amounts."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({
+        "Name": ["Alice", "Bob", "Carol"],
+        "Age": [100, 200, 300],
+        "Gender": ["USD", "EUR", "GBP"],
+    }, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    with pytest.warns(DataLossWarning, match="No fx rate for currency 'GBP'"):
+        template.fill_with(df, overwrite=True, fx={"rates": {"USD": 0.5, "EUR": 1}, "currency": "Gender", "columns": {"Age": "Age EUR"}})
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert [sheet.cell(row=row, column=2).value for row in range(4, 7)] == [100, 200, 300]
+    assert [sheet.cell(row=row, column=4).value for row in range(4, 7)] == [50, 200, None]
+    assert sheet["B4"].number_format == '#,##0.00 "USD"'
+    assert sheet["B5"].number_format == '#,##0.00 "EUR"'
+    assert sheet["D4"].number_format == "#,##0.00"
+    assert df.columns == ["Name", "Age", "Gender"]
+
+    with pytest.raises(ValueError):
+        template.fill_with(df, fx={"rates": {"USD": 0.5}, "currency": "Gender", "columns": ["Name"]})
+
+
+def test_fill_sheet_with_generated_columns(create_test_excel):
+    """Test writing row numbers and constants generated for each row."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({"Name": ["Alice", "Bob"]}, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df, overwrite=True, generated_columns={"Age": "row_number:10", "Gender": "constant:2024-06"})
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert [[cell.value for cell in row] for row in sheet["A4:C5"]] == [["Alice", 10, "2024-06"], ["Bob", 11, "2024-06"]]
+    assert df.columns == ["Name"]
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df, overwrite=True, generated_columns={"Age": "row_number"})
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert [sheet.cell(row=row, column=2).value for row in (4, 5)] == [1, 2]
+
+    with pytest.raises(ValueError):
+        template.fill_with(df, generated_columns={"Age": "uuid"})
+    with pytest.raises(ValueError):
+        template.fill_with(df, generated_columns={"Name": "row_number"})
+
+
+def test_fill_sheet_with_verification(create_test_excel):
+    """Test reading the filled region back from the saved bytes and comparing it with the data."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({
+        "Name": ["Alice", "Bob\r\nJunior", None],
+        "Age": [30, None, 12345678901234567],
+        "Gender": ["F", "M", "M"],
+    }, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df, overwrite=True, verify_fill=True)
+    template.fill_with(df, overwrite=True, skip_null=True, verify_fill=True)
+    template.fill_with(df, overwrite=True, verify_fill=True, subtotals={"by": "Gender", "agg": {"Age": "count"}})
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert sheet["A5"].value == "Bob\nJunior"
+
+
+def test_fill_crosstab(create_test_excel):
+    """Test pivoting data into a matrix with row and column headers and totals."""
+    excel_path, sheet_name, _ = create_test_excel
+
+    df = generate_test_data({
+        "product": ["Apples", "Pears", "Apples", "Apples"],
+        "month": ["Jan", "Jan", "Feb", "Feb"],
+        "amount": [10, 20, 30, 5],
+    }, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name, cell="F1")
+    assert template.fill_crosstab(df, index="product", columns="month", values="amount", totals=True) == "F1:I4"
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert [[cell.value for cell in row] for row in sheet["F1:I4"]] == [
+        ["product", "Jan", "Feb", "Total"],
+        ["Apples", 10, 35, 45],
+        ["Pears", 20, None, 20],
+        ["Total", 30, 35, 65],
+    ]
+    assert sheet["I4"].font.bold
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name, cell="F1")
+    assert template.fill_crosstab(df, index="product", columns="month", values="product", agg="count") == "F1:H3"
+
+    with pytest.raises(ValueError):
+        template.fill_crosstab(df, index="product", columns="month", values="product")
+
+
+def test_fill_gantt(create_test_excel):
+    """Test laying out tasks on a grid of date columns with their periods filled."""
+    excel_path, sheet_name, _ = create_test_excel
+
+    df = generate_test_data({
+        "task": ["Design", "Build", "Review"],
+        "start": ["2024-06-03", "2024-06-05", "2024-06-09"],
+        "end": ["2024-06-04", "2024-06-10", "2024-06-01"],
+        "color": [None, "red", None],
+    }, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name, cell="F1")
+    with pytest.warns(DataLossWarning, match="Review"):
+        assert template.fill_gantt(df, task="task", start="start", end="end", color_from="color") == "F1:N4"
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert sheet["F1"].value == "task"
+    assert sheet["G1"].value.date().isoformat() == "2024-06-03"
+    assert sheet["N1"].value.date().isoformat() == "2024-06-10"
+    assert [sheet.cell(row=2, column=col).fill.fgColor.rgb for col in (7, 8)] == ["FF4472C4", "FF4472C4"]
+    assert sheet["I2"].fill.fill_type is None
+    assert sheet["I3"].fill.fgColor.rgb == "FFFF0000"
+    assert sheet["F4"].value == "Review"
+    assert all(sheet.cell(row=4, column=col).fill.fill_type is None for col in range(7, 15))
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name, cell="F1")
+    with pytest.warns(DataLossWarning):
+        assert template.fill_gantt(df, task="task", start="start", end="end", period="week") == "F1:H4"
+
+    with pytest.raises(ValueError):
+        template.fill_gantt(df, task="task", start="start", end="end", period="year")
+
+
+def test_fill_sheet_grows_excel_table(create_test_excel_with_table):
+    """Test that rows appended below an Excel Table become part of it."""
+    excel_path, sheet_name, header_row = create_test_excel_with_table
+
+    df = generate_test_data({"Name": ["Alice", "Bob"], "Age": [30, 35], "City": ["Nice", "Lille"]}, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df)
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert sheet.tables["People"].ref == "A1:C5"
+    assert sheet["A5"].value == "Bob"
+
+    # Overwriting with fewer rows shrinks the table to the data
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(generate_test_data({"Name": ["Carol"], "Age": [40], "City": ["Metz"]}, "polars"), overwrite=True)
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert sheet.tables["People"].ref == "A1:C2"
+
+
+@pytest.mark.parametrize("data_type", ["polars", "pandas", "dict"])
+def test_fill_sheet_writes_typed_values(create_test_excel, data_type):
+    """Test that numbers, booleans and texts keep the type of their column."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({
+        "Name": ["007", "TRUE"],
+        "Age": [25.5, 30.0],
+        "Gender": [True, False],
+    }, data_type)
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df, overwrite=True)
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert sheet["A4"].value == "007"
+    assert sheet["A5"].value == "TRUE"
+    assert sheet["B4"].value == 25.5
+    assert sheet["B5"].data_type == "n"
+    assert sheet["C4"].value is True
+    assert sheet["C5"].value is False
+
+
+@pytest.mark.parametrize("data_type", ["polars", "pandas", "dict"])
+def test_fill_sheet_writes_dates(create_test_excel, data_type):
+    """Test that dates and datetimes are written as Excel dates with a number format."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({
+        "Name": ["Irène", "Matthieu"],
+        "Age": [datetime.datetime(2024, 1, 15, 12, 30), datetime.datetime(2024, 1, 16, 6, 0)],
+    }, data_type)
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df, overwrite=True, date_format="dd/mm/yyyy")
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert sheet["B4"].value == datetime.datetime(2024, 1, 15, 12, 30)
+    assert sheet["B5"].value == datetime.datetime(2024, 1, 16, 6, 0)
+    assert sheet["B4"].number_format == "dd/mm/yyyy hh:mm:ss"
+
+
+def test_fill_sheet_writes_date_columns(create_test_excel):
+    """Test that Polars dates and durations get a default number format."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = pl.DataFrame({
+        "Age": [datetime.date(2024, 1, 15)],
+        "Gender": [datetime.timedelta(hours=36)],
+    })
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df, overwrite=True)
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert sheet["B4"].value == datetime.datetime(2024, 1, 15)
+    assert sheet["B4"].number_format == "yyyy-mm-dd"
+    assert sheet["C4"].number_format == "[h]:mm:ss"
+
+
+@pytest.mark.parametrize("data_type", ["polars", "pandas"])
+def test_fill_sheet_normalizes_newlines(create_test_excel, data_type):
+    """Test that CRLF and CR line breaks are written as wrapped multi-line text."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({
+        "Name": ["Alice\r\nSmith", "Bob\rJones", "Carol"],
+        "Gender": ["F_x000D_\n", "M\x07", "F_x0041_"],
+    }, data_type)
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df, overwrite=True)
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert sheet["A4"].value == "Alice\nSmith"
+    assert sheet["A5"].value == "Bob\nJones"
+    assert sheet["A4"].alignment.wrap_text
+    assert not sheet["A6"].alignment.wrap_text
+    assert "_x000D_" not in sheet["C4"].value
+    assert sheet["C6"].value.startswith("F")
+
+
+def test_fill_sheet_with_number_locale(create_test_excel_float):
+    """Test rendering numbers with locale separators."""
+    excel_path, sheet_name, header_row = create_test_excel_float
+
+    df = generate_test_data({
+        "Name": ["Alice", "Bob"],
+        "Revenue": [1234567.5, -42.25],
+    }, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.set_number_locale(",", " ")
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df, overwrite=True)
+    template.goto_cell("F1")
+    template.set_value(98765.125)
+    template.save(excel_path)
+
+    workbook = openpyxl.load_workbook(excel_path)
+    sheet = workbook[sheet_name]
+
+    assert sheet["A4"].value == "Alice"
+    assert sheet["D4"].value == "1 234 567,5"
+    assert sheet["D5"].value == "-42,25"
+    assert sheet["F1"].value == "98 765,125"
+
+
+def test_fill_sheet_with_number_locale_and_large_integers(create_test_excel):
+    """Test that integers beyond the precision of floats keep all their digits with locale separators."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({
+        "Name": ["Alice", "Bob"],
+        "Age": [1234567890123456789, -12345678901234567],
+    }, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.set_number_locale(",", " ")
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df, overwrite=True)
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+
+    assert sheet["B4"].value == "1 234 567 890 123 456 789"
+    assert sheet["B5"].value == "-12 345 678 901 234 567"
+
+
+@pytest.mark.parametrize("data_type", ["polars", "pandas", "dict"])
+def test_fill_sheet_with_large_integers(create_test_excel, data_type):
+    """Test that integers of 12+ digits are not displayed in scientific notation."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({
+        "Name": ["Alice", "Bob", "Carol"],
+        "Age": [123456789012, 12345678901234, 1234567890123456789],
+    }, data_type)
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df, overwrite=True)
+    template.write_cell(sheet_name, "E1", 987654321098)
+    template.save(excel_path)
+
+    workbook = openpyxl.load_workbook(excel_path)
+    sheet = workbook[sheet_name]
+
+    assert sheet["B4"].value == 123456789012
+    assert sheet["B4"].number_format == "0"
+    assert sheet["B5"].value == 12345678901234
+    assert sheet["B5"].number_format == "0"
+    # Beyond Excel's 15 digits of precision, integers are written as text
+    assert sheet["B6"].value == "1234567890123456789"
+    assert sheet["E1"].value == 987654321098
+    assert sheet["E1"].number_format == "0"
+
+
+def test_fill_sheet_with_integer_format(create_test_excel):
+    """Test explicit control of the integer number format."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({
+        "Name": ["Alice", "Bob"],
+        "Age": [25, 1234567],
+    }, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.set_integer_format("#,##0", min_digits=4)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df, overwrite=True)
+    template.save(excel_path)
+
+    workbook = openpyxl.load_workbook(excel_path)
+    sheet = workbook[sheet_name]
+
+    assert sheet["B4"].number_format == "General"
+    assert sheet["B5"].value == 1234567
+    assert sheet["B5"].number_format == "#,##0"
+
+
+def test_fill_sheet_with_defaults(create_test_excel):
+    """Test that fill_with falls back to the defaults profile of the template and of the sheet."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({
+        "Name": ["Alice", None, "Carol"],
+        "Age": [30, 35, None],
+    }, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.set_defaults(overwrite=True, skip_null=True, styles={"Age": "0.00"})
+    template.set_defaults(skip_null=False, null_display="n/a", sheet_name=sheet_name)
+    assert template.get_defaults(sheet_name)["overwrite"] is True
+    assert template.get_defaults(sheet_name)["skip_null"] is False
+
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df)
+    template.save(excel_path)
+
+    workbook = openpyxl.load_workbook(excel_path)
+    sheet = workbook[sheet_name]
+
+    assert sheet["A4"].value == "Alice"
+    assert sheet["A5"].value == "n/a"
+    assert sheet["B4"].value == 30
+    assert sheet["B4"].number_format == "0.00"
+    assert sheet["B6"].value == "n/a"
+
+
+def test_fill_sheet_with_explicit_options_over_defaults(create_test_excel):
+    """Test that options given to fill_with take precedence over the defaults."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({"Name": ["Alice"], "Age": [30], "Unknown": [1]}, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.set_defaults(strict=True)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+
+    with pytest.raises(ValueError):
+        template.fill_with(df)
+
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df, strict=False, overwrite=True)
+    template.reset_defaults()
+    assert template.get_defaults()["strict"] is None
+
+
+def test_fill_sheet_copies_template_row_styles(create_test_excel):
+    """Test that appended rows take the styles of the first data row of the template."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    workbook = openpyxl.load_workbook(excel_path)
+    sheet = workbook[sheet_name]
+    sheet["B4"].number_format = "0.00"
+    sheet["B4"].fill = PatternFill(start_color="FFFFC7CE", end_color="FFFFC7CE", fill_type="solid")
+    sheet["B4"].border = Border(bottom=Side(style="thin"))
+    workbook.save(excel_path)
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with({"Name": ["Alice", "Bob"], "Age": [30, 35]})
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with({"Name": ["Carol"], "Age": [40]}, copy_style_from_row=0)
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    for cell in ("B6", "B7"):
+        assert sheet[cell].number_format == "0.00"
+        assert sheet[cell].fill.start_color.rgb == "FFFFC7CE"
+        assert sheet[cell].border.bottom.style == "thin"
+    assert sheet["B8"].value == 40
+    assert sheet["B8"].number_format == "General"
+
+
+def test_fill_many_sheets(create_test_excel_with_monthly_sheets):
+    """Test that fill_many fills each sheet at its header location in one pass."""
+    excel_path, months = create_test_excel_with_monthly_sheets
+
+    template = ExcelTemplate(excel_path)
+    for month in months:
+        template.goto_sheet(month)
+        template.set_header_location("A1", "col")
+    template.goto_sheet(months[0], cell="A2")
+
+    frames = {month: {"Sales": [100 * i], "Costs": [50 * i]} for i, month in enumerate(months, start=1)}
+    template.fill_many(frames, mode="col", overwrite=True)
+
+    template.add_sheet("Apr")
+    with pytest.raises(ValueError, match="no header location"):
+        template.fill_many({"Apr": frames[months[0]]})
+    with pytest.raises(ValueError, match="not found"):
+        template.fill_many({"May": frames[months[0]]})
+    template.save(excel_path)
+
+    workbook = openpyxl.load_workbook(excel_path)
+    assert workbook["Jan"]["B1"].value == 100
+    assert workbook["Mar"]["B1"].value == 300
+    assert workbook["Mar"]["B2"].value == 150
+
+
+def test_fill_with_csv(create_test_excel, tmp_path):
+    """Test that fill_with_csv fills the sheet with the rows of a CSV file, with or without a header row."""
+    excel_path, sheet_name, header_row = create_test_excel
+    csv_path = tmp_path / "data.csv"
+    csv_path.write_text("Name;Age;Gender\nAlice;25;F\nBob;30;M\n")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), "row")
+    template.fill_with_csv(str(csv_path), delimiter=";", overwrite=True)
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert sheet["A4"].value == "Alice"
+    assert sheet["B4"].value == 25
+    assert sheet["C5"].value == "M"
+
+    csv_path.write_text("Carl,40,M\n")
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), "row")
+    template.fill_with_csv(str(csv_path), has_header=False, columns=["Name", "Age", "Gender"], overwrite=True)
+    with pytest.raises(ValueError, match="column names"):
+        template.fill_with_csv(str(csv_path), has_header=False, columns=["Name"])
+    with pytest.raises(ValueError, match="single ASCII character"):
+        template.fill_with_csv(str(csv_path), delimiter=";;")
+    with pytest.raises(FileNotFoundError):
+        template.fill_with_csv(str(tmp_path / "missing.csv"))
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert sheet["A4"].value == "Carl"
+    assert sheet["B4"].value == 40
+
+
+def test_fill_with_ipc(create_test_excel, tmp_path):
+    """Test that fill_with_ipc fills the sheet with the rows of an Arrow IPC file, only the columns given."""
+    excel_path, sheet_name, header_row = create_test_excel
+    ipc_path = tmp_path / "data.arrow"
+    pl.DataFrame({"Name": ["Alice", "Bob"], "Age": [25, 30], "Gender": ["F", "M"], "Score": [1.5, 2.5]}).write_ipc(ipc_path)
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), "row")
+    with pytest.raises(ValueError, match="Missing"):
+        template.fill_with_ipc(str(ipc_path), columns=["Missing"])
+    with pytest.raises(FileNotFoundError):
+        template.fill_with_ipc(str(tmp_path / "missing.arrow"))
+    template.fill_with_ipc(str(ipc_path), columns=["Name", "Age", "Gender"], strict=True, overwrite=True)
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert sheet["A4"].value == "Alice"
+    assert sheet["B5"].value == 30
+    assert sheet["D3"].value is None
+
+
+def test_fill_with_parquet(create_test_excel, tmp_path):
+    """Test that fill_with_parquet fills the sheet with the rows of a Parquet file, only the columns given."""
+    excel_path, sheet_name, header_row = create_test_excel
+    parquet_path = tmp_path / "data.parquet"
+    df = pl.DataFrame({"Name": ["Alice", "Bob", "Carol"], "Age": [25, 30, None], "Gender": ["F", "M", "F"], "Score": [1.5, 2.5, 3.5]})
+    df.write_parquet(parquet_path, compression="zstd", row_group_size=2)
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), "row")
+    with pytest.raises(ValueError, match="Missing"):
+        template.fill_with_parquet(str(parquet_path), columns=["Missing"])
+    with pytest.raises(FileNotFoundError):
+        template.fill_with_parquet(str(tmp_path / "missing.parquet"))
+    template.fill_with_parquet(str(parquet_path), columns=["Name", "Age", "Gender"], strict=True, overwrite=True)
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert sheet["A4"].value == "Alice"
+    assert sheet["B5"].value == 30
+    assert sheet["A6"].value == "Carol"
+    assert sheet["B6"].value is None
+    assert sheet["D3"].value is None
+
+
+@pytest.mark.parametrize("data_type", ["polars", "pandas", "arrow", "dict"])
+def test_fill_partitioned(create_test_excel, data_type):
+    """Test that fill_partitioned fills a copy of the template sheet per value of a column."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({"Name": ["Alice", "Bob", "Carol"], "Age": [30, 35, 40], "Gender": ["F", "M", "F"]}, data_type)
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.set_defaults(overwrite=True, sheet_name=sheet_name)
+    with pytest.raises(ValueError, match="Unknown"):
+        template.fill_partitioned(df, "Unknown", sheet_name)
+    assert template.fill_partitioned(df, "Gender", sheet_name, keep_template=False) == ["F", "M"]
+    template.save(excel_path)
+
+    workbook = openpyxl.load_workbook(excel_path)
+    assert workbook.sheetnames == ["F", "M"]
+    assert [workbook["F"].cell(row=row, column=1).value for row in range(4, 7)] == ["Alice", "Carol", None]
+    assert workbook["F"]["A1"].value == "First row"
+    assert workbook["M"]["A4"].value == "Bob"
+    assert workbook["M"]["B4"].value == 35
+
+
+def test_fill_partitioned_failure_deletes_the_copies(create_test_excel):
+    """Test that fill_partitioned leaves no copy of the template sheet behind when a fill fails."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({"Name": ["Alice", "Bob"], "Gender": ["F", "M"], "City": ["Paris", "Lyon"]}, "dict")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.set_defaults(strict=True, sheet_name=sheet_name)
+    with pytest.raises(ColumnMismatchError, match="Failed to fill sheet 'F'"):
+        template.fill_partitioned(df, "Gender", sheet_name)
+    assert template.sheet_names() == [sheet_name]
+
+
+@pytest.mark.parametrize("data_type", ["polars", "pandas", "dict"])
+def test_fill_sheet_upserts_on_key(create_test_excel, data_type):
+    """Test that upsert_on updates the rows whose key is in the sheet and appends the others."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({"Name": ["Matthieu", "Alice"], "Age": [27, 30], "Gender": ["M", "F"]}, data_type)
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df, upsert_on="Name")
+    template.set_header_location((header_row, 1), 'row')
+    with pytest.raises(ValueError, match="upsert_on"):
+        template.fill_with(df, upsert_on="City")
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert [[cell.value for cell in row] for row in sheet.iter_rows(min_row=4, max_col=3)] == [
+        ["Irène", 25, None],
+        ["Matthieu", 27, "M"],
+        ["Alice", 30, "F"],
+    ]
+
+
+@pytest.mark.parametrize("truncate", [True, False])
+def test_fill_sheet_keeps_rows_below(create_test_excel, truncate):
+    """Test that truncate=False leaves the rows below the data, e.g. a footer, intact."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({"Name": ["Alice", "Bob"], "Age": [30, 31], "Gender": ["F", "M"]}, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.write_cell(sheet_name, "A8", "Signature")
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df, overwrite=True, truncate=truncate)
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert sheet["A5"].value == "Bob"
+    assert sheet["A8"].value == (None if truncate else "Signature")
+
+
+def test_fill_sheet_inserts_rows(create_test_excel):
+    """Test that insert=True pushes the rows below the header down, with the formulas referring to them."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    workbook = openpyxl.load_workbook(excel_path)
+    workbook[sheet_name]["A7"] = "Total"
+    workbook[sheet_name]["B7"] = "=SUM(B4:B5)"
+    workbook.create_sheet("Summary")["A1"] = f"={sheet_name}!B7"
+    workbook.save(excel_path)
+
+    df = generate_test_data({"Name": ["Alice"], "Age": [30], "Gender": ["F"]}, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    with pytest.raises(ValueError, match="upsert_on"):
+        template.fill_with(df, insert=True, upsert_on="Name")
+    template.fill_with(df, insert=True)
+    template.save(excel_path)
+
+    workbook = openpyxl.load_workbook(excel_path)
+    sheet = workbook[sheet_name]
+    assert [sheet[f"A{row}"].value for row in range(4, 9)] == ["Alice", "Irène", "Matthieu", None, "Total"]
+    assert sheet["B8"].value == "=SUM(B5:B6)"
+    assert workbook["Summary"]["A1"].value == f"={sheet_name}!B8"
+
+
+def test_fill_sheet_freezes_header_and_filters(create_test_excel):
+    """Test that freeze_header and autofilter finish the filled region."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({"Name": ["Alice", "Bob", "Carol"], "Age": [30, 40, 50], "Gender": ["F", "M", "F"]}, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df, freeze_header=True, autofilter=True)
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert sheet.freeze_panes == "A4"
+    assert sheet.auto_filter.ref == "A3:C8"
+
+    template = ExcelTemplate(excel_path)
+    template.freeze_panes("B2", sheet_name=sheet_name)
+    assert template.add_autofilter("A3:B5", sheet_name=sheet_name) == "A3:B5"
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert sheet.freeze_panes == "B2"
+    assert sheet.auto_filter.ref == "A3:B5"
+
+
+def test_fill_sheet_writes_merged_cells_to_their_anchor(create_test_excel):
+    """Test that values falling on cells hidden by a merge are written to its top left cell."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    workbook = openpyxl.load_workbook(excel_path)
+    sheet = workbook[sheet_name]
+    sheet["E3"] = "Notes"
+    sheet.merge_cells("D6:E6")
+    sheet.merge_cells("A6:A7")
+    workbook.save(excel_path)
+
+    df = generate_test_data({"Name": ["Alice", "Bob"], "Age": [30, 40], "Gender": ["F", "M"], "Notes": ["n1", "n2"]}, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    with pytest.warns(DataLossWarning, match="A7"):
+        template.fill_with(df)
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert sheet["D6"].value == "n1"
+    assert sheet["E7"].value == "n2"
+    assert sheet["A6"].value == "Alice"
+    assert sheet["A7"].value is None
+
+
+def test_fill_sheet_with_formula_columns(create_test_excel):
+    """Test that the texts of formula columns are written as formulas."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({"Name": ["=A1", "Bob"], "Age": ["=B4+1", "B5*2"], "Gender": ["F", None]}, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    with pytest.raises(ValueError, match="Unknown"):
+        template.fill_with(df, formula_columns=["Unknown"])
+    template.fill_with(df, formula_columns=["Age"], verify_fill=True)
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert sheet["A6"].value == "=A1"
+    assert sheet["A6"].data_type == "s"
+    assert sheet["B6"].value == "=B4+1"
+    assert sheet["B7"].value == "=B5*2"
+
+
+def test_fill_sheet_with_totals_row(create_test_excel):
+    """Test that a bold totals row of SUBTOTAL formulas is written below the data."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({"Name": ["Alice", "Bob"], "Age": [30, 40], "Gender": ["F", None]}, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    with pytest.raises(ValueError, match="Unknown"):
+        template.fill_with(df, totals={"Unknown": "sum"})
+    template.fill_with(df, overwrite=True, totals={"Age": "sum", "Gender": "count"})
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert sheet["A6"].value == "Total"
+    assert sheet["B6"].value == "=SUBTOTAL(9,B4:B5)"
+    assert sheet["C6"].value == "=SUBTOTAL(3,C4:C5)"
+    assert all(sheet[cell].font.bold for cell in ["A6", "B6", "C6"])
+
+
+def test_fill_sheet_grouped_by_column(create_test_excel):
+    """Test that group_by gathers the rows of each group and sums the numeric columns in subtotal rows."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({"Name": ["Alice", "Carol", "Bob"], "Age": [30, 40, 35], "Gender": ["F", "M", "F"]}, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    with pytest.raises(ValueError, match="subtotals"):
+        template.fill_with(df, group_by="Gender", subtotals={"by": "Gender", "agg": {"Age": "sum"}})
+    template.fill_with(df, overwrite=True, group_by="Gender")
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert [sheet.cell(row=row, column=1).value for row in range(4, 9)] == ["Alice", "Bob", None, "Carol", None]
+    assert sheet["C6"].value == "F Total"
+    assert sheet["B6"].value == "=SUBTOTAL(9,B4:B5)"
+    assert sheet["B8"].value == "=SUBTOTAL(9,B7:B7)"
+    assert sheet["C9"].value == "Grand Total"
+    assert sheet.row_dimensions[4].outline_level == 2
+
+
+@pytest.mark.parametrize("data_type", ["polars", "pandas", "dict"])
+def test_fill_sheet_matches_headers(create_test_excel, data_type):
+    """Test that columns named differently from the headers are matched by policy or alias."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({"name": ["Alice"], " AGE ": [30], "sex": ["F"]}, data_type)
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    with pytest.raises(ValueError):
+        template.fill_with(df, strict=True)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df, overwrite=True, strict=True, header_matching="normalized", aliases={"sex": "Gender"})
+    with pytest.raises(ValueError, match="header matching"):
+        template.fill_with(df, header_matching="fuzzy")
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert [cell.value for cell in sheet[header_row + 1]] == ["Alice", 30, "F"]
+
+
+@pytest.mark.parametrize("data_type", ["polars", "pandas", "dict"])
+def test_fill_sheet_coerces_columns(create_test_excel, data_type):
+    """Test that coerce converts the columns before writing, reporting or raising on unparseable values."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({"Name": ["Alice", "Bob"], "Age": ["30", "n/a"], "Gender": ["2024-01-15", "F"]}, data_type)
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    with pytest.raises(ValueError, match="is not an integer"):
+        template.fill_with(df, overwrite=True, coerce={"Age": "int"}, coerce_strict=True)
+    template.set_header_location((header_row, 1), 'row')
+    with pytest.warns(DataLossWarning, match="'Age'"):
+        template.fill_with(df, overwrite=True, coerce={"Age": "int", "Gender": "date"})
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert sheet["B4"].value == 30
+    assert sheet["B5"].value is None
+    assert sheet["C4"].value == datetime.datetime(2024, 1, 15)
+    assert sheet["C5"].value is None
+
+
+def test_fill_sheet_comments_coerced_values(create_test_excel):
+    """Test that comment_coercions puts a comment with the warning on the cells of coerced values."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = pl.DataFrame({"Name": ["Alice", "Bob", "Carol"], "Age": ["30", "n/a", "41.5"]})
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    with pytest.warns(DataLossWarning):
+        warnings = template.fill_with(df, overwrite=True, coerce={"Age": "int"}, comment_coercions=True)
+    assert [w["cell"] for w in warnings if w["code"] == "coercion"] == ["B5", "B6"]
+    assert [c["cell"] for c in template.read_comments()] == ["B5", "B6"]
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert sheet["B4"].comment is None
+    assert "is not an integer" in sheet["B5"].comment.text
+    assert "Written as 41" in sheet["B6"].comment.text
+
+
+def test_fill_sheet_warns_on_unmatched_columns(create_test_excel):
+    """Test that unmatched columns in non-strict mode are reported as warnings."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({"Name": ["Alice"], "Age": [30], "City": ["Paris"]}, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+
+    with pytest.warns(DataLossWarning) as record:
+        template.fill_with(df, overwrite=True)
+
+    messages = [str(w.message) for w in record]
+    assert any("'Gender'" in m for m in messages)
+    assert any("'City'" in m for m in messages)
+
+
+def test_fill_sheet_returns_warnings(create_test_excel):
+    """Test that fill_with returns its warnings with their code, column and cell."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({"Name": ["Alice"], "Age": [30], "City": ["Paris"]}, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+
+    with pytest.warns(DataLossWarning):
+        warnings = template.fill_with(df, overwrite=True)
+
+    by_code = {w["code"]: w for w in warnings}
+    assert by_code["missing_in_data"]["column"] == "Gender"
+    assert by_code["missing_in_data"]["cell"] == "C3"
+    assert by_code["column_added"]["column"] == "City"
+    assert by_code["column_added"]["cell"] == "D3"
+    assert template.fill_with(df.drop("City").with_columns(Gender=pl.lit("F")), overwrite=True) == []
+
+
+@pytest.mark.parametrize("data_type", ["polars", "pandas", "dict"])
+def test_fill_sheet_with_column_positions(create_test_excel, data_type):
+    """Test mapping a DataFrame column to an explicit sheet column."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({
+        "Name": ["Alice", "Bob"],
+        "Age": [30, 35],
+        "amount": [10.5, 20.5],
+    }, data_type)
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df, columns={"amount": "F"}, overwrite=True)
+    template.save(excel_path)
+
+    workbook = openpyxl.load_workbook(excel_path)
+    sheet = workbook[sheet_name]
+
+    assert sheet["A4"].value == "Alice"
+    assert sheet["B5"].value == 35
+    assert sheet["F3"].value is None
+    assert sheet["F4"].value == 10.5
+    assert sheet["F5"].value == 20.5
+
+
+def test_fill_sheet_with_list_and_column_positions(create_test_excel):
+    """Test that the keys of a dictionary of positions name the lists of a list of lists."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with([["Alice", "Bob"], [1, 2]], columns={"Name": "A", "amount": 5}, overwrite=True)
+    template.save(excel_path)
+
+    workbook = openpyxl.load_workbook(excel_path)
+    sheet = workbook[sheet_name]
+
+    assert sheet["A4"].value == "Alice"
+    assert sheet["E5"].value == 2
+
+
+def test_fill_sheet_with_spacer_columns(create_test_excel):
+    """Test that blank header cells are skipped instead of being matched as a column."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    workbook = openpyxl.load_workbook(excel_path)
+    workbook[sheet_name]["E3"] = "City"
+    workbook.save(excel_path)
+
+    df = generate_test_data({
+        "Name": ["Alice", "Bob"],
+        "Age": [30, 35],
+        "Gender": ["F", "M"],
+        "City": ["Paris", "Liège"],
+    }, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    warnings = template.fill_with(df, strict=True, overwrite=True, warn_blank_headers=True)
+    template.save(excel_path)
+
+    assert [(warning["code"], warning["cell"]) for warning in warnings] == [("blank_header", "D3")]
+
+    workbook = openpyxl.load_workbook(excel_path)
+    sheet = workbook[sheet_name]
+
+    assert sheet["D4"].value is None
+    assert sheet["E4"].value == "Paris"
+    assert sheet["E5"].value == "Liège"
+
+
+def test_fill_sheet_without_gaps(create_test_excel):
+    """Test that the header ends at the first blank cell when gaps are not allowed."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    workbook = openpyxl.load_workbook(excel_path)
+    workbook[sheet_name]["E3"] = "Notes"
+    workbook.save(excel_path)
+
+    df = generate_test_data({
+        "Name": ["Alice"],
+        "Age": [30],
+        "Gender": ["F"],
+    }, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    template.fill_with(df, strict=True, overwrite=True, allow_gaps=False)
+    template.save(excel_path)
+
+    workbook = openpyxl.load_workbook(excel_path)
+    sheet = workbook[sheet_name]
+
+    assert sheet["C4"].value == "F"
+    assert sheet["E4"].value is None
+
+
+def test_fill_sheet_validate_only(create_test_excel):
+    """Test that validate_only reports the fill without writing it."""
+    excel_path, sheet_name, header_row = create_test_excel
+
+    df = generate_test_data({"Name": ["Alice", "Bob"], "Age": [30, 35], "City": ["Paris", "Liège"]}, "polars")
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet(sheet_name)
+    template.set_header_location((header_row, 1), 'row')
+    report = template.fill_with(df, strict=True, overwrite=True, validate_only=True)
+    template.save(excel_path)
+
+    assert report["valid"] is False
+    assert report["rows"] == 2
+    assert report["range"] == "A4:B5"
+    assert report["matched_columns"] == ["Name", "Age"]
+    assert report["missing_columns"] == ["Gender"]
+    assert report["extra_columns"] == ["City"]
+    assert len(report["errors"]) == 2
+
+    sheet = openpyxl.load_workbook(excel_path)[sheet_name]
+    assert sheet["A4"].value == "Irène"
+    assert template.fill_with(df.drop("City"), overwrite=True, validate_only=True)["valid"] is True
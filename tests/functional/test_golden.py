@@ -0,0 +1,40 @@
+# This project uses Poetry for dependency management.
+import polars as pl
+from ez_excel_mgt import ExcelTemplate
+from workbooks import build_table, build_workbook
+
+
+def test_golden_fill_sheet(tmp_path, golden):
+    """Test filling a template against its golden snapshot."""
+    excel_path = build_table(
+        tmp_path / "template.xlsx", "Sheet1", 3, ["Name", "Age", "Gender"], [["Old", 99, "X"]],
+        above={"A1": "First row", "A2": "Second row"},
+    )
+    df = pl.DataFrame({"Name": ["Alice", "Bob"], "Age": [30, 35], "Gender": ["F", "M"]})
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet("Sheet1")
+    template.set_header_location((3, 1), 'row')
+    template.fill_with(df, overwrite=True)
+    template.save(excel_path)
+
+    golden(excel_path, "fill_sheet")
+
+
+def test_golden_aggregate_and_copy(tmp_path, golden):
+    """Test aggregations and a transposed copy against their golden snapshot."""
+    months = {
+        month: {"A1": "Sales", "B1": 10 * i, "C1": i, "A2": "Costs", "B2": 5 * i, "C2": 2 * i}
+        for i, month in enumerate(["Jan", "Feb", "Mar"], start=1)
+    }
+    source_path = build_workbook(tmp_path / "months.xlsx", months)
+    dest_path = build_workbook(tmp_path / "dest.xlsx", {"Summary": {"A1": "Summary"}})
+
+    template = ExcelTemplate(dest_path)
+    template.goto_sheet("Summary", cell="B2")
+    template.aggregate_across_sheets(source_path, ["Jan", "Feb", "Mar"], "B1:C2", action='sum')
+    template.goto_cell("B5")
+    template.copy_range_from(source_path, "Jan", "A1:C2", transpose=True)
+    template.save(dest_path)
+
+    golden(dest_path, "aggregate_and_copy")
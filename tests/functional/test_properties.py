@@ -0,0 +1,81 @@
+# This project uses Poetry for dependency management.
+import string
+
+import openpyxl
+import pytest
+from hypothesis import HealthCheck, given, settings, strategies as st
+from ez_excel_mgt import ExcelTemplate
+from workbooks import build_table, column_letters
+
+MAX_ROWS = 1_048_576
+MAX_COLUMNS = 16_384
+
+# Function-scoped fixtures are only read from, so they can be shared between examples
+SETTINGS = settings(max_examples=50, deadline=None, suppress_health_check=[HealthCheck.function_scoped_fixture])
+
+rows = st.integers(min_value=1, max_value=MAX_ROWS)
+columns = st.integers(min_value=1, max_value=MAX_COLUMNS)
+
+
+@SETTINGS
+@given(row=rows, col=columns)
+def test_tuple_and_letter_cells_are_the_same(create_test_excel, row, col):
+    """Property: (row, col) and its letters reference designate the same cell."""
+    file_path, sheet_name, _ = create_test_excel
+    template = ExcelTemplate(file_path)
+    reference = f"{column_letters(col)}{row}"
+
+    template.write_cell(sheet_name, (row, col), f"v{row}_{col}")
+
+    assert template.get_control_value(reference, sheet_name=sheet_name) == f"v{row}_{col}"
+    assert template.get_control_value(reference.lower(), sheet_name=sheet_name) == f"v{row}_{col}"
+    assert template.get_control_value(f"${column_letters(col)}${row}", sheet_name=sheet_name) == f"v{row}_{col}"
+
+
+@SETTINGS
+@given(first=st.tuples(st.integers(1, 20), st.integers(1, 10)), last=st.tuples(st.integers(1, 20), st.integers(1, 10)))
+def test_range_corners_in_any_order(create_test_excel, create_empty_test_excel, first, last):
+    """Property: a range copies the same cells whatever the order of its corners."""
+    source_file_path, source_sheet_name, _ = create_test_excel
+    dest_file_path, dest_sheet_name, _ = create_empty_test_excel
+
+    results = []
+    for i, source_range in enumerate([(first, last), (last, first)]):
+        output_path = f"{dest_file_path}.{i}.xlsx"
+        template = ExcelTemplate(dest_file_path)
+        template.goto_sheet(dest_sheet_name, cell=(1, 1))
+        template.copy_range_from(source_file_path, source_sheet_name, source_range)
+        template.save(output_path)
+        results.append([[c.value for c in row] for row in openpyxl.load_workbook(output_path)[dest_sheet_name].iter_rows()])
+
+    assert results[0] == results[1]
+
+
+@pytest.mark.parametrize("data_type", ["polars", "pandas", "dict"])
+@SETTINGS
+@given(data=st.data())
+def test_fill_round_trip(tmp_path_factory, data_type, data):
+    """Property: the values of a filled DataFrame are read back unchanged."""
+    import pandas as pd
+    import polars as pl
+
+    height = data.draw(st.integers(min_value=1, max_value=20))
+    ints = data.draw(st.lists(st.integers(min_value=-10**15 + 1, max_value=10**15 - 1), min_size=height, max_size=height))
+    floats = data.draw(st.lists(st.floats(allow_nan=False, allow_infinity=False, width=64).filter(lambda f: not f.is_integer()),
+                                min_size=height, max_size=height))
+    texts = data.draw(st.lists(st.text(alphabet=string.ascii_letters + "éàü", min_size=1, max_size=20).map(lambda s: "s" + s),
+                               min_size=height, max_size=height))
+    columns = {"Int": ints, "Float": floats, "Text": texts}
+
+    excel_path = build_table(tmp_path_factory.mktemp("fill") / "template.xlsx", "Data", 1, list(columns), [])
+    df = {"polars": pl.DataFrame, "pandas": pd.DataFrame, "dict": dict}[data_type](columns)
+
+    template = ExcelTemplate(excel_path)
+    template.goto_sheet("Data")
+    template.set_header_location((1, 1), 'row')
+    template.fill_with(df, strict=True, overwrite=True)
+    template.save(excel_path)
+
+    sheet = openpyxl.load_workbook(excel_path)["Data"]
+    for j, (name, values) in enumerate(columns.items(), start=1):
+        assert [sheet.cell(row=2 + i, column=j).value for i in range(height)] == values, name
@@ -1,7 +1,8 @@
 # This project uses Poetry for dependency management.
 from pathlib import Path
 import openpyxl
-from ez_excel_mgt import ExcelTemplate
+import pytest
+from ez_excel_mgt import ExcelTemplate, DataLossWarning
 
 
 def test_transform_sum_row(create_test_excel_with_data_to_aggregate, create_empty_test_excel):
@@ -88,3 +89,262 @@ def test_transform_avg_col(create_test_excel_with_data_to_aggregate, create_empt
     assert sheet["B1"].value == 4
     assert sheet["C1"].value == 5
     assert sheet["D1"].value == 6
+
+def test_transform_avg_row_with_number_locale(create_test_excel_with_data_to_aggregate, create_empty_test_excel):
+    """Test pasting aggregation results with locale separators."""
+    source_file_path, source_sheet_name, _header_row = create_test_excel_with_data_to_aggregate
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    template = ExcelTemplate(dest_file_path)
+    template.set_number_locale(",")
+    template.goto_sheet(dest_sheet_name, cell=(1, 1))
+    template.aggregate_range_from(source_file_path, source_sheet_name, ((2, 1), (3, 4)), action='avg', mode='row')
+    template.save(dest_file_path)
+
+    workbook = openpyxl.load_workbook(dest_file_path)
+    sheet = workbook[dest_sheet_name]
+
+    assert sheet["A1"].value == "2,5"
+    assert sheet["A2"].value == "6,5"
+
+
+def test_transform_sum_warns_on_non_numeric(create_test_excel_with_data_to_aggregate, create_empty_test_excel):
+    """Test that skipped non-numeric cells are reported as warnings."""
+    source_file_path, source_sheet_name, _header_row = create_test_excel_with_data_to_aggregate
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell=(1, 1))
+
+    with pytest.warns(DataLossWarning, match="A1"):
+        warnings = template.aggregate_range_from(source_file_path, source_sheet_name, ((1, 1), (3, 4)), action='sum', mode='col')
+    assert {"code": "non_numeric", "cell": "A1"}.items() <= warnings[0].items()
+    template.save(dest_file_path)
+
+    workbook = openpyxl.load_workbook(dest_file_path)
+    sheet = workbook[dest_sheet_name]
+
+    assert sheet["A1"].value == 6
+
+
+@pytest.mark.parametrize("action, expected", [
+    ("min", [1, 5]),
+    ("max", [4, 8]),
+    ("median", [2.5, 6.5]),
+    ("variance", [5 / 3, 5 / 3]),
+    ("stddev", [(5 / 3) ** 0.5, (5 / 3) ** 0.5]),
+    ("product", [24, 1680]),
+])
+def test_transform_statistics_row(create_test_excel_with_data_to_aggregate, create_empty_test_excel, action, expected):
+    """Test the statistical actions of aggregate_range_from."""
+    source_file_path, source_sheet_name, _header_row = create_test_excel_with_data_to_aggregate
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell=(1, 1))
+    template.aggregate_range_from(source_file_path, source_sheet_name, ((2, 1), (3, 4)), action=action, mode='row')
+    template.save(dest_file_path)
+
+    sheet = openpyxl.load_workbook(dest_file_path)[dest_sheet_name]
+    assert sheet["A1"].value == pytest.approx(expected[0])
+    assert sheet["A2"].value == pytest.approx(expected[1])
+
+
+def test_aggregate_across_sheets_statistics(create_test_excel_with_monthly_sheets, create_empty_test_excel):
+    """Test that cells with too few numbers for the action are left empty."""
+    source_file_path, months = create_test_excel_with_monthly_sheets
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell="B2")
+    template.aggregate_across_sheets(source_file_path, months, "B1:C1", action='max')
+    template.goto_cell("B3")
+    template.aggregate_across_sheets(source_file_path, months[:1], "B1:C1", action='stddev')
+    template.save(dest_file_path)
+
+    sheet = openpyxl.load_workbook(dest_file_path)[dest_sheet_name]
+    assert sheet["B2"].value == 30
+    assert sheet["C2"].value == 3
+    assert sheet["B3"].value is None
+
+    with pytest.raises(ValueError, match="Invalid action"):
+        template.aggregate_across_sheets(source_file_path, months, "B1:C1", action='mode')
+
+
+def test_aggregate_across_sheets(create_test_excel_with_monthly_sheets, create_empty_test_excel):
+    """Test the cell per cell aggregation of the same range across sheets."""
+    source_file_path, months = create_test_excel_with_monthly_sheets
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell="B2")
+    template.aggregate_across_sheets(source_file_path, months, "B1:C2", action='sum')
+    template.goto_cell("B5")
+    template.aggregate_across_sheets(source_file_path, months[:2], "B1:C2", action='avg')
+    template.save(dest_file_path)
+
+    workbook = openpyxl.load_workbook(dest_file_path)
+    sheet = workbook[dest_sheet_name]
+
+    assert sheet["B2"].value == 60
+    assert sheet["C2"].value == 6
+    assert sheet["B3"].value == 30
+    assert sheet["C3"].value == 12
+    assert sheet["B5"].value == 15
+    assert sheet["C6"].value == 3
+
+
+def test_aggregate_across_sheets_warns_on_non_numeric(create_test_excel_with_monthly_sheets, create_empty_test_excel):
+    """Test that non-numeric cells are skipped with a warning and unknown sheets are rejected."""
+    source_file_path, months = create_test_excel_with_monthly_sheets
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell=(1, 1))
+
+    with pytest.warns(DataLossWarning, match="A1 of Jan"):
+        template.aggregate_across_sheets(source_file_path, months, "A1:B1", action='count')
+
+    with pytest.raises(ValueError):
+        template.aggregate_across_sheets(source_file_path, ["Jan", "Dec"], "A1:B1", action='sum')
+
+
+def test_aggregate_across_sheets_with_pattern(create_test_excel_with_monthly_sheets, create_empty_test_excel):
+    """Test selecting the aggregated sheets with a pattern."""
+    source_file_path, _months = create_test_excel_with_monthly_sheets
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell=(1, 1))
+    template.aggregate_across_sheets(source_file_path, "re:(Jan|Feb)", "B1:B1", action='sum')
+    template.save(dest_file_path)
+
+    workbook = openpyxl.load_workbook(dest_file_path)
+    sheet = workbook[dest_sheet_name]
+
+    assert sheet["A1"].value == 30
+
+
+def test_aggregate_across_files(tmp_path, create_empty_test_excel):
+    """Test aggregating the same range of several files, cell per cell and by row."""
+    monthly = tmp_path / "monthly"
+    monthly.mkdir()
+    for i in range(1, 4):
+        workbook = openpyxl.Workbook()
+        workbook.active.title = "Summary"
+        workbook.active.append([10 * i, i])
+        workbook.active.append([5 * i, 2 * i])
+        workbook.save(monthly / f"2024-0{i}.xlsx")
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell="B2")
+    template.aggregate_across_files(str(monthly / "2024-*.xlsx"), "Summary", "A1:B2", action='sum')
+    template.goto_cell("E2")
+    template.aggregate_across_files(str(monthly / "2024-*.xlsx"), "Summary", "A1:B2", action='max', mode='row')
+    with pytest.raises(FileNotFoundError):
+        template.aggregate_across_files(str(monthly / "2023-*.xlsx"), "Summary", "A1:B2", action='sum')
+    template.save(dest_file_path)
+
+    sheet = openpyxl.load_workbook(dest_file_path)[dest_sheet_name]
+    assert sheet["B2"].value == 60
+    assert sheet["C3"].value == 12
+    assert sheet["E2"].value == 30
+    assert sheet["E3"].value == 15
+    assert sheet["F2"].value is None
+
+
+def test_transform_non_numeric_and_blank_policies(create_test_excel_with_data_to_aggregate, create_empty_test_excel):
+    """Test taking non-numeric and blank cells as 0 or raising on them."""
+    source_file_path, source_sheet_name, _header_row = create_test_excel_with_data_to_aggregate
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell=(1, 1))
+
+    with pytest.raises(ValueError, match="A1"):
+        template.aggregate_range_from(source_file_path, source_sheet_name, ((1, 1), (3, 4)), action='sum', mode='col', on_non_numeric='error')
+    with pytest.warns(DataLossWarning, match="Taken as 0"):
+        template.aggregate_range_from(source_file_path, source_sheet_name, ((1, 1), (3, 4)), action='count', mode='col', on_non_numeric='zero')
+    template.goto_cell((2, 1))
+    template.aggregate_range_from(source_file_path, source_sheet_name, ((2, 1), (3, 6)), action='avg', mode='col', skip_blanks=False)
+    template.save(dest_file_path)
+
+    sheet = openpyxl.load_workbook(dest_file_path)[dest_sheet_name]
+    assert sheet["A1"].value == 3
+    assert sheet["A2"].value == 3
+    assert sheet["E2"].value == 0
+    assert sheet["F2"].value == 0
+
+
+def test_transform_with_criteria(create_test_excel_with_data_to_aggregate, create_empty_test_excel):
+    """Test conditional aggregations, on the aggregated cells and on a criteria range."""
+    source_file_path, source_sheet_name, _header_row = create_test_excel_with_data_to_aggregate
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell=(1, 1))
+    template.aggregate_range_from(source_file_path, source_sheet_name, "A2:D3", action='sum', mode='row', criteria=(">", 2))
+    template.goto_cell("B1")
+    template.aggregate_range_from(source_file_path, source_sheet_name, "B2:D3", action='sum', mode='col', criteria=(">=", 5), criteria_range="A2:A3")
+    with pytest.raises(ValueError, match="criteria"):
+        template.aggregate_range_from(source_file_path, source_sheet_name, "B2:D3", action='sum', mode='col', criteria_range="A2:A3")
+    template.save(dest_file_path)
+
+    sheet = openpyxl.load_workbook(dest_file_path)[dest_sheet_name]
+    assert sheet["A1"].value == 7
+    assert sheet["A2"].value == 26
+    assert sheet["B1"].value == 6
+    assert sheet["D1"].value == 8
+
+
+def test_transform_with_function(create_test_excel_with_data_to_aggregate, create_empty_test_excel):
+    """Test aggregations with a Python function as action."""
+    source_file_path, source_sheet_name, _header_row = create_test_excel_with_data_to_aggregate
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell=(1, 1))
+    template.aggregate_range_from(source_file_path, source_sheet_name, "A2:D3", action=lambda values: max(values) - min(values), mode='row')
+    template.goto_cell("B1")
+    template.aggregate_range_from(source_file_path, source_sheet_name, "A2:D3", action=lambda values: values[1] / values[0], mode='col')
+    with pytest.raises(ValueError, match="ZeroDivisionError"):
+        template.aggregate_range_from(source_file_path, source_sheet_name, "A2:D3", action=lambda values: 1 / 0, mode='row')
+    with pytest.raises(ValueError, match="must return a number"):
+        template.aggregate_range_from(source_file_path, source_sheet_name, "A2:D3", action=lambda values: "spread", mode='row')
+    template.save(dest_file_path)
+
+    sheet = openpyxl.load_workbook(dest_file_path)[dest_sheet_name]
+    assert sheet["A1"].value == 3
+    assert sheet["A2"].value == 3
+    assert sheet["B1"].value == 5
+    assert sheet["C1"].value == 3
+    assert sheet["E1"].value == 2
+
+
+def test_transform_as_formula(create_test_excel_with_data_to_aggregate, create_empty_test_excel):
+    """Test aggregations written as formulas, referencing another workbook or the workbook itself."""
+    source_file_path, source_sheet_name, _header_row = create_test_excel_with_data_to_aggregate
+    dest_file_path, dest_sheet_name, _header_row = create_empty_test_excel
+
+    template = ExcelTemplate(dest_file_path)
+    template.goto_sheet(dest_sheet_name, cell=(1, 1))
+    template.aggregate_range_from(source_file_path, source_sheet_name, "A2:D3", action='sum', mode='row', as_formula=True)
+    with pytest.raises(ValueError, match="as_formula"):
+        template.aggregate_range_from(source_file_path, source_sheet_name, "A2:D3", action='sum', mode='row', as_formula=True, criteria=(">", 2))
+    template.save(dest_file_path)
+
+    sheet = openpyxl.load_workbook(dest_file_path)[dest_sheet_name]
+    assert sheet["A1"].value.startswith("=SUM('")
+    assert sheet["A1"].value.endswith("[test.xlsx]Sheet1'!A2:D2)")
+    assert sheet["A2"].value.endswith("[test.xlsx]Sheet1'!A3:D3)")
+    assert openpyxl.load_workbook(dest_file_path, data_only=True)[dest_sheet_name]["A2"].value == 26
+
+    template = ExcelTemplate(source_file_path)
+    template.goto_sheet(source_sheet_name, cell=(4, 1))
+    template.aggregate_range_from(source_file_path, source_sheet_name, "A2:D3", action='max', mode='col', as_formula=True)
+    template.save(source_file_path)
+
+    sheet = openpyxl.load_workbook(source_file_path)[source_sheet_name]
+    assert sheet["A4"].value == "=MAX(Sheet1!A2:A3)"
+    assert sheet["D4"].value == "=MAX(Sheet1!D2:D3)"
@@ -0,0 +1,84 @@
+# This project uses Poetry for dependency management.
+"""Utilities to build fixture workbooks programmatically and compare workbooks at value level."""
+import datetime
+import json
+from pathlib import Path
+
+import openpyxl
+
+# Hidden metadata sheets (provenance...) are left out of the comparisons unless asked for
+META_SHEET_PREFIX = "_ezxl_"
+
+GOLDEN_DIR = Path(__file__).parent / "golden"
+
+
+def build_workbook(path, sheets):
+    """Create a workbook from {sheet name: {cell: value}}, sheets in the order given."""
+    workbook = openpyxl.Workbook()
+    workbook.remove(workbook.active)
+    for sheet_name, cells in sheets.items():
+        sheet = workbook.create_sheet(sheet_name)
+        for cell, value in cells.items():
+            sheet[cell] = value
+    workbook.save(path)
+    return str(path)
+
+
+def build_table(path, sheet_name, header_row, columns, rows, above=None):
+    """Create a workbook with one table: headers on header_row from column A, rows below."""
+    cells = dict(above or {})
+    for j, column in enumerate(columns):
+        cells[f"{column_letters(j + 1)}{header_row}"] = column
+    for i, row in enumerate(rows):
+        for j, value in enumerate(row):
+            cells[f"{column_letters(j + 1)}{header_row + 1 + i}"] = value
+    return build_workbook(path, {sheet_name: cells})
+
+
+def column_letters(col):
+    """Reference implementation of the column letters of a 1-based column number."""
+    letters = ""
+    while col > 0:
+        col, remainder = divmod(col - 1, 26)
+        letters = chr(ord("A") + remainder) + letters
+    return letters
+
+
+def _normalize(value):
+    """Normalize a cell value for comparison and JSON serialization."""
+    if isinstance(value, (datetime.datetime, datetime.date, datetime.time)):
+        return value.isoformat()
+    if isinstance(value, float) and value.is_integer():
+        return int(value)
+    return value
+
+
+def read_values(path, include_meta=False):
+    """Read the values of a workbook as {sheet name: {cell: value}}, ignoring styles, properties and empty cells."""
+    workbook = openpyxl.load_workbook(path)
+    values = {}
+    for sheet in workbook.worksheets:
+        if sheet.title.startswith(META_SHEET_PREFIX) and not include_meta:
+            continue
+        values[sheet.title] = {
+            cell.coordinate: _normalize(cell.value)
+            for row in sheet.iter_rows()
+            for cell in row
+            if cell.value is not None and cell.value != ""
+        }
+    return values
+
+
+def assert_matches_golden(path, name, update=False):
+    """Compare the values of a workbook with the golden snapshot tests/golden/<name>.json.
+
+    With update=True (pytest --update-golden), the snapshot is written from the workbook instead.
+    """
+    golden_path = GOLDEN_DIR / f"{name}.json"
+    actual = read_values(path)
+    if update:
+        golden_path.write_text(json.dumps(actual, indent=2, ensure_ascii=False, sort_keys=True) + "\n")
+        return
+    assert golden_path.exists(), f"Golden file {golden_path} is missing. Run pytest with --update-golden to create it."
+    expected = json.loads(golden_path.read_text())
+    assert actual == expected